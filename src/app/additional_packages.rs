@@ -271,6 +271,10 @@ impl AppState {
                 // limine
                 boot_pkgs.insert("limine");
             }
+            4 => {
+                // refind
+                boot_pkgs.insert("refind");
+            }
             _ => {}
         }
         if boot_pkgs.contains(&n) {
@@ -8,6 +8,8 @@ use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 pub const EFISTUB_MENU_LABEL: &str = "Efistub (experimental)";
 /// Lowercase-style label for summary lines (install review, disk info).
 pub const EFISTUB_SLUG_LABEL: &str = "efistub (experimental)";
+/// Config `bootloader.kind` values, indexed like `bootloader_index`.
+pub const BOOTLOADER_KINDS: [&str; 5] = ["systemd-boot", "grub", "efistub", "limine", "refind"];
 
 impl AppState {
     #[allow(dead_code)]
@@ -21,7 +23,8 @@ impl AppState {
             0 => "Systemd-boot",
             1 => "Grub",
             2 => EFISTUB_MENU_LABEL,
-            _ => "Limine",
+            3 => "Limine",
+            _ => "rEFInd",
         }
     }
 }
@@ -36,7 +39,13 @@ pub fn draw_bootloader(frame: &mut ratatui::Frame, app: &mut AppState, area: Rec
 
     let mut lines: Vec<Line> = vec![Line::from(title), Line::from("")];
 
-    let choices = ["Systemd-boot", "Grub", EFISTUB_MENU_LABEL, "Limine"];
+    let choices = [
+        "Systemd-boot",
+        "Grub",
+        EFISTUB_MENU_LABEL,
+        "Limine",
+        "rEFInd",
+    ];
 
     for (idx, label) in choices.iter().enumerate() {
         let is_focused_line = app.bootloader_focus_index == idx;
@@ -65,7 +74,48 @@ pub fn draw_bootloader(frame: &mut ratatui::Frame, app: &mut AppState, area: Rec
         lines.push(line);
     }
 
-    let continue_style = if app.bootloader_focus_index == 4 && matches!(app.focus, Focus::Content) {
+    lines.push(Line::from(""));
+    let signing_rows = [
+        (
+            5,
+            format!("Secure Boot signing: {}", app.secure_boot_signing().label()),
+        ),
+        (
+            6,
+            format!(
+                "Keep Microsoft keys: {}",
+                if app.secure_boot_microsoft_keys {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            ),
+        ),
+    ];
+    for (idx, label) in signing_rows {
+        let is_focused_line = app.bootloader_focus_index == idx;
+        let is_active_line = is_focused_line && matches!(app.focus, Focus::Content);
+        let bullet = if is_focused_line { "▶" } else { " " };
+        let style = if is_active_line {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{bullet} "), style),
+            Span::styled(label, style),
+        ]));
+    }
+    if let Some(warning) = app.secure_boot_signing_warning() {
+        lines.push(Line::from(Span::styled(
+            warning,
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let continue_style = if app.bootloader_focus_index == 7 && matches!(app.focus, Focus::Content) {
         Style::default()
             .fg(Color::Yellow)
             .add_modifier(Modifier::BOLD)
@@ -9,6 +9,12 @@ use crate::app::{
 };
 
 use super::types::*;
+use crate::app::install::hooks::{self, HookTarget, InstallHook};
+use crate::app::secure_boot::SecureBootSigning;
+use crate::core::storage::{
+    LUKS_INTEGRITY, LUKS_PBKDFS, LUKS_SECTOR_SIZES, LvmStacking, RaidLevel, SwapEncryption,
+    SwapMode, ZRAM_ALGORITHMS,
+};
 
 #[derive(Debug)]
 pub enum ConfigLoadError {
@@ -113,6 +119,30 @@ impl AppState {
                 }
                 .into(),
             ),
+            root_filesystem: Some(
+                crate::core::storage::RootFilesystem::from_index(self.disks_root_fs_index)
+                    .name()
+                    .into(),
+            ),
+            lvm: self.lvm_enabled.then(|| ConfigLvm {
+                volume_group: self.lvm_vg_name.clone(),
+                stacking: Some(self.lvm_stacking().name().into()),
+                volumes: self
+                    .lvm_volumes
+                    .iter()
+                    .map(|lv| ConfigLogicalVolume {
+                        name: lv.name.clone(),
+                        size: lv.size.clone(),
+                        fs: lv.fs.clone(),
+                        mountpoint: lv.mountpoint.clone(),
+                    })
+                    .collect(),
+            }),
+            raid: self.raid_enabled.then(|| ConfigRaid {
+                level: Some(self.raid_level().name().into()),
+                devices: self.raid_devices.clone(),
+            }),
+            install_alongside: self.alongside_enabled.then_some(true),
         };
         let encryption_type = match self.disk_encryption_type_index {
             0 => "None",
@@ -132,17 +162,53 @@ impl AppState {
             encryption_type,
             partition: self.disk_encryption_selected_partition.clone(),
             password_hash,
+            tpm2: self.disk_encryption_tpm2,
+            tpm2_pcrs: self
+                .disk_encryption_tpm2
+                .then(|| self.disk_encryption_tpm2_pcrs.clone()),
+            fido2: self.disk_encryption_fido2,
+            recovery_key: self.disk_encryption_recovery_key,
+            cipher: Some(self.luks_cipher.clone()).filter(|s| !s.is_empty()),
+            key_size: self.luks_key_size.parse().ok(),
+            hash: Some(self.luks_hash.clone()).filter(|s| !s.is_empty()),
+            pbkdf: self
+                .luks_pbkdf_index
+                .checked_sub(1)
+                .and_then(|i| LUKS_PBKDFS.get(i))
+                .map(|p| p.to_string()),
+            pbkdf_memory: self.luks_pbkdf_memory.parse().ok(),
+            pbkdf_time: self.luks_pbkdf_time.parse().ok(),
+            sector_size: self
+                .luks_sector_size_index
+                .checked_sub(1)
+                .and_then(|i| LUKS_SECTOR_SIZES.get(i))
+                .copied(),
+            label: Some(self.luks_label.clone()).filter(|s| !s.is_empty()),
+            integrity: self
+                .luks_integrity_index
+                .checked_sub(1)
+                .and_then(|i| LUKS_INTEGRITY.get(i))
+                .map(|i| i.to_string()),
         };
         let swap = ConfigSwap {
-            enabled: self.swap_enabled,
+            enabled: self.swap_mode() != SwapMode::None,
+            mode: Some(self.swap_mode().name().into()),
+            swapfile_size: Some(self.swapfile_size.clone()),
+            zram_size: Some(self.zram_size.clone()),
+            zram_algorithm: Some(self.zram_algorithm().into()),
+            encryption: Some(self.swap_encryption().name().into()),
         };
         let bootloader = ConfigBootloader {
             kind: match self.bootloader_index {
                 0 => "systemd-boot".into(),
                 1 => "grub".into(),
                 2 => "efistub".into(),
-                _ => "limine".into(),
+                3 => "limine".into(),
+                _ => "refind".into(),
             },
+            signing: Some(self.secure_boot_signing().name().into()),
+            microsoft_keys: (self.secure_boot_signing() == SecureBootSigning::Sbctl)
+                .then_some(self.secure_boot_microsoft_keys),
         };
         let system = ConfigSystem {
             hostname: self.hostname_value.clone(),
@@ -260,6 +326,8 @@ impl AppState {
                 v.sort();
                 v
             },
+            default: self.default_kernel.is_some().then(|| self.default_kernel()),
+            parameters: self.extra_kernel_parameters(),
         };
         let audio = ConfigAudio {
             kind: match self.audio_index {
@@ -301,6 +369,16 @@ impl AppState {
                 },
             },
             additional_packages,
+            hooks: {
+                let mut hooks: BTreeMap<String, Vec<ConfigHook>> = BTreeMap::new();
+                for h in &self.install_hooks {
+                    hooks.entry(h.key()).or_default().push(ConfigHook {
+                        run: h.run.clone(),
+                        target: h.target.name().into(),
+                    });
+                }
+                hooks
+            },
         }
     }
 
@@ -326,7 +404,8 @@ impl AppState {
             0 => "systemd-boot",
             1 => "grub",
             2 => "efistub",
-            _ => "limine",
+            3 => "limine",
+            _ => "refind",
         };
         let has_root_hash = self.root_password_hash.is_some()
             || (!self.root_password.is_empty()
@@ -357,15 +436,41 @@ impl AppState {
         }
     }
 
+    /// The effective config as TOML with every password hash stripped.
+    pub fn redacted_config_toml(&self) -> Result<String, String> {
+        let mut cfg = self.build_config();
+        cfg.disk_encryption.password_hash = None;
+        cfg.system.root_password_hash = None;
+        for user in cfg.users.iter_mut() {
+            user.password_hash.clear();
+        }
+        toml::to_string_pretty(&cfg).map_err(|e| e.to_string())
+    }
+
     pub fn load_config(&mut self) -> Result<(), ConfigLoadError> {
         self.load_config_from_path(&Self::config_path())
     }
 
     pub fn load_config_from_path(&mut self, path: &std::path::Path) -> Result<(), ConfigLoadError> {
-        // Ensure option lists are available before mapping names back to indices
-        let _ = self.load_locales_options();
-        let _ = self.load_mirrors_options();
+        self.load_config_with_disks(path, false)
+    }
 
+    /// Load a config for `--unattended` installs.
+    ///
+    /// Unlike [`AppState::load_config_from_path`], the target device and explicit partitions
+    /// from the config are kept: there is no interactive step to re-confirm them.
+    pub fn load_config_unattended(
+        &mut self,
+        path: &std::path::Path,
+    ) -> Result<(), ConfigLoadError> {
+        self.load_config_with_disks(path, true)
+    }
+
+    fn load_config_with_disks(
+        &mut self,
+        path: &std::path::Path,
+        keep_disk_layout: bool,
+    ) -> Result<(), ConfigLoadError> {
         self.debug_log(&format!("load_config: path={}", path.display()));
         let text = std::fs::read_to_string(path).map_err(|e| {
             self.debug_log(&format!("load_config: read error: {e}"));
@@ -376,6 +481,16 @@ impl AppState {
             ConfigLoadError::ParseToml
         })?;
         self.debug_log("load_config: parse ok");
+        self.apply_config(cfg, keep_disk_layout);
+        Ok(())
+    }
+
+    /// Map a parsed config onto the state (names back to option indices).
+    pub(crate) fn apply_config(&mut self, cfg: AppConfig, keep_disk_layout: bool) {
+        // Ensure option lists are available before mapping names back to indices
+        let _ = self.load_locales_options();
+        let _ = self.load_mirrors_options();
+
         self.last_load_missing_sections.clear();
 
         // Locales
@@ -438,7 +553,11 @@ impl AppState {
                 .push("Mirrors: regions".into());
         }
         for name in cfg.mirrors.regions {
-            if let Some(idx) = self.mirrors_regions_options.iter().position(|s| s == &name) {
+            if let Some(idx) = self
+                .mirrors_regions_options
+                .iter()
+                .position(|s| crate::app::mirrors::mirror_region_matches(s, &name))
+            {
                 self.mirrors_regions_selected.insert(idx);
             }
         }
@@ -560,6 +679,48 @@ impl AppState {
             Some("extended") => 2,
             _ => 0,
         };
+        self.disks_root_fs_index = cfg
+            .disks
+            .root_filesystem
+            .as_deref()
+            .and_then(crate::core::storage::RootFilesystem::from_name)
+            .map_or(0, |fs| fs as usize);
+        self.lvm_enabled = cfg.disks.lvm.is_some();
+        if let Some(lvm) = cfg.disks.lvm.clone() {
+            if !lvm.volume_group.is_empty() {
+                self.lvm_vg_name = lvm.volume_group;
+            }
+            self.lvm_stacking_index = lvm
+                .stacking
+                .as_deref()
+                .and_then(LvmStacking::from_name)
+                .map_or(0, |s| s as usize);
+            self.lvm_volumes = lvm
+                .volumes
+                .into_iter()
+                .map(|lv| crate::app::LogicalVolumeSpec {
+                    name: lv.name,
+                    size: lv.size,
+                    fs: lv.fs,
+                    mountpoint: lv.mountpoint,
+                })
+                .collect();
+        }
+        self.alongside_enabled = cfg.disks.install_alongside.unwrap_or(false);
+        self.raid_enabled = cfg.disks.raid.is_some();
+        self.raid_level_index = cfg
+            .disks
+            .raid
+            .as_ref()
+            .and_then(|r| r.level.as_deref())
+            .and_then(RaidLevel::from_name)
+            .map_or(1, |l| l as usize);
+        self.raid_devices = cfg
+            .disks
+            .raid
+            .clone()
+            .map(|r| r.devices)
+            .unwrap_or_default();
         self.disks_partitions = cfg
             .disks
             .partitions
@@ -576,12 +737,20 @@ impl AppState {
                 encrypt: p.encrypt,
             })
             .collect();
-        // Safety: require the user to re-confirm partitioning on every load.
-        // Ignore device selection and partitions from the config; force a fresh setup.
-        self.disks_selected_device = None;
-        self.disks_partitions.clear();
-        self.last_load_missing_sections
-            .push("Disks: reconfigure partitioning (safety)".into());
+        if keep_disk_layout {
+            // Unattended: trust the config verbatim; the device is checked before installing.
+            self.disks_selected_device = cfg.disks.selected_device.clone();
+            self.last_load_missing_sections
+                .retain(|s| s != "Disks: selected_device");
+        } else {
+            // Safety: require the user to re-confirm partitioning on every load.
+            // Ignore device selection and partitions from the config; force a fresh setup.
+            self.disks_selected_device = None;
+            self.disks_partitions.clear();
+            self.raid_devices.clear();
+            self.last_load_missing_sections
+                .push("Disks: reconfigure partitioning (safety)".into());
+        }
 
         // Disk encryption
         if cfg.disk_encryption.encryption_type.is_empty() {
@@ -602,9 +771,96 @@ impl AppState {
                 .retain(|s| s != "DiskEncryption: partition");
         }
         // do not load plaintext password; hash in config is not converted back
+        self.disk_encryption_tpm2 = cfg.disk_encryption.tpm2;
+        if let Some(pcrs) = cfg
+            .disk_encryption
+            .tpm2_pcrs
+            .filter(|s| !s.trim().is_empty())
+        {
+            self.disk_encryption_tpm2_pcrs = pcrs.trim().into();
+        }
+        self.disk_encryption_fido2 = cfg.disk_encryption.fido2;
+        self.disk_encryption_recovery_key = cfg.disk_encryption.recovery_key;
+        self.luks_cipher = cfg
+            .disk_encryption
+            .cipher
+            .as_deref()
+            .unwrap_or("")
+            .trim()
+            .into();
+        self.luks_key_size = cfg
+            .disk_encryption
+            .key_size
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        self.luks_hash = cfg
+            .disk_encryption
+            .hash
+            .as_deref()
+            .unwrap_or("")
+            .trim()
+            .into();
+        self.luks_pbkdf_index = cfg
+            .disk_encryption
+            .pbkdf
+            .as_deref()
+            .and_then(|p| {
+                LUKS_PBKDFS
+                    .iter()
+                    .position(|n| n.eq_ignore_ascii_case(p.trim()))
+            })
+            .map_or(0, |i| i + 1);
+        self.luks_pbkdf_memory = cfg
+            .disk_encryption
+            .pbkdf_memory
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        self.luks_pbkdf_time = cfg
+            .disk_encryption
+            .pbkdf_time
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        self.luks_sector_size_index = cfg
+            .disk_encryption
+            .sector_size
+            .and_then(|s| LUKS_SECTOR_SIZES.iter().position(|n| *n == s))
+            .map_or(0, |i| i + 1);
+        self.luks_label = cfg.disk_encryption.label.clone().unwrap_or_default();
+        self.luks_integrity_index = cfg
+            .disk_encryption
+            .integrity
+            .as_deref()
+            .and_then(|i| {
+                LUKS_INTEGRITY
+                    .iter()
+                    .position(|n| n.eq_ignore_ascii_case(i.trim()))
+            })
+            .map_or(0, |i| i + 1);
 
         // Swap
-        self.swap_enabled = cfg.swap.enabled;
+        self.swap_mode_index = match cfg.swap.mode.as_deref().and_then(SwapMode::from_name) {
+            Some(mode) => mode as usize,
+            None if cfg.swap.enabled => SwapMode::Partition as usize,
+            None => SwapMode::None as usize,
+        };
+        if let Some(size) = cfg.swap.swapfile_size.filter(|s| !s.trim().is_empty()) {
+            self.swapfile_size = size.trim().into();
+        }
+        if let Some(size) = cfg.swap.zram_size.filter(|s| !s.trim().is_empty()) {
+            self.zram_size = size.trim().into();
+        }
+        if let Some(alg) = cfg.swap.zram_algorithm.as_deref() {
+            self.zram_algorithm_index = ZRAM_ALGORITHMS
+                .iter()
+                .position(|a| a.eq_ignore_ascii_case(alg.trim()))
+                .unwrap_or(0);
+        }
+        self.swap_encryption_index = cfg
+            .swap
+            .encryption
+            .as_deref()
+            .and_then(SwapEncryption::from_name)
+            .unwrap_or(SwapEncryption::None) as usize;
 
         // Bootloader
         if cfg.bootloader.kind.is_empty() {
@@ -619,8 +875,16 @@ impl AppState {
             "systemd-boot" => 0,
             "grub" => 1,
             "efistub" => 2,
+            "refind" => 4,
             _ => 3,
         };
+        self.secure_boot_signing_index = cfg
+            .bootloader
+            .signing
+            .as_deref()
+            .and_then(SecureBootSigning::from_name)
+            .unwrap_or(SecureBootSigning::None) as usize;
+        self.secure_boot_microsoft_keys = cfg.bootloader.microsoft_keys.unwrap_or(true);
 
         // System
         if cfg.system.hostname.is_empty() {
@@ -652,6 +916,8 @@ impl AppState {
 
         // Kernels
         self.selected_kernels = cfg.kernels.selected.into_iter().collect();
+        self.default_kernel = cfg.kernels.default;
+        self.kernel_parameters = cfg.kernels.parameters.unwrap_or_default();
         if self.selected_kernels.is_empty() {
             self.last_load_missing_sections
                 .push("Kernels: selected".into());
@@ -743,6 +1009,43 @@ impl AppState {
             })
             .collect();
 
+        // Hooks (invalid keys/targets and pre-pacstrap chroot hooks are reported by `validate`)
+        self.install_hooks.clear();
+        for (key, hooks) in cfg.hooks {
+            let (when, section) = match hooks::parse_hook_key(&key) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    self.debug_log(&format!("load_config: skipping hooks: {e}"));
+                    continue;
+                }
+            };
+            for h in hooks {
+                let Some(target) = HookTarget::parse(&h.target) else {
+                    self.debug_log(&format!(
+                        "load_config: skipping hook '{key}': unknown target '{}'",
+                        h.target
+                    ));
+                    continue;
+                };
+                if h.run.trim().is_empty() {
+                    continue;
+                }
+                if target == HookTarget::Chroot && !hooks::chroot_available(when, section) {
+                    self.debug_log(&format!(
+                        "load_config: skipping hook: {}",
+                        hooks::chroot_unavailable_message(&key)
+                    ));
+                    continue;
+                }
+                self.install_hooks.push(InstallHook {
+                    section: section.to_string(),
+                    when,
+                    target,
+                    run: h.run,
+                });
+            }
+        }
+
         // Final summary
         self.debug_log(&format!(
             "load_config: summary regions={} repos={} custom_servers={} custom_repos={} users={} addpkgs={} missing_sections={}",
@@ -760,7 +1063,5 @@ impl AppState {
                 self.last_load_missing_sections.join(", ")
             ));
         }
-
-        Ok(())
     }
 }
@@ -1,6 +1,7 @@
 pub mod io;
 pub mod presets;
 pub mod types;
+pub mod validate;
 pub mod view;
 
 pub use view::draw_configuration;
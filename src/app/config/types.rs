@@ -44,6 +44,34 @@ pub struct ConfigDisks {
     pub align: Option<String>, // e.g. "1MiB"
     pub partitions: Vec<ConfigPartition>,
     pub btrfs_subvolume_preset: Option<String>, // "flat", "standard", "extended"
+    pub root_filesystem: Option<String>,        // automatic layout: "btrfs", "zfs", "bcachefs"
+    pub lvm: Option<ConfigLvm>,                 // present when LVM is enabled
+    pub raid: Option<ConfigRaid>,               // present when software RAID is enabled
+    pub install_alongside: Option<bool>, // automatic layout in free space, keeping other OSes
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ConfigRaid {
+    pub level: Option<String>, // "raid0" | "raid1" | "raid5" | "raid6" | "raid10"
+    pub devices: Vec<String>,  // member disks, e.g. /dev/sda, /dev/sdb
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ConfigLvm {
+    pub volume_group: String,
+    pub stacking: Option<String>, // "lvm_on_luks" | "luks_on_lvm"
+    pub volumes: Vec<ConfigLogicalVolume>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ConfigLogicalVolume {
+    pub name: String,
+    pub size: String, // e.g. 40G, 100%FREE
+    pub fs: String,   // ext4, xfs, btrfs, f2fs, linux-swap
+    pub mountpoint: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -66,18 +94,39 @@ pub struct ConfigDiskEncryption {
     pub encryption_type: String,
     pub partition: Option<String>,
     pub password_hash: Option<String>,
+    pub tpm2: bool,
+    pub tpm2_pcrs: Option<String>, // systemd-cryptenroll --tpm2-pcrs, e.g. "7" or "0+7"
+    pub fido2: bool,
+    pub recovery_key: bool,
+    // luksFormat parameters; omitted ones keep the cryptsetup default
+    pub cipher: Option<String>,    // e.g. "aes-xts-plain64"
+    pub key_size: Option<u32>,     // bits
+    pub hash: Option<String>,      // e.g. "sha512"
+    pub pbkdf: Option<String>,     // "argon2id", "argon2i", "pbkdf2"
+    pub pbkdf_memory: Option<u32>, // KiB (argon2 only)
+    pub pbkdf_time: Option<u32>,   // ms
+    pub sector_size: Option<u32>,  // 512, 1024, 2048, 4096
+    pub label: Option<String>,
+    pub integrity: Option<String>, // "hmac-sha256", "hmac-sha512"
 }
 
 #[derive(Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ConfigSwap {
-    pub enabled: bool,
+    pub enabled: bool,                  // older configs: true means a swap partition
+    pub mode: Option<String>,           // "partition", "swapfile", "zram", "none"
+    pub swapfile_size: Option<String>,  // e.g. "4G"
+    pub zram_size: Option<String>,      // MiB or formula, e.g. "ram / 2"
+    pub zram_algorithm: Option<String>, // "zstd", "lz4", "lzo-rle", "lzo", "842"
+    pub encryption: Option<String>,     // "none", "random", "keyfile" (swap partitions)
 }
 
 #[derive(Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ConfigBootloader {
     pub kind: String,
+    pub signing: Option<String>, // Secure Boot signing: "none" | "sbctl" | "shim"
+    pub microsoft_keys: Option<bool>, // keep Microsoft keys when enrolling sbctl keys
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -139,6 +188,9 @@ pub struct AppConfig {
     pub users: Vec<ConfigUser>,
     pub network: ConfigNetwork,
     pub additional_packages: Vec<ConfigAdditionalPackage>,
+    /// Hook lists keyed by `"before <section>"` / `"after <section>"`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hooks: BTreeMap<String, Vec<ConfigHook>>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -153,6 +205,8 @@ pub struct ConfigUser {
 #[serde(default)]
 pub struct ConfigKernels {
     pub selected: Vec<String>,
+    pub default: Option<String>, // default boot entry; first selected kernel when absent
+    pub parameters: Option<String>, // extra kernel cmdline options, e.g. "quiet splash"
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -163,3 +217,11 @@ pub struct ConfigAdditionalPackage {
     pub version: String,
     pub description: String,
 }
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ConfigHook {
+    pub run: String,
+    /// "host" or "chroot" (default)
+    pub target: String,
+}
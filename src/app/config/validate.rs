@@ -0,0 +1,726 @@
+//! Field-level config validation for `archinstall-rs validate <config.toml>`.
+//!
+//! Unlike loading, which maps unknown values to defaults and records a loose
+//! `last_load_missing_sections` list, this reports every problem with the TOML line/column
+//! and a field path such as `disks.partitions[2].size`.
+
+use std::fmt;
+use std::ops::Range;
+
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+
+use crate::app::AppState;
+use crate::app::install::hooks::{
+    HookTarget, chroot_available, chroot_unavailable_message, parse_hook_key,
+};
+use crate::app::secure_boot::SecureBootSigning;
+use crate::core::storage::planner::StoragePlanner;
+use crate::core::storage::{
+    LVM_FILESYSTEMS, LuksFormatOptions, LvmStacking, PartitionRole, RaidLevel, RootFilesystem,
+    SwapEncryption, SwapMode, ZRAM_ALGORITHMS, is_valid_swapfile_size, is_valid_tpm2_pcrs,
+    is_valid_zram_size,
+};
+
+use super::types::AppConfig;
+
+const DISK_MODES: [&str; 3] = [
+    "Best-effort partition layout",
+    "Manual Partitioning",
+    "Pre-mounted configuration",
+];
+const ENCRYPTION_TYPES: [&str; 2] = ["None", "LUKS"];
+const BTRFS_PRESETS: [&str; 3] = ["flat", "standard", "extended"];
+const AUDIO_KINDS: [&str; 3] = ["None", "pipewire", "pulseaudio"];
+const NETWORK_MODES: [&str; 3] = ["CopyISO", "Manual", "NetworkManager"];
+const EXPERIENCE_MODES: [&str; 4] = ["Desktop", "Minimal", "Server", "Xorg"];
+const PARTITION_FILESYSTEMS: [&str; 13] = [
+    "bcachefs",
+    "btrfs",
+    "ext2",
+    "ext3",
+    "ext4",
+    "f2fs",
+    "fat12",
+    "fat16",
+    "fat32",
+    "vfat",
+    "ntfs",
+    "xfs",
+    "linux-swap",
+];
+const SIZE_UNITS: [&str; 9] = ["KiB", "MiB", "GiB", "TiB", "KB", "MB", "GB", "TB", "B"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// One problem found in a config file. `line` and `column` are 1-based.
+#[derive(Clone, Debug)]
+pub struct ConfigDiagnostic {
+    pub severity: Severity,
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "<document>"
+        } else {
+            &self.path
+        };
+        write!(
+            f,
+            "{}:{}: {}: {}: {}",
+            self.line, self.column, self.severity, path, self.message
+        )
+    }
+}
+
+enum PathSeg<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+fn split_path(path: &str) -> Vec<PathSeg<'_>> {
+    let mut segs = Vec::new();
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        let (key, mut rest) = part.split_once('[').map_or((part, ""), |(k, r)| (k, r));
+        segs.push(PathSeg::Key(key));
+        while let Some((idx, tail)) = rest.split_once(']') {
+            if let Ok(i) = idx.parse() {
+                segs.push(PathSeg::Index(i));
+            }
+            rest = tail.strip_prefix('[').unwrap_or(tail);
+        }
+    }
+    segs
+}
+
+fn table_get<'a, 'i>(table: &'a DeTable<'i>, key: &str) -> Option<&'a Spanned<DeValue<'i>>> {
+    table
+        .iter()
+        .find(|(k, _)| k.get_ref().as_ref() == key)
+        .map(|(_, v)| v)
+}
+
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+struct Collector<'t, 'i> {
+    text: &'t str,
+    root: Option<&'t Spanned<DeTable<'i>>>,
+    items: Vec<ConfigDiagnostic>,
+}
+
+impl<'t, 'i> Collector<'t, 'i> {
+    /// Span of the deepest existing value along `path` (missing fields point at their table).
+    fn locate(&self, path: &str) -> Range<usize> {
+        let Some(root) = self.root else {
+            return 0..0;
+        };
+        let mut span = root.span();
+        let mut table: Option<&DeTable<'i>> = Some(root.get_ref());
+        let mut array: Option<&[Spanned<DeValue<'i>>]> = None;
+        for seg in split_path(path) {
+            let next = match seg {
+                PathSeg::Key(k) => table.and_then(|t| table_get(t, k)),
+                PathSeg::Index(i) => array.and_then(|a| a.get(i)),
+            };
+            let Some(value) = next else {
+                break;
+            };
+            span = value.span();
+            table = value.get_ref().as_table();
+            array = value.get_ref().as_array().map(|a| &a[..]);
+        }
+        span
+    }
+
+    /// Field path of the innermost value containing `offset`.
+    fn path_at(&self, offset: usize) -> String {
+        // Table header spans do not cover their body, so search every entry
+        // depth-first instead of only descending into containing spans.
+        fn search_table(table: &DeTable<'_>, offset: usize, path: &str) -> Option<String> {
+            for (k, v) in table.iter() {
+                let child = if path.is_empty() {
+                    k.get_ref().to_string()
+                } else {
+                    format!("{path}.{}", k.get_ref())
+                };
+                if let Some(found) = search(v, offset, &child) {
+                    return Some(found);
+                }
+                if k.span().contains(&offset) {
+                    return Some(child);
+                }
+            }
+            None
+        }
+        fn search(value: &Spanned<DeValue<'_>>, offset: usize, path: &str) -> Option<String> {
+            let nested = match value.get_ref() {
+                DeValue::Table(t) => search_table(t, offset, path),
+                DeValue::Array(arr) => arr
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, v)| search(v, offset, &format!("{path}[{i}]"))),
+                _ => None,
+            };
+            nested.or_else(|| value.span().contains(&offset).then(|| path.to_string()))
+        }
+        self.root
+            .and_then(|root| search_table(root.get_ref(), offset, ""))
+            .unwrap_or_default()
+    }
+
+    fn push_at(&mut self, severity: Severity, path: String, offset: usize, message: String) {
+        let (line, column) = line_col(self.text, offset);
+        self.items.push(ConfigDiagnostic {
+            severity,
+            path,
+            line,
+            column,
+            message,
+        });
+    }
+
+    fn push(&mut self, severity: Severity, path: impl Into<String>, message: impl Into<String>) {
+        let path = path.into();
+        let offset = self.locate(&path).start;
+        self.push_at(severity, path, offset, message.into());
+    }
+
+    /// Report `value` unless it is empty or one of `allowed` (case-sensitive, like loading).
+    fn check_one_of(&mut self, path: &str, what: &str, value: &str, allowed: &[&str]) {
+        if !value.is_empty() && !allowed.contains(&value) {
+            self.push(
+                Severity::Error,
+                path,
+                format!(
+                    "unknown {what} '{value}' (expected one of: {})",
+                    allowed.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Report `value` when `options` could be probed on this host and do not contain it.
+    /// `options` is the list probed from the host, empty when the host has none; `matches`
+    /// compares a listed option with the configured value.
+    fn check_probed(
+        &mut self,
+        path: &str,
+        what: &str,
+        value: &str,
+        options: &[String],
+        matches: impl Fn(&str, &str) -> bool,
+    ) {
+        if value.is_empty() {
+            return;
+        }
+        if options.is_empty() {
+            self.push(
+                Severity::Warning,
+                path,
+                format!("cannot verify {what} '{value}': no list available on this host"),
+            );
+        } else if !options.iter().any(|o| matches(o, value)) {
+            self.push(Severity::Error, path, format!("unknown {what} '{value}'"));
+        }
+    }
+}
+
+/// `100%`, a plain byte count, or a number followed by a parted unit (`512MiB`, `8GiB`, ...).
+fn partition_size_is_valid(size: &str) -> bool {
+    let s = size.trim();
+    if let Some(pct) = s.strip_suffix('%') {
+        return pct
+            .parse::<f64>()
+            .is_ok_and(|p| (0.0..=100.0).contains(&p) && p > 0.0);
+    }
+    if crate::core::storage::spec_size_is_byte_length(s) {
+        return true;
+    }
+    SIZE_UNITS.iter().any(|unit| {
+        s.strip_suffix(unit)
+            .is_some_and(|n| !n.is_empty() && n.trim().parse::<f64>().is_ok_and(|v| v >= 0.0))
+    })
+}
+
+impl AppState {
+    /// Validate config `text` and return every problem found (empty when the config is clean).
+    ///
+    /// The state is used to probe host option lists (keymaps, locales, mirrors, timezones) and
+    /// ends up with the config applied, so callers should pass a fresh dry-run state.
+    pub fn validate_config_text(&mut self, text: &str) -> Vec<ConfigDiagnostic> {
+        let root = match DeTable::parse(text) {
+            Ok(root) => root,
+            Err(e) => {
+                let mut c = Collector {
+                    text,
+                    root: None,
+                    items: Vec::new(),
+                };
+                let offset = e.span().map_or(0, |s| s.start);
+                c.push_at(
+                    Severity::Error,
+                    String::new(),
+                    offset,
+                    e.message().trim().to_string(),
+                );
+                return c.items;
+            }
+        };
+        let mut c = Collector {
+            text,
+            root: Some(&root),
+            items: Vec::new(),
+        };
+
+        let cfg: AppConfig = match toml::from_str(text) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                let offset = e.span().map_or(0, |s| s.start);
+                let path = c.path_at(offset);
+                c.push_at(
+                    Severity::Error,
+                    path,
+                    offset,
+                    e.message().trim().to_string(),
+                );
+                return c.items;
+            }
+        };
+
+        // Probe the host directly: in dry-run mode the loaders fill in placeholder lists,
+        // which would reject every value they do not happen to contain.
+        let keyboard_layouts = crate::app::locales::probe_keyboard_layouts().unwrap_or_default();
+        let locale_languages = crate::app::locales::probe_locale_gen()
+            .map(|(languages, _)| languages)
+            .unwrap_or_default();
+        let mirror_regions = crate::app::mirrors::probe_mirror_regions().unwrap_or_default();
+
+        // Locales
+        c.check_probed(
+            "locales.keyboard_layout",
+            "keyboard layout",
+            &cfg.locales.keyboard_layout,
+            &keyboard_layouts,
+            |o, v| o == v,
+        );
+        c.check_probed(
+            "locales.locale_language",
+            "locale",
+            &cfg.locales.locale_language,
+            &locale_languages,
+            |o, v| o == v,
+        );
+
+        // Mirrors
+        for (i, region) in cfg.mirrors.regions.iter().enumerate() {
+            c.check_probed(
+                &format!("mirrors.regions[{i}]"),
+                "mirror region",
+                region,
+                &mirror_regions,
+                crate::app::mirrors::mirror_region_matches,
+            );
+        }
+
+        // Disks
+        c.check_one_of("disks.mode", "disk mode", &cfg.disks.mode, &DISK_MODES);
+        if let Some(preset) = cfg.disks.btrfs_subvolume_preset.as_deref() {
+            c.check_one_of(
+                "disks.btrfs_subvolume_preset",
+                "btrfs subvolume preset",
+                preset,
+                &BTRFS_PRESETS,
+            );
+        }
+        if let Some(fs) = cfg.disks.root_filesystem.as_deref() {
+            c.check_one_of(
+                "disks.root_filesystem",
+                "root filesystem",
+                fs,
+                &RootFilesystem::NAMES,
+            );
+        }
+        if let Some(lvm) = cfg.disks.lvm.as_ref() {
+            if let Some(stacking) = lvm.stacking.as_deref() {
+                c.check_one_of(
+                    "disks.lvm.stacking",
+                    "LVM stacking",
+                    stacking,
+                    &LvmStacking::NAMES,
+                );
+            }
+            for (i, lv) in lvm.volumes.iter().enumerate() {
+                c.check_one_of(
+                    &format!("disks.lvm.volumes[{i}].fs"),
+                    "logical volume filesystem",
+                    &lv.fs,
+                    &LVM_FILESYSTEMS,
+                );
+            }
+        }
+        if let Some(level) = cfg.disks.raid.as_ref().and_then(|r| r.level.as_deref()) {
+            c.check_one_of("disks.raid.level", "RAID level", level, &RaidLevel::NAMES);
+        }
+        for (i, p) in cfg.disks.partitions.iter().enumerate() {
+            let base = format!("disks.partitions[{i}]");
+            if let Some(role) = p.role.as_deref()
+                && !role.is_empty()
+                && !PartitionRole::KNOWN_NAMES
+                    .iter()
+                    .any(|k| k.eq_ignore_ascii_case(role))
+            {
+                c.push(
+                    Severity::Error,
+                    format!("{base}.role"),
+                    format!(
+                        "unknown partition role '{role}' (expected one of: {})",
+                        PartitionRole::KNOWN_NAMES.join(", ")
+                    ),
+                );
+            }
+            if let Some(fs) = p.fs.as_deref() {
+                c.check_one_of(
+                    &format!("{base}.fs"),
+                    "filesystem",
+                    fs,
+                    &PARTITION_FILESYSTEMS,
+                );
+            }
+            if let Some(size) = p.size.as_deref()
+                && !size.is_empty()
+                && !partition_size_is_valid(size)
+            {
+                c.push(
+                    Severity::Error,
+                    format!("{base}.size"),
+                    format!("invalid size '{size}' (use e.g. 512MiB, 8GiB, 100% or a byte count)"),
+                );
+            }
+            if let Some(start) = p.start.as_deref()
+                && !start.is_empty()
+                && !partition_size_is_valid(start)
+            {
+                c.push(
+                    Severity::Error,
+                    format!("{base}.start"),
+                    format!("invalid start '{start}' (use e.g. 1MiB or a byte offset)"),
+                );
+            }
+        }
+
+        // Encryption, bootloader, system
+        c.check_one_of(
+            "disk_encryption.encryption_type",
+            "encryption type",
+            &cfg.disk_encryption.encryption_type,
+            &ENCRYPTION_TYPES,
+        );
+        if let Some(pcrs) = cfg.disk_encryption.tpm2_pcrs.as_deref()
+            && !is_valid_tpm2_pcrs(pcrs.trim())
+        {
+            c.push(
+                Severity::Error,
+                "disk_encryption.tpm2_pcrs",
+                format!("invalid TPM2 PCRs '{pcrs}' (expected e.g. 7 or 0+7)"),
+            );
+        }
+        let enc = &cfg.disk_encryption;
+        let luks_format = LuksFormatOptions {
+            cipher: enc.cipher.clone(),
+            key_size: enc.key_size,
+            hash: enc.hash.clone(),
+            pbkdf: enc.pbkdf.clone(),
+            pbkdf_memory: enc.pbkdf_memory,
+            pbkdf_time: enc.pbkdf_time,
+            sector_size: enc.sector_size,
+            label: enc.label.clone(),
+            integrity: enc.integrity.clone(),
+        };
+        for (field, message) in luks_format.problems() {
+            c.push(Severity::Error, format!("disk_encryption.{field}"), message);
+        }
+        let enroll = cfg.disk_encryption.tpm2
+            || cfg.disk_encryption.fido2
+            || cfg.disk_encryption.recovery_key;
+        if enroll
+            && cfg.disk_encryption.encryption_type != "LUKS"
+            && cfg.disks.mode == DISK_MODES[0]
+        {
+            c.push(
+                Severity::Warning,
+                "disk_encryption",
+                "TPM2/FIDO2/recovery key enrollment has no effect without LUKS encryption",
+            );
+        }
+        c.check_one_of(
+            "bootloader.kind",
+            "bootloader kind",
+            &cfg.bootloader.kind,
+            &crate::app::bootloader::BOOTLOADER_KINDS,
+        );
+        if let Some(signing) = cfg.bootloader.signing.as_deref() {
+            c.check_one_of(
+                "bootloader.signing",
+                "Secure Boot signing",
+                signing,
+                &SecureBootSigning::NAMES,
+            );
+            if signing.eq_ignore_ascii_case("sbctl") && cfg.bootloader.kind == "grub" {
+                c.push(
+                    Severity::Error,
+                    "bootloader.signing",
+                    "sbctl signing needs systemd-boot, EFISTUB, Limine or rEFInd; GRUB loads unsigned modules (use \"shim\")",
+                );
+            }
+            if signing.eq_ignore_ascii_case("shim")
+                && !matches!(cfg.bootloader.kind.as_str(), "systemd-boot" | "grub")
+            {
+                c.push(
+                    Severity::Error,
+                    "bootloader.signing",
+                    "shim signing supports GRUB and systemd-boot only",
+                );
+            }
+            if cfg.disk_encryption.tpm2
+                && SecureBootSigning::from_name(signing)
+                    .is_some_and(|s| s != SecureBootSigning::None)
+            {
+                c.push(
+                    Severity::Error,
+                    "disk_encryption.tpm2",
+                    format!(
+                        "TPM2 unlock cannot be combined with {} signing: the key is sealed against PCR 7 before the signing setup changes the Secure Boot state, so it would never unseal; enroll TPM2 after the first boot",
+                        signing.trim().to_ascii_lowercase()
+                    ),
+                );
+            }
+        }
+        if !cfg.system.timezone.is_empty() {
+            let zones = Self::list_timezones();
+            let tz = cfg.system.timezone.as_str();
+            let zoneinfo = std::path::Path::new("/usr/share/zoneinfo");
+            if !zones.is_empty() {
+                if !zones.iter().any(|z| z == tz) {
+                    c.push(
+                        Severity::Error,
+                        "system.timezone",
+                        format!("unknown timezone '{tz}'"),
+                    );
+                }
+            } else if zoneinfo.is_dir() {
+                if !zoneinfo.join(tz).is_file() {
+                    c.push(
+                        Severity::Error,
+                        "system.timezone",
+                        format!("unknown timezone '{tz}'"),
+                    );
+                }
+            } else {
+                c.push(
+                    Severity::Warning,
+                    "system.timezone",
+                    format!("cannot verify timezone '{tz}': no timezone list on this host"),
+                );
+            }
+        }
+
+        // Swap
+        if let Some(mode) = cfg.swap.mode.as_deref() {
+            c.check_one_of("swap.mode", "swap mode", mode, &SwapMode::NAMES);
+        }
+        if let Some(encryption) = cfg.swap.encryption.as_deref() {
+            c.check_one_of(
+                "swap.encryption",
+                "swap encryption",
+                encryption,
+                &SwapEncryption::NAMES,
+            );
+            let partition = cfg
+                .swap
+                .mode
+                .as_deref()
+                .map_or(cfg.swap.enabled, |m| m.eq_ignore_ascii_case("partition"));
+            if SwapEncryption::from_name(encryption).is_some_and(|e| e != SwapEncryption::None)
+                && (!partition
+                    || (cfg.disk_encryption.encryption_type != "LUKS"
+                        && cfg.disks.mode == DISK_MODES[0]))
+            {
+                c.push(
+                    Severity::Warning,
+                    "swap.encryption",
+                    "swap encryption only applies to a swap partition under an encrypted root",
+                );
+            }
+        }
+        if let Some(size) = cfg.swap.swapfile_size.as_deref()
+            && !is_valid_swapfile_size(size.trim())
+        {
+            c.push(
+                Severity::Error,
+                "swap.swapfile_size",
+                format!("invalid swapfile size '{size}' (expected e.g. 4G or 512M)"),
+            );
+        }
+        if let Some(size) = cfg.swap.zram_size.as_deref()
+            && !is_valid_zram_size(size)
+        {
+            c.push(
+                Severity::Error,
+                "swap.zram_size",
+                format!("invalid zram size '{size}' (expected MiB or a formula such as ram / 2)"),
+            );
+        }
+        if let Some(alg) = cfg.swap.zram_algorithm.as_deref() {
+            c.check_one_of(
+                "swap.zram_algorithm",
+                "zram compression algorithm",
+                alg,
+                &ZRAM_ALGORITHMS,
+            );
+        }
+
+        // Kernels, audio, experience, network
+        for (i, kernel) in cfg.kernels.selected.iter().enumerate() {
+            c.check_one_of(
+                &format!("kernels.selected[{i}]"),
+                "kernel",
+                kernel,
+                &crate::app::kernels::KERNEL_PACKAGES,
+            );
+        }
+        if let Some(default) = &cfg.kernels.default
+            && !cfg.kernels.selected.contains(default)
+        {
+            c.push(
+                Severity::Error,
+                "kernels.default",
+                format!("default kernel '{default}' is not in kernels.selected"),
+            );
+        }
+        if let Some(reason) = cfg
+            .kernels
+            .parameters
+            .as_deref()
+            .and_then(crate::app::kernels::kernel_parameters_error)
+        {
+            c.push(Severity::Error, "kernels.parameters", reason);
+        }
+        c.check_one_of("audio.kind", "audio server", &cfg.audio.kind, &AUDIO_KINDS);
+        c.check_one_of(
+            "experience.mode",
+            "experience mode",
+            &cfg.experience.mode,
+            &EXPERIENCE_MODES,
+        );
+        c.check_one_of(
+            "network.mode",
+            "network mode",
+            &cfg.network.mode,
+            &NETWORK_MODES,
+        );
+
+        // Hooks
+        for (key, hooks) in &cfg.hooks {
+            let base = format!("hooks.{key}");
+            let parsed = parse_hook_key(key);
+            if let Err(e) = &parsed {
+                c.push(Severity::Error, base.clone(), e.clone());
+            }
+            for (i, h) in hooks.iter().enumerate() {
+                let target = HookTarget::parse(&h.target);
+                if let (Ok((when, section)), Some(HookTarget::Chroot)) = (&parsed, target)
+                    && !chroot_available(*when, section)
+                {
+                    c.push(
+                        Severity::Error,
+                        format!("{base}[{i}].target"),
+                        chroot_unavailable_message(key.trim()),
+                    );
+                }
+                if target.is_none() {
+                    c.push(
+                        Severity::Error,
+                        format!("{base}[{i}].target"),
+                        format!(
+                            "unknown hook target '{}' (expected one of: {})",
+                            h.target,
+                            HookTarget::NAMES.join(", ")
+                        ),
+                    );
+                }
+                if h.run.trim().is_empty() {
+                    c.push(
+                        Severity::Error,
+                        format!("{base}[{i}].run"),
+                        "hook command is empty",
+                    );
+                }
+            }
+        }
+
+        // Storage plan (compiled exactly like the installer would)
+        let pre_mounted = cfg.disks.mode == DISK_MODES[2];
+        self.apply_config(cfg, true);
+        if pre_mounted {
+            c.push(
+                Severity::Warning,
+                "disks.mode",
+                "pre-mounted layouts are probed from /mnt at install time; storage not checked",
+            );
+        } else if let Err(errors) = StoragePlanner::compile(self) {
+            for e in errors {
+                let path = e.field.unwrap_or_else(|| "disks".into());
+                c.push(Severity::Error, path, e.message);
+            }
+        } else if !self.install_hooks.is_empty() {
+            let target = self.disks_selected_device.clone().unwrap_or_default();
+            let titles: Vec<String> = self
+                .build_install_sections(&target)
+                .into_iter()
+                .map(|(t, _)| t)
+                .collect();
+            let mut reported: Vec<String> = Vec::new();
+            for hook in &self.install_hooks {
+                let key = hook.key();
+                if titles.contains(&hook.section) || reported.contains(&key) {
+                    continue;
+                }
+                c.push(
+                    Severity::Warning,
+                    format!("hooks.{key}"),
+                    format!(
+                        "section '{}' is not part of this plan; the hook will not run",
+                        hook.section
+                    ),
+                );
+                reported.push(key);
+            }
+        }
+
+        c.items
+    }
+}
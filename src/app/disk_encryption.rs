@@ -2,6 +2,7 @@ use serde_json;
 use std::process::Command;
 
 use super::AppState;
+use crate::core::storage::{LUKS_INTEGRITY, LUKS_PBKDFS, LUKS_SECTOR_SIZES};
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
@@ -51,6 +52,96 @@ impl AppState {
         self.popup_open = true;
     }
 
+    /// `luksFormat` options for the summary, e.g. `--pbkdf=argon2id --sector-size=4096`.
+    pub fn luks_format_summary(&self) -> String {
+        match crate::core::storage::planner::StoragePlanner::luks_format_options(self) {
+            Ok(options) if options == Default::default() => "cryptsetup defaults".into(),
+            Ok(options) => options.args().join(" "),
+            Err(errors) => errors[0].message.clone(),
+        }
+    }
+
+    /// Focus index of `[ Continue ]`: after the LUKS rows when LUKS is selected.
+    pub fn diskenc_continue_index(&self) -> usize {
+        if self.disk_encryption_type_index == 1 {
+            17
+        } else {
+            1
+        }
+    }
+
+    /// Text value of a LUKS parameter row (cipher, key size, hash, PBKDF costs, label).
+    pub fn luks_param_text_mut(&mut self, row: usize) -> Option<&mut String> {
+        match row {
+            8 => Some(&mut self.luks_cipher),
+            9 => Some(&mut self.luks_key_size),
+            10 => Some(&mut self.luks_hash),
+            12 => Some(&mut self.luks_pbkdf_memory),
+            13 => Some(&mut self.luks_pbkdf_time),
+            15 => Some(&mut self.luks_label),
+            _ => None,
+        }
+    }
+
+    /// Step a choice row (PBKDF, sector size, integrity); index 0 is the cryptsetup default.
+    pub fn cycle_luks_param(&mut self, row: usize, next: bool) {
+        let (index, len) = match row {
+            11 => (&mut self.luks_pbkdf_index, LUKS_PBKDFS.len() + 1),
+            14 => (
+                &mut self.luks_sector_size_index,
+                LUKS_SECTOR_SIZES.len() + 1,
+            ),
+            16 => (&mut self.luks_integrity_index, LUKS_INTEGRITY.len() + 1),
+            _ => return,
+        };
+        *index = if next {
+            (*index + 1) % len
+        } else {
+            (*index + len - 1) % len
+        };
+    }
+
+    pub fn open_luks_param_input(&mut self) {
+        let value = match self.luks_param_text_mut(self.diskenc_focus_index) {
+            Some(value) => value.clone(),
+            None => return,
+        };
+        self.popup_kind = Some(super::PopupKind::DiskEncryptionLuksParam);
+        self.custom_input_buffer = value;
+        self.popup_items.clear();
+        self.popup_visible_indices.clear();
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        self.popup_open = true;
+    }
+
+    pub fn open_disk_encryption_tpm2_pcrs_input(&mut self) {
+        self.popup_kind = Some(super::PopupKind::DiskEncryptionTpm2Pcrs);
+        self.custom_input_buffer = self.disk_encryption_tpm2_pcrs.clone();
+        self.popup_items.clear();
+        self.popup_visible_indices.clear();
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        self.popup_open = true;
+    }
+
+    /// Extra unlock methods for the summary, e.g. `TPM2 (PCR 7), recovery key`.
+    pub fn disk_encryption_unlock_summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.disk_encryption_tpm2 {
+            parts.push(format!("TPM2 (PCR {})", self.disk_encryption_tpm2_pcrs));
+        }
+        if self.disk_encryption_fido2 {
+            parts.push("FIDO2".to_string());
+        }
+        if self.disk_encryption_recovery_key {
+            parts.push("recovery key".to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
     pub fn open_disk_encryption_partition_list(&mut self) {
         self.popup_kind = Some(super::PopupKind::DiskEncryptionPartitionList);
         let selected_device = self.disks_selected_device.clone();
@@ -342,14 +433,111 @@ pub fn draw_disk_encryption(frame: &mut ratatui::Frame, app: &mut AppState, area
             Span::styled("Partition: ", label_style),
             Span::styled(part, value_style),
         ]));
+
+        // Unlock methods enrolled next to the password
+        let check = |on: bool| if on { "[x]" } else { "[ ]" }.to_string();
+        let options = vec![
+            (4, "TPM2 Unlock", check(app.disk_encryption_tpm2), true),
+            (
+                5,
+                "TPM2 PCRs",
+                app.disk_encryption_tpm2_pcrs.clone(),
+                app.disk_encryption_tpm2,
+            ),
+            (6, "FIDO2 Unlock", check(app.disk_encryption_fido2), true),
+            (
+                7,
+                "Recovery Key",
+                check(app.disk_encryption_recovery_key),
+                true,
+            ),
+        ];
+        // luksFormat parameters
+        let or_default = |s: &str| {
+            if s.is_empty() {
+                "default".to_string()
+            } else {
+                s.to_string()
+            }
+        };
+        let choice = |index: usize, names: Vec<String>, none: &str| {
+            index
+                .checked_sub(1)
+                .and_then(|i| names.get(i).cloned())
+                .unwrap_or_else(|| none.to_string())
+        };
+        let argon2 = app.luks_pbkdf_index == 0 || LUKS_PBKDFS[app.luks_pbkdf_index - 1] != "pbkdf2";
+        let options = options.into_iter().chain([
+            (8, "Cipher", or_default(&app.luks_cipher), true),
+            (9, "Key Size (bits)", or_default(&app.luks_key_size), true),
+            (10, "Hash", or_default(&app.luks_hash), true),
+            (
+                11,
+                "PBKDF",
+                choice(
+                    app.luks_pbkdf_index,
+                    LUKS_PBKDFS.iter().map(|p| p.to_string()).collect(),
+                    "default",
+                ),
+                true,
+            ),
+            (
+                12,
+                "PBKDF Memory (KiB)",
+                or_default(&app.luks_pbkdf_memory),
+                argon2,
+            ),
+            (
+                13,
+                "PBKDF Time (ms)",
+                or_default(&app.luks_pbkdf_time),
+                true,
+            ),
+            (
+                14,
+                "Sector Size",
+                choice(
+                    app.luks_sector_size_index,
+                    LUKS_SECTOR_SIZES.iter().map(|s| s.to_string()).collect(),
+                    "default",
+                ),
+                true,
+            ),
+            (15, "Label", or_default(&app.luks_label), true),
+            (
+                16,
+                "Integrity",
+                choice(
+                    app.luks_integrity_index,
+                    LUKS_INTEGRITY.iter().map(|i| i.to_string()).collect(),
+                    "none",
+                ),
+                true,
+            ),
+        ]);
+        for (idx, label, value, enabled) in options {
+            let is_focused_line = app.diskenc_focus_index == idx;
+            let is_act = is_focused_line && matches!(app.focus, super::Focus::Content);
+            let style = if is_act && enabled {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else if !enabled {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let bullet = if is_focused_line { "▶" } else { " " };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{bullet} "), style),
+                Span::styled(format!("{label}: "), style),
+                Span::styled(value, style),
+            ]));
+        }
     }
 
     // Continue
-    let continue_index = if app.disk_encryption_type_index == 1 {
-        4
-    } else {
-        1
-    };
+    let continue_index = app.diskenc_continue_index();
     let continue_style = if app.diskenc_focus_index == continue_index
         && matches!(app.focus, super::Focus::Content)
     {
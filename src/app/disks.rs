@@ -3,6 +3,7 @@ use std::process::Command;
 use std::time::{Duration, Instant};
 
 use super::AppState;
+use crate::core::storage::RootFilesystem;
 use crate::core::types::Screen;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
@@ -22,6 +23,14 @@ pub struct DiskDevice {
 }
 
 pub fn draw_disks(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
+    if app.lvm_open {
+        super::lvm::draw_lvm(frame, app, area);
+        return;
+    }
+    if app.raid_open {
+        super::raid::draw_raid(frame, app, area);
+        return;
+    }
     let title = Span::styled(
         "Disk Partitioning",
         Style::default()
@@ -63,24 +72,47 @@ pub fn draw_disks(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
         lines.push(line);
     }
 
-    // Btrfs subvolume preset row: interactive in automatic mode (0), or in manual mode (1) when
-    // root is btrfs (subvolume layout only applies to btrfs root).
+    // Root filesystem row: only the automatic layout (0) picks it; manual partitions and
+    // logical volumes carry their own filesystem.
+    let is_root_fs_active =
+        app.disks_focus_index == 3 && matches!(app.focus, super::Focus::Content);
+    let root_fs_available = app.disks_mode_index == 0 && !app.lvm_selected();
+    let root_fs_bullet = if app.disks_focus_index == 3 {
+        "▶"
+    } else {
+        " "
+    };
+    let root_fs_style = if is_root_fs_active && root_fs_available {
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD)
+    } else if !root_fs_available {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(format!("{root_fs_bullet} "), root_fs_style),
+        Span::styled(
+            format!("Root Filesystem: {}", app.root_filesystem().label()),
+            root_fs_style,
+        ),
+    ]));
+
+    // Btrfs subvolume preset row: interactive in automatic mode (0) with a btrfs root, or in
+    // manual mode (1) when root is btrfs (subvolume layout only applies to btrfs root).
     let preset_label = match app.btrfs_subvolume_preset {
         1 => "Standard (@, @home, @snapshots)",
         2 => "Extended (@, @home, @var_log, @snapshots)",
         _ => "Flat (no subvolumes)",
     };
-    let is_preset_focused = app.disks_focus_index == 3;
+    let is_preset_focused = app.disks_focus_index == 4;
     let is_preset_active = is_preset_focused && matches!(app.focus, super::Focus::Content);
-    let has_btrfs_root = app.disks_partitions.iter().any(|p| {
-        p.role
-            .as_deref()
-            .map(|r| r.eq_ignore_ascii_case("ROOT"))
-            .unwrap_or(false)
-            && p.fs.as_deref() == Some("btrfs")
-    });
-    let preset_available =
-        app.disks_mode_index == 0 || (app.disks_mode_index == 1 && has_btrfs_root);
+    let preset_available = app.disks_mode_index < 2
+        && app.btrfs_root_selected()
+        && !app.lvm_selected()
+        && !app.raid_selected();
     let preset_bullet = if is_preset_focused { "▶" } else { " " };
     let preset_style = if is_preset_active && preset_available {
         Style::default()
@@ -91,13 +123,93 @@ pub fn draw_disks(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
     } else {
         Style::default().fg(Color::White)
     };
-    lines.push(Line::from(""));
     lines.push(Line::from(vec![
         Span::styled(format!("{preset_bullet} "), preset_style),
         Span::styled(format!("Btrfs Subvolumes: {preset_label}"), preset_style),
     ]));
 
-    let continue_style = if app.disks_focus_index == 4 && matches!(app.focus, super::Focus::Content)
+    // LVM row: opens the LVM sub-screen for the automatic (0) and manual (1) layouts.
+    let lvm_label = if app.lvm_enabled {
+        format!(
+            "Enabled ({}, {} volumes)",
+            app.lvm_vg_name,
+            app.lvm_volumes.len()
+        )
+    } else {
+        "Disabled".to_string()
+    };
+    let is_lvm_focused = app.disks_focus_index == 5;
+    let is_lvm_active = is_lvm_focused && matches!(app.focus, super::Focus::Content);
+    let lvm_available = app.disks_mode_index < 2;
+    let lvm_bullet = if is_lvm_focused { "▶" } else { " " };
+    let lvm_style = if is_lvm_active && lvm_available {
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD)
+    } else if !lvm_available {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    lines.push(Line::from(vec![
+        Span::styled(format!("{lvm_bullet} "), lvm_style),
+        Span::styled(format!("Logical Volumes (LVM): {lvm_label}"), lvm_style),
+    ]));
+
+    // RAID row: opens the software RAID sub-screen; only the automatic layout (0) spans disks.
+    let raid_label = if app.raid_enabled {
+        format!("Enabled ({})", app.raid_summary())
+    } else {
+        "Disabled".to_string()
+    };
+    let is_raid_focused = app.disks_focus_index == 6;
+    let is_raid_active = is_raid_focused && matches!(app.focus, super::Focus::Content);
+    let raid_available = app.disks_mode_index == 0;
+    let raid_bullet = if is_raid_focused { "▶" } else { " " };
+    let raid_style = if is_raid_active && raid_available {
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD)
+    } else if !raid_available {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    lines.push(Line::from(vec![
+        Span::styled(format!("{raid_bullet} "), raid_style),
+        Span::styled(format!("Software RAID: {raid_label}"), raid_style),
+    ]));
+
+    // Alongside row: the automatic layout (0) keeps the existing partitions and installs into
+    // the largest free region.
+    let is_alongside_focused = app.disks_focus_index == 7;
+    let is_alongside_active = is_alongside_focused && matches!(app.focus, super::Focus::Content);
+    let alongside_available = app.disks_mode_index == 0;
+    let alongside_bullet = if is_alongside_focused { "▶" } else { " " };
+    let alongside_style = if is_alongside_active && alongside_available {
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD)
+    } else if !alongside_available {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    lines.push(Line::from(vec![
+        Span::styled(format!("{alongside_bullet} "), alongside_style),
+        Span::styled(
+            format!(
+                "Install Alongside Existing OS: {}",
+                if app.alongside_enabled {
+                    "Enabled (free space only)"
+                } else {
+                    "Disabled"
+                }
+            ),
+            alongside_style,
+        ),
+    ]));
+    let continue_style = if app.disks_focus_index == 8 && matches!(app.focus, super::Focus::Content)
     {
         Style::default()
             .fg(Color::Yellow)
@@ -122,6 +234,21 @@ pub fn draw_disks(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
 }
 
 impl AppState {
+    /// True when the automatic layout goes into the free space next to an existing OS.
+    pub fn alongside_selected(&self) -> bool {
+        self.alongside_enabled && self.disks_mode_index == 0
+    }
+
+    /// Target passed to [`Self::build_install_sections`]: empty in pre-mounted mode, whose
+    /// filesystems are already in place, else the selected disk (`None` when there is none).
+    pub fn install_target(&self) -> Option<String> {
+        if self.disks_mode_index == 2 {
+            Some(String::new())
+        } else {
+            self.disks_selected_device.clone()
+        }
+    }
+
     pub(crate) fn human_bytes(n: u64) -> String {
         const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
         let mut value = n as f64;
@@ -586,7 +713,8 @@ impl AppState {
             0 => vec!["fat32", "fat16", "fat12"],
             1 => vec!["linux-swap"],
             2 | 3 => vec![
-                "btrfs", "ext2", "ext3", "ext4", "f2fs", "fat12", "fat16", "fat32", "ntfs", "xfs",
+                "bcachefs", "btrfs", "ext2", "ext3", "ext4", "f2fs", "fat12", "fat16", "fat32",
+                "ntfs", "xfs",
             ],
             _ => vec!["ext4"],
         };
@@ -619,6 +747,63 @@ impl AppState {
         self.popup_search_query.clear();
     }
 
+    /// Root filesystem of the automatic layout.
+    pub fn root_filesystem(&self) -> RootFilesystem {
+        RootFilesystem::from_index(self.disks_root_fs_index)
+    }
+
+    /// True when the automatic layout puts `/` on a ZFS pool (logical volumes replace it).
+    pub fn zfs_root_selected(&self) -> bool {
+        self.disks_mode_index == 0
+            && self.root_filesystem() == RootFilesystem::Zfs
+            && !self.lvm_selected()
+    }
+
+    /// True when the layout formats any bcachefs filesystem (automatic root or manual
+    /// partitions).
+    pub fn bcachefs_selected(&self) -> bool {
+        match self.disks_mode_index {
+            0 => self.root_filesystem() == RootFilesystem::Bcachefs && !self.lvm_selected(),
+            1 => self
+                .disks_partitions
+                .iter()
+                .any(|p| p.fs.as_deref() == Some("bcachefs")),
+            _ => false,
+        }
+    }
+
+    /// True when `/` will be btrfs: the automatic btrfs root, a manual btrfs ROOT partition or
+    /// a btrfs root logical volume.
+    pub fn btrfs_root_selected(&self) -> bool {
+        if let Some(root) = self.lvm_root_volume() {
+            return root.fs == "btrfs";
+        }
+        match self.disks_mode_index {
+            0 => self.root_filesystem() == RootFilesystem::Btrfs,
+            1 => self.disks_partitions.iter().any(|p| {
+                p.role
+                    .as_deref()
+                    .is_some_and(|r| r.eq_ignore_ascii_case("ROOT"))
+                    && p.fs.as_deref() == Some("btrfs")
+            }),
+            _ => false,
+        }
+    }
+
+    pub fn open_root_filesystem_popup(&mut self) {
+        self.popup_kind = Some(super::PopupKind::RootFilesystemSelect);
+        self.popup_items = RootFilesystem::NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, _)| RootFilesystem::from_index(i).label().to_string())
+            .collect();
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = self.disks_root_fs_index;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        self.popup_open = true;
+    }
+
     pub fn open_btrfs_subvolume_preset_popup(&mut self) {
         self.popup_kind = Some(super::PopupKind::BtrfsSubvolumePreset);
         self.popup_items = vec![
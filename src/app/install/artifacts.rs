@@ -0,0 +1,76 @@
+//! Install record kept in the installed system after a successful run.
+//!
+//! Written to [`TARGET_LOG_DIR`] under the target root: the redacted install log, the
+//! effective config without password hashes, and the compiled storage plan.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::app::AppState;
+use crate::core::storage::planner::StoragePlanner;
+
+/// Install record directory, relative to the target root.
+pub const TARGET_LOG_DIR: &str = "var/log/archinstall-rs";
+pub const INSTALL_LOG_FILE: &str = "install.log";
+pub const CONFIG_FILE: &str = "config.toml";
+pub const STORAGE_PLAN_FILE: &str = "storage-plan.json";
+
+/// Config and storage plan captured before the install thread starts.
+#[derive(Clone, Debug)]
+pub struct InstallArtifacts {
+    pub target_root: PathBuf,
+    pub config_toml: String,
+    pub storage_plan_json: String,
+}
+
+impl InstallArtifacts {
+    /// Capture the record for an install into `/mnt`.
+    pub fn collect(app: &AppState) -> Result<Self, String> {
+        let config_toml = app
+            .redacted_config_toml()
+            .map_err(|e| format!("config: {e}"))?;
+        let plan = StoragePlanner::compile(app).map_err(|errors| {
+            let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+            format!("storage plan: {}", messages.join(", "))
+        })?;
+        let storage_plan_json =
+            serde_json::to_string_pretty(&plan).map_err(|e| format!("storage plan: {e}"))?;
+        Ok(Self {
+            target_root: PathBuf::from("/mnt"),
+            config_toml,
+            storage_plan_json,
+        })
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.target_root.join(TARGET_LOG_DIR)
+    }
+
+    /// Write the record with `log` as the install transcript. Files are readable by root only.
+    pub fn write(&self, log: &[String]) -> Result<PathBuf, String> {
+        let dir = self.dir();
+        std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        let mut transcript = log.join("\n");
+        transcript.push('\n');
+        for (name, contents) in [
+            (INSTALL_LOG_FILE, transcript.as_str()),
+            (CONFIG_FILE, self.config_toml.as_str()),
+            (STORAGE_PLAN_FILE, self.storage_plan_json.as_str()),
+        ] {
+            write_private(&dir.join(name), contents)?;
+        }
+        Ok(dir)
+    }
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut f| f.write_all(contents.as_bytes()))
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
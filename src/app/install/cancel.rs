@@ -0,0 +1,115 @@
+//! Cancelling a running install from the TUI.
+//!
+//! A [`CancelToken`] is shared between the TUI and the install thread. Cancelling sets the
+//! flag the thread checks between commands and terminates the command that is running; the
+//! thread then releases the target with [`StoragePlan::teardown_commands`] and exits.
+//!
+//! [`StoragePlan::teardown_commands`]: crate::core::storage::StoragePlan::teardown_commands
+
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::app::{AppState, PopupKind};
+
+/// How long a cancelled command gets to exit after SIGTERM before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    /// PID of the command the executor is waiting on.
+    child: Mutex<Option<u32>>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Request cancellation and terminate the running command: SIGTERM first, SIGKILL if it
+    /// is still running after [`KILL_GRACE`]. Later calls do nothing, so the cleanup
+    /// commands that follow are never interrupted.
+    pub fn cancel(&self) {
+        if self.0.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let Some(pid) = self.running_child() else {
+            return;
+        };
+        send_signal(pid, "TERM");
+        let token = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(KILL_GRACE);
+            if token.running_child() == Some(pid) {
+                send_signal(pid, "KILL");
+            }
+        });
+    }
+
+    /// Record the command the executor is about to wait on (`None` once it exited).
+    pub fn set_running_child(&self, pid: Option<u32>) {
+        if let Ok(mut child) = self.0.child.lock() {
+            *child = pid;
+        }
+    }
+
+    fn running_child(&self) -> Option<u32> {
+        self.0.child.lock().ok().and_then(|child| *child)
+    }
+}
+
+fn send_signal(pid: u32, signal: &str) {
+    let _ = Command::new("kill")
+        .args([format!("-{signal}"), pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+impl AppState {
+    /// Ask before cancelling; only real installs can be cancelled.
+    pub fn open_install_cancel_popup(&mut self) {
+        if !self.install_running || self.install_cancel.is_none() {
+            return;
+        }
+        self.popup_kind = Some(PopupKind::InstallCancelConfirm);
+        self.popup_open = true;
+        self.popup_items = vec![
+            "Yes, stop and clean up the target".into(),
+            "No, keep installing".into(),
+        ];
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = 1; // default to No
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+    }
+
+    /// Signal the install thread to stop. It finishes with the cleanup commands and the
+    /// runner brings the user back to the menu.
+    pub fn cancel_install(&mut self) {
+        self.close_popup();
+        let Some(cancel) = self
+            .install_cancel
+            .as_ref()
+            .filter(|_| self.install_running)
+        else {
+            return;
+        };
+        if cancel.is_cancelled() {
+            return;
+        }
+        self.debug_log("cancel_install: signalling the install thread");
+        self.install_log
+            .push("Cancelling the installation...".to_string());
+        cancel.cancel();
+    }
+}
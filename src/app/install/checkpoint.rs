@@ -0,0 +1,309 @@
+//! Per-section install checkpoints so a failed install can be resumed.
+//!
+//! Every completed section is recorded in a JSON file on the host (see
+//! [`host_checkpoint_path`]) and, once the target is mounted, mirrored to
+//! [`TARGET_CHECKPOINT_PATH`] under `/mnt`. Sections are identified by title and a
+//! digest of their (redacted) commands so edits made after a failure are noticed.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::common::install_cmd::InstallCmd;
+
+/// Environment variable overriding the host checkpoint location.
+pub const ENV_CHECKPOINT_PATH: &str = "ARCHINSTALL_CHECKPOINT";
+/// Default checkpoint location on the live host (survives TUI restarts, not reboots).
+pub const HOST_CHECKPOINT_PATH: &str = "/var/tmp/archinstall-rs/checkpoint.json";
+/// Checkpoint mirror inside the installed system, relative to the target root.
+pub const TARGET_CHECKPOINT_PATH: &str = "var/lib/archinstall-rs/checkpoint.json";
+
+/// Sections that prepare the target disk. They only make sense as a unit: resuming
+/// inside them restarts storage setup from the first one present in the plan.
+pub const STORAGE_SECTIONS: [&str; 4] = [
+    "Pre-cleanup",
+    "Partitioning",
+    "Volume stack setup (LVM/RAID)",
+    "Mounting",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionCheckpoint {
+    pub title: String,
+    pub digest: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallCheckpoint {
+    /// Target disk of the run (empty for pre-mounted installs).
+    pub target: String,
+    pub completed: Vec<SectionCheckpoint>,
+    /// Title of the section that failed last, if any.
+    pub failed: Option<String>,
+    pub updated: String,
+}
+
+/// Stable digest of a section's commands (uses the redacted log form, never secrets).
+pub fn section_digest(cmds: &[InstallCmd]) -> String {
+    let mut hasher = Sha256::new();
+    for cmd in cmds {
+        hasher.update(cmd.for_log().as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn host_checkpoint_path() -> PathBuf {
+    std::env::var_os(ENV_CHECKPOINT_PATH)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(HOST_CHECKPOINT_PATH))
+}
+
+fn is_storage_section(title: &str) -> bool {
+    STORAGE_SECTIONS.contains(&title)
+}
+
+impl InstallCheckpoint {
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    fn entry(&self, title: &str) -> Option<&SectionCheckpoint> {
+        self.completed.iter().find(|c| c.title == title)
+    }
+
+    /// Titles of completed sections whose commands differ from `sections` now.
+    pub fn changed_sections(&self, sections: &[(String, Vec<InstallCmd>)]) -> Vec<String> {
+        sections
+            .iter()
+            .filter(|(title, cmds)| {
+                self.entry(title)
+                    .is_some_and(|c| c.digest != section_digest(cmds))
+            })
+            .map(|(title, _)| title.clone())
+            .collect()
+    }
+
+    /// Index of the first section that still has to run.
+    ///
+    /// Completed sections are skipped even when their commands changed, except storage
+    /// sections: a changed disk layout restarts storage setup.
+    pub fn resume_index(&self, sections: &[(String, Vec<InstallCmd>)]) -> usize {
+        let idx = sections
+            .iter()
+            .position(|(title, cmds)| match self.entry(title) {
+                None => true,
+                Some(c) => is_storage_section(title) && c.digest != section_digest(cmds),
+            })
+            .unwrap_or(sections.len());
+        storage_aligned_start(sections, idx)
+    }
+
+    /// Keep the completed entries for `sections[..start]` with their current digests.
+    pub fn restart_at(&mut self, sections: &[(String, Vec<InstallCmd>)], start: usize) {
+        self.completed = sections[..start.min(sections.len())]
+            .iter()
+            .map(|(title, cmds)| SectionCheckpoint {
+                title: title.clone(),
+                digest: section_digest(cmds),
+            })
+            .collect();
+        self.failed = None;
+    }
+}
+
+/// Move a start index that falls inside the storage sections back to the first of them.
+pub fn storage_aligned_start(sections: &[(String, Vec<InstallCmd>)], idx: usize) -> usize {
+    match sections.get(idx) {
+        Some((title, _)) if is_storage_section(title) => sections
+            .iter()
+            .position(|(t, _)| is_storage_section(t))
+            .unwrap_or(idx),
+        _ => idx,
+    }
+}
+
+/// Section indices a run may be resumed from, given the furthest possible start.
+pub fn resume_choices(sections: &[(String, Vec<InstallCmd>)], furthest: usize) -> Vec<usize> {
+    let mut out: Vec<usize> = (0..=furthest.min(sections.len().saturating_sub(1)))
+        .map(|i| storage_aligned_start(sections, i))
+        .collect();
+    out.dedup();
+    out
+}
+
+/// Pick the section index to resume from.
+///
+/// Without `requested` the checkpoint decides; otherwise `requested` names a section
+/// (case-insensitive) that must not come after the first unfinished one.
+pub fn plan_resume(
+    checkpoint: Option<&InstallCheckpoint>,
+    sections: &[(String, Vec<InstallCmd>)],
+    requested: Option<&str>,
+) -> Result<usize, String> {
+    let furthest = checkpoint.map_or(0, |cp| cp.resume_index(sections));
+    let Some(name) = requested else {
+        return match checkpoint {
+            Some(_) if furthest < sections.len() => Ok(furthest),
+            Some(_) => Err("every section of this plan already completed".into()),
+            None => Err("no checkpoint to resume from".into()),
+        };
+    };
+    let Some(idx) = sections
+        .iter()
+        .position(|(t, _)| t.eq_ignore_ascii_case(name.trim()))
+    else {
+        let titles: Vec<&str> = sections.iter().map(|(t, _)| t.as_str()).collect();
+        return Err(format!(
+            "unknown section '{name}' (sections: {})",
+            titles.join(", ")
+        ));
+    };
+    let start = storage_aligned_start(sections, idx);
+    if start > furthest {
+        let pending = sections
+            .get(furthest)
+            .map_or("the first section", |(t, _)| t.as_str());
+        return Err(format!(
+            "cannot resume from '{}': '{pending}' has not completed yet",
+            sections[idx].0
+        ));
+    }
+    Ok(start)
+}
+
+/// True when starting at `start` skips storage setup, so the target must still be mounted.
+pub fn start_is_past_storage(sections: &[(String, Vec<InstallCmd>)], start: usize) -> bool {
+    sections[..start.min(sections.len())]
+        .iter()
+        .any(|(t, _)| is_storage_section(t))
+}
+
+/// True when `path` is a mountpoint (its device differs from its parent's).
+pub fn is_mountpoint(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(meta) = std::fs::metadata(path) else {
+        return false;
+    };
+    let parent = path.parent().unwrap_or(Path::new("/"));
+    match std::fs::metadata(parent) {
+        Ok(p) => p.dev() != meta.dev() || p.ino() == meta.ino(),
+        Err(_) => false,
+    }
+}
+
+/// Writes the checkpoint after every section, on the host and (once mounted) the target.
+pub struct CheckpointRecorder {
+    checkpoint: InstallCheckpoint,
+    host_path: PathBuf,
+    target_root: PathBuf,
+    mirror_ready: bool,
+}
+
+impl CheckpointRecorder {
+    /// `target_root` is mirrored into once the "Mounting" section completed, or right
+    /// away when the plan has no such section (pre-mounted installs, resumed runs).
+    pub fn new(
+        checkpoint: InstallCheckpoint,
+        host_path: PathBuf,
+        target_root: PathBuf,
+        mirror_ready: bool,
+    ) -> Self {
+        Self {
+            checkpoint,
+            host_path,
+            target_root,
+            mirror_ready,
+        }
+    }
+
+    /// Recorder for a real run on this host that starts at `sections[start]`.
+    pub fn for_run(
+        mut checkpoint: InstallCheckpoint,
+        sections: &[(String, Vec<InstallCmd>)],
+        start: usize,
+    ) -> Self {
+        checkpoint.restart_at(sections, start);
+        let mirror_ready = !sections.iter().any(|(t, _)| t == "Mounting")
+            || start_is_past_storage(sections, start);
+        Self::new(
+            checkpoint,
+            host_checkpoint_path(),
+            PathBuf::from("/mnt"),
+            mirror_ready,
+        )
+    }
+
+    pub fn checkpoint(&self) -> &InstallCheckpoint {
+        &self.checkpoint
+    }
+
+    pub fn target_path(&self) -> PathBuf {
+        self.target_root.join(TARGET_CHECKPOINT_PATH)
+    }
+
+    pub fn section_done(&mut self, title: &str, cmds: &[InstallCmd]) -> Result<(), String> {
+        self.checkpoint.completed.retain(|c| c.title != title);
+        self.checkpoint.completed.push(SectionCheckpoint {
+            title: title.to_string(),
+            digest: section_digest(cmds),
+        });
+        if self.checkpoint.failed.as_deref() == Some(title) {
+            self.checkpoint.failed = None;
+        }
+        if title == "Mounting" {
+            self.mirror_ready = true;
+        }
+        self.save()
+    }
+
+    pub fn section_failed(&mut self, title: &str) -> Result<(), String> {
+        self.checkpoint.failed = Some(title.to_string());
+        self.save()
+    }
+
+    /// The install finished: keep the record in the target, drop the host copy so the
+    /// next run on this live system starts fresh.
+    pub fn finish(&mut self) -> Result<(), String> {
+        self.save()?;
+        match std::fs::remove_file(&self.host_path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!(
+                "could not remove {}: {e}",
+                self.host_path.display()
+            )),
+        }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        self.checkpoint.updated = chrono::Local::now().to_rfc3339();
+        self.checkpoint
+            .save(&self.host_path)
+            .map_err(|e| format!("could not write {}: {e}", self.host_path.display()))?;
+        if self.mirror_ready {
+            let target = self.target_path();
+            self.checkpoint
+                .save(&target)
+                .map_err(|e| format!("could not write {}: {e}", target.display()))?;
+        }
+        Ok(())
+    }
+}
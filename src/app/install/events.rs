@@ -0,0 +1,176 @@
+//! Structured install events, written as JSON lines next to the human-readable log.
+//!
+//! Every record is one JSON object with an RFC 3339 `ts` and an `event` tag, e.g.
+//! `{"ts":"…","event":"command_end","section":"Partitioning","command":"…","exit_code":0,"duration_ms":12}`.
+//! Commands are always in their redacted log form.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Environment variable naming the event log file (`--event-log` takes precedence).
+pub const ENV_EVENT_LOG_PATH: &str = "ARCHINSTALL_EVENT_LOG";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum InstallEvent {
+    InstallStart {
+        hostname: String,
+        /// Target disk (empty for pre-mounted installs).
+        target: String,
+        sections: Vec<String>,
+    },
+    SectionStart {
+        section: String,
+    },
+    CommandStart {
+        section: String,
+        command: String,
+    },
+    /// One sanitized line of command output.
+    Output {
+        section: String,
+        line: String,
+    },
+    CommandEnd {
+        section: String,
+        command: String,
+        /// `None` when the command could not be spawned or waited for.
+        exit_code: Option<i32>,
+        duration_ms: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Warning {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        section: Option<String>,
+        message: String,
+    },
+    SectionEnd {
+        section: String,
+        status: EventStatus,
+        duration_ms: u64,
+    },
+    InstallEnd {
+        status: EventStatus,
+        duration_ms: u64,
+        warnings: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub ts: String,
+    #[serde(flatten)]
+    pub event: InstallEvent,
+}
+
+/// The event log file: `cli` (from `--event-log`) or [`ENV_EVENT_LOG_PATH`], if either is set.
+pub fn event_log_path(cli: Option<PathBuf>) -> Option<PathBuf> {
+    cli.or_else(|| {
+        std::env::var_os(ENV_EVENT_LOG_PATH)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Parse a JSONL event log (blank lines are ignored).
+pub fn parse_event_log(text: &str) -> Result<Vec<EventRecord>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {e}", i + 1)))
+        .collect()
+}
+
+/// Destination for [`InstallEvent`]s. A disabled log accepts and drops every event.
+pub struct EventLog {
+    writer: Option<Box<dyn Write + Send>>,
+    hostname: String,
+    target: String,
+}
+
+impl EventLog {
+    pub fn disabled() -> Self {
+        Self {
+            writer: None,
+            hostname: String::new(),
+            target: String::new(),
+        }
+    }
+
+    /// Append to `path`, creating it and its parent directories. Runs are delimited by
+    /// `install_start` records, so resumed installs extend the same file.
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self::from_writer(Box::new(file)))
+    }
+
+    pub fn from_writer(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Some(writer),
+            ..Self::disabled()
+        }
+    }
+
+    /// Machine identity reported in `install_start`.
+    pub fn with_machine(mut self, hostname: &str, target: &str) -> Self {
+        self.hostname = hostname.to_string();
+        self.target = target.to_string();
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn install_start(&mut self, sections: Vec<String>) -> Result<(), String> {
+        self.emit(InstallEvent::InstallStart {
+            hostname: self.hostname.clone(),
+            target: self.target.clone(),
+            sections,
+        })
+    }
+
+    /// Write one record. The first write error disables the log and is returned so the
+    /// caller can report it once; later events are dropped.
+    pub fn emit(&mut self, event: InstallEvent) -> Result<(), String> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        let record = EventRecord {
+            ts: chrono::Local::now().to_rfc3339(),
+            event,
+        };
+        let result = serde_json::to_string(&record)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                writeln!(writer, "{json}")
+                    .and_then(|()| writer.flush())
+                    .map_err(|e| e.to_string())
+            });
+        if result.is_err() {
+            self.writer = None;
+        }
+        result
+    }
+}
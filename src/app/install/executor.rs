@@ -0,0 +1,164 @@
+//! How install commands are executed.
+//!
+//! [`SystemExecutor`] spawns the real processes (`script` for shell steps, `cryptsetup`
+//! for LUKS steps). [`FakeExecutor`] records every command and replays scripted exit codes
+//! and output, so install flows can be exercised end to end without root.
+
+use std::collections::VecDeque;
+use std::io;
+use std::process::Stdio;
+
+use super::cancel::CancelToken;
+use crate::common::install_cmd::InstallCmd;
+use crate::common::install_stdout::pump_install_stdout;
+
+/// Output produced by a running command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandOutput<'a> {
+    /// A complete raw line (the caller sanitizes it).
+    Line(&'a str),
+    /// A sanitized progress line that replaces the previous one.
+    Progress(&'a str),
+}
+
+#[derive(Debug)]
+pub enum ExecError {
+    Spawn(io::Error),
+    Stdin(io::Error),
+    Wait(io::Error),
+}
+
+pub trait CommandExecutor {
+    /// Run `cmd` to completion, forwarding its output, and return the exit code.
+    fn run(
+        &mut self,
+        cmd: &InstallCmd,
+        output: &mut dyn FnMut(CommandOutput<'_>),
+    ) -> Result<i32, ExecError>;
+}
+
+/// Runs commands on the host.
+#[derive(Clone, Debug, Default)]
+pub struct SystemExecutor {
+    pub debug_enabled: bool,
+    /// Told about each running child so a cancel can terminate it.
+    pub cancel: Option<CancelToken>,
+}
+
+impl CommandExecutor for SystemExecutor {
+    fn run(
+        &mut self,
+        cmd: &InstallCmd,
+        output: &mut dyn FnMut(CommandOutput<'_>),
+    ) -> Result<i32, ExecError> {
+        // Force all output through our pipe using `script` to avoid /dev/tty writes.
+        // -q: quiet (no start/stop banner), -f: flush, -e: return child status, -c: command
+        let mut child = cmd
+            .spawn_script_pipeline(Stdio::piped())
+            .map_err(ExecError::Spawn)?;
+        cmd.write_passphrase_to_stdin(&mut child)
+            .map_err(ExecError::Stdin)?;
+        if let Some(cancel) = &self.cancel {
+            cancel.set_running_child(Some(child.id()));
+        }
+        if let Some(stdout) = child.stdout.take() {
+            let mut line_buf = String::new();
+            let mut byte_buf = Vec::new();
+            let mut scratch = [0u8; 4096];
+            let output = std::cell::RefCell::new(output);
+            if let Err(e) = pump_install_stdout(
+                stdout,
+                &mut line_buf,
+                &mut byte_buf,
+                &mut scratch,
+                |raw| (output.borrow_mut())(CommandOutput::Line(raw)),
+                |clean| (output.borrow_mut())(CommandOutput::Progress(clean)),
+            ) {
+                super::flow::install_thread_debug(
+                    self.debug_enabled,
+                    &format!("error reading child stdout: {e}"),
+                );
+            }
+        } else {
+            super::flow::install_thread_debug(
+                self.debug_enabled,
+                "stdout piping unavailable (child.stdout None)",
+            );
+        }
+        let status = child.wait();
+        if let Some(cancel) = &self.cancel {
+            cancel.set_running_child(None);
+        }
+        Ok(status.map_err(ExecError::Wait)?.code().unwrap_or(-1))
+    }
+}
+
+/// Scripted result for [`FakeExecutor`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FakeResponse {
+    pub exit_code: i32,
+    pub output: Vec<String>,
+}
+
+/// In-memory executor for tests: records commands and replays scripted responses.
+#[derive(Debug, Default)]
+pub struct FakeExecutor {
+    /// Log text (`for_log`) of every command run, in order.
+    pub calls: Vec<String>,
+    scripted: Vec<(String, VecDeque<FakeResponse>)>,
+}
+
+impl FakeExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a response for the next command whose log text contains `pattern`.
+    ///
+    /// Responses for a pattern are consumed in order; once drained (or when nothing
+    /// matches) a command succeeds without output.
+    pub fn respond(&mut self, pattern: &str, exit_code: i32, output: &[&str]) -> &mut Self {
+        let response = FakeResponse {
+            exit_code,
+            output: output.iter().map(|s| s.to_string()).collect(),
+        };
+        match self.scripted.iter_mut().find(|(p, _)| p == pattern) {
+            Some((_, queue)) => queue.push_back(response),
+            None => self
+                .scripted
+                .push((pattern.to_string(), VecDeque::from([response]))),
+        }
+        self
+    }
+
+    /// Queue a failure with `exit_code` for the next command matching `pattern`.
+    pub fn fail(&mut self, pattern: &str, exit_code: i32) -> &mut Self {
+        self.respond(pattern, exit_code, &[])
+    }
+
+    /// Number of recorded commands whose log text contains `pattern`.
+    pub fn calls_matching(&self, pattern: &str) -> usize {
+        self.calls.iter().filter(|c| c.contains(pattern)).count()
+    }
+}
+
+impl CommandExecutor for FakeExecutor {
+    fn run(
+        &mut self,
+        cmd: &InstallCmd,
+        output: &mut dyn FnMut(CommandOutput<'_>),
+    ) -> Result<i32, ExecError> {
+        let text = cmd.for_log();
+        let response = self
+            .scripted
+            .iter_mut()
+            .find(|(p, queue)| text.contains(p.as_str()) && !queue.is_empty())
+            .and_then(|(_, queue)| queue.pop_front())
+            .unwrap_or_default();
+        self.calls.push(text);
+        for line in &response.output {
+            output(CommandOutput::Line(line));
+        }
+        Ok(response.exit_code)
+    }
+}
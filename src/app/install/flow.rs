@@ -1,13 +1,21 @@
+use super::artifacts::InstallArtifacts;
+use super::cancel::CancelToken;
+use super::checkpoint::{self, CheckpointRecorder};
+use super::events::{EventLog, EventStatus, InstallEvent};
+use super::executor::{CommandExecutor, CommandOutput, ExecError, SystemExecutor};
+use super::hooks;
+use crate::app::secure_boot::SecureBootSigning;
 use crate::app::{AppState, PopupKind};
-use crate::common::InstallLogMsg;
 use crate::common::install_cmd::InstallCmd;
-use crate::common::install_stdout::pump_install_stdout;
+use crate::common::{CommandFailure, FailureAction, InstallLogMsg};
 use crate::core::services::network::NetworkService;
 use crate::core::services::sysconfig::SysConfigService;
 use crate::core::services::system::SystemService;
 use crate::core::storage::planner::StoragePlanner;
-use std::process::Stdio;
+use crate::core::storage::{LuksEnrollment, StoragePlan};
+use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
+use std::time::Instant;
 
 impl AppState {
     pub fn start_install(&mut self) {
@@ -46,10 +54,22 @@ impl AppState {
     }
 
     pub fn start_install_flow(&mut self) {
-        // Pre-mounted mode (index 2) skips device selection/prechecks — filesystems are ready
-        let target = if self.disks_mode_index == 2 {
+        let resume_from = self.install_resume_from.take();
+        if !self.dry_run && resume_from.is_none() && self.offer_install_resume() {
+            return;
+        }
+        // Pre-mounted mode skips device selection/prechecks — filesystems are ready
+        let target = if self.install_target().is_some_and(|t| t.is_empty()) {
             self.debug_log("start_install_flow: pre-mounted mode, skipping prechecks");
             String::new()
+        } else if resume_from.is_some() {
+            // The interrupted run already passed the prechecks and may have left the
+            // target mounted on purpose.
+            let Some(t) = self.install_target() else {
+                self.open_info_popup("No target disk selected.".into());
+                return;
+            };
+            t
         } else {
             let Some(t) = self.select_target_and_run_prechecks() else {
                 self.debug_log("start_install_flow: target selection/prechecks returned None");
@@ -183,229 +203,258 @@ impl AppState {
             });
             return;
         }
+        let previous = checkpoint::InstallCheckpoint::load(&checkpoint::host_checkpoint_path())
+            .filter(|cp| cp.target == target);
+        let start = match resume_from {
+            Some(idx) => checkpoint::storage_aligned_start(&sections, idx.min(sections.len())),
+            None => 0,
+        };
+        if start > 0
+            && checkpoint::start_is_past_storage(&sections, start)
+            && !checkpoint::is_mountpoint(std::path::Path::new("/mnt"))
+        {
+            self.open_info_popup(format!(
+                "Cannot resume from '{}': the target is no longer mounted at /mnt.\n\nResume from an earlier section to set up storage again, or start over.",
+                sections[start].0
+            ));
+            return;
+        }
+        let mut recorder = checkpoint::CheckpointRecorder::for_run(
+            previous
+                .clone()
+                .unwrap_or_else(|| checkpoint::InstallCheckpoint::new(&target)),
+            &sections,
+            start,
+        );
+
         // Launch background installer and keep TUI running with live logs
         self.install_running = true;
         self.install_section_titles = sections.iter().map(|(t, _)| t.clone()).collect();
-        self.install_section_done = vec![false; self.install_section_titles.len()];
+        self.install_section_done = (0..sections.len()).map(|i| i < start).collect();
         self.install_current_section = None;
+        if start > 0 {
+            self.install_log.push(format!(
+                "Resuming from '{}' ({start} section(s) already completed).",
+                sections[start].0
+            ));
+            let changed =
+                previous.map_or_else(Vec::new, |cp| cp.changed_sections(&sections[..start]));
+            for title in changed {
+                self.install_log.push(format!(
+                    "WARN: '{title}' changed since it ran and will not be re-run; resume from it to apply the change."
+                ));
+            }
+        }
+        let sections: Vec<(String, Vec<InstallCmd>)> = sections.into_iter().skip(start).collect();
 
         let (tx, rx) = std::sync::mpsc::channel::<InstallLogMsg>();
         self.install_log_rx = Some(rx);
+        let (decision_tx, decision_rx) = std::sync::mpsc::channel::<FailureAction>();
+        self.install_decision_tx = Some(decision_tx);
+        self.install_failure = None;
+        self.install_warnings.clear();
 
+        let mut events = self.open_install_event_log(&target);
+        let artifacts = match InstallArtifacts::collect(self) {
+            Ok(artifacts) => Some(artifacts),
+            Err(e) => {
+                self.install_log
+                    .push(format!("WARN: the install record will not be saved: {e}"));
+                None
+            }
+        };
+        let cancel = CancelToken::new();
+        self.install_cancel = Some(cancel.clone());
+        let plan = StoragePlanner::compile(self).ok();
+        let teardown = plan
+            .as_ref()
+            .map(|plan| plan.teardown_commands())
+            .unwrap_or_default();
+        self.install_recovery_keys = plan
+            .map(|plan| recovery_key_report_paths(&plan))
+            .unwrap_or_default();
+        let recovery_keys = self.install_recovery_keys.clone();
         let debug_enabled = self.debug_enabled;
         self.debug_log(&format!(
-            "install thread: spawning (sections={})",
+            "install thread: spawning (sections={}, start={start})",
             self.install_section_titles.len()
         ));
         thread::spawn(move || {
-            // Lightweight formatter to compact pacstrap logs
-            struct LogFormatter {
-                dl_count: usize,
-                inst_count: usize,
-                total_pkgs: Option<usize>,
-            }
-            impl LogFormatter {
-                fn new() -> Self {
-                    Self {
-                        dl_count: 0,
-                        inst_count: 0,
-                        total_pkgs: None,
-                    }
-                }
-                // Returns true if the line was consumed (i.e., do not forward raw)
-                fn handle_line<F: FnMut(String)>(&mut self, line: &str, mut send: F) -> bool {
-                    let t = line.trim_start();
-                    // Parse total package hint: "Packages (780) ..."
-                    if self.total_pkgs.is_none() && t.starts_with("Packages (") {
-                        // extract number until ')'
-                        if let Some(end) = t.find(')')
-                            && let Some(start) = t.find('(')
-                        {
-                            let num = &t[start + 1..end];
-                            if let Ok(n) = num.trim().parse::<usize>() {
-                                self.total_pkgs = Some(n);
-                            }
-                        }
-                        // still forward the header line
-                        return false;
-                    }
+            let thread_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                run_install_sections(
+                    sections,
+                    &tx,
+                    debug_enabled,
+                    &mut SystemExecutor {
+                        debug_enabled,
+                        cancel: Some(cancel.clone()),
+                    },
+                    InstallRunOptions {
+                        checkpoint: Some(&mut recorder),
+                        decisions: Some(&decision_rx),
+                        events: Some(&mut events),
+                        artifacts: artifacts.as_ref(),
+                        cancel: Some(&cancel),
+                        teardown,
+                        recovery_keys,
+                    },
+                )
+            }));
+            match thread_result {
+                Ok(_) => install_thread_debug(debug_enabled, "thread exiting normally"),
+                Err(e) => install_thread_debug(debug_enabled, &format!("thread panicked: {e:?}")),
+            }
+        });
+    }
 
-                    // Match downloading lines: "<pkg> ... downloading..."
-                    if t.ends_with("downloading...") {
-                        self.dl_count += 1;
-                        // Show every 20th download to reduce noise
-                        if self.dl_count.is_multiple_of(20) {
-                            if let Some(total) = self.total_pkgs {
-                                send(format!("Downloading: {} / {}", self.dl_count, total));
-                            } else {
-                                send(format!("Downloading: {} packages", self.dl_count));
-                            }
-                        }
-                        return true; // consume raw line
-                    }
+    /// Open the configured event log; a failure is logged and the install runs without it.
+    fn open_install_event_log(&mut self, target: &str) -> EventLog {
+        let Some(path) = self.install_event_log.clone() else {
+            return EventLog::disabled();
+        };
+        match EventLog::create(&path) {
+            Ok(log) => {
+                self.install_log
+                    .push(format!("Writing install events to {}", path.display()));
+                log.with_machine(&self.hostname_value, target)
+            }
+            Err(e) => {
+                self.install_log.push(format!(
+                    "WARN: could not open event log {}: {e}",
+                    path.display()
+                ));
+                EventLog::disabled()
+            }
+        }
+    }
 
-                    // Match installing lines: "installing <pkg>..."
-                    let ti = t.to_lowercase();
-                    if ti.starts_with("installing ") {
-                        self.inst_count += 1;
-                        // Show every 10th install
-                        if self.inst_count.is_multiple_of(10) {
-                            if let Some(total) = self.total_pkgs {
-                                send(format!("Installing: {} / {}", self.inst_count, total));
-                            } else {
-                                send(format!("Installing: {} packages", self.inst_count));
-                            }
-                        }
-                        return true; // consume raw line
-                    }
+    /// Offer to resume when the host checkpoint matches this target and plan.
+    /// Returns true when the resume popup was opened.
+    fn offer_install_resume(&mut self) -> bool {
+        let Some(target) = self.install_target() else {
+            return false;
+        };
+        let Some(cp) = checkpoint::InstallCheckpoint::load(&checkpoint::host_checkpoint_path())
+        else {
+            return false;
+        };
+        if cp.target != target || cp.completed.is_empty() {
+            return false;
+        }
+        let sections = self.build_install_sections(&target);
+        let Ok(furthest) = checkpoint::plan_resume(Some(&cp), &sections, None) else {
+            return false;
+        };
+        if furthest == 0 {
+            return false;
+        }
+        self.debug_log(&format!(
+            "offer_install_resume: checkpoint for '{target}' resumes at {furthest}"
+        ));
+        self.install_resume_choices = checkpoint::resume_choices(&sections, furthest);
+        self.popup_kind = Some(PopupKind::ResumeInstall);
+        self.popup_open = true;
+        self.popup_items = vec![
+            format!(
+                "Resume from '{}' ({furthest} of {} sections done)",
+                sections[furthest].0,
+                sections.len()
+            ),
+            "Resume from an earlier section...".into(),
+            "Start over (discard checkpoint)".into(),
+        ];
+        self.popup_visible_indices = vec![0, 1, 2];
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        true
+    }
 
-                    false
-                }
-                fn flush<F: FnMut(String)>(&self, mut send: F) {
-                    if self.dl_count > 0 {
-                        if let Some(total) = self.total_pkgs {
-                            send(format!("Downloaded: {} / {}", self.dl_count, total));
-                        } else {
-                            send(format!("Downloaded: {} packages", self.dl_count));
-                        }
-                    }
-                    if self.inst_count > 0 {
-                        if let Some(total) = self.total_pkgs {
-                            send(format!("Installed: {} / {}", self.inst_count, total));
-                        } else {
-                            send(format!("Installed: {} packages", self.inst_count));
-                        }
-                    }
+    /// Handle a choice from the resume popups; `None` starts over.
+    pub fn choose_install_resume(&mut self, start: Option<usize>) {
+        self.close_popup();
+        match start {
+            Some(idx) => self.install_resume_from = Some(idx),
+            None => {
+                let path = checkpoint::host_checkpoint_path();
+                if let Err(e) = std::fs::remove_file(&path)
+                    && e.kind() != std::io::ErrorKind::NotFound
+                {
+                    self.open_info_popup(format!(
+                        "Could not remove checkpoint {}: {e}",
+                        path.display()
+                    ));
+                    return;
                 }
             }
+        }
+        self.install_resume_choices.clear();
+        self.start_install_flow();
+    }
 
-            let debug_tag = "install thread";
-            let mut any_error: Option<String> = None;
-            let dbg = |msg: &str| {
-                if debug_enabled {
-                    let now = chrono::Local::now();
-                    let ts = now.format("%Y-%m-%d %H:%M:%S");
-                    let _ = std::fs::OpenOptions::new()
-                        .create(true)
-                        .append(true)
-                        .open("debug.log")
-                        .and_then(|mut f| {
-                            use std::io::Write;
-                            writeln!(f, "[DEBUG {ts}] {debug_tag}: {msg}")
-                        });
-                }
-            };
-            let thread_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                let send = |tx: &std::sync::mpsc::Sender<InstallLogMsg>, s: String| {
-                    let _ = tx.send(InstallLogMsg::Line(s));
-                };
+    pub fn open_resume_section_popup(&mut self) {
+        let titles = self.install_section_titles_for_resume().unwrap_or_default();
+        self.popup_kind = Some(PopupKind::ResumeSectionSelect);
+        self.popup_open = true;
+        self.popup_items = titles;
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = self.popup_items.len().saturating_sub(1);
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+    }
 
-                send(&tx, "Starting installation...".to_string());
-                dbg(
-                    "env: TERM=dumb NO_COLOR=1 PACMAN_COLOR=never SYSTEMD_PAGER=cat SYSTEMD_COLORS=0 PAGER=cat LESS=FRX",
-                );
-                let thread_panicked = false;
-                'outer: for (title, cmds) in sections.into_iter() {
-                    dbg(&format!("section_start: '{}' ({} cmds)", title, cmds.len()));
-                    send(&tx, format!("::section_start::{title}"));
-                    send(&tx, format!("=== {title} ==="));
-                    for cmd in cmds {
-                        let red = cmd.for_log();
-                        send(&tx, format!("$ {red}"));
-                        dbg(&format!("spawn: '{red}'"));
-                        // Force all output through our pipe using `script` to avoid /dev/tty writes.
-                        // -q: quiet (no start/stop banner), -f: flush, -e: return child status, -c: command
-                        let mut child = match cmd.spawn_script_pipeline(Stdio::piped()) {
-                            Ok(ch) => ch,
-                            Err(e) => {
-                                any_error = Some(format!("Failed to spawn: {red} ({e})"));
-                                send(&tx, any_error.as_ref().unwrap().clone());
-                                dbg(&format!("failed to spawn: {red} ({e})"));
-                                break 'outer;
-                            }
-                        };
-                        if let Err(e) = cmd.write_passphrase_to_stdin(&mut child) {
-                            any_error =
-                                Some(format!("Failed to pass LUKS passphrase: {red} ({e})"));
-                            send(&tx, any_error.as_ref().unwrap().clone());
-                            dbg(&format!("stdin error: {red} ({e})"));
-                            break 'outer;
-                        }
-                        let thin_pacstrap = cmd.is_thin_pacstrap();
-                        let mut fmt = LogFormatter::new();
-                        if let Some(stdout) = child.stdout.take() {
-                            let mut line_buf = String::new();
-                            let mut byte_buf = Vec::new();
-                            let mut scratch = [0u8; 4096];
-                            if let Err(e) = pump_install_stdout(
-                                stdout,
-                                &mut line_buf,
-                                &mut byte_buf,
-                                &mut scratch,
-                                |raw| {
-                                    let clean =
-                                        crate::common::utils::sanitize_terminal_output_line(raw);
-                                    if clean.is_empty() {
-                                        return;
-                                    }
-                                    if thin_pacstrap {
-                                        let mut sent = false;
-                                        if fmt.handle_line(&clean, |msg| {
-                                            sent = true;
-                                            send(&tx, msg);
-                                        }) {
-                                            return;
-                                        }
-                                        if sent {
-                                            return;
-                                        }
-                                    }
-                                    send(&tx, clean);
-                                },
-                                |clean| {
-                                    let _ =
-                                        tx.send(InstallLogMsg::ReplaceLastLine(clean.to_string()));
-                                },
-                            ) {
-                                dbg(&format!("error reading child stdout: {e}"));
-                            }
-                        } else {
-                            dbg("stdout piping unavailable (child.stdout None)");
-                        }
-                        if thin_pacstrap {
-                            fmt.flush(|msg| send(&tx, msg));
-                        }
-                        match child.wait() {
-                            Ok(st) if st.success() => {}
-                            Ok(st) => {
-                                let code = st.code().unwrap_or(-1);
-                                any_error = Some(format!("Command failed (exit {code}): {red}"));
-                                send(&tx, any_error.as_ref().unwrap().clone());
-                                dbg(&format!("command failed (exit {code}): {red}"));
-                                break 'outer;
-                            }
-                            Err(e) => {
-                                any_error = Some(format!("Failed to wait: {red} ({e})"));
-                                send(&tx, any_error.as_ref().unwrap().clone());
-                                dbg(&format!("failed to wait: {red} ({e})"));
-                                break 'outer;
-                            }
-                        }
-                    }
-                    dbg(&format!("section_done: '{title}'"));
-                    send(&tx, format!("::section_done::{title}"));
-                    send(&tx, String::new());
-                }
-                if any_error.is_none() {
-                    send(&tx, "Installation completed.".to_string());
-                }
-                thread_panicked
-            }));
-            match thread_result {
-                Ok(_) => dbg("thread exiting normally"),
-                Err(e) => dbg(&format!("thread panicked: {e:?}")),
-            }
-        });
+    fn install_section_titles_for_resume(&self) -> Option<Vec<String>> {
+        let target = self.install_target()?;
+        let sections = self.build_install_sections(&target);
+        Some(
+            self.install_resume_choices
+                .iter()
+                .filter_map(|&i| sections.get(i).map(|(t, _)| t.clone()))
+                .collect(),
+        )
+    }
+
+    /// Called once the install thread ended without completing every section.
+    pub fn handle_install_failed(&mut self) {
+        let section = self
+            .install_current_section
+            .and_then(|i| self.install_section_titles.get(i).cloned())
+            .unwrap_or_else(|| "unknown section".into());
+        let reason = self
+            .install_log
+            .iter()
+            .rev()
+            .find(|l| l.starts_with("Command failed") || l.starts_with("Failed to"))
+            .cloned()
+            .unwrap_or_default();
+        let cleanup = self
+            .install_log
+            .iter()
+            .rev()
+            .find(|l| l.starts_with(TEARDOWN_SUMMARY))
+            .cloned()
+            .unwrap_or_else(|| {
+                format!("{TEARDOWN_SUMMARY}the target was not released; check /mnt before installing again.")
+            });
+        self.debug_log(&format!("handle_install_failed: section='{section}'"));
+        // Back to the menu with every selection intact
+        self.install_section_titles.clear();
+        self.install_section_done.clear();
+        self.install_current_section = None;
+        if self
+            .install_cancel
+            .take()
+            .is_some_and(|cancel| cancel.is_cancelled())
+        {
+            self.open_info_popup(format!(
+                "Installation cancelled during '{section}'.\n{cleanup}\n\nYour selections are unchanged; choose Install to start again."
+            ));
+            return;
+        }
+        self.open_info_popup(format!(
+            "Installation failed during '{section}'.\n{reason}\n\nCompleted sections were saved to {}.\nFix the cause, then choose Install again to resume.",
+            checkpoint::host_checkpoint_path().display()
+        ));
     }
 
     fn select_target_and_run_prechecks(&mut self) -> Option<String> {
@@ -421,17 +470,23 @@ impl AppState {
             "select_target_and_run_prechecks: target='{target}'"
         ));
 
-        if self.disk_has_mounted_partitions(&target) {
+        // RAID members are partitioned like the target
+        let disks = self.target_disks();
+        if let Some(disk) = disks.iter().find(|d| self.disk_has_mounted_partitions(d)) {
             self.debug_log(&format!(
-                "select_target_and_run_prechecks: mounted partitions detected on {target}"
+                "select_target_and_run_prechecks: mounted partitions detected on {disk}"
             ));
             self.open_info_popup(format!(
-                "Device {target} has mounted partitions. Unmount before proceeding."
+                "Device {disk} has mounted partitions. Unmount before proceeding."
             ));
             return None;
         }
 
-        if !self.disks_wipe && self.disk_freespace_low(&target) {
+        // Installing alongside needs the free space instead (checked by the planner)
+        if !self.disks_wipe
+            && !self.alongside_selected()
+            && disks.iter().any(|d| self.disk_freespace_low(d))
+        {
             self.debug_log(
                 "select_target_and_run_prechecks: low free space and wipe disabled -> opening WipeConfirm",
             );
@@ -448,7 +503,7 @@ impl AppState {
         Some(target)
     }
 
-    fn validate_install_requirements(&self) -> Option<String> {
+    pub(crate) fn validate_install_requirements(&self) -> Option<String> {
         let mut issues: Vec<String> = Vec::new();
 
         // Locales: keyboard, language, encoding must not be <none>
@@ -488,6 +543,10 @@ impl AppState {
                 );
             }
         }
+        if let Some(reason) = crate::app::kernels::kernel_parameters_error(&self.kernel_parameters)
+        {
+            issues.push(format!("Extra kernel parameters: {reason}."));
+        }
         if self.bootloader_index == 0 && !self.is_uefi() {
             issues.push(
                 "systemd-boot requires UEFI firmware. In BIOS/legacy mode, choose GRUB.".into(),
@@ -499,6 +558,11 @@ impl AppState {
                     .into(),
             );
         }
+        if self.bootloader_index == 4 && !self.is_uefi() {
+            issues.push(
+                "rEFInd requires UEFI firmware. In BIOS/legacy mode, choose GRUB or Limine.".into(),
+            );
+        }
         if self.bootloader_index == 2
             && self.is_uefi()
             && !self.uki_enabled
@@ -511,6 +575,61 @@ impl AppState {
                     .into(),
             );
         }
+        if self.bootloader_index == 4
+            && self.is_uefi()
+            && !self.uki_enabled
+            && let Ok(sp) = StoragePlanner::compile(self)
+            && sp.esp_chroot_mountpoint() == "/efi"
+        {
+            issues.push(
+                "rEFInd without UKI requires the ESP mounted at /boot (refind_linux.conf and the \
+                 kernels must sit on the ESP). Either mount the ESP at /boot, or enable Unified \
+                 Kernel Images."
+                    .into(),
+            );
+        }
+        if self.secure_boot_signing() == SecureBootSigning::Sbctl {
+            if !self.is_uefi() {
+                issues.push("Secure Boot signing with sbctl requires UEFI firmware.".into());
+            } else if self.bootloader_index == 1 {
+                issues.push(
+                    "Secure Boot signing with sbctl needs systemd-boot, EFISTUB, Limine or rEFInd: \
+                     GRUB loads modules that sbctl cannot sign. Use shim + MOK for GRUB."
+                        .into(),
+                );
+            }
+            if self.is_secure_boot_enabled() && !self.secure_boot_setup_mode {
+                issues.push(
+                    "Secure Boot is enabled and the firmware is not in Setup Mode: the sbctl keys \
+                     cannot be enrolled, so the installed system would not boot. Clear the Secure \
+                     Boot keys in the firmware setup (Setup Mode), disable Secure Boot first, or \
+                     choose shim + MOK signing."
+                        .into(),
+                );
+            }
+        }
+        if self.secure_boot_signing() == SecureBootSigning::Shim {
+            if !self.is_uefi() {
+                issues.push("Secure Boot signing with shim requires UEFI firmware.".into());
+            } else if self.bootloader_index > 1 {
+                issues.push(
+                    "Secure Boot signing with shim + MOK supports GRUB and systemd-boot only."
+                        .into(),
+                );
+            }
+        }
+        if self.secure_boot_signing() != SecureBootSigning::None
+            && self.is_uefi()
+            && StoragePlanner::compile(self).is_ok_and(|sp| sp.has_tpm2_enrollment())
+        {
+            issues.push(format!(
+                "TPM2 unlock cannot be combined with {} Secure Boot signing: the key is sealed \
+                 against PCR 7 on the live system, and the signing setup changes what the \
+                 firmware measures into PCR 7, so the TPM would never release it. Disable TPM2 \
+                 unlock (enroll it after the first boot) or choose no signing.",
+                self.secure_boot_signing().name()
+            ));
+        }
         if self.bootloader_index == 2 && self.is_secure_boot_enabled() && !self.uki_enabled {
             issues.push(
                 "Secure Boot with Efistub (experimental) requires Unified Kernel Images (UKI). \
@@ -575,7 +694,7 @@ impl AppState {
         }
     }
 
-    fn build_install_sections(&self, target: &str) -> Vec<(String, Vec<InstallCmd>)> {
+    pub fn build_install_sections(&self, target: &str) -> Vec<(String, Vec<InstallCmd>)> {
         let mut sections: Vec<(String, Vec<InstallCmd>)> = Vec::new();
 
         // Compile the storage plan (validated at start_install time, safe to unwrap-or here)
@@ -618,16 +737,8 @@ impl AppState {
         // Pre-cleanup to avoid device busy if re-running installer or previous mounts exist.
         // Skip for pre-mounted mode — the user's mounts must stay intact.
         if storage_plan.mode != crate::core::storage::StorageMode::PreMounted {
-            let mut cleanup_cmds = vec![
-                InstallCmd::shell("swapoff -a || true"),
-                InstallCmd::shell("umount -R /mnt 2>/dev/null || true"),
-            ];
-            // Close any stale LUKS mappings from a previous installer run
-            for mapper in storage_plan.luks_mapper_names() {
-                cleanup_cmds.push(InstallCmd::shell(format!(
-                    "cryptsetup close {mapper} 2>/dev/null || true"
-                )));
-            }
+            // Also closes any stale LUKS mappings from a previous installer run
+            let mut cleanup_cmds = storage_plan.teardown_commands();
             cleanup_cmds.push(InstallCmd::shell("udevadm settle || true"));
             sections.push(("Pre-cleanup".into(), cleanup_cmds));
         }
@@ -707,6 +818,7 @@ impl AppState {
             .commands
             .into_iter()
             .map(InstallCmd::shell)
+            .chain(crate::core::services::bootloader::BootloaderService::mok_enroll_cmd(self))
             .collect(),
         ));
         sections.push((
@@ -717,6 +829,12 @@ impl AppState {
                 .map(InstallCmd::shell)
                 .collect(),
         ));
+        for hook in hooks::apply_hooks(&mut sections, &self.install_hooks) {
+            self.debug_log(&format!(
+                "build_install_sections: hook '{}' skipped, section not in this plan",
+                hook.key()
+            ));
+        }
         // Log assembled sections summary
         let summary: String = sections
             .iter()
@@ -733,7 +851,7 @@ impl AppState {
 
     // Background thread streams logs into AppState via mpsc
 
-    fn disk_has_mounted_partitions(&self, dev: &str) -> bool {
+    pub(crate) fn disk_has_mounted_partitions(&self, dev: &str) -> bool {
         let output = std::process::Command::new("lsblk")
             .args(["-J", "-o", "PATH,MOUNTPOINT"])
             .output();
@@ -761,7 +879,7 @@ impl AppState {
         false
     }
 
-    fn disk_freespace_low(&self, dev: &str) -> bool {
+    pub(crate) fn disk_freespace_low(&self, dev: &str) -> bool {
         if let Some(found) = self.disks_devices.iter().find(|d| d.path == dev)
             && !found.freespace.is_empty()
             && found.freespace != "-"
@@ -877,3 +995,538 @@ impl AppState {
         cmds
     }
 }
+
+// Lightweight formatter to compact pacstrap logs
+struct LogFormatter {
+    dl_count: usize,
+    inst_count: usize,
+    total_pkgs: Option<usize>,
+}
+
+impl LogFormatter {
+    fn new() -> Self {
+        Self {
+            dl_count: 0,
+            inst_count: 0,
+            total_pkgs: None,
+        }
+    }
+    // Returns true if the line was consumed (i.e., do not forward raw)
+    fn handle_line<F: FnMut(String)>(&mut self, line: &str, mut send: F) -> bool {
+        let t = line.trim_start();
+        // Parse total package hint: "Packages (780) ..."
+        if self.total_pkgs.is_none() && t.starts_with("Packages (") {
+            // extract number until ')'
+            if let Some(end) = t.find(')')
+                && let Some(start) = t.find('(')
+            {
+                let num = &t[start + 1..end];
+                if let Ok(n) = num.trim().parse::<usize>() {
+                    self.total_pkgs = Some(n);
+                }
+            }
+            // still forward the header line
+            return false;
+        }
+
+        // Match downloading lines: "<pkg> ... downloading..."
+        if t.ends_with("downloading...") {
+            self.dl_count += 1;
+            // Show every 20th download to reduce noise
+            if self.dl_count.is_multiple_of(20) {
+                if let Some(total) = self.total_pkgs {
+                    send(format!("Downloading: {} / {}", self.dl_count, total));
+                } else {
+                    send(format!("Downloading: {} packages", self.dl_count));
+                }
+            }
+            return true; // consume raw line
+        }
+
+        // Match installing lines: "installing <pkg>..."
+        let ti = t.to_lowercase();
+        if ti.starts_with("installing ") {
+            self.inst_count += 1;
+            // Show every 10th install
+            if self.inst_count.is_multiple_of(10) {
+                if let Some(total) = self.total_pkgs {
+                    send(format!("Installing: {} / {}", self.inst_count, total));
+                } else {
+                    send(format!("Installing: {} packages", self.inst_count));
+                }
+            }
+            return true; // consume raw line
+        }
+
+        false
+    }
+    fn flush<F: FnMut(String)>(&self, mut send: F) {
+        if self.dl_count > 0 {
+            if let Some(total) = self.total_pkgs {
+                send(format!("Downloaded: {} / {}", self.dl_count, total));
+            } else {
+                send(format!("Downloaded: {} packages", self.dl_count));
+            }
+        }
+        if self.inst_count > 0 {
+            if let Some(total) = self.total_pkgs {
+                send(format!("Installed: {} / {}", self.inst_count, total));
+            } else {
+                send(format!("Installed: {} packages", self.inst_count));
+            }
+        }
+    }
+}
+
+pub(super) fn install_thread_debug(debug_enabled: bool, msg: &str) {
+    if debug_enabled {
+        let now = chrono::Local::now();
+        let ts = now.format("%Y-%m-%d %H:%M:%S");
+        let _ = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open("debug.log")
+            .and_then(|mut f| {
+                use std::io::Write;
+                writeln!(f, "[DEBUG {ts}] install thread: {msg}")
+            });
+    }
+}
+
+/// Optional parts of an install run.
+#[derive(Default)]
+pub struct InstallRunOptions<'a> {
+    /// Record every completed (or failed) section so the run can be resumed later.
+    pub checkpoint: Option<&'a mut CheckpointRecorder>,
+    /// Where to ask how to continue after a failed command.
+    pub decisions: Option<&'a Receiver<FailureAction>>,
+    /// Structured JSONL events.
+    pub events: Option<&'a mut EventLog>,
+    /// Install record written into the target after a successful run.
+    pub artifacts: Option<&'a InstallArtifacts>,
+    /// Checked between commands; once cancelled the run stops and runs `teardown`.
+    pub cancel: Option<&'a CancelToken>,
+    /// Commands that release the target after a cancelled run.
+    pub teardown: Vec<InstallCmd>,
+    /// LUKS recovery keys in the installed system, listed in the completion report.
+    pub recovery_keys: Vec<String>,
+}
+
+/// Installed-system paths of the recovery keys `plan` generates.
+pub fn recovery_key_report_paths(plan: &StoragePlan) -> Vec<String> {
+    plan.recovery_key_mappers()
+        .iter()
+        .map(|mapper| LuksEnrollment::installed_recovery_key_path(mapper))
+        .collect()
+}
+
+/// Error returned by a cancelled run.
+const INSTALL_CANCELLED: &str = "Installation cancelled by the user";
+/// Event section name of the teardown commands.
+const TEARDOWN_SECTION: &str = "Cancel cleanup";
+/// Prefix of the log line summarising what the teardown released.
+const TEARDOWN_SUMMARY: &str = "Cleanup: ";
+
+/// What a successful teardown command released, for the cancel summary.
+fn describe_teardown_step(cmd: &str) -> String {
+    let mut words = cmd.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("swapoff"), ..) => "swap turned off".into(),
+        (Some("umount"), ..) => "/mnt unmounted".into(),
+        (Some("zpool"), Some("export"), Some(pool)) => format!("ZFS pool {pool} exported"),
+        (Some("cryptsetup"), Some("close"), Some(mapper)) => {
+            format!("LUKS mapping {mapper} closed")
+        }
+        (Some("vgchange"), Some("-an"), Some(vg)) => format!("volume group {vg} deactivated"),
+        (Some("mdadm"), Some("--stop"), Some(md)) => format!("RAID array {md} stopped"),
+        _ => format!("'{cmd}' ran"),
+    }
+}
+
+/// One line describing what the teardown did; an empty teardown (pre-mounted installs)
+/// leaves the target as-is.
+fn teardown_summary(released: &[String], failed: &[String]) -> String {
+    if released.is_empty() && failed.is_empty() {
+        return format!("{TEARDOWN_SUMMARY}the target was left as-is (nothing to release).");
+    }
+    let mut summary = TEARDOWN_SUMMARY.to_string();
+    if !released.is_empty() {
+        summary.push_str(&format!("{}.", released.join(", ")));
+    }
+    if !failed.is_empty() {
+        if !released.is_empty() {
+            summary.push(' ');
+        }
+        summary.push_str(&format!(
+            "Failed: {}; check the target before installing again.",
+            failed.join(", ")
+        ));
+    }
+    summary
+}
+
+/// Execute install sections in order, streaming section markers and command output to `tx`.
+///
+/// Shared by the TUI install thread and the headless `--unattended` runner; commands run
+/// through `executor` ([`SystemExecutor`] on a real install). Without
+/// `decisions` the run stops at the first failing command and returns its error message;
+/// with it, a [`InstallLogMsg::CommandFailed`] is sent and the thread waits for a
+/// [`FailureAction`]. See [`InstallRunOptions`] for checkpoints, events, the install
+/// record and cancellation.
+pub fn run_install_sections(
+    sections: Vec<(String, Vec<InstallCmd>)>,
+    tx: &Sender<InstallLogMsg>,
+    debug_enabled: bool,
+    executor: &mut dyn CommandExecutor,
+    options: InstallRunOptions<'_>,
+) -> Result<(), String> {
+    let InstallRunOptions {
+        mut checkpoint,
+        decisions,
+        events,
+        artifacts,
+        cancel,
+        teardown,
+        recovery_keys,
+    } = options;
+    let mut disabled = EventLog::disabled();
+    let mut run = SectionRunner {
+        executor,
+        tx,
+        debug_enabled,
+        decisions,
+        cancel,
+        events: events.unwrap_or(&mut disabled),
+        warnings: Vec::new(),
+        transcript: Vec::new(),
+    };
+    let started = Instant::now();
+    let titles = sections.iter().map(|(t, _)| t.clone()).collect();
+    let result = run.events.install_start(titles);
+    run.event_result(result);
+
+    run.send("Starting installation...".to_string());
+    run.dbg(
+        "env: TERM=dumb NO_COLOR=1 PACMAN_COLOR=never SYSTEMD_PAGER=cat SYSTEMD_COLORS=0 PAGER=cat LESS=FRX",
+    );
+    for (title, cmds) in sections.into_iter() {
+        run.dbg(&format!("section_start: '{}' ({} cmds)", title, cmds.len()));
+        run.send(format!("::section_start::{title}"));
+        run.send(format!("=== {title} ==="));
+        run.event(InstallEvent::SectionStart {
+            section: title.clone(),
+        });
+        let section_started = Instant::now();
+        if let Err(e) = run.run_section(&title, &cmds) {
+            if let Some(cp) = checkpoint.as_deref_mut() {
+                let result = cp.section_failed(&title);
+                run.record_checkpoint(result);
+            }
+            let status = if run.cancelled() {
+                EventStatus::Cancelled
+            } else {
+                EventStatus::Failed
+            };
+            run.event(InstallEvent::SectionEnd {
+                section: title,
+                status,
+                duration_ms: elapsed_ms(section_started),
+            });
+            if status == EventStatus::Cancelled {
+                run.teardown(&teardown);
+            }
+            run.event(InstallEvent::InstallEnd {
+                status,
+                duration_ms: elapsed_ms(started),
+                warnings: run.warnings.len(),
+                error: Some(e.clone()),
+            });
+            return Err(e);
+        }
+        if let Some(cp) = checkpoint.as_deref_mut() {
+            let result = cp.section_done(&title, &cmds);
+            run.record_checkpoint(result);
+        }
+        run.dbg(&format!("section_done: '{title}'"));
+        run.send(format!("::section_done::{title}"));
+        run.send(String::new());
+        run.event(InstallEvent::SectionEnd {
+            section: title,
+            status: EventStatus::Completed,
+            duration_ms: elapsed_ms(section_started),
+        });
+    }
+    if let Some(cp) = checkpoint {
+        let result = cp.finish();
+        run.record_checkpoint(result);
+    }
+    let mut report = if run.warnings.is_empty() {
+        vec!["Installation completed.".to_string()]
+    } else {
+        vec![format!(
+            "Installation completed with {} warning(s):",
+            run.warnings.len()
+        )]
+    };
+    report.extend(run.warnings.iter().map(|w| format!("  - {w}")));
+    report.extend(recovery_keys.iter().map(|path| {
+        format!(
+            "LUKS recovery key saved to {path} in the installed system; copy it somewhere safe."
+        )
+    }));
+    if let Some(artifacts) = artifacts {
+        let mut transcript = run.transcript.clone();
+        transcript.extend(report.iter().cloned());
+        match artifacts.write(&transcript) {
+            Ok(dir) => run.send(format!(
+                "Saved the install log, config and storage plan to {}",
+                dir.display()
+            )),
+            Err(e) => {
+                run.dbg(&format!("install record: {e}"));
+                run.send(format!("WARN: could not save the install record: {e}"));
+                run.event(InstallEvent::Warning {
+                    section: None,
+                    message: format!("install record: {e}"),
+                });
+            }
+        }
+    }
+    for line in report {
+        run.send(line);
+    }
+    run.event(InstallEvent::InstallEnd {
+        status: EventStatus::Completed,
+        duration_ms: elapsed_ms(started),
+        warnings: run.warnings.len(),
+        error: None,
+    });
+    Ok(())
+}
+
+/// In-band markers for the TUI progress list; never part of the install record.
+fn is_log_marker(line: &str) -> bool {
+    line.starts_with("::section_start::")
+        || line.starts_with("::section_done::")
+        || line.starts_with("::warning::")
+}
+
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+/// State shared by the sections of one install run.
+struct SectionRunner<'a> {
+    executor: &'a mut dyn CommandExecutor,
+    tx: &'a Sender<InstallLogMsg>,
+    debug_enabled: bool,
+    decisions: Option<&'a Receiver<FailureAction>>,
+    cancel: Option<&'a CancelToken>,
+    events: &'a mut EventLog,
+    /// Skipped commands, listed in the final report.
+    warnings: Vec<String>,
+    /// Log lines sent so far (without markers), for the install record.
+    transcript: Vec<String>,
+}
+
+impl SectionRunner<'_> {
+    fn dbg(&self, msg: &str) {
+        install_thread_debug(self.debug_enabled, msg);
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_some_and(CancelToken::is_cancelled)
+    }
+
+    fn send(&mut self, line: String) {
+        if !is_log_marker(&line) {
+            self.transcript.push(line.clone());
+        }
+        let _ = self.tx.send(InstallLogMsg::Line(line));
+    }
+
+    fn event(&mut self, event: InstallEvent) {
+        let result = self.events.emit(event);
+        self.event_result(result);
+    }
+
+    fn event_result(&mut self, result: Result<(), String>) {
+        if let Err(e) = result {
+            self.dbg(&format!("event log: {e}"));
+            self.send(format!(
+                "WARN: event log: {e} (no further events are written)"
+            ));
+        }
+    }
+
+    fn record_checkpoint(&mut self, result: Result<(), String>) {
+        if let Err(e) = result {
+            self.dbg(&format!("checkpoint: {e}"));
+            self.send(format!("WARN: checkpoint: {e}"));
+            self.event(InstallEvent::Warning {
+                section: None,
+                message: format!("checkpoint: {e}"),
+            });
+        }
+    }
+
+    /// Run one section's commands, asking `decisions` how to continue after a failure.
+    fn run_section(&mut self, title: &str, cmds: &[InstallCmd]) -> Result<(), String> {
+        for planned in cmds {
+            if self.cancelled() {
+                return Err(INSTALL_CANCELLED.into());
+            }
+            let mut cmd = planned.clone();
+            while let Err(message) = self.run_command(title, &cmd) {
+                self.send(message.clone());
+                if self.cancelled() {
+                    return Err(INSTALL_CANCELLED.into());
+                }
+                let Some(rx) = self.decisions else {
+                    return Err(message);
+                };
+                let _ = self.tx.send(InstallLogMsg::CommandFailed(CommandFailure {
+                    section: title.to_string(),
+                    command: cmd.for_log(),
+                    editable: cmd.editable_text(),
+                    message: message.clone(),
+                }));
+                match rx.recv() {
+                    Ok(FailureAction::Retry) => self.send("Retrying...".into()),
+                    Ok(FailureAction::RetryWith(edited)) => {
+                        self.send("Retrying with edited command...".into());
+                        cmd = InstallCmd::shell(edited);
+                    }
+                    Ok(FailureAction::Skip) => {
+                        let warning = format!("skipped in '{title}': {}", cmd.for_log());
+                        self.send(format!("WARN: {warning}"));
+                        self.send(format!("::warning::{warning}"));
+                        self.event(InstallEvent::Warning {
+                            section: Some(title.to_string()),
+                            message: warning.clone(),
+                        });
+                        self.warnings.push(warning);
+                        break;
+                    }
+                    Ok(FailureAction::Abort) | Err(_) => return Err(message),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Release the target after a cancelled run. Failures are reported and the remaining
+    /// commands still run.
+    fn teardown(&mut self, cmds: &[InstallCmd]) {
+        self.send(format!("{INSTALL_CANCELLED}; releasing the target..."));
+        let mut released = Vec::new();
+        let mut failed = Vec::new();
+        for cmd in cmds {
+            match self.run_command(TEARDOWN_SECTION, cmd) {
+                Ok(()) => released.push(describe_teardown_step(&cmd.for_log())),
+                Err(message) => {
+                    self.send(format!("WARN: {message}"));
+                    failed.push(cmd.for_log());
+                }
+            }
+        }
+        self.send(teardown_summary(&released, &failed));
+        self.send("Installation cancelled.".into());
+    }
+
+    /// Run one command through the executor and stream its output. Returns the failure
+    /// message.
+    fn run_command(&mut self, section: &str, cmd: &InstallCmd) -> Result<(), String> {
+        let red = cmd.for_log();
+        self.send(format!("$ {red}"));
+        self.dbg(&format!("spawn: '{red}'"));
+        self.event(InstallEvent::CommandStart {
+            section: section.to_string(),
+            command: red.clone(),
+        });
+        let thin_pacstrap = cmd.is_thin_pacstrap();
+        let mut fmt = LogFormatter::new();
+        let tx = self.tx;
+        let transcript = &mut self.transcript;
+        let mut send = |s: String| {
+            transcript.push(s.clone());
+            let _ = tx.send(InstallLogMsg::Line(s));
+        };
+        let mut event_err: Option<String> = None;
+        let events = &mut *self.events;
+        let mut record = |event: InstallEvent| {
+            if let Err(e) = events.emit(event) {
+                event_err.get_or_insert(e);
+            }
+        };
+        let started = Instant::now();
+        let result = self.executor.run(cmd, &mut |out| match out {
+            CommandOutput::Line(raw) => {
+                let clean = crate::common::utils::sanitize_terminal_output_line(raw);
+                if clean.is_empty() {
+                    return;
+                }
+                if let Some(message) = clean.strip_prefix("WARN:") {
+                    record(InstallEvent::Warning {
+                        section: Some(section.to_string()),
+                        message: message.trim().to_string(),
+                    });
+                }
+                record(InstallEvent::Output {
+                    section: section.to_string(),
+                    line: clean.clone(),
+                });
+                if thin_pacstrap {
+                    let mut sent = false;
+                    if fmt.handle_line(&clean, |msg| {
+                        sent = true;
+                        send(msg);
+                    }) {
+                        return;
+                    }
+                    if sent {
+                        return;
+                    }
+                }
+                send(clean);
+            }
+            CommandOutput::Progress(clean) => {
+                let _ = tx.send(InstallLogMsg::ReplaceLastLine(clean.to_string()));
+            }
+        });
+        let duration_ms = elapsed_ms(started);
+        if thin_pacstrap {
+            fmt.flush(&mut send);
+        }
+        if let Some(e) = event_err {
+            self.event_result(Err(e));
+        }
+        let (exit_code, message) = match result {
+            Ok(0) => (Some(0), None),
+            Ok(code) => (
+                Some(code),
+                Some(format!("Command failed (exit {code}): {red}")),
+            ),
+            Err(ExecError::Spawn(e)) => (None, Some(format!("Failed to spawn: {red} ({e})"))),
+            Err(ExecError::Stdin(e)) => (
+                None,
+                Some(format!("Failed to pass passphrase: {red} ({e})")),
+            ),
+            Err(ExecError::Wait(e)) => (None, Some(format!("Failed to wait: {red} ({e})"))),
+        };
+        self.event(InstallEvent::CommandEnd {
+            section: section.to_string(),
+            command: red,
+            exit_code,
+            duration_ms,
+            error: message.clone(),
+        });
+        match message {
+            None => Ok(()),
+            Some(message) => {
+                self.dbg(&message);
+                Err(message)
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Site-specific hook commands injected before or after install sections.
+//!
+//! Configured as `[hooks]` lists keyed by `"before <section>"` / `"after <section>"`; each
+//! hook runs on the live host or inside `arch-chroot /mnt`.
+
+use crate::common::install_cmd::InstallCmd;
+
+/// Every section title `build_install_sections` can produce, in plan order.
+pub const HOOK_SECTIONS: [&str; 12] = [
+    "Mirrors & Repos",
+    "Pre-cleanup",
+    "Partitioning",
+    "Volume stack setup (LVM/RAID)",
+    "Mounting",
+    "System pre-install",
+    "System package installations",
+    "fstab and checks",
+    "System configuration",
+    "Network configuration",
+    "Bootloader setup",
+    "User setup",
+];
+
+/// Index in [`HOOK_SECTIONS`] of the section that pacstraps the target; `/mnt` has no
+/// system to chroot into before it finishes.
+const PACSTRAP_SECTION: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookWhen {
+    Before,
+    After,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookTarget {
+    Host,
+    Chroot,
+}
+
+impl HookTarget {
+    pub const NAMES: [&str; 2] = ["host", "chroot"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "host" => Some(Self::Host),
+            "chroot" | "" => Some(Self::Chroot),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Host => "host",
+            Self::Chroot => "chroot",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstallHook {
+    /// Title from [`HOOK_SECTIONS`].
+    pub section: String,
+    pub when: HookWhen,
+    pub target: HookTarget,
+    /// Shell script run under `bash -lc`.
+    pub run: String,
+}
+
+impl InstallHook {
+    /// `"before <section>"` / `"after <section>"`, the key used in the config.
+    pub fn key(&self) -> String {
+        match self.when {
+            HookWhen::Before => format!("before {}", self.section),
+            HookWhen::After => format!("after {}", self.section),
+        }
+    }
+
+    pub fn command(&self) -> InstallCmd {
+        match self.target {
+            HookTarget::Host => InstallCmd::shell(self.run.clone()),
+            HookTarget::Chroot => {
+                let escaped = self.run.replace('\'', "'\\''");
+                InstallCmd::shell(format!("arch-chroot /mnt bash -lc '{escaped}'"))
+            }
+        }
+    }
+}
+
+/// Whether a chroot hook at this position finds an installed system under `/mnt`.
+pub fn chroot_available(when: HookWhen, section: &str) -> bool {
+    match HOOK_SECTIONS.iter().position(|s| *s == section) {
+        Some(i) => i > PACSTRAP_SECTION || (i == PACSTRAP_SECTION && when == HookWhen::After),
+        None => false,
+    }
+}
+
+/// Message for a chroot hook placed before the target is pacstrapped.
+pub fn chroot_unavailable_message(key: &str) -> String {
+    format!(
+        "'{key}' runs before the system is installed, so there is nothing to chroot into; \
+         use target = \"host\" or 'after {}' or later",
+        HOOK_SECTIONS[PACSTRAP_SECTION]
+    )
+}
+
+/// Parse a hook key into its position and canonical section title (case-insensitive).
+pub fn parse_hook_key(key: &str) -> Result<(HookWhen, &'static str), String> {
+    let key = key.trim();
+    let (when, section) = match key.split_once(char::is_whitespace) {
+        Some((w, rest)) if w.eq_ignore_ascii_case("before") => (HookWhen::Before, rest),
+        Some((w, rest)) if w.eq_ignore_ascii_case("after") => (HookWhen::After, rest),
+        _ => {
+            return Err(format!(
+                "hook key '{key}' must start with 'before' or 'after' followed by a section name"
+            ));
+        }
+    };
+    HOOK_SECTIONS
+        .iter()
+        .find(|s| s.eq_ignore_ascii_case(section.trim()))
+        .map(|s| (when, *s))
+        .ok_or_else(|| {
+            format!(
+                "unknown section '{}' (sections: {})",
+                section.trim(),
+                HOOK_SECTIONS.join(", ")
+            )
+        })
+}
+
+/// Insert hook commands at the start or end of their sections. Returns the hooks whose
+/// section is not part of this plan.
+pub fn apply_hooks<'h>(
+    sections: &mut [(String, Vec<InstallCmd>)],
+    hooks: &'h [InstallHook],
+) -> Vec<&'h InstallHook> {
+    let mut unused = Vec::new();
+    for (title, cmds) in sections.iter_mut() {
+        let before: Vec<InstallCmd> = hooks
+            .iter()
+            .filter(|h| h.when == HookWhen::Before && h.section == *title)
+            .map(InstallHook::command)
+            .collect();
+        if !before.is_empty() {
+            cmds.splice(0..0, before);
+        }
+        cmds.extend(
+            hooks
+                .iter()
+                .filter(|h| h.when == HookWhen::After && h.section == *title)
+                .map(InstallHook::command),
+        );
+    }
+    for hook in hooks {
+        if !sections.iter().any(|(t, _)| *t == hook.section) {
+            unused.push(hook);
+        }
+    }
+    unused
+}
@@ -1,5 +1,14 @@
+pub mod artifacts;
+pub mod cancel;
+pub mod checkpoint;
+pub mod events;
+pub mod executor;
 mod flow;
+pub mod hooks;
 mod packages;
+mod recovery;
 mod ui;
 
+pub use flow::{InstallRunOptions, recovery_key_report_paths, run_install_sections};
+pub use recovery::recovery_shell_command;
 pub use ui::draw_install;
@@ -0,0 +1,100 @@
+//! Failure recovery while the install thread waits on a failed command.
+
+use std::process::Command;
+
+use super::checkpoint::is_mountpoint;
+use crate::app::{AppState, PopupKind};
+use crate::common::FailureAction;
+
+/// Entries of the recovery popup, by index.
+const RECOVERY_RETRY: usize = 0;
+const RECOVERY_EDIT: usize = 1;
+const RECOVERY_SKIP: usize = 2;
+const RECOVERY_SHELL: usize = 3;
+const RECOVERY_ABORT: usize = 4;
+
+impl AppState {
+    pub fn open_install_failure_popup(&mut self) {
+        let Some(failure) = &self.install_failure else {
+            return;
+        };
+        let edit = if failure.editable.is_some() {
+            "Edit the command and retry"
+        } else {
+            "Edit and retry (unavailable: the command carries secrets)"
+        };
+        self.popup_kind = Some(PopupKind::InstallFailureRecovery);
+        self.popup_open = true;
+        self.popup_items = vec![
+            "Retry the command".into(),
+            edit.into(),
+            "Skip it (recorded as a warning)".into(),
+            "Open a shell in arch-chroot /mnt, then come back".into(),
+            "Abort the installation".into(),
+        ];
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = RECOVERY_RETRY;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+    }
+
+    /// Open the command editor; returns false when the failed command cannot be edited.
+    pub fn open_install_failure_edit(&mut self) -> bool {
+        let Some(text) = self
+            .install_failure
+            .as_ref()
+            .and_then(|f| f.editable.clone())
+        else {
+            return false;
+        };
+        self.close_popup();
+        self.popup_kind = Some(PopupKind::InstallFailureEdit);
+        self.popup_open = true;
+        self.custom_input_buffer = text;
+        true
+    }
+
+    /// Send the user's decision to the waiting install thread.
+    pub fn resolve_install_failure(&mut self, action: FailureAction) {
+        self.debug_log(&format!("resolve_install_failure: {action:?}"));
+        self.close_popup();
+        self.custom_input_buffer.clear();
+        self.install_failure = None;
+        if let Some(tx) = &self.install_decision_tx {
+            let _ = tx.send(action);
+        }
+    }
+
+    /// Handle Enter on the recovery popup.
+    pub fn handle_install_failure_choice(&mut self, idx: usize) {
+        match idx {
+            RECOVERY_RETRY => self.resolve_install_failure(FailureAction::Retry),
+            RECOVERY_EDIT => {
+                // Not editable: leave the popup open on the same entry
+                let _ = self.open_install_failure_edit();
+            }
+            RECOVERY_SKIP => self.resolve_install_failure(FailureAction::Skip),
+            RECOVERY_SHELL => {
+                // The runner owns the terminal; it suspends the TUI and reopens this popup
+                self.close_popup();
+                self.install_shell_requested = true;
+            }
+            RECOVERY_ABORT => self.resolve_install_failure(FailureAction::Abort),
+            _ => {}
+        }
+    }
+}
+
+/// Interactive shell for fixing a failure: `arch-chroot /mnt` once the target is mounted,
+/// otherwise a login shell on the live system.
+pub fn recovery_shell_command() -> Command {
+    if is_mountpoint(std::path::Path::new("/mnt")) {
+        let mut cmd = Command::new("arch-chroot");
+        cmd.arg("/mnt");
+        cmd
+    } else {
+        let mut cmd = Command::new("bash");
+        cmd.arg("-l");
+        cmd
+    }
+}
@@ -49,13 +49,15 @@ pub fn draw_install(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect)
                 Span::styled(title.clone(), style),
             ]));
         }
-        let left_block = Block::default()
-            .borders(Borders::ALL)
-            .title(if app.install_running {
+        let left_block = Block::default().borders(Borders::ALL).title(
+            if app.install_running && app.install_cancel.is_some() {
+                " Installation in progress (Esc: cancel) "
+            } else if app.install_running {
                 " Installation in progress "
             } else {
                 " Installation status "
-            });
+            },
+        );
         let left_par = Paragraph::new(left_lines)
             .block(left_block)
             .wrap(Wrap { trim: false });
@@ -159,29 +161,44 @@ pub fn draw_install(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect)
         if let Some(p) = &app.disk_encryption_selected_partition {
             items.push(format!("Partition: {p}"));
         }
+        if app.disk_encryption_type_index == 1
+            && let Some(unlock) = app.disk_encryption_unlock_summary()
+        {
+            items.push(format!("Unlock: passphrase, {unlock}"));
+        }
+        if app.disk_encryption_type_index == 1 {
+            items.push(format!("Format: {}", app.luks_format_summary()));
+        }
         let diskenc_items = items;
         push_section_lines(&mut sections, "Disk Encryption", &diskenc_items);
     }
 
-    let swap_items = vec![format!(
-        "{}",
-        if app.swap_enabled {
-            "Enabled"
-        } else {
-            "Disabled"
-        }
-    )];
+    let swap_items = vec![app.swap_summary()];
     push_section_lines(&mut sections, "Swap", &swap_items);
 
-    let boot_items = vec![format!(
+    let mut boot_items = vec![format!(
         "{}",
         match app.bootloader_index {
             0 => "systemd-boot",
             1 => "grub",
             2 => crate::app::bootloader::EFISTUB_SLUG_LABEL,
-            _ => "limine",
+            3 => "limine",
+            _ => "refind",
         }
     )];
+    if app.secure_boot_signing() != crate::app::secure_boot::SecureBootSigning::None {
+        boot_items.push(format!(
+            "Secure Boot signing: {}{}",
+            app.secure_boot_signing().label(),
+            if app.secure_boot_signing() == crate::app::secure_boot::SecureBootSigning::Sbctl
+                && app.secure_boot_microsoft_keys
+            {
+                ", Microsoft keys kept"
+            } else {
+                ""
+            }
+        ));
+    }
     push_section_lines(&mut sections, "Bootloader", &boot_items);
 
     if app.bootloader_index != 1 {
@@ -364,7 +381,10 @@ pub fn draw_install(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect)
     if !app.selected_kernels.is_empty() {
         let mut names: Vec<&str> = app.selected_kernels.iter().map(|s| s.as_str()).collect();
         names.sort_unstable();
-        let kern_items = vec![names.join(", ")];
+        let mut kern_items = vec![names.join(", ")];
+        if let Some(params) = app.extra_kernel_parameters() {
+            kern_items.push(format!("Extra parameters: {params}"));
+        }
         push_section_lines(&mut sections, "Kernels", &kern_items);
     }
 
@@ -1,9 +1,48 @@
 use super::{AppState, Focus};
+use crate::core::types::PopupKind;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
+/// Kernel packages offered on the Kernels screen (and accepted in `kernels.selected`).
+pub const KERNEL_PACKAGES: [&str; 4] = ["linux", "linux-hardened", "linux-lts", "linux-zen"];
+
+/// Cmdline options the installer derives from the storage layout; extra parameters may not set them.
+const MANAGED_KERNEL_PARAMETERS: [&str; 9] = [
+    "root",
+    "rootfstype",
+    "rw",
+    "cryptdevice",
+    "rd.luks.name",
+    "rd.luks.options",
+    "resume",
+    "resume_offset",
+    "initrd",
+];
+
+/// Why `params` cannot go on the kernel command line, or `None` when every parameter is
+/// usable. Parameters are `key` or `key=value` separated by spaces; quotes and shell
+/// metacharacters are rejected because the options are substituted into shell commands.
+pub fn kernel_parameters_error(params: &str) -> Option<String> {
+    for param in params.split_whitespace() {
+        if let Some(c) = param
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || "-_.,:=+/@%~!".contains(*c)))
+        {
+            return Some(format!("'{param}' contains '{c}'"));
+        }
+        let key = param.split('=').next().unwrap_or_default();
+        if key.is_empty() {
+            return Some(format!("'{param}' has no name"));
+        }
+        if MANAGED_KERNEL_PARAMETERS.contains(&key) {
+            return Some(format!("'{key}' is set by the installer"));
+        }
+    }
+    None
+}
+
 impl AppState {
     #[allow(dead_code)]
     pub fn init_kernels(&mut self) {
@@ -19,6 +58,64 @@ impl AppState {
             v.join(", ")
         }
     }
+
+    /// Kernel whose entry boots by default: the chosen one while it is still selected,
+    /// otherwise the first selected kernel.
+    pub fn default_kernel(&self) -> String {
+        self.default_kernel
+            .as_ref()
+            .filter(|k| self.selected_kernels.contains(*k))
+            .or_else(|| self.selected_kernels.iter().next())
+            .cloned()
+            .unwrap_or_else(|| "linux".into())
+    }
+
+    /// Selected kernels in boot menu order: the default kernel first, then the rest.
+    pub fn boot_kernels(&self) -> Vec<String> {
+        let default = self.default_kernel();
+        let mut kernels = vec![default.clone()];
+        kernels.extend(
+            self.selected_kernels
+                .iter()
+                .filter(|k| **k != default)
+                .cloned(),
+        );
+        kernels
+    }
+
+    /// Extra kernel parameters joined by single spaces, or `None` when there are none.
+    pub fn extra_kernel_parameters(&self) -> Option<String> {
+        let params: Vec<&str> = self.kernel_parameters.split_whitespace().collect();
+        (!params.is_empty()).then(|| params.join(" "))
+    }
+
+    pub fn open_kernel_parameters_input(&mut self) {
+        self.popup_kind = Some(PopupKind::KernelParametersInput);
+        self.custom_input_buffer = self.kernel_parameters.clone();
+        self.popup_open = true;
+        self.popup_items.clear();
+        self.popup_visible_indices.clear();
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+    }
+
+    /// Move the default boot entry to the next (or previous) selected kernel.
+    pub fn cycle_default_kernel(&mut self, next: bool) {
+        let kernels: Vec<&String> = self.selected_kernels.iter().collect();
+        if kernels.is_empty() {
+            return;
+        }
+        let current = self.default_kernel();
+        let pos = kernels.iter().position(|k| **k == current).unwrap_or(0);
+        let len = kernels.len();
+        let new_pos = if next {
+            (pos + 1) % len
+        } else {
+            (pos + len - 1) % len
+        };
+        self.default_kernel = Some(kernels[new_pos].clone());
+    }
 }
 
 pub fn draw_kernels(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
@@ -33,7 +130,16 @@ pub fn draw_kernels(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect)
 
     let options = vec![
         (format!("Selected: {}", app.kernels_summary()), 0),
-        ("Continue".to_string(), 1),
+        (format!("Default: {}", app.default_kernel()), 1),
+        (
+            format!(
+                "Extra parameters: {}",
+                app.extra_kernel_parameters()
+                    .unwrap_or_else(|| "none".into())
+            ),
+            2,
+        ),
+        ("Continue".to_string(), 3),
     ];
 
     for (label, idx) in options {
@@ -54,7 +160,7 @@ pub fn draw_kernels(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect)
         } else {
             Style::default().fg(Color::White)
         };
-        let shown = if idx == 1 {
+        let shown = if idx == 3 {
             format!("[ {label} ]")
         } else {
             label
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::process::Command;
 
 use super::{AppState, PopupKind, Screen};
@@ -165,45 +166,14 @@ impl AppState {
         }
 
         // Keyboard layouts
-        if let Ok(output) = Command::new("localectl").arg("list-keymaps").output()
-            && output.status.success()
-        {
-            let text = String::from_utf8_lossy(&output.stdout);
-            let mut items: Vec<String> = text
-                .lines()
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            items.sort();
+        if let Some(items) = probe_keyboard_layouts() {
             self.keyboard_layout_options = items;
         }
 
         // Locale languages and their encodings from /etc/locale.gen
-        // Note: On the Arch ISO, most locales are commented out. We still want
-        // to present them as selectable options, so we parse commented lines too.
-        if let Ok(text) = std::fs::read_to_string("/etc/locale.gen") {
-            let mut set = std::collections::BTreeSet::new();
-            let mut map = std::collections::BTreeMap::new();
-            for raw_line in text.lines() {
-                let trimmed = raw_line.trim();
-                if trimmed.is_empty() {
-                    continue;
-                }
-                // Strip optional leading '#'
-                let l = trimmed.trim_start_matches('#').trim();
-                if l.is_empty() {
-                    continue;
-                }
-                let mut parts = l.split_whitespace();
-                if let Some(locale_spec) = parts.next() {
-                    set.insert(locale_spec.to_string());
-                    if let Some(charmap) = parts.next() {
-                        map.insert(locale_spec.to_string(), charmap.to_string());
-                    }
-                }
-            }
-            self.locale_language_options = set.into_iter().collect();
-            self.locale_language_to_encoding = map;
+        if let Some((languages, encodings)) = probe_locale_gen() {
+            self.locale_language_options = languages;
+            self.locale_language_to_encoding = encodings;
         }
 
         // Encodings
@@ -318,3 +288,51 @@ pub fn draw_locales(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect)
         .wrap(Wrap { trim: false });
     frame.render_widget(content, area);
 }
+
+/// Keyboard layouts from `localectl list-keymaps`, sorted; `None` when the host cannot list them.
+pub(crate) fn probe_keyboard_layouts() -> Option<Vec<String>> {
+    let output = Command::new("localectl")
+        .arg("list-keymaps")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut items: Vec<String> = text
+        .lines()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    items.sort();
+    Some(items)
+}
+
+/// Locale languages and their charmaps from `/etc/locale.gen`; `None` when it is missing.
+///
+/// On the Arch ISO most locales are commented out. They are still selectable, so commented
+/// lines are parsed too.
+pub(crate) fn probe_locale_gen() -> Option<(Vec<String>, BTreeMap<String, String>)> {
+    let text = std::fs::read_to_string("/etc/locale.gen").ok()?;
+    let mut set = std::collections::BTreeSet::new();
+    let mut map = BTreeMap::new();
+    for raw_line in text.lines() {
+        let trimmed = raw_line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        // Strip optional leading '#'
+        let l = trimmed.trim_start_matches('#').trim();
+        if l.is_empty() {
+            continue;
+        }
+        let mut parts = l.split_whitespace();
+        if let Some(locale_spec) = parts.next() {
+            set.insert(locale_spec.to_string());
+            if let Some(charmap) = parts.next() {
+                map.insert(locale_spec.to_string(), charmap.to_string());
+            }
+        }
+    }
+    Some((set.into_iter().collect(), map))
+}
@@ -0,0 +1,284 @@
+use super::{AppState, Focus, LogicalVolumeSpec, PopupKind};
+use crate::core::storage::{LVM_FILESYSTEMS, LvmStacking};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+/// Volumes offered when LVM is first enabled: a fixed-size root and a home taking the rest.
+fn default_logical_volumes() -> Vec<LogicalVolumeSpec> {
+    vec![
+        LogicalVolumeSpec {
+            name: "root".into(),
+            size: "40G".into(),
+            fs: "ext4".into(),
+            mountpoint: Some("/".into()),
+        },
+        LogicalVolumeSpec {
+            name: "home".into(),
+            size: "100%FREE".into(),
+            fs: "ext4".into(),
+            mountpoint: Some("/home".into()),
+        },
+    ]
+}
+
+impl AppState {
+    pub fn lvm_stacking(&self) -> LvmStacking {
+        LvmStacking::from_index(self.lvm_stacking_index)
+    }
+
+    /// True when the installer partitions the disk and builds the LVM screen's volume group.
+    pub fn lvm_selected(&self) -> bool {
+        self.lvm_enabled && self.disks_mode_index < 2
+    }
+
+    /// The logical volume mounted at `/`, when LVM is in use.
+    pub fn lvm_root_volume(&self) -> Option<&LogicalVolumeSpec> {
+        self.lvm_volumes
+            .iter()
+            .find(|lv| lv.mountpoint.as_deref() == Some("/"))
+            .filter(|_| self.lvm_selected())
+    }
+
+    /// Manual partition holding the physical volume (role LVM).
+    pub fn lvm_physical_volume(&self) -> Option<usize> {
+        self.disks_partitions.iter().position(|p| {
+            p.role
+                .as_deref()
+                .is_some_and(|r| r.eq_ignore_ascii_case("LVM"))
+        })
+    }
+
+    /// True when the volume group is encrypted: LUKS on the automatic layout, or the manual
+    /// physical volume marked for encryption.
+    pub fn lvm_encrypted(&self) -> bool {
+        match self.disks_mode_index {
+            0 => self.disk_encryption_type_index == 1,
+            1 => self
+                .lvm_physical_volume()
+                .and_then(|i| self.disks_partitions[i].encrypt)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    pub fn lvm_physical_volume_label(&self) -> String {
+        if self.raid_selected() {
+            return "The /dev/md/root RAID array".into();
+        }
+        if self.disks_mode_index == 0 {
+            return "Rest of the disk (automatic layout)".into();
+        }
+        match self.lvm_physical_volume() {
+            Some(i) => {
+                let size = self.disks_partitions[i].size.as_deref().unwrap_or("");
+                let size = size
+                    .parse::<u64>()
+                    .map(Self::human_bytes)
+                    .unwrap_or_else(|_| size.to_string());
+                format!("Partition {} ({size})", i + 1)
+            }
+            None => "None (create an OTHER partition)".into(),
+        }
+    }
+
+    pub fn toggle_lvm(&mut self) {
+        self.lvm_enabled = !self.lvm_enabled;
+        if self.lvm_enabled && self.lvm_volumes.is_empty() {
+            self.lvm_volumes = default_logical_volumes();
+        }
+    }
+
+    /// Manual mode: move the LVM role to the next (or previous) OTHER partition; the one it
+    /// leaves goes back to OTHER.
+    pub fn cycle_lvm_physical_volume(&mut self, next: bool) {
+        let candidates: Vec<usize> = self
+            .disks_partitions
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| {
+                p.role.as_deref().is_some_and(|r| {
+                    r.eq_ignore_ascii_case("LVM") || r.eq_ignore_ascii_case("OTHER")
+                })
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let current = self.lvm_physical_volume();
+        let len = candidates.len();
+        let target = match current.and_then(|c| candidates.iter().position(|&i| i == c)) {
+            Some(pos) if next => (pos + 1) % len,
+            Some(pos) => (pos + len - 1) % len,
+            None => 0,
+        };
+        if let Some(c) = current {
+            self.disks_partitions[c].role = Some("OTHER".into());
+        }
+        self.disks_partitions[candidates[target]].role = Some("LVM".into());
+    }
+
+    pub fn cycle_lvm_stacking(&mut self) {
+        self.lvm_stacking_index = (self.lvm_stacking_index + 1) % LvmStacking::NAMES.len();
+    }
+
+    /// Focus index of the "Add logical volume" row; "Back" follows it.
+    pub fn lvm_add_row(&self) -> usize {
+        4 + self.lvm_volumes.len()
+    }
+
+    pub fn open_lvm_vg_name_input(&mut self) {
+        self.custom_input_buffer = self.lvm_vg_name.clone();
+        self.open_lvm_text_popup(PopupKind::LvmVolumeGroupName);
+    }
+
+    /// Start the add (`None`) or edit flow: name, size, filesystem, then mountpoint.
+    pub fn start_lvm_volume(&mut self, edit: Option<usize>) {
+        self.lvm_edit_index = edit;
+        self.lvm_draft = edit
+            .and_then(|i| self.lvm_volumes.get(i).cloned())
+            .unwrap_or_else(|| LogicalVolumeSpec {
+                name: String::new(),
+                size: "100%FREE".into(),
+                fs: "ext4".into(),
+                mountpoint: None,
+            });
+        self.custom_input_buffer = self.lvm_draft.name.clone();
+        self.open_lvm_text_popup(PopupKind::LvmVolumeName);
+    }
+
+    pub fn open_lvm_volume_size(&mut self) {
+        self.custom_input_buffer = self.lvm_draft.size.clone();
+        self.open_lvm_text_popup(PopupKind::LvmVolumeSize);
+    }
+
+    pub fn open_lvm_volume_filesystem(&mut self) {
+        self.popup_kind = Some(PopupKind::LvmVolumeFilesystem);
+        self.popup_items = LVM_FILESYSTEMS.iter().map(|s| s.to_string()).collect();
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = LVM_FILESYSTEMS
+            .iter()
+            .position(|f| *f == self.lvm_draft.fs)
+            .unwrap_or(0);
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        self.popup_open = true;
+    }
+
+    pub fn open_lvm_volume_mountpoint(&mut self) {
+        self.custom_input_buffer = self.lvm_draft.mountpoint.clone().unwrap_or_default();
+        self.open_lvm_text_popup(PopupKind::LvmVolumeMountpoint);
+    }
+
+    fn open_lvm_text_popup(&mut self, kind: PopupKind) {
+        self.popup_kind = Some(kind);
+        self.popup_open = true;
+        self.popup_items.clear();
+        self.popup_visible_indices.clear();
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+    }
+
+    pub fn finalize_lvm_volume(&mut self) {
+        let mut lv = std::mem::take(&mut self.lvm_draft);
+        if lv.fs == "linux-swap" {
+            lv.mountpoint = None;
+        }
+        match self.lvm_edit_index.take() {
+            Some(i) if i < self.lvm_volumes.len() => self.lvm_volumes[i] = lv,
+            _ => self.lvm_volumes.push(lv),
+        }
+        self.custom_input_buffer.clear();
+        self.close_popup();
+    }
+
+    pub fn remove_lvm_volume(&mut self, index: usize) {
+        if index < self.lvm_volumes.len() {
+            self.lvm_volumes.remove(index);
+        }
+    }
+}
+
+pub fn draw_lvm(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
+    let title = Span::styled(
+        "Logical Volumes (LVM)",
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut lines: Vec<Line> = vec![Line::from(title), Line::from("")];
+
+    let enabled = app.lvm_enabled;
+    let stacking = if app.lvm_encrypted() {
+        app.lvm_stacking().label().to_string()
+    } else {
+        "None (volume group not encrypted)".to_string()
+    };
+    let mut rows: Vec<(String, bool)> = vec![
+        (
+            format!("LVM: {}", if enabled { "Enabled" } else { "Disabled" }),
+            true,
+        ),
+        (format!("Volume Group: {}", app.lvm_vg_name), enabled),
+        (
+            format!("Physical Volume: {}", app.lvm_physical_volume_label()),
+            enabled,
+        ),
+        (
+            format!("Encryption: {stacking}"),
+            enabled && app.lvm_encrypted(),
+        ),
+    ];
+    for lv in &app.lvm_volumes {
+        rows.push((
+            format!(
+                "  {}  {}  {}  {}",
+                lv.name,
+                lv.size,
+                lv.fs,
+                lv.mountpoint.as_deref().unwrap_or("[swap]")
+            ),
+            enabled,
+        ));
+    }
+    rows.push(("[ Add logical volume ]".into(), enabled));
+    rows.push(("[ Back ]".into(), true));
+
+    let back_row = rows.len() - 1;
+    for (idx, (text, available)) in rows.into_iter().enumerate() {
+        if idx == 4 || idx == back_row {
+            lines.push(Line::from(""));
+        }
+        let is_focused_line = app.lvm_focus_index == idx;
+        let is_active_line = is_focused_line && matches!(app.focus, Focus::Content);
+        let bullet = if is_focused_line { "▶" } else { " " };
+        let style = if is_active_line && available {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else if !available {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{bullet} "), style),
+            Span::styled(text, style),
+        ]));
+    }
+
+    let content = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(match app.focus {
+                    Focus::Content => " Desicion Menu (focused) ",
+                    _ => " Desicion Menu ",
+                }),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(content, area);
+}
@@ -6,6 +6,60 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
+/// Country lines from `reflector --list-countries`, sorted; `None` when reflector is unavailable.
+pub(crate) fn probe_mirror_regions() -> Option<Vec<String>> {
+    let output = Command::new("reflector")
+        .arg("--list-countries")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut items: Vec<String> = text
+        .lines()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    items.sort();
+    Some(items)
+}
+
+/// Country name and two-letter code of a region: a `reflector --list-countries` line such as
+/// `United Kingdom         GB    33`, or just the name or code. The trailing mirror count
+/// changes over time and is dropped.
+fn mirror_region_parts(region: &str) -> (String, Option<String>) {
+    let mut tokens: Vec<&str> = region.split_whitespace().collect();
+    if tokens.len() > 1 && tokens.last().is_some_and(|t| t.parse::<u32>().is_ok()) {
+        tokens.pop();
+    }
+    let code = tokens
+        .last()
+        .filter(|t| t.len() == 2 && t.chars().all(|c| c.is_ascii_uppercase()))
+        .map(|t| t.to_string());
+    if code.is_some() {
+        tokens.pop();
+    }
+    (tokens.join(" "), code)
+}
+
+/// True when the configured `value` names the same country as the region line `option`,
+/// compared by country name or code rather than the whole line with its mirror count.
+pub fn mirror_region_matches(option: &str, value: &str) -> bool {
+    let (option_name, option_code) = mirror_region_parts(option);
+    let (value_name, value_code) = mirror_region_parts(value);
+    match (option_code, value_code) {
+        (Some(oc), Some(vc)) => {
+            oc == vc && (value_name.is_empty() || value_name.eq_ignore_ascii_case(&option_name))
+        }
+        (oc, _) => {
+            !value_name.is_empty()
+                && (value_name.eq_ignore_ascii_case(&option_name)
+                    || oc.is_some_and(|c| value_name.eq_ignore_ascii_case(&c)))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum MirrorsLoadError {
     ListCountries,
@@ -26,16 +80,7 @@ impl AppState {
         if self.mirrors_loaded {
             return Ok(());
         }
-        if let Ok(output) = Command::new("reflector").arg("--list-countries").output()
-            && output.status.success()
-        {
-            let text = String::from_utf8_lossy(&output.stdout);
-            let mut items: Vec<String> = text
-                .lines()
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            items.sort();
+        if let Some(items) = probe_mirror_regions() {
             self.mirrors_regions_options = items;
         }
 
@@ -1,8 +1,9 @@
 // Re-export core state and types to preserve crate::app::* paths
 pub use crate::core::state::AppState;
 pub use crate::core::types::{
-    AdditionalPackage, CustomRepo, DiskPartitionSpec, Focus, MenuEntry, NetworkConfigMode,
-    NetworkInterfaceConfig, PopupKind, RepoSignOption, RepoSignature, Screen, UserAccount,
+    AdditionalPackage, CustomRepo, DiskPartitionSpec, Focus, LogicalVolumeSpec, MenuEntry,
+    NetworkConfigMode, NetworkInterfaceConfig, PopupKind, RepoSignOption, RepoSignature, Screen,
+    UserAccount,
 };
 
 mod abort;
@@ -18,8 +19,10 @@ pub mod hostname;
 pub mod install;
 pub mod kernels;
 pub mod locales;
+pub mod lvm;
 pub mod mirrors;
 pub mod network_configuration;
+pub mod raid;
 pub mod root_password;
 mod save_configuration;
 pub mod secure_boot;
@@ -0,0 +1,169 @@
+use super::{AppState, Focus, PopupKind};
+use crate::core::storage::RaidLevel;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+impl AppState {
+    pub fn raid_level(&self) -> RaidLevel {
+        RaidLevel::from_index(self.raid_level_index)
+    }
+
+    /// True when the automatic layout is built on a software RAID array across the member disks.
+    pub fn raid_selected(&self) -> bool {
+        self.raid_enabled && self.disks_mode_index == 0
+    }
+
+    /// Disks the layout writes to: the selected disk first, then the other RAID members.
+    pub fn target_disks(&self) -> Vec<String> {
+        let mut disks: Vec<String> = self.disks_selected_device.iter().cloned().collect();
+        if self.raid_selected() {
+            for dev in &self.raid_devices {
+                if !disks.contains(dev) {
+                    disks.push(dev.clone());
+                }
+            }
+        }
+        disks
+    }
+
+    pub fn toggle_raid(&mut self) {
+        self.raid_enabled = !self.raid_enabled;
+    }
+
+    pub fn cycle_raid_level(&mut self, next: bool) {
+        let len = RaidLevel::NAMES.len();
+        self.raid_level_index = if next {
+            (self.raid_level_index + 1) % len
+        } else {
+            (self.raid_level_index + len - 1) % len
+        };
+    }
+
+    /// Multi-select of the member disks; the selected disk starts out checked.
+    pub fn open_raid_member_disks(&mut self) {
+        self.load_disks_devices();
+        if self.raid_devices.is_empty()
+            && let Some(dev) = &self.disks_selected_device
+        {
+            self.raid_devices.push(dev.clone());
+        }
+        self.popup_kind = Some(PopupKind::RaidMemberDisks);
+        self.popup_items = self
+            .disks_devices
+            .iter()
+            .filter(|d| !d.read_only)
+            .map(|d| format!("{:<20} | {:<10} | {}", d.path, d.size, d.model))
+            .collect();
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        self.popup_open = true;
+    }
+
+    /// Disk path of a [`PopupKind::RaidMemberDisks`] row.
+    pub fn raid_member_row_path(row: &str) -> &str {
+        row.split('|').next().unwrap_or("").trim()
+    }
+
+    pub fn toggle_raid_member(&mut self, path: &str) {
+        if let Some(i) = self.raid_devices.iter().position(|d| d == path) {
+            self.raid_devices.remove(i);
+        } else {
+            self.raid_devices.push(path.to_string());
+        }
+    }
+
+    /// Closing the member list: when the selected disk was unchecked, the first member becomes
+    /// the selected disk (it holds the primary ESP and the bootloader).
+    pub fn finalize_raid_members(&mut self) {
+        let selected_is_member = self
+            .disks_selected_device
+            .as_ref()
+            .is_some_and(|d| self.raid_devices.contains(d));
+        if !selected_is_member && let Some(first) = self.raid_devices.first().cloned() {
+            if let Some(dev) = self.disks_devices.iter().find(|d| d.path == first) {
+                self.disks_selected_device_model = Some(dev.model.clone());
+                self.disks_selected_device_devtype = Some(dev.devtype.clone());
+                self.disks_selected_device_size = Some(dev.size.clone());
+                self.disks_selected_device_freespace = Some(dev.freespace.clone());
+                self.disks_selected_device_sector_size = Some(dev.sector_size.clone());
+                self.disks_selected_device_read_only = Some(dev.read_only);
+            }
+            self.disks_selected_device = Some(first);
+        }
+        self.close_popup();
+    }
+
+    pub fn raid_summary(&self) -> String {
+        format!(
+            "{} on {} disks",
+            self.raid_level().name().to_uppercase(),
+            self.target_disks().len()
+        )
+    }
+}
+
+pub fn draw_raid(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
+    let title = Span::styled(
+        "Software RAID",
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut lines: Vec<Line> = vec![Line::from(title), Line::from("")];
+
+    let enabled = app.raid_enabled;
+    let members = app.target_disks();
+    let members = if !enabled || members.is_empty() {
+        "None".to_string()
+    } else {
+        members.join(", ")
+    };
+    let rows: Vec<(String, bool)> = vec![
+        (
+            format!("RAID: {}", if enabled { "Enabled" } else { "Disabled" }),
+            true,
+        ),
+        (format!("Level: {}", app.raid_level().label()), enabled),
+        (format!("Member Disks: {members}"), enabled),
+        ("[ Back ]".into(), true),
+    ];
+
+    let back_row = rows.len() - 1;
+    for (idx, (text, available)) in rows.into_iter().enumerate() {
+        if idx == back_row {
+            lines.push(Line::from(""));
+        }
+        let is_focused_line = app.raid_focus_index == idx;
+        let is_active_line = is_focused_line && matches!(app.focus, Focus::Content);
+        let bullet = if is_focused_line { "▶" } else { " " };
+        let style = if is_active_line && available {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else if !available {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{bullet} "), style),
+            Span::styled(text, style),
+        ]));
+    }
+
+    let content = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(match app.focus {
+                    Focus::Content => " Desicion Menu (focused) ",
+                    _ => " Desicion Menu ",
+                }),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(content, area);
+}
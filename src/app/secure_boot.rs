@@ -7,6 +7,49 @@ const SECURE_BOOT_EFIVAR: &str =
 const SETUP_MODE_EFIVAR: &str =
     "/sys/firmware/efi/efivars/SetupMode-8be4df61-93ca-11d2-aa0d-00e098032b8c";
 
+/// How the installed boot chain is signed for Secure Boot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecureBootSigning {
+    None,
+    /// Own keys from `sbctl create-keys`, enrolled when the firmware is in Setup Mode.
+    Sbctl,
+    /// Microsoft-signed shim chainloading a loader signed with a Machine Owner Key (MOK),
+    /// for firmware whose key management is locked.
+    Shim,
+}
+
+impl SecureBootSigning {
+    /// Config names, in Bootloader screen order (`AppState::secure_boot_signing_index`).
+    pub const NAMES: [&str; 3] = ["none", "sbctl", "shim"];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => SecureBootSigning::Sbctl,
+            2 => SecureBootSigning::Shim,
+            _ => SecureBootSigning::None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name.trim()))
+            .map(Self::from_index)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            SecureBootSigning::None => "None",
+            SecureBootSigning::Sbctl => "sbctl (own keys)",
+            SecureBootSigning::Shim => "shim + MOK (GRUB or systemd-boot)",
+        }
+    }
+}
+
 impl AppState {
     /// Detect Secure Boot from EFI variable storage. Safe to call repeatedly.
     pub fn detect_secure_boot_state(&mut self) {
@@ -30,6 +73,7 @@ impl AppState {
         }
         ensure_efivarfs_mounted();
         self.secure_boot_setup_mode = read_efivar_bool(SETUP_MODE_EFIVAR).unwrap_or(false);
+        self.secure_boot_third_party_ca = detect_third_party_ca_from_mokutil();
         if self.secure_boot_setup_mode {
             self.debug_log("secure_boot: firmware is in Setup Mode (no keys enrolled)");
         }
@@ -99,6 +143,55 @@ impl AppState {
     pub fn is_uki_forced_for_efistub(&self) -> bool {
         self.bootloader_index == 2 && self.is_secure_boot_enabled()
     }
+
+    pub fn secure_boot_signing(&self) -> SecureBootSigning {
+        SecureBootSigning::from_index(self.secure_boot_signing_index)
+    }
+
+    /// True when the install signs its boot binaries with sbctl keys (UEFI only).
+    pub fn sbctl_signing_selected(&self) -> bool {
+        self.secure_boot_signing() == SecureBootSigning::Sbctl && self.is_uefi()
+    }
+
+    /// True when the install boots through shim with a MOK-signed loader (UEFI only).
+    pub fn shim_signing_selected(&self) -> bool {
+        self.secure_boot_signing() == SecureBootSigning::Shim && self.is_uefi()
+    }
+
+    pub fn cycle_secure_boot_signing(&mut self, next: bool) {
+        let len = SecureBootSigning::NAMES.len();
+        self.secure_boot_signing_index = if next {
+            (self.secure_boot_signing_index + 1) % len
+        } else {
+            (self.secure_boot_signing_index + len - 1) % len
+        };
+    }
+
+    /// Why the chosen signing will not boot with Secure Boot on: nothing signs the boot chain,
+    /// or shim is not trusted by the firmware.
+    pub fn secure_boot_signing_warning(&self) -> Option<&'static str> {
+        if !self.is_secure_boot_enabled() {
+            return None;
+        }
+        match self.secure_boot_signing() {
+            SecureBootSigning::None if self.secure_boot_known && !self.secure_boot_setup_mode => {
+                Some(
+                    "Secure Boot is enabled but no signing is configured: the installed system \
+                     will not boot until Secure Boot is turned off. The firmware keys are set, so \
+                     choose shim + MOK (or clear the keys for sbctl).",
+                )
+            }
+            SecureBootSigning::None => Some(
+                "Secure Boot is enabled but no signing is configured: the installed system will \
+                 not boot until Secure Boot is turned off. Choose sbctl signing.",
+            ),
+            SecureBootSigning::Shim if self.secure_boot_third_party_ca == Some(false) => Some(
+                "The firmware does not trust the Microsoft UEFI CA that signs shim: enable \
+                 third-party UEFI CAs in the firmware setup or shim will not start.",
+            ),
+            _ => None,
+        }
+    }
 }
 
 fn ensure_efivarfs_mounted() {
@@ -138,6 +231,20 @@ fn detect_secure_boot_from_bootctl() -> Option<bool> {
     }
 }
 
+/// Whether the firmware `db` holds the Microsoft UEFI CA that signs shim (Secured-core
+/// machines ship with it disabled). `None` when mokutil cannot read the database.
+fn detect_third_party_ca_from_mokutil() -> Option<bool> {
+    let out = Command::new("mokutil").arg("--db").output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    Some(
+        text.contains("Microsoft Corporation UEFI CA 2011")
+            || text.contains("Microsoft UEFI CA 2023"),
+    )
+}
+
 fn detect_secure_boot_from_mokutil() -> Option<bool> {
     let out = Command::new("mokutil").arg("--sb-state").output().ok()?;
     if !out.status.success() {
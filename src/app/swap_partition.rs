@@ -1,4 +1,5 @@
-use super::{AppState, Focus};
+use super::{AppState, Focus, PopupKind};
+use crate::core::storage::{SwapEncryption, SwapMode, ZRAM_ALGORITHMS};
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
@@ -9,11 +10,96 @@ impl AppState {
     pub fn init_swap_partition(&mut self) {
         // nothing to initialize for now
     }
+
+    pub fn swap_mode(&self) -> SwapMode {
+        SwapMode::from_index(self.swap_mode_index)
+    }
+
+    pub fn swap_encryption(&self) -> SwapEncryption {
+        SwapEncryption::from_index(self.swap_encryption_index)
+    }
+
+    /// True when the swap encryption choice takes effect: a swap partition under an
+    /// encrypted root (the automatic layout's LUKS, or manual layouts).
+    pub fn swap_encryption_available(&self) -> bool {
+        self.swap_mode() == SwapMode::Partition
+            && (self.disks_mode_index == 1 || self.disk_encryption_type_index == 1)
+    }
+
+    /// One-line description for the install summary and the info pane.
+    pub fn swap_summary(&self) -> String {
+        match self.swap_mode() {
+            SwapMode::Partition
+                if self.swap_encryption_available()
+                    && self.swap_encryption() != SwapEncryption::None =>
+            {
+                format!(
+                    "Swap partition (encrypted, {})",
+                    self.swap_encryption().label()
+                )
+            }
+            SwapMode::Partition => "Swap partition".into(),
+            SwapMode::Swapfile => format!("Swapfile ({})", self.swapfile_size),
+            SwapMode::Zram => format!("zram ({}, {})", self.zram_size, self.zram_algorithm()),
+            SwapMode::None => "None".into(),
+        }
+    }
+
+    pub fn zram_algorithm(&self) -> &'static str {
+        ZRAM_ALGORITHMS
+            .get(self.zram_algorithm_index)
+            .copied()
+            .unwrap_or(ZRAM_ALGORITHMS[0])
+    }
+
+    pub fn cycle_zram_algorithm(&mut self, next: bool) {
+        let len = ZRAM_ALGORITHMS.len();
+        self.zram_algorithm_index = if next {
+            (self.zram_algorithm_index + 1) % len
+        } else {
+            (self.zram_algorithm_index + len - 1) % len
+        };
+    }
+
+    pub fn cycle_swap_encryption(&mut self, next: bool) {
+        let len = SwapEncryption::NAMES.len();
+        self.swap_encryption_index = if next {
+            (self.swap_encryption_index + 1) % len
+        } else {
+            (self.swap_encryption_index + len - 1) % len
+        };
+    }
+
+    pub fn open_swap_mode_popup(&mut self) {
+        self.popup_kind = Some(PopupKind::SwapModeSelect);
+        self.popup_items = (0..SwapMode::NAMES.len())
+            .map(|i| SwapMode::from_index(i).label().to_string())
+            .collect();
+        self.popup_visible_indices = (0..self.popup_items.len()).collect();
+        self.popup_selected_visible = self.swap_mode_index;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+        self.popup_open = true;
+    }
+
+    pub fn open_swap_size_input(&mut self, kind: PopupKind) {
+        self.custom_input_buffer = match kind {
+            PopupKind::ZramSizeInput => self.zram_size.clone(),
+            _ => self.swapfile_size.clone(),
+        };
+        self.popup_kind = Some(kind);
+        self.popup_open = true;
+        self.popup_items.clear();
+        self.popup_visible_indices.clear();
+        self.popup_selected_visible = 0;
+        self.popup_in_search = false;
+        self.popup_search_query.clear();
+    }
 }
 
 pub fn draw_swap_partition(frame: &mut ratatui::Frame, app: &mut AppState, area: Rect) {
     let title = Span::styled(
-        "Swap Partition",
+        "Swap",
         Style::default()
             .fg(Color::Green)
             .add_modifier(Modifier::BOLD),
@@ -21,50 +107,62 @@ pub fn draw_swap_partition(frame: &mut ratatui::Frame, app: &mut AppState, area:
 
     let mut lines: Vec<Line> = vec![Line::from(title), Line::from("")];
 
-    let options = vec![(
-        "Swapon",
-        if app.swap_enabled {
-            "Enabled"
-        } else {
-            "Disabled"
-        },
-        app.swap_focus_index == 0,
-    )];
+    let mode = app.swap_mode();
+    let options = vec![
+        (
+            "Swap",
+            mode.label().to_string(),
+            app.swap_focus_index == 0,
+            true,
+        ),
+        (
+            "Swapfile Size",
+            app.swapfile_size.clone(),
+            app.swap_focus_index == 1,
+            mode == SwapMode::Swapfile,
+        ),
+        (
+            "zram Size (MiB or formula)",
+            app.zram_size.clone(),
+            app.swap_focus_index == 2,
+            mode == SwapMode::Zram,
+        ),
+        (
+            "zram Compression",
+            app.zram_algorithm().to_string(),
+            app.swap_focus_index == 3,
+            mode == SwapMode::Zram,
+        ),
+        (
+            "Encryption",
+            app.swap_encryption().label().to_string(),
+            app.swap_focus_index == 4,
+            app.swap_encryption_available(),
+        ),
+    ];
 
-    for (label, value, is_focused_line) in options {
+    for (label, value, is_focused_line, enabled) in options {
         let is_active_line = is_focused_line && matches!(app.focus, Focus::Content);
         let bullet = if is_focused_line { "▶" } else { " " };
-        let bullet_style = if is_active_line {
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::White)
-        };
-        let label_style = if is_active_line {
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::White)
-        };
-        let value_style = if is_active_line {
+        let style = if is_active_line && enabled {
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD)
+        } else if !enabled {
+            Style::default().fg(Color::DarkGray)
         } else {
             Style::default().fg(Color::White)
         };
         let line = Line::from(vec![
-            Span::styled(format!("{bullet} "), bullet_style),
-            Span::styled(format!("{label}: "), label_style),
-            Span::styled(value.to_string(), value_style),
+            Span::styled(format!("{bullet} "), style),
+            Span::styled(format!("{label}: "), style),
+            Span::styled(value, style),
         ]);
         lines.push(line);
     }
 
     // Continue
-    let continue_style = if app.swap_focus_index == 1 && matches!(app.focus, Focus::Content) {
+    let continue_style = if app.swap_focus_index == 5 && matches!(app.focus, Focus::Content) {
         Style::default()
             .fg(Color::Yellow)
             .add_modifier(Modifier::BOLD)
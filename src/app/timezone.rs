@@ -76,7 +76,7 @@ impl AppState {
         self.popup_search_query.clear();
     }
 
-    fn list_timezones() -> Vec<String> {
+    pub(crate) fn list_timezones() -> Vec<String> {
         if let Ok(output) = Command::new("timedatectl").arg("list-timezones").output()
             && output.status.success()
         {
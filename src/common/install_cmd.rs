@@ -1,7 +1,9 @@
-//! Install-time commands: mostly opaque shell strings, plus LUKS steps where the
-//! passphrase must never be concatenated into a logged `bash -lc` script.
+//! Install-time commands: mostly opaque shell strings, plus LUKS and bcachefs encryption
+//! steps where the passphrase must never be concatenated into a logged `bash -lc` script.
 
+use std::fs::OpenOptions;
 use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
 use std::process::{Command, Stdio};
 
 /// One step of the installation plan.
@@ -9,14 +11,36 @@ use std::process::{Command, Stdio};
 pub enum InstallCmd {
     /// Arbitrary shell fragment run under `bash -lc` inside `script(1)`.
     Shell(String),
-    /// `cryptsetup luksFormat` with passphrase supplied via stdin (not in the shell string).
-    CryptsetupLuksFormat { device: String, passphrase: String },
+    /// `cryptsetup luksFormat <args>` with passphrase supplied via stdin (not in the shell string).
+    CryptsetupLuksFormat {
+        device: String,
+        args: Vec<String>,
+        passphrase: String,
+    },
     /// `cryptsetup open` with passphrase supplied via stdin.
     CryptsetupOpen {
         device: String,
         mapper: String,
         passphrase: String,
     },
+    /// `bcachefs format --encrypted` over all member devices, passphrase via stdin.
+    BcachefsFormat {
+        devices: Vec<String>,
+        passphrase: String,
+    },
+    /// `bcachefs unlock` into the user keyring, passphrase via stdin.
+    BcachefsUnlock { device: String, passphrase: String },
+    /// `systemd-cryptenroll <args> <device>`, unlocked by the passphrase in `$PASSWORD`.
+    /// `output`, when set, receives stdout (a generated recovery key) instead of the log.
+    CryptEnroll {
+        device: String,
+        args: Vec<String>,
+        passphrase: String,
+        output: Option<String>,
+    },
+    /// `arch-chroot /mnt mokutil --import <cert>`; the enrollment password and its
+    /// confirmation are answered on stdin.
+    MokImport { cert: String, password: String },
 }
 
 impl InstallCmd {
@@ -28,8 +52,11 @@ impl InstallCmd {
     pub fn for_log(&self) -> String {
         match self {
             InstallCmd::Shell(s) => crate::common::utils::redact_command_for_logging(s),
-            InstallCmd::CryptsetupLuksFormat { device, .. } => format!(
-                "cryptsetup luksFormat --type luks2 -q --key-file=- {}",
+            InstallCmd::CryptsetupLuksFormat { device, args, .. } => format!(
+                "cryptsetup luksFormat --type luks2 -q {}--key-file=- {}",
+                args.iter()
+                    .map(|a| format!("{} ", shell_quote_arg(a)))
+                    .collect::<String>(),
                 shell_single_quote(device)
             ),
             InstallCmd::CryptsetupOpen { device, mapper, .. } => format!(
@@ -37,6 +64,60 @@ impl InstallCmd {
                 shell_single_quote(device),
                 shell_single_quote(mapper)
             ),
+            InstallCmd::BcachefsFormat { devices, .. } => format!(
+                "bcachefs format -f --encrypted {}",
+                devices
+                    .iter()
+                    .map(|d| shell_single_quote(d))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            InstallCmd::BcachefsUnlock { device, .. } => {
+                format!("bcachefs unlock -k user {}", shell_single_quote(device))
+            }
+            InstallCmd::CryptEnroll {
+                device,
+                args,
+                output,
+                ..
+            } => {
+                let redirect = output
+                    .as_ref()
+                    .map(|path| format!(" > {}", shell_single_quote(path)))
+                    .unwrap_or_default();
+                format!(
+                    "systemd-cryptenroll {} {}{redirect}",
+                    args.join(" "),
+                    shell_single_quote(device)
+                )
+            }
+            InstallCmd::MokImport { cert, .. } => {
+                format!(
+                    "arch-chroot /mnt mokutil --import {}",
+                    shell_single_quote(cert)
+                )
+            }
+        }
+    }
+
+    /// Stable variant name for machine-readable plans (`archinstall-rs plan`).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InstallCmd::Shell(_) => "shell",
+            InstallCmd::CryptsetupLuksFormat { .. } => "cryptsetup_luks_format",
+            InstallCmd::CryptsetupOpen { .. } => "cryptsetup_open",
+            InstallCmd::BcachefsFormat { .. } => "bcachefs_format",
+            InstallCmd::BcachefsUnlock { .. } => "bcachefs_unlock",
+            InstallCmd::CryptEnroll { .. } => "cryptenroll",
+            InstallCmd::MokImport { .. } => "mok_import",
+        }
+    }
+
+    /// Raw shell text that may be shown and edited, or `None` when it carries secrets.
+    pub fn editable_text(&self) -> Option<String> {
+        match self {
+            InstallCmd::Shell(s) if self.for_log() == *s => Some(s.clone()),
+            _ => None,
         }
     }
 
@@ -52,21 +133,25 @@ impl InstallCmd {
     /// wrapping them in `script` would give `cryptsetup` a PTY as stdin, so `--key-file=-`
     /// would not receive the passphrase and would error (e.g. "Error reading passphrase from terminal").
     ///
-    /// For LUKS variants, [`Self::write_passphrase_to_stdin`] must be called after spawn.
+    /// bcachefs steps are spawned the same way: without a TTY on stdin, `bcachefs` reads the
+    /// passphrase as a single line.
+    ///
+    /// `systemd-cryptenroll` reads the passphrase from `$PASSWORD`; a recovery key is written
+    /// to a fresh `0600` file.
+    ///
+    /// `mokutil --import` prompts for the MOK password twice and reads both answers from a
+    /// non-TTY stdin, so the password is never part of a shell string or argv.
+    ///
+    /// For the passphrase variants, [`Self::write_passphrase_to_stdin`] must be called after spawn.
     pub fn spawn_script_pipeline(&self, stdout: Stdio) -> io::Result<std::process::Child> {
         match self {
-            InstallCmd::CryptsetupLuksFormat { device, .. } => {
+            InstallCmd::CryptsetupLuksFormat { device, args, .. } => {
                 let mut cmd = Command::new("cryptsetup");
-                cmd.args([
-                    "luksFormat",
-                    "--type",
-                    "luks2",
-                    "-q",
-                    "--key-file=-",
-                    device.as_str(),
-                ])
-                .stdin(Stdio::piped())
-                .stdout(stdout);
+                cmd.args(["luksFormat", "--type", "luks2", "-q"])
+                    .args(args)
+                    .args(["--key-file=-", device.as_str()])
+                    .stdin(Stdio::piped())
+                    .stdout(stdout);
                 configure_install_command(&mut cmd);
                 cmd.spawn()
             }
@@ -85,6 +170,57 @@ impl InstallCmd {
                 configure_install_command(&mut cmd);
                 cmd.spawn()
             }
+            InstallCmd::BcachefsFormat { devices, .. } => {
+                let mut cmd = Command::new("bcachefs");
+                cmd.args(["format", "-f", "--encrypted"])
+                    .args(devices)
+                    .stdin(Stdio::piped())
+                    .stdout(stdout);
+                configure_install_command(&mut cmd);
+                cmd.spawn()
+            }
+            InstallCmd::BcachefsUnlock { device, .. } => {
+                let mut cmd = Command::new("bcachefs");
+                cmd.args(["unlock", "-k", "user", device.as_str()])
+                    .stdin(Stdio::piped())
+                    .stdout(stdout);
+                configure_install_command(&mut cmd);
+                cmd.spawn()
+            }
+            InstallCmd::CryptEnroll {
+                device,
+                args,
+                passphrase,
+                output,
+            } => {
+                let stdout = match output {
+                    Some(path) => Stdio::from(
+                        OpenOptions::new()
+                            .write(true)
+                            .create(true)
+                            .truncate(true)
+                            .mode(0o600)
+                            .open(path)?,
+                    ),
+                    None => stdout,
+                };
+                let mut cmd = Command::new("systemd-cryptenroll");
+                cmd.args(args)
+                    .arg(device)
+                    .env("PASSWORD", passphrase)
+                    .stdin(Stdio::null())
+                    .stdout(stdout);
+                configure_install_command(&mut cmd);
+                cmd.spawn()
+            }
+            InstallCmd::MokImport { cert, .. } => {
+                let mut cmd = Command::new("arch-chroot");
+                cmd.args(["/mnt", "mokutil", "--import", cert.as_str()])
+                    .stdin(Stdio::piped())
+                    .stdout(stdout);
+                configure_install_command(&mut cmd);
+                cmd.spawn()
+            }
             InstallCmd::Shell(c) => {
                 let mut cmd = Command::new("script");
                 cmd.args(["-qfe", "-c", c.as_str(), "/dev/null"])
@@ -98,7 +234,7 @@ impl InstallCmd {
 
     pub fn write_passphrase_to_stdin(&self, child: &mut std::process::Child) -> io::Result<()> {
         match self {
-            InstallCmd::Shell(_) => Ok(()),
+            InstallCmd::Shell(_) | InstallCmd::CryptEnroll { .. } => Ok(()),
             InstallCmd::CryptsetupLuksFormat { passphrase, .. }
             | InstallCmd::CryptsetupOpen { passphrase, .. } => {
                 let mut stdin = child
@@ -108,6 +244,27 @@ impl InstallCmd {
                 stdin.write_all(passphrase.as_bytes())?;
                 Ok(())
             }
+            InstallCmd::BcachefsFormat { passphrase, .. }
+            | InstallCmd::BcachefsUnlock { passphrase, .. } => {
+                let mut stdin = child
+                    .stdin
+                    .take()
+                    .ok_or_else(|| io::Error::other("bcachefs child missing stdin pipe"))?;
+                stdin.write_all(passphrase.as_bytes())?;
+                stdin.write_all(b"\n")?;
+                Ok(())
+            }
+            InstallCmd::MokImport { password, .. } => {
+                let mut stdin = child
+                    .stdin
+                    .take()
+                    .ok_or_else(|| io::Error::other("mokutil child missing stdin pipe"))?;
+                for _ in 0..2 {
+                    stdin.write_all(password.as_bytes())?;
+                    stdin.write_all(b"\n")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -129,3 +286,15 @@ pub fn configure_install_command(cmd: &mut Command) -> &mut Command {
 fn shell_single_quote(s: &str) -> String {
     format!("'{}'", s.replace('\'', "'\\''"))
 }
+
+/// `s` as one shell word: unchanged when it has no special characters, else single-quoted.
+pub(crate) fn shell_quote_arg(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_=+,.:/@%".contains(c))
+    {
+        s.to_string()
+    } else {
+        shell_single_quote(s)
+    }
+}
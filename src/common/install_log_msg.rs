@@ -5,4 +5,29 @@ pub enum InstallLogMsg {
     Line(String),
     /// Replace the last log line (in-place progress updates, e.g. `[###---] 42%`).
     ReplaceLastLine(String),
+    /// A command failed and the install thread now waits for a [`FailureAction`].
+    CommandFailed(CommandFailure),
+}
+
+/// Details of a failed install command, shown in the recovery popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandFailure {
+    pub section: String,
+    /// Redacted command text (safe to display).
+    pub command: String,
+    /// Raw shell text offered for editing; `None` for LUKS steps and commands whose
+    /// log form had secrets redacted.
+    pub editable: Option<String>,
+    pub message: String,
+}
+
+/// How the install thread continues after a [`CommandFailure`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureAction {
+    Retry,
+    /// Run this shell command in place of the failed one.
+    RetryWith(String),
+    /// Continue with the next command; recorded as a warning in the final report.
+    Skip,
+    Abort,
 }
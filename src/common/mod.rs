@@ -5,4 +5,4 @@ pub mod install_stdout;
 pub mod popups;
 pub mod utils;
 
-pub use install_log_msg::InstallLogMsg;
+pub use install_log_msg::{CommandFailure, FailureAction, InstallLogMsg};
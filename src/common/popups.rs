@@ -236,12 +236,10 @@ impl AppState {
     pub fn open_kernels_popup(&mut self) {
         self.popup_kind = Some(PopupKind::KernelSelect);
         self.popup_open = true;
-        self.popup_items = vec![
-            "linux".into(),
-            "linux-hardened".into(),
-            "linux-lts".into(),
-            "linux-zen".into(),
-        ];
+        self.popup_items = crate::app::kernels::KERNEL_PACKAGES
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
         self.popup_visible_indices = (0..self.popup_items.len()).collect();
         self.popup_selected_visible = 0;
         self.popup_in_search = false;
@@ -1,7 +1,21 @@
+use crate::common::install_cmd::InstallCmd;
 use crate::core::state::AppState;
-use crate::core::storage::StoragePlan;
+use crate::core::storage::{ResumeDevice, StoragePlan};
 use std::process::Command;
 
+/// Modules built into a shim-booted GRUB image, which may not load modules from disk.
+const GRUB_SHIM_MODULES: &str = "all_video boot btrfs cat chain configfile echo efifwsetup efinet ext2 fat font gettext gfxmenu gfxterm gfxterm_background gzio halt help hfsplus iso9660 jpeg keystatus loadenv loopback linux ls lsefi lsefimmap lsefisystab lssal memdisk minicmd normal ntfs part_apple part_msdos part_gpt password_pbkdf2 png probe reboot regexp search search_fs_uuid search_fs_file search_label sleep smbios squash4 test true video xfs zfs zstd tpm cryptodisk luks luks2 lvm mdraid09 mdraid1x raid5rec raid6rec gcry_rijndael gcry_sha256 gcry_sha512 pbkdf2";
+
+/// Reviewed AUR `shim-signed` commit that is built for shim + MOK signing.
+///
+/// Update it together with [`SHIMX64_SHA256`] and [`MMX64_SHA256`] after reviewing the
+/// PKGBUILD at the new commit; the install stops when the built binaries do not match.
+pub const SHIM_SIGNED_AUR_COMMIT: &str = "0000000000000000000000000000000000000000";
+/// sha256 of `/usr/share/shim-signed/shimx64.efi` built from [`SHIM_SIGNED_AUR_COMMIT`].
+pub const SHIMX64_SHA256: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// sha256 of `/usr/share/shim-signed/mmx64.efi` built from [`SHIM_SIGNED_AUR_COMMIT`].
+pub const MMX64_SHA256: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
 #[derive(Clone, Debug)]
 pub struct BootloaderPlan {
     pub commands: Vec<String>,
@@ -57,40 +71,177 @@ impl BootloaderService {
     /// Inputs:
     /// - `encrypted`: When true, resolves LUKS mapper + UUID and emits `rd.luks.name=…` or
     ///   `cryptdevice=UUID=…` based on `/etc/mkinitcpio.conf` hooks; otherwise emits `root=UUID=… rw`.
+    /// - `luks_options`: Token options of the root volume (`tpm2-device=auto`, ...), emitted as
+    ///   `rd.luks.options=<uuid>=…` next to `rd.luks.name`.
     ///
     /// Output:
     /// - A shell script body (no surrounding quotes) suitable for `OPTS=$(…)` substitution.
     ///
     /// Details:
     /// - Strips btrfs `[@subvol]` suffixes from `findmnt` output before `blkid`/`cryptsetup`.
-    pub(crate) fn boot_options_script(encrypted: bool) -> String {
+    /// - A bcachefs root (possibly multi-device, `findmnt` reports `dev1:dev2`) boots by its
+    ///   filesystem UUID, which every member shares: `root=UUID=… rootfstype=bcachefs`.
+    pub(crate) fn boot_options_script(encrypted: bool, luks_options: Option<&str>) -> String {
         let strip_subvol = "rootdev=$(findmnt -n -o SOURCE /); rootdev=\"${rootdev%%\\[*}\"";
+        let bcachefs = "if [ \"$(findmnt -n -o FSTYPE /)\" = bcachefs ]; then \
+                          rootuuid=$(blkid -s UUID -o value \"${rootdev%%:*}\" || true); \
+                          echo \"root=UUID=$rootuuid rootfstype=bcachefs rw\"; \
+                        else";
         if encrypted {
+            let luks_options = luks_options
+                .map(|o| format!(" rd.luks.options=$luksuuid={o}"))
+                .unwrap_or_default();
             format!(
-                "{strip_subvol}; \
+                "{strip_subvol}; {bcachefs} \
                  mapper=$(basename \"$rootdev\"); \
                  if cryptsetup status \"$mapper\" >/dev/null 2>&1; then \
                    underlying=$(cryptsetup status \"$mapper\" | awk '/device:/{{print $2}}'); \
                    luksuuid=$(blkid -s UUID -o value \"$underlying\" || true); \
                    if grep -qP '^HOOKS=.*\\bsystemd\\b' /etc/mkinitcpio.conf; then \
-                     echo \"rd.luks.name=$luksuuid=$mapper root=$rootdev rw\"; \
+                     echo \"rd.luks.name=$luksuuid=$mapper{luks_options} root=$rootdev rw\"; \
                    else \
                      echo \"cryptdevice=UUID=$luksuuid:$mapper root=$rootdev rw\"; \
                    fi; \
                  else \
                    rootuuid=$(blkid -s UUID -o value \"$rootdev\" || true); \
                    echo \"root=UUID=$rootuuid rw\"; \
-                 fi"
+                 fi; fi"
             )
         } else {
             format!(
-                "{strip_subvol}; \
+                "{strip_subvol}; {bcachefs} \
                  rootuuid=$(blkid -s UUID -o value \"$rootdev\" || true); \
-                 echo \"root=UUID=$rootuuid rw\""
+                 echo \"root=UUID=$rootuuid rw\"; fi"
             )
         }
     }
 
+    /// Kernel cmdline script for `storage_plan`: the root options followed by the
+    /// [`Self::resume_options_script`] options when the plan has disk swap.
+    pub(crate) fn root_options_script(storage_plan: &StoragePlan) -> String {
+        let root = Self::root_device_options_script(storage_plan);
+        match Self::resume_options_script(storage_plan) {
+            Some(resume) => format!("echo \"$({root}) $({resume})\""),
+            None => root,
+        }
+    }
+
+    /// Kernel cmdline script for an install: [`Self::root_options_script`] followed by the
+    /// extra parameters from the Kernels screen, last so they can override loader defaults.
+    pub(crate) fn kernel_options_script(state: &AppState, storage_plan: &StoragePlan) -> String {
+        Self::with_extra_parameters(state, Some(Self::root_options_script(storage_plan)))
+            .unwrap_or_default()
+    }
+
+    /// `script` with the extra kernel parameters appended to what it prints; only the
+    /// parameters when there is no script. `None` when both are absent.
+    fn with_extra_parameters(state: &AppState, script: Option<String>) -> Option<String> {
+        match (script, state.extra_kernel_parameters()) {
+            (Some(script), Some(params)) => Some(format!("echo \"$({script}) {params}\"")),
+            (None, Some(params)) => Some(format!("echo \"{params}\"")),
+            (script, None) => script,
+        }
+    }
+
+    /// Root options: [`Self::boot_options_script`], or `root=ZFS=<pool>/<bootfs>` for a ZFS
+    /// root (with `cryptdevice=` when the pool sits on LUKS; the zfs hook needs the
+    /// udev-based `encrypt` hook). A root logical volume on LUKS (LVM on LUKS) names the
+    /// LUKS volume under the group and boots `root=/dev/<vg>/<lv>`.
+    fn root_device_options_script(storage_plan: &StoragePlan) -> String {
+        if let (Some(root), Some(enc)) = (
+            storage_plan.root_device_path(),
+            storage_plan.root_lvm_luks(),
+        ) {
+            let luks_options = enc
+                .enroll
+                .unlock_options()
+                .map(|o| format!(" rd.luks.options=$luksuuid={o}"))
+                .unwrap_or_default();
+            return format!(
+                "underlying=$(cryptsetup status {mapper} | awk '/device:/{{print $2}}'); \
+                 luksuuid=$(blkid -s UUID -o value \"$underlying\" || true); \
+                 if grep -qP '^HOOKS=.*\\bsystemd\\b' /etc/mkinitcpio.conf; then \
+                   echo \"rd.luks.name=$luksuuid={mapper}{luks_options} root={root} rw\"; \
+                 else \
+                   echo \"cryptdevice=UUID=$luksuuid:{mapper} root={root} rw\"; \
+                 fi",
+                mapper = enc.mapper_name
+            );
+        }
+        let Some(pool) = storage_plan.zfs_root_pool() else {
+            let luks_options = storage_plan
+                .root_device_path()
+                .and_then(|root| storage_plan.luks_enrollment(root.strip_prefix("/dev/mapper/")?))
+                .and_then(|enroll| enroll.unlock_options());
+            return Self::boot_options_script(storage_plan.has_encryption(), luks_options);
+        };
+        let root = format!("root=ZFS={} rw", pool.dataset_path(&pool.bootfs));
+        match pool
+            .members
+            .iter()
+            .find_map(|m| m.strip_prefix("/dev/mapper/"))
+        {
+            Some(mapper) => format!(
+                "underlying=$(cryptsetup status {mapper} | awk '/device:/{{print $2}}'); \
+                 luksuuid=$(blkid -s UUID -o value \"$underlying\" || true); \
+                 echo \"cryptdevice=UUID=$luksuuid:{mapper} {root}\""
+            ),
+            None => format!("echo \"{root}\""),
+        }
+    }
+
+    /// Script printing the hibernation options for `storage_plan`, run in the chroot:
+    /// - plain swap partition: `resume=UUID=<swap uuid>`;
+    /// - LUKS mapper or LVM LV: `resume=<device path>`, plus `rd.luks.name=` (and the token's
+    ///   `rd.luks.options=`) for a LUKS swap mapper when the initramfs uses the `systemd` hook
+    ///   (`encrypt` unlocks only the root);
+    /// - swap file: `resume=UUID=<filesystem uuid> resume_offset=<physical offset>`, from
+    ///   `btrfs inspect-internal map-swapfile -r` on btrfs and `filefrag` elsewhere.
+    ///
+    /// `None` without disk swap (zram, or no swap at all).
+    pub(crate) fn resume_options_script(storage_plan: &StoragePlan) -> Option<String> {
+        let script = match storage_plan.resume_device()? {
+            ResumeDevice::Swap(source) => match source.strip_prefix("/dev/mapper/") {
+                Some(mapper) => {
+                    let luks_options = storage_plan
+                        .luks_enrollment(mapper)
+                        .and_then(|enroll| enroll.unlock_options())
+                        .map(|o| format!(" rd.luks.options=$swapluks={o}"))
+                        .unwrap_or_default();
+                    format!(
+                        "if cryptsetup status {mapper} >/dev/null 2>&1 && grep -qP '^HOOKS=.*\\bsystemd\\b' /etc/mkinitcpio.conf; then \
+                           underlying=$(cryptsetup status {mapper} | awk '/device:/{{print $2}}'); \
+                           swapluks=$(blkid -s UUID -o value \"$underlying\" || true); \
+                           echo \"rd.luks.name=$swapluks={mapper}{luks_options} resume={source}\"; \
+                         else \
+                           echo \"resume={source}\"; \
+                         fi"
+                    )
+                }
+                // LVM LVs and md arrays keep stable paths; partitions resume by UUID
+                None if source.matches('/').count() > 2 => format!("echo \"resume={source}\""),
+                None => format!(
+                    "swapuuid=$(blkid -s UUID -o value {source} || true); echo \"resume=UUID=$swapuuid\""
+                ),
+            },
+            ResumeDevice::Swapfile(swapfile) => {
+                let path = &swapfile.path;
+                let offset = if swapfile.btrfs {
+                    format!("btrfs inspect-internal map-swapfile -r {path}")
+                } else {
+                    format!("filefrag -v {path} | awk '$1 == \"0:\" {{print $4 + 0}}'")
+                };
+                format!(
+                    "swapdev=$(findmnt -n -o SOURCE -T {path}); swapdev=\"${{swapdev%%\\[*}}\"; \
+                     swapuuid=$(blkid -s UUID -o value \"$swapdev\" || true); \
+                     offset=$({offset}); \
+                     echo \"resume=UUID=$swapuuid resume_offset=$offset\""
+                )
+            }
+        };
+        Some(script)
+    }
+
     /// Returns true when UKI install integration should run (`uki_enabled` and bootloader is not GRUB).
     ///
     /// Same rule as UKI TUI visibility (hidden for GRUB).
@@ -98,19 +249,12 @@ impl BootloaderService {
         state.uki_enabled && state.bootloader_index != 1
     }
 
-    /// `efibootmgr --label` for the first selected kernel (primary entry, not fallback).
-    fn efistub_first_primary_label(
-        selected_kernels: &std::collections::BTreeSet<String>,
-    ) -> String {
-        let first = selected_kernels
-            .iter()
-            .next()
-            .map(|s| s.as_str())
-            .unwrap_or("linux");
-        let label_suffix = if first == "linux" {
+    /// `efibootmgr --label` for a kernel's primary entry (not the fallback).
+    fn efistub_primary_label(kernel: &str) -> String {
+        let label_suffix = if kernel == "linux" {
             String::new()
         } else {
-            format!(" ({first})")
+            format!(" ({kernel})")
         };
         format!("Arch Linux{label_suffix}")
     }
@@ -282,7 +426,7 @@ done"
         if uki {
             // Build UKI limine.conf entries for each selected kernel
             let mut conf = format!("cat > {esp}/limine.conf <<'LIMINEOF'\ntimeout: 5\n");
-            for kernel in state.selected_kernels.iter() {
+            for kernel in state.boot_kernels().iter() {
                 let ka = kernel_artifacts(kernel);
                 let suffix = if kernel == "linux" {
                     String::new()
@@ -303,7 +447,7 @@ done"
             let mut conf = format!(
                 "OPTS=$({boot_options_script}); cat > {esp}/limine.conf <<LIMINEOF\ntimeout: 5\n"
             );
-            for kernel in state.selected_kernels.iter() {
+            for kernel in state.boot_kernels().iter() {
                 let ka = kernel_artifacts(kernel);
                 let suffix = if kernel == "linux" {
                     String::new()
@@ -324,6 +468,13 @@ done"
             conf.push_str("LIMINEOF");
             out.push(chroot_cmd(&conf));
         }
+        // Installed alongside Windows: chainload its boot manager from the shared ESP
+        if state.alongside_selected() && state.is_uefi() {
+            out.push(chroot_cmd(&format!(
+                "if [ -f {esp}/EFI/Microsoft/Boot/bootmgfw.efi ]; then \
+                 printf '\\n/Windows\\n    protocol: efi\\n    path: boot():/EFI/Microsoft/Boot/bootmgfw.efi\\n' >> {esp}/limine.conf; fi"
+            )));
+        }
         if state.is_uefi() {
             out.push(chroot_cmd(&format!(
                 "install -d -m 0755 {esp}/EFI/limine && \
@@ -376,6 +527,322 @@ HOOK_EOF"
         out
     }
 
+    /// What: Builds `arch-chroot` shell commands for rEFInd (UEFI only).
+    ///
+    /// Inputs:
+    /// - `state`: Selected kernels and UKI mode.
+    /// - `boot_options_script`: Bash snippet that prints kernel cmdline options (`boot_options_script` output).
+    /// - `esp`: ESP mountpoint inside the chroot.
+    ///
+    /// Output:
+    /// - Ordered `arch-chroot … bash -lc '…'` command strings.
+    ///
+    /// Details:
+    /// - Copies `refind_x64.efi` and its icons to `EFI/refind`, writes `refind.conf` and registers NVRAM.
+    /// - Without UKI, writes `/boot/refind_linux.conf` next to the kernels; rEFInd finds the kernels
+    ///   itself and substitutes `%v` with the kernel name. With UKI, bare kernels are hidden and the
+    ///   images under `EFI/Linux` are the entries.
+    /// - Installs `99-refind.hook` so package upgrades refresh the copy on the ESP.
+    fn refind_install_chroot_commands(
+        state: &AppState,
+        boot_options_script: &str,
+        esp: &str,
+    ) -> Vec<String> {
+        fn chroot_cmd(inner: &str) -> String {
+            let escaped = inner.replace("'", "'\\''");
+            format!("arch-chroot /mnt bash -lc '{escaped}'")
+        }
+        let mut out: Vec<String> = Vec::new();
+        let uki = Self::uki_requested(state);
+
+        out.push(chroot_cmd(&format!(
+            "install -d -m 0755 {esp}/EFI/refind && \
+             install -m 0644 /usr/share/refind/refind_x64.efi {esp}/EFI/refind/refind_x64.efi && \
+             cp -r /usr/share/refind/icons {esp}/EFI/refind/"
+        )));
+
+        // Kernel names without a version number: longest first so `linux` does not
+        // shadow `linux-lts`
+        let mut version_strings: Vec<&str> =
+            state.selected_kernels.iter().map(String::as_str).collect();
+        version_strings.sort_by_key(|k| std::cmp::Reverse(k.len()));
+        let mut conf = format!(
+            "cat > {esp}/EFI/refind/refind.conf <<'REFINDEOF'\n\
+             timeout 5\n\
+             use_nvram false\n\
+             scanfor internal,external,optical,manual\n\
+             extra_kernel_version_strings {}\n",
+            version_strings.join(",")
+        );
+        if uki {
+            conf.push_str("also_scan_dirs +,EFI/Linux\nscan_all_linux_kernels false\n");
+        }
+        // One menu entry per kernel instead of a single folded one, the default kernel preselected
+        if state.selected_kernels.len() > 1 {
+            let ka = kernel_artifacts(&state.default_kernel());
+            let loader = if uki { ka.uki_default } else { ka.vmlinuz };
+            conf.push_str(&format!(
+                "fold_linux_kernels false\ndefault_selection \"{loader}\"\n"
+            ));
+        }
+        conf.push_str("REFINDEOF");
+        out.push(chroot_cmd(&conf));
+
+        if uki {
+            out.push(chroot_cmd(&format!(
+                "ls {esp}/EFI/Linux/*.efi >/dev/null 2>&1 || \
+                 echo 'WARNING: no UKIs found under {esp}/EFI/Linux; rEFInd has no Linux entries to show'"
+            )));
+        } else {
+            let ucode_opt = detect_microcode()
+                .map(|u| format!(" initrd=\\{u}"))
+                .unwrap_or_default();
+            out.push(chroot_cmd(&format!(
+                "OPTS=$({boot_options_script}); cat > {esp}/refind_linux.conf <<REFINDEOF\n\
+                 \"Boot with standard options\"  \"$OPTS{ucode_opt} initrd=\\initramfs-%v.img\"\n\
+                 \"Boot using fallback initramfs\"  \"$OPTS{ucode_opt} initrd=\\initramfs-%v-fallback.img\"\n\
+                 REFINDEOF"
+            )));
+        }
+
+        out.push(chroot_cmd(&format!(
+            "install -d -m 0755 /etc/pacman.d/hooks && cat > /etc/pacman.d/hooks/99-refind.hook <<HOOK_EOF\n\
+[Trigger]\n\
+Operation = Install\n\
+Operation = Upgrade\n\
+Type = Package\n\
+Target = refind\n\
+\n\
+[Action]\n\
+Description = Updating rEFInd on the ESP\n\
+When = PostTransaction\n\
+Exec = /bin/sh -c \"/usr/bin/install -Dm 0644 /usr/share/refind/refind_x64.efi {esp}/EFI/refind/refind_x64.efi && /usr/bin/cp -r /usr/share/refind/icons {esp}/EFI/refind/\"\n\
+HOOK_EOF"
+        )));
+        out.push(chroot_cmd(&format!(
+            "if mountpoint -q /sys/firmware/efi/efivars || mount -t efivarfs efivarfs /sys/firmware/efi/efivars 2>/dev/null; then \
+             BOOTSRC=$(findmnt -n -o SOURCE {esp}); \
+             DISK=$(lsblk -no pkname \"$BOOTSRC\"); \
+             PART=$(lsblk -no PARTN \"$BOOTSRC\"); \
+             efibootmgr --create --disk \"/dev/$DISK\" --part \"$PART\" --label 'rEFInd Boot Manager' --loader '\\EFI\\refind\\refind_x64.efi' --unicode || \
+             echo 'WARNING: efibootmgr failed to create the rEFInd NVRAM entry'; \
+             efibootmgr --verbose || true; \
+             else echo 'WARNING: efivarfs unavailable; skipping rEFInd NVRAM entry creation'; \
+             fi"
+        )));
+        out
+    }
+
+    /// What: Builds `arch-chroot` shell commands that sign the boot chain with sbctl keys.
+    ///
+    /// Inputs:
+    /// - `state`: Bootloader choice and whether Microsoft keys are kept.
+    /// - `esp`: ESP mountpoint inside the chroot.
+    ///
+    /// Output:
+    /// - Ordered `arch-chroot … bash -lc '…'` command strings; run after the loader and UKIs exist.
+    ///
+    /// Details:
+    /// - Creates the keys and enrolls them only when the firmware reports Setup Mode.
+    /// - Signs every `.efi` on the ESP (except `EFI/Microsoft`) and the kernels with `sbctl sign -s`,
+    ///   so the `zz-sbctl.hook` shipped with sbctl re-signs them after kernel and initramfs updates.
+    /// - systemd-boot gets a signed `systemd-bootx64.efi.signed` that `bootctl update` installs;
+    ///   Limine and rEFInd copy their binaries from `/usr/share`, so a hook re-signs after those.
+    fn sbctl_signing_chroot_commands(state: &AppState, esp: &str) -> Vec<String> {
+        fn chroot_cmd(inner: &str) -> String {
+            let escaped = inner.replace("'", "'\\''");
+            format!("arch-chroot /mnt bash -lc '{escaped}'")
+        }
+        let mut out: Vec<String> = Vec::new();
+        let enroll_flag = if state.secure_boot_microsoft_keys {
+            "--microsoft"
+        } else {
+            "--yes-this-might-brick-my-machine"
+        };
+
+        out.push(chroot_cmd("sbctl create-keys"));
+        out.push(chroot_cmd(&format!(
+            "if sbctl status --json | grep -Eq '\"setup_mode\": ?true'; then \
+             chattr -i /sys/firmware/efi/efivars/PK-* /sys/firmware/efi/efivars/KEK-* /sys/firmware/efi/efivars/db-* 2>/dev/null || true; \
+             sbctl enroll-keys {enroll_flag}; \
+             else echo 'WARNING: firmware is not in Setup Mode; enroll the keys later with: sbctl enroll-keys {enroll_flag}'; fi"
+        )));
+        if state.bootloader_index == 0 {
+            out.push(chroot_cmd(
+                "sbctl sign -s -o /usr/lib/systemd/boot/efi/systemd-bootx64.efi.signed /usr/lib/systemd/boot/efi/systemd-bootx64.efi",
+            ));
+        }
+        out.push(chroot_cmd(&format!(
+            "for f in $(find {esp}/EFI -path {esp}/EFI/Microsoft -prune -o -type f -iname \"*.efi\" -print) /boot/vmlinuz-* {esp}/EFI/Linux/vmlinuz-*; do \
+             if [ -f \"$f\" ]; then sbctl sign -s \"$f\" || exit 1; fi; \
+             done"
+        )));
+        let loader_pkg = match state.bootloader_index {
+            3 => Some("limine"),
+            4 => Some("refind"),
+            _ => None,
+        };
+        if let Some(pkg) = loader_pkg {
+            out.push(chroot_cmd(&format!(
+                "install -d -m 0755 /etc/pacman.d/hooks && cat > /etc/pacman.d/hooks/zz-sbctl-bootloader.hook <<HOOK_EOF\n\
+[Trigger]\n\
+Operation = Install\n\
+Operation = Upgrade\n\
+Type = Package\n\
+Target = {pkg}\n\
+\n\
+[Action]\n\
+Description = Signing the updated boot loader with sbctl\n\
+When = PostTransaction\n\
+Exec = /usr/bin/sbctl sign-all -g\n\
+HOOK_EOF"
+            )));
+        }
+        out.push(chroot_cmd("sbctl verify || true"));
+        out
+    }
+
+    /// What: Builds `arch-chroot` shell commands that boot through shim with a Machine Owner Key.
+    ///
+    /// Inputs:
+    /// - `state`: Bootloader choice (GRUB or systemd-boot).
+    /// - `esp`: ESP mountpoint inside the chroot.
+    ///
+    /// Output:
+    /// - Ordered `arch-chroot … bash -lc '…'` command strings; run after the loader and UKIs exist.
+    ///
+    /// Details:
+    /// - Builds `shim-signed` from the AUR and places the Microsoft-signed shim in front of the
+    ///   loader, which shim looks up as `grubx64.efi` in its own directory (also for systemd-boot).
+    /// - Creates a MOK, signs the loader and kernels with it, and installs a hook that re-signs
+    ///   them after kernel, initramfs and systemd-boot updates.
+    /// - The MOK itself is queued for enrollment by [`Self::mok_enroll_cmd`].
+    fn shim_mok_chroot_commands(state: &AppState, esp: &str) -> Vec<String> {
+        fn chroot_cmd(inner: &str) -> String {
+            let escaped = inner.replace("'", "'\\''");
+            format!("arch-chroot /mnt bash -lc '{escaped}'")
+        }
+        let mut out: Vec<String> = Vec::new();
+        let (loader_dir, loader_src) = if state.bootloader_index == 1 {
+            ("GRUB", None)
+        } else {
+            (
+                "systemd",
+                Some("/usr/lib/systemd/boot/efi/systemd-bootx64.efi"),
+            )
+        };
+
+        // shim-signed is only in the AUR; build the reviewed commit with a temporary
+        // unprivileged user and refuse binaries that differ from the pinned ones
+        out.push(chroot_cmd("pacman -S --noconfirm --needed base-devel git"));
+        out.push(chroot_cmd(
+            "id -u aurbuild >/dev/null 2>&1 || useradd -m -s /bin/bash aurbuild",
+        ));
+        out.push(chroot_cmd(&format!(
+            "sudo -u aurbuild bash -lc 'cd /tmp && rm -rf shim-signed && git clone https://aur.archlinux.org/shim-signed.git && cd shim-signed && git checkout --detach {SHIM_SIGNED_AUR_COMMIT} && makepkg --noconfirm'"
+        )));
+        out.push(chroot_cmd(
+            "pacman -U --noconfirm /tmp/shim-signed/shim-signed-*.pkg.tar.zst",
+        ));
+        out.push(chroot_cmd(&format!(
+            "printf '%s  %s\\n' {SHIMX64_SHA256} /usr/share/shim-signed/shimx64.efi {MMX64_SHA256} /usr/share/shim-signed/mmx64.efi | sha256sum -c - || \
+             {{ echo 'shim-signed binaries do not match the pinned sha256; refusing to install them' >&2; exit 1; }}"
+        )));
+        out.push(chroot_cmd("rm -rf /tmp/shim-signed || true"));
+        out.push(chroot_cmd("userdel -r aurbuild || true"));
+
+        out.push(chroot_cmd(
+            "install -d -m 0700 /etc/secureboot/mok && cd /etc/secureboot/mok && \
+             if [ ! -f MOK.key ]; then \
+             openssl req -newkey rsa:2048 -nodes -keyout MOK.key -new -x509 -sha256 -days 3650 -subj \"/CN=Arch Linux Machine Owner Key/\" -out MOK.crt && \
+             openssl x509 -outform DER -in MOK.crt -out MOK.cer; fi",
+        ));
+        // shim starts grubx64.efi next to itself, so systemd-boot goes in under that name
+        if let Some(src) = loader_src {
+            out.push(chroot_cmd(&format!(
+                "install -d -m 0755 {esp}/EFI/{loader_dir} && \
+                 sbsign --key /etc/secureboot/mok/MOK.key --cert /etc/secureboot/mok/MOK.crt --output {esp}/EFI/{loader_dir}/grubx64.efi {src}"
+            )));
+        } else {
+            out.push(chroot_cmd(&format!(
+                "sbsign --key /etc/secureboot/mok/MOK.key --cert /etc/secureboot/mok/MOK.crt --output {esp}/EFI/{loader_dir}/grubx64.efi {esp}/EFI/{loader_dir}/grubx64.efi"
+            )));
+        }
+        out.push(chroot_cmd(&format!(
+            "cp /usr/share/shim-signed/shimx64.efi /usr/share/shim-signed/mmx64.efi {esp}/EFI/{loader_dir}/ && \
+             install -d -m 0755 {esp}/EFI/BOOT && \
+             cp /usr/share/shim-signed/shimx64.efi {esp}/EFI/BOOT/BOOTX64.EFI && \
+             cp /usr/share/shim-signed/mmx64.efi {esp}/EFI/BOOT/ && \
+             cp {esp}/EFI/{loader_dir}/grubx64.efi {esp}/EFI/BOOT/grubx64.efi"
+        )));
+
+        // Re-signing script shared by the install and the pacman hook
+        let loader_resign = match loader_src {
+            Some(src) => format!(
+                "for dst in {esp}/EFI/{loader_dir}/grubx64.efi {esp}/EFI/BOOT/grubx64.efi; do\n\
+  if [ {src} -nt \"$dst\" ]; then\n\
+    sbsign --key \"$KEY\" --cert \"$CERT\" --output \"$dst\" {src}\n\
+  fi\n\
+done\n"
+            ),
+            None => String::new(),
+        };
+        out.push(chroot_cmd(&format!(
+            "cat > /usr/local/bin/mok-sign <<'MOK_EOF'\n\
+#!/bin/sh\n\
+KEY=/etc/secureboot/mok/MOK.key\n\
+CERT=/etc/secureboot/mok/MOK.crt\n\
+sign() {{\n\
+  sbverify --cert \"$CERT\" \"$1\" >/dev/null 2>&1 || sbsign --key \"$KEY\" --cert \"$CERT\" --output \"$1\" \"$1\"\n\
+}}\n\
+for f in /boot/vmlinuz-* {esp}/EFI/Linux/*.efi; do\n\
+  [ -f \"$f\" ] && sign \"$f\"\n\
+done\n\
+{loader_resign}\
+exit 0\n\
+MOK_EOF\n\
+chmod 0755 /usr/local/bin/mok-sign"
+        )));
+        let loader_target = if loader_src.is_some() {
+            "Target = usr/lib/systemd/boot/efi/systemd-bootx64.efi\n"
+        } else {
+            ""
+        };
+        out.push(chroot_cmd(&format!(
+            "install -d -m 0755 /etc/pacman.d/hooks && cat > /etc/pacman.d/hooks/zz-mok-sign.hook <<HOOK_EOF\n\
+[Trigger]\n\
+Operation = Install\n\
+Operation = Upgrade\n\
+Type = Path\n\
+Target = usr/lib/modules/*/vmlinuz\n\
+Target = usr/lib/initcpio/*\n\
+{loader_target}\
+\n\
+[Action]\n\
+Description = Signing kernels and the boot loader with the Machine Owner Key\n\
+When = PostTransaction\n\
+Exec = /usr/local/bin/mok-sign\n\
+HOOK_EOF"
+        )));
+        out.push(chroot_cmd("/usr/local/bin/mok-sign"));
+        out.push(chroot_cmd(&format!(
+            "if mountpoint -q /sys/firmware/efi/efivars || mount -t efivarfs efivarfs /sys/firmware/efi/efivars 2>/dev/null; then \
+             timeout 5 efibootmgr --create --disk /dev/$(lsblk -no pkname $(findmnt -n -o SOURCE {esp})) --part $(lsblk -no PARTN $(findmnt -n -o SOURCE {esp})) --loader '\\EFI\\{loader_dir}\\shimx64.efi' --label 'Arch Linux (shim)' --unicode || true; fi"
+        )));
+        out
+    }
+
+    /// Queue the MOK created by the shim setup for enrollment with the root password, which
+    /// MokManager asks for on the first boot. The password goes to `mokutil` on stdin.
+    pub fn mok_enroll_cmd(state: &AppState) -> Option<InstallCmd> {
+        (state.shim_signing_selected() && state.bootloader_index <= 1).then(|| {
+            InstallCmd::MokImport {
+                cert: "/etc/secureboot/mok/MOK.cer".into(),
+                password: state.root_password.clone(),
+            }
+        })
+    }
+
     pub fn build_plan(
         state: &AppState,
         device: &str,
@@ -400,7 +867,8 @@ HOOK_EOF"
             esp
         ));
 
-        let boot_options_script = Self::boot_options_script(encrypted);
+        let boot_options_script = Self::kernel_options_script(state, storage_plan);
+        let zfs_root = storage_plan.zfs_root_pool().is_some();
         let uki = Self::uki_requested(state)
             || (state.bootloader_index == 2 && state.is_secure_boot_enabled());
         let ucode = detect_microcode();
@@ -416,17 +884,12 @@ HOOK_EOF"
                     "install -d -m 0755 {esp}/loader && install -d -m 0755 {esp}/loader/entries"
                 )));
 
-                // First kernel determines the default entry name
-                let first_kernel = state
-                    .selected_kernels
-                    .iter()
-                    .next()
-                    .cloned()
-                    .unwrap_or_else(|| "linux".into());
-                let default_conf = if first_kernel == "linux" {
+                // The default kernel's entry boots unless another one is picked
+                let default_kernel = state.default_kernel();
+                let default_conf = if default_kernel == "linux" {
                     "arch.conf".to_string()
                 } else {
-                    format!("arch-{first_kernel}.conf")
+                    format!("arch-{default_kernel}.conf")
                 };
 
                 cmds.push(chroot_cmd(&format!(
@@ -480,7 +943,21 @@ HOOK_EOF"
             }
             // 1: grub
             1 => {
-                if state.is_uefi() {
+                if state.shim_signing_selected() {
+                    // shim only starts a GRUB with an SBAT section, and a signed GRUB cannot
+                    // load modules from disk, so everything it needs is built into the image
+                    cmds.push(chroot_cmd(
+                        "GRUB_VER=$(pacman -Q grub | cut -d\" \" -f2) && \
+                         printf \"%s\\n\" \"sbat,1,SBAT Version,sbat,1,https://github.com/rhboot/shim/blob/main/SBAT.md\" \
+                         \"grub,4,Free Software Foundation,grub,$GRUB_VER,https://www.gnu.org/software/grub/\" \
+                         \"grub.arch,1,Arch Linux,grub,$GRUB_VER,https://archlinux.org/packages/core/x86_64/grub/\" \
+                         > /usr/share/grub/sbat.csv",
+                    ));
+                    cmds.push(chroot_cmd(&format!(
+                        "grub-install --target=x86_64-efi --efi-directory={esp} --bootloader-id=GRUB \
+                         --modules=\"{GRUB_SHIM_MODULES}\" --sbat /usr/share/grub/sbat.csv --no-nvram"
+                    )));
+                } else if state.is_uefi() {
                     cmds.push(chroot_cmd(&format!(
                         "grub-install --target=x86_64-efi --efi-directory={esp} --bootloader-id=GRUB"
                     )));
@@ -490,42 +967,71 @@ HOOK_EOF"
                     )));
                 }
 
-                // For LUKS, inject rd.luks.name / root= into GRUB_CMDLINE_LINUX before grub-mkconfig
-                if encrypted {
+                // For LUKS, inject rd.luks.name / root= into GRUB_CMDLINE_LINUX before grub-mkconfig;
+                // for ZFS, root=ZFS= (grub-probe cannot resolve pool vdevs without
+                // ZPOOL_VDEV_NAME_PATH).
+                // Otherwise grub-mkconfig finds the root itself and only the resume options
+                // and extra parameters are added.
+                let grub_opts_script = if encrypted || zfs_root {
+                    Some(boot_options_script.clone())
+                } else {
+                    Self::with_extra_parameters(state, Self::resume_options_script(storage_plan))
+                };
+                if let Some(grub_opts_script) = grub_opts_script {
                     cmds.push(chroot_cmd(
-                        &format!("OPTS=$({boot_options_script}); \
+                        &format!("OPTS=$({grub_opts_script}); \
                          sed -i \"s|^GRUB_CMDLINE_LINUX=.*|GRUB_CMDLINE_LINUX=\\\"$OPTS\\\"|\" /etc/default/grub")
                     ));
                 }
-                cmds.push(chroot_cmd(&format!("grub-mkconfig -o {esp}/grub/grub.cfg")));
+                // Installed alongside another OS: os-prober adds its entries to the menu
+                if state.alongside_selected() {
+                    cmds.push(chroot_cmd(
+                        "if grep -qE '^#?GRUB_DISABLE_OS_PROBER=' /etc/default/grub; then \
+                         sed -i -E 's/^#?GRUB_DISABLE_OS_PROBER=.*/GRUB_DISABLE_OS_PROBER=false/' /etc/default/grub; \
+                         else echo 'GRUB_DISABLE_OS_PROBER=false' >> /etc/default/grub; fi",
+                    ));
+                }
+                // Several kernels: grub-mkconfig lists each with its fallback initramfs under
+                // "Advanced options"; GRUB_TOP_LEVEL puts the default kernel first
+                if state.selected_kernels.len() > 1 {
+                    cmds.push(chroot_cmd(&format!(
+                        "sed -i -E '/^#?GRUB_TOP_LEVEL=/d' /etc/default/grub && \
+                         echo 'GRUB_TOP_LEVEL=\"/boot/{vmlinuz}\"' >> /etc/default/grub",
+                        vmlinuz = kernel_artifacts(&state.default_kernel()).vmlinuz,
+                    )));
+                }
+                let vdev_env = if zfs_root {
+                    "ZPOOL_VDEV_NAME_PATH=1 "
+                } else {
+                    ""
+                };
+                // grub-install reads the config from its boot directory (/boot), also when
+                // the ESP is mounted at /efi
+                cmds.push(chroot_cmd(&format!(
+                    "{vdev_env}grub-mkconfig -o /boot/grub/grub.cfg"
+                )));
             }
             // 2: EFISTUB — direct kernel boot via firmware
-            2 => {
-                if state.is_uefi() {
-                    let first_kernel = state
-                        .selected_kernels
-                        .iter()
-                        .next()
-                        .cloned()
-                        .unwrap_or_else(|| "linux".into());
-                    let first_ka = kernel_artifacts(&first_kernel);
+            2 if state.is_uefi() => {
+                let default_kernel = state.default_kernel();
+                let default_ka = kernel_artifacts(&default_kernel);
 
-                    if uki {
-                        // Copy primary UKI to UEFI standard fallback path
-                        cmds.push(chroot_cmd(&format!(
+                if uki {
+                    // Copy the default kernel's UKI to UEFI standard fallback path
+                    cmds.push(chroot_cmd(&format!(
                             "install -d -m 0755 {esp}/EFI/BOOT && if [ -f {esp}/EFI/Linux/{uki_default} ]; then \
                              install -m 0644 {esp}/EFI/Linux/{uki_default} {esp}/EFI/BOOT/BOOTX64.EFI; \
                              else echo \"WARNING: {esp}/EFI/Linux/{uki_default} missing; UKI fallback copy skipped\"; fi",
-                            uki_default = first_ka.uki_default,
+                            uki_default = default_ka.uki_default,
                         )));
 
-                        // Pacman hook: refresh fallback copy on kernel upgrade (any selected kernel)
-                        let hook_targets: String = state
-                            .selected_kernels
-                            .iter()
-                            .map(|k| format!("Target = {k}\n"))
-                            .collect();
-                        cmds.push(chroot_cmd(&format!(
+                    // Pacman hook: refresh fallback copy on kernel upgrade (any selected kernel)
+                    let hook_targets: String = state
+                        .selected_kernels
+                        .iter()
+                        .map(|k| format!("Target = {k}\n"))
+                        .collect();
+                    cmds.push(chroot_cmd(&format!(
                             "install -d -m 0755 /etc/pacman.d/hooks && cat > /etc/pacman.d/hooks/99-efistub-uki-fallback.hook <<HOOK_EOF\n\
 [Trigger]\n\
 Operation = Install\n\
@@ -538,33 +1044,33 @@ Description = Refresh UEFI fallback UKI copy after kernel upgrade\n\
 When = PostTransaction\n\
 Exec = /bin/sh -c \"for f in {esp}/EFI/Linux/{uki_default} {esp}/EFI/Linux/{uki_fallback}; do [ -f \\\"$f\\\" ] && /usr/bin/install -Dm 0644 \\\"$f\\\" {esp}/EFI/BOOT/BOOTX64.EFI && break; done\"\n\
 HOOK_EOF",
-                            uki_default = first_ka.uki_default,
-                            uki_fallback = first_ka.uki_fallback,
+                            uki_default = default_ka.uki_default,
+                            uki_fallback = default_ka.uki_fallback,
                         )));
 
-                        // efibootmgr: drop only our EFISTUB UKI labels on this ESP, then register each kernel's UKI
-                        let nvram_cleanup =
-                            Self::efistub_nvram_cleanup_snippet(true, &state.selected_kernels);
-                        let first_arch_snippet = Self::efistub_first_arch_reorder_snippet(
-                            &Self::efistub_first_primary_label(&state.selected_kernels),
-                        );
-                        let mut efi_script = format!(
-                            "if mountpoint -q /sys/firmware/efi/efivars || mount -t efivarfs efivarfs /sys/firmware/efi/efivars 2>/dev/null; then \
+                    // efibootmgr: drop only our EFISTUB UKI labels on this ESP, then register each kernel's UKI
+                    let nvram_cleanup =
+                        Self::efistub_nvram_cleanup_snippet(true, &state.selected_kernels);
+                    let first_arch_snippet = Self::efistub_first_arch_reorder_snippet(
+                        &Self::efistub_primary_label(&default_kernel),
+                    );
+                    let mut efi_script = format!(
+                        "if mountpoint -q /sys/firmware/efi/efivars || mount -t efivarfs efivarfs /sys/firmware/efi/efivars 2>/dev/null; then \
                              BOOTSRC=$(findmnt -n -o SOURCE {esp}); \
                              DISK=$(lsblk -no pkname \"$BOOTSRC\"); \
                              PART=$(lsblk -no PARTN \"$BOOTSRC\"); \
                              PARTUUID=$(lsblk -no PARTUUID \"$BOOTSRC\" 2>/dev/null | head -1 | tr 'A-Z' 'a-z'); \
                              {nvram_cleanup}; \
                              "
-                        );
-                        for kernel in state.selected_kernels.iter() {
-                            let ka = kernel_artifacts(kernel);
-                            let label_suffix = if kernel == "linux" {
-                                String::new()
-                            } else {
-                                format!(" ({kernel})")
-                            };
-                            efi_script.push_str(&format!(
+                    );
+                    for kernel in state.boot_kernels().iter() {
+                        let ka = kernel_artifacts(kernel);
+                        let label_suffix = if kernel == "linux" {
+                            String::new()
+                        } else {
+                            format!(" ({kernel})")
+                        };
+                        efi_script.push_str(&format!(
                                 "efibootmgr --create --disk \"/dev/$DISK\" --part \"$PART\" --label 'Arch Linux{label_suffix}' --loader '\\EFI\\Linux\\{uki_default}' || \
                                  echo \"WARNING: efibootmgr failed for {kernel} UKI NVRAM entry\"; \
                                  efibootmgr --create --disk \"/dev/$DISK\" --part \"$PART\" --label 'Arch Linux{label_suffix} (fallback UKI)' --loader '\\EFI\\Linux\\{uki_fallback}' || \
@@ -572,9 +1078,9 @@ HOOK_EOF",
                                 uki_default = ka.uki_default,
                                 uki_fallback = ka.uki_fallback,
                             ));
-                        }
-                        efi_script.push_str(&first_arch_snippet);
-                        efi_script.push_str(
+                    }
+                    efi_script.push_str(&first_arch_snippet);
+                    efi_script.push_str(
                             "if [ -n \"$first_arch\" ]; then \
                                current=$(efibootmgr | awk -F'BootOrder: ' '/BootOrder:/{print $2}' | tr -d ' \\r'); \
                                if [ -n \"$current\" ]; then \
@@ -585,13 +1091,13 @@ HOOK_EOF",
                              fi; \
                              efibootmgr --verbose || true; fi",
                         );
-                        cmds.push(chroot_cmd(&efi_script));
-                    } else {
-                        // Non-UKI: startup.nsh with FS-scanning loop (primary kernel only)
-                        // Ensure firmware-loadable artifacts exist on the ESP for EFISTUB paths.
-                        for kernel in state.selected_kernels.iter() {
-                            let ka = kernel_artifacts(kernel);
-                            cmds.push(chroot_cmd(&format!(
+                    cmds.push(chroot_cmd(&efi_script));
+                } else {
+                    // Non-UKI: startup.nsh with FS-scanning loop (default kernel only)
+                    // Ensure firmware-loadable artifacts exist on the ESP for EFISTUB paths.
+                    for kernel in state.selected_kernels.iter() {
+                        let ka = kernel_artifacts(kernel);
+                        cmds.push(chroot_cmd(&format!(
                                 "install -d -m 0755 {esp}/EFI/Linux && \
                                  for f in /boot/{vmlinuz} /boot/{initramfs} /boot/{initramfs_fb}; do \
                                    if [ -f \"$f\" ]; then \
@@ -604,18 +1110,18 @@ HOOK_EOF",
                                 initramfs = ka.initramfs,
                                 initramfs_fb = ka.initramfs_fallback,
                             )));
-                        }
-                        if let Some(u) = ucode {
-                            cmds.push(chroot_cmd(&format!(
+                    }
+                    if let Some(u) = ucode {
+                        cmds.push(chroot_cmd(&format!(
                                 "if [ -f /boot/{u} ]; then install -Dm 0644 /boot/{u} {esp}/EFI/Linux/{u}; fi"
                             )));
-                        }
+                    }
 
-                        let ucode_nsh = ucode
-                            .map(|u| format!(" initrd=%d:\\EFI\\Linux\\{u}"))
-                            .unwrap_or_default();
-                        cmds.push(chroot_cmd(&format!(
-                            "OPTS=$({boot_options_script}); cat > {esp}/startup.nsh <<'NSHEOF'\n\
+                    let ucode_nsh = ucode
+                        .map(|u| format!(" initrd=%d:\\EFI\\Linux\\{u}"))
+                        .unwrap_or_default();
+                    cmds.push(chroot_cmd(&format!(
+                        "OPTS=$({boot_options_script}); cat > {esp}/startup.nsh <<'NSHEOF'\n\
 @echo -off\n\
 for %d in FS0 FS1 FS2 FS3 FS4 FS5 FS6 FS7 FS8 FS9\n\
   if exist %d:\\EFI\\Linux\\{vmlinuz} then\n\
@@ -623,17 +1129,17 @@ for %d in FS0 FS1 FS2 FS3 FS4 FS5 FS6 FS7 FS8 FS9\n\
   endif\n\
 endfor\n\
 NSHEOF\nchmod 0644 {esp}/startup.nsh",
-                            vmlinuz = first_ka.vmlinuz,
-                            initramfs = first_ka.initramfs,
-                        )));
+                        vmlinuz = default_ka.vmlinuz,
+                        initramfs = default_ka.initramfs,
+                    )));
 
-                        // Pacman hook: regenerate startup.nsh on kernel upgrade
-                        let hook_targets: String = state
-                            .selected_kernels
-                            .iter()
-                            .map(|k| format!("Target = {k}\n"))
-                            .collect();
-                        cmds.push(chroot_cmd(&format!(
+                    // Pacman hook: regenerate startup.nsh on kernel upgrade
+                    let hook_targets: String = state
+                        .selected_kernels
+                        .iter()
+                        .map(|k| format!("Target = {k}\n"))
+                        .collect();
+                    cmds.push(chroot_cmd(&format!(
                             "install -d -m 0755 /etc/pacman.d/hooks && cat > /etc/pacman.d/hooks/99-efistub-direct.hook <<HOOK_EOF\n\
 [Trigger]\n\
 Operation = Install\n\
@@ -646,21 +1152,21 @@ Description = Refresh EFISTUB startup.nsh after kernel upgrade\n\
 When = PostTransaction\n\
 Exec = /bin/sh -c \"install -d -m 0755 {esp}/EFI/Linux; for f in /boot/vmlinuz-* /boot/initramfs-*.img /boot/*-ucode.img; do [ -f \\\"\\$f\\\" ] && /usr/bin/install -Dm 0644 \\\"\\$f\\\" {esp}/EFI/Linux/\\$(basename \\\"\\$f\\\"); done; OPTS=$({boot_options_script}); cat > {esp}/startup.nsh <<NSH_INNER\\n@echo -off\\nfor %%d in FS0 FS1 FS2 FS3 FS4 FS5 FS6 FS7 FS8 FS9\\n  if exist %%d:\\\\EFI\\\\Linux\\\\{vmlinuz} then\\n    %%d:\\\\EFI\\\\Linux\\\\{vmlinuz} \\$OPTS{ucode_hook} initrd=%%d:\\\\EFI\\\\Linux\\\\{initramfs}\\n  endif\\nendfor\\nNSH_INNER\"\n\
 HOOK_EOF",
-                            vmlinuz = first_ka.vmlinuz,
-                            initramfs = first_ka.initramfs,
+                            vmlinuz = default_ka.vmlinuz,
+                            initramfs = default_ka.initramfs,
                             ucode_hook = ucode
                                 .map(|u| format!(" initrd=%%d:\\\\EFI\\\\Linux\\\\{u}"))
                                 .unwrap_or_default(),
                         )));
 
-                        // efibootmgr: drop only our EFISTUB labels on this ESP, then register each kernel
-                        let nvram_cleanup =
-                            Self::efistub_nvram_cleanup_snippet(false, &state.selected_kernels);
-                        let first_arch_snippet = Self::efistub_first_arch_reorder_snippet(
-                            &Self::efistub_first_primary_label(&state.selected_kernels),
-                        );
-                        let mut efi_script = format!(
-                            "if mountpoint -q /sys/firmware/efi/efivars || mount -t efivarfs efivarfs /sys/firmware/efi/efivars 2>/dev/null; then \
+                    // efibootmgr: drop only our EFISTUB labels on this ESP, then register each kernel
+                    let nvram_cleanup =
+                        Self::efistub_nvram_cleanup_snippet(false, &state.selected_kernels);
+                    let first_arch_snippet = Self::efistub_first_arch_reorder_snippet(
+                        &Self::efistub_primary_label(&default_kernel),
+                    );
+                    let mut efi_script = format!(
+                        "if mountpoint -q /sys/firmware/efi/efivars || mount -t efivarfs efivarfs /sys/firmware/efi/efivars 2>/dev/null; then \
                              BOOTSRC=$(findmnt -n -o SOURCE {esp}); \
                              DISK=$(lsblk -no pkname \"$BOOTSRC\"); \
                              PART=$(lsblk -no PARTN \"$BOOTSRC\"); \
@@ -674,18 +1180,18 @@ HOOK_EOF",
                                lsblk -f || true; \
                              fi; \
                              ls -l {esp}/EFI/Linux 2>/dev/null || echo 'EFISTUB-DIAG: EFI/Linux missing or unreadable'; "
-                        );
-                        let ucode_efi = ucode
-                            .map(|u| format!("initrd=\\\\\\\\EFI\\\\\\\\Linux\\\\\\\\{u} "))
-                            .unwrap_or_default();
-                        for kernel in state.selected_kernels.iter() {
-                            let ka = kernel_artifacts(kernel);
-                            let label_suffix = if kernel == "linux" {
-                                String::new()
-                            } else {
-                                format!(" ({kernel})")
-                            };
-                            efi_script.push_str(&format!(
+                    );
+                    let ucode_efi = ucode
+                        .map(|u| format!("initrd=\\\\\\\\EFI\\\\\\\\Linux\\\\\\\\{u} "))
+                        .unwrap_or_default();
+                    for kernel in state.boot_kernels().iter() {
+                        let ka = kernel_artifacts(kernel);
+                        let label_suffix = if kernel == "linux" {
+                            String::new()
+                        } else {
+                            format!(" ({kernel})")
+                        };
+                        efi_script.push_str(&format!(
                                 "for req in {vmlinuz} {initramfs} {initramfs_fb}; do \
                                    if [ ! -f {esp}/EFI/Linux/$req ]; then \
                                      echo \"EFISTUB-DIAG: missing ESP artifact {esp}/EFI/Linux/$req\"; \
@@ -707,9 +1213,9 @@ HOOK_EOF",
                                 initramfs = ka.initramfs,
                                 initramfs_fb = ka.initramfs_fallback,
                             ));
-                        }
-                        efi_script.push_str(&first_arch_snippet);
-                        efi_script.push_str(
+                    }
+                    efi_script.push_str(&first_arch_snippet);
+                    efi_script.push_str(
                             "if [ -n \"$first_arch\" ]; then \
                                current=$(efibootmgr | awk -F'BootOrder: ' '/BootOrder:/{print $2}' | tr -d ' \\r'); \
                                if [ -n \"$current\" ]; then \
@@ -726,8 +1232,7 @@ HOOK_EOF",
                              mount | grep -i efivarfs || true; \
                              fi",
                         );
-                        cmds.push(chroot_cmd(&efi_script));
-                    }
+                    cmds.push(chroot_cmd(&efi_script));
                 }
             }
             3 => {
@@ -738,9 +1243,72 @@ HOOK_EOF",
                     esp,
                 ));
             }
+            // 4: rEFInd — UEFI only
+            4 if state.is_uefi() => {
+                cmds.extend(Self::refind_install_chroot_commands(
+                    state,
+                    boot_options_script.as_str(),
+                    esp,
+                ));
+            }
             _ => {}
         }
 
+        if state.sbctl_signing_selected() {
+            cmds.extend(Self::sbctl_signing_chroot_commands(state, esp));
+        }
+        if state.shim_signing_selected() && state.bootloader_index <= 1 {
+            cmds.extend(Self::shim_mok_chroot_commands(state, esp));
+        }
+
+        // RAID: every member disk can boot on its own. BIOS GRUB goes into each disk's boot
+        // area; on UEFI the mirror ESPs get a copy of the primary one now and after every
+        // package transaction (kernels, initramfs and loaders all live there).
+        if state.is_uefi() {
+            let mirrors = storage_plan.esp_mirrors();
+            if !mirrors.is_empty() {
+                cmds.push(chroot_cmd(&format!(
+                    "cat > /usr/local/bin/esp-mirror <<'MIRROR_EOF'\n\
+#!/bin/sh\n\
+mountpoint -q {esp} || exit 0\n\
+for esp in {mirrors}; do\n\
+  mountpoint -q \"$esp\" || continue\n\
+  find \"$esp\" -mindepth 1 -delete\n\
+  cp -r {esp}/. \"$esp\"/\n\
+done\n\
+MIRROR_EOF\n\
+chmod 0755 /usr/local/bin/esp-mirror",
+                    mirrors = mirrors.join(" ")
+                )));
+                // Signed installs copy only after zz-sbctl.hook / zz-mok-sign.hook re-signed the ESP
+                let hook_name = if state.sbctl_signing_selected() || state.shim_signing_selected() {
+                    "zzz-esp-mirror.hook"
+                } else {
+                    "99-esp-mirror.hook"
+                };
+                cmds.push(chroot_cmd(&format!(
+                    "install -d -m 0755 /etc/pacman.d/hooks && cat > /etc/pacman.d/hooks/{hook_name} <<HOOK_EOF\n\
+[Trigger]\n\
+Operation = Install\n\
+Operation = Upgrade\n\
+Operation = Remove\n\
+Type = Package\n\
+Target = *\n\
+\n\
+[Action]\n\
+Description = Copying the ESP to the RAID mirror disks\n\
+When = PostTransaction\n\
+Exec = /usr/local/bin/esp-mirror\n\
+HOOK_EOF"
+                )));
+                cmds.push(chroot_cmd("/usr/local/bin/esp-mirror"));
+            }
+        } else if state.bootloader_index == 1 {
+            for disk in storage_plan.mirror_disks() {
+                cmds.push(chroot_cmd(&format!("grub-install --target=i386-pc {disk}")));
+            }
+        }
+
         state.debug_log(&format!(
             "bootloader: choice={} mode={} (uefi={}, encrypted={}, uki={}, esp={}, kernels={:?})",
             match state.bootloader_index {
@@ -748,6 +1316,7 @@ HOOK_EOF",
                 1 => "grub",
                 2 => "efistub",
                 3 => "limine",
+                4 => "refind",
                 _ => "unknown",
             },
             if state.is_uefi() { "UEFI" } else { "BIOS" },
@@ -766,6 +1335,9 @@ HOOK_EOF",
 mod tests {
     use super::BootloaderService;
     use crate::app::AppState;
+    use crate::core::storage::planner::StoragePlanner;
+    use crate::core::storage::planner::tests::uefi_auto_state;
+    use crate::core::storage::{StoragePlan, SwapMode, SwapfileSpec};
 
     #[test]
     fn effective_bios_grub_disk_passes_through_partition_target() {
@@ -801,4 +1373,80 @@ mod tests {
         state.firmware_uefi_override = Some(true);
         assert!(BootloaderService::effective_bios_grub_disk(&state, "").is_empty());
     }
+
+    fn auto_plan(swap_mode: SwapMode) -> StoragePlan {
+        let mut state = uefi_auto_state();
+        state.swap_mode_index = swap_mode as usize;
+        StoragePlanner::compile(&state).expect("plan should compile")
+    }
+
+    #[test]
+    fn resume_options_follow_the_swap_device() {
+        let mut plan = auto_plan(SwapMode::Partition);
+        let script = BootloaderService::resume_options_script(&plan).unwrap();
+        assert!(
+            script.contains("blkid -s UUID -o value /dev/sda2"),
+            "{script}"
+        );
+        assert!(script.contains("resume=UUID=$swapuuid"), "{script}");
+        assert!(
+            BootloaderService::root_options_script(&plan).contains("resume=UUID=$swapuuid"),
+            "root options carry the resume device"
+        );
+
+        let swap = plan.mounts.iter_mut().find(|m| m.is_swap).unwrap();
+        swap.source = "/dev/mapper/cryptswap".into();
+        let script = BootloaderService::resume_options_script(&plan).unwrap();
+        assert!(
+            script.contains("rd.luks.name=$swapluks=cryptswap resume=/dev/mapper/cryptswap"),
+            "{script}"
+        );
+
+        let swap = plan.mounts.iter_mut().find(|m| m.is_swap).unwrap();
+        swap.source = "/dev/vg0/swap".into();
+        let script = BootloaderService::resume_options_script(&plan).unwrap();
+        assert_eq!(script, "echo \"resume=/dev/vg0/swap\"");
+
+        assert!(BootloaderService::resume_options_script(&auto_plan(SwapMode::Zram)).is_none());
+        assert!(BootloaderService::resume_options_script(&auto_plan(SwapMode::None)).is_none());
+    }
+
+    #[test]
+    fn resume_options_for_swapfiles_include_the_offset() {
+        let mut plan = auto_plan(SwapMode::Swapfile);
+        let script = BootloaderService::resume_options_script(&plan).unwrap();
+        assert!(
+            script.contains("btrfs inspect-internal map-swapfile -r /swap/swapfile"),
+            "{script}"
+        );
+        assert!(
+            script.contains("resume=UUID=$swapuuid resume_offset=$offset"),
+            "{script}"
+        );
+
+        plan.swapfile = Some(SwapfileSpec::new("4G", false));
+        let script = BootloaderService::resume_options_script(&plan).unwrap();
+        assert!(script.contains("filefrag -v /swapfile"), "{script}");
+    }
+
+    #[test]
+    fn root_options_name_the_enrolled_luks_token() {
+        let mut state = uefi_auto_state();
+        state.swap_mode_index = SwapMode::None as usize;
+        state.disk_encryption_type_index = 1;
+        state.disk_encryption_fido2 = true;
+        let plan = StoragePlanner::compile(&state).expect("plan should compile");
+        let script = BootloaderService::root_options_script(&plan);
+        assert!(
+            script.contains(
+                "rd.luks.name=$luksuuid=$mapper rd.luks.options=$luksuuid=fido2-device=auto root="
+            ),
+            "{script}"
+        );
+
+        state.disk_encryption_fido2 = false;
+        let plan = StoragePlanner::compile(&state).expect("plan should compile");
+        let script = BootloaderService::root_options_script(&plan);
+        assert!(!script.contains("rd.luks.options"), "{script}");
+    }
 }
@@ -2,6 +2,7 @@
 // Kept temporarily so existing integration tests in tests/logic.rs still compile.
 
 use crate::core::state::AppState;
+use crate::core::storage::SwapMode;
 
 #[derive(Clone, Debug)]
 pub struct FstabPlan {
@@ -33,7 +34,7 @@ impl FstabService {
                 "blkid -o export {p1} | grep -Eq 'PARTLABEL=ESP|PARTLABEL=EFI System Partition|PARTTYPE=EF00|PARTUUID=' || echo 'WARN: ESP not detected on {p1}'"
             ));
         }
-        if state.swap_enabled {
+        if state.swap_mode() == SwapMode::Partition {
             cmds.push(format!(
                 "blkid {p2} | grep -q 'TYPE=\"swap\"' || echo 'WARN: swap not found on {p2}'"
             ));
@@ -51,7 +52,7 @@ impl FstabService {
         if state.is_uefi() {
             cmds.push("mountpoint -q /mnt/boot || echo 'ERROR: /mnt/boot is not mounted'".into());
         }
-        if state.swap_enabled {
+        if state.swap_mode() == SwapMode::Partition {
             cmds.push(
                 "swapon --noheadings --raw | grep -q '^' || echo 'ERROR: swap not active'".into(),
             );
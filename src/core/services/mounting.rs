@@ -2,6 +2,7 @@
 // Kept temporarily so existing integration tests in tests/logic.rs still compile.
 
 use crate::core::state::AppState;
+use crate::core::storage::SwapMode;
 
 #[derive(Clone, Debug)]
 pub struct MountPlan {
@@ -68,7 +69,7 @@ impl MountingService {
                 "mount -t vfat --mkdir {esp_part} /mnt/boot || mount -t fat --mkdir {esp_part} /mnt/boot || mount -t msdos --mkdir {esp_part} /mnt/boot"
             ));
         }
-        if state.swap_enabled {
+        if state.swap_mode() == SwapMode::Partition {
             let swap_part = Self::partition_path(device, 2);
             cmds.push(format!("swapon {swap_part}"));
         }
@@ -82,7 +83,7 @@ impl MountingService {
             "mounting: root={} uefi={} esp=/mnt/boot swap={} luks={}",
             root_path,
             state.is_uefi(),
-            state.swap_enabled,
+            state.swap_mode().name(),
             luks
         ));
         MountPlan::new(cmds)
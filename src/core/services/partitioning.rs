@@ -3,6 +3,7 @@
 // Remove once those tests migrate to the storage planner API.
 
 use crate::core::state::AppState;
+use crate::core::storage::SwapMode;
 
 #[derive(Clone, Debug)]
 pub struct PartitionPlan {
@@ -65,7 +66,7 @@ impl PartitioningService {
                 state.disks_wipe,
                 align,
                 state.is_uefi(),
-                state.swap_enabled,
+                state.swap_mode().name(),
                 state.disk_encryption_type_index == 1
             ));
         }
@@ -102,7 +103,7 @@ impl PartitioningService {
             next_start = "2MiB".into();
         }
 
-        if state.swap_enabled {
+        if state.swap_mode() == SwapMode::Partition {
             let swap_end = if state.is_uefi() {
                 "5121MiB"
             } else {
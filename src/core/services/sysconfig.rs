@@ -1,6 +1,7 @@
 use crate::core::services::bootloader::{BootloaderService, kernel_artifacts};
+use crate::core::services::system::ARCHZFS_KEY;
 use crate::core::state::AppState;
-use crate::core::storage::StoragePlan;
+use crate::core::storage::{StoragePlan, SwapMode};
 
 #[derive(Clone, Debug)]
 pub struct SysConfigPlan {
@@ -20,7 +21,8 @@ impl SysConfigService {
         let mut cmds: Vec<String> = Vec::new();
         let encrypted = storage_plan.has_encryption();
         let uki = BootloaderService::uki_requested(state);
-        let boot_options_script = BootloaderService::boot_options_script(encrypted);
+        let boot_options_script = BootloaderService::kernel_options_script(state, storage_plan);
+        let zfs_root = storage_plan.zfs_root_pool().is_some();
 
         const MKINITCPIO_P: &str = "out=$(mkinitcpio -P 2>&1); rc=$?; printf '%s\\n' \"$out\"; if [ \"$rc\" -ne 0 ]; then if printf '%s\\n' \"$out\" | grep -q '^==> ERROR:'; then exit \"$rc\"; fi; if printf '%s\\n' \"$out\" | grep -q 'WARNING: errors were encountered during the build'; then echo 'mkinitcpio returned warnings-only non-zero exit; continuing install' >&2; else exit \"$rc\"; fi; fi";
 
@@ -101,6 +103,19 @@ impl SysConfigService {
             cmds.push("systemctl --root=/mnt enable systemd-timesyncd".into());
         }
 
+        // zram swap: zram-generator creates /dev/zram0 at boot; the sysctl values are the
+        // ones recommended for swap that lives in RAM.
+        if state.swap_mode() == SwapMode::Zram {
+            cmds.push(chroot_cmd(&format!(
+                "printf '%s\\n' '[zram0]' 'zram-size = {}' 'compression-algorithm = {}' > /etc/systemd/zram-generator.conf",
+                state.zram_size,
+                state.zram_algorithm()
+            )));
+            cmds.push(chroot_cmd(
+                "printf '%s\\n' 'vm.swappiness = 180' 'vm.watermark_boost_factor = 0' 'vm.watermark_scale_factor = 125' 'vm.page-cluster = 0' > /etc/sysctl.d/99-vm-zram-parameters.conf",
+            ));
+        }
+
         // Root password (set only if provided and confirmed)
         if !state.root_password.is_empty() && state.root_password == state.root_password_confirm {
             if state.dry_run {
@@ -181,6 +196,53 @@ impl SysConfigService {
             }
         }
 
+        // ZFS root: the zfs hook only works in a udev-based initramfs and must come before
+        // `filesystems`; the encrypt step below then adds `encrypt` after `block`.
+        if zfs_root {
+            cmds.push(chroot_cmd(
+                "sed -i 's/^HOOKS=.*/HOOKS=(base udev autodetect microcode modconf kms keyboard keymap consolefont block zfs filesystems)/' /etc/mkinitcpio.conf",
+            ));
+            cmds.push(chroot_cmd(&format!(
+                "pacman-key --recv-keys {ARCHZFS_KEY} && pacman-key --lsign-key {ARCHZFS_KEY}"
+            )));
+            cmds.push(
+                "systemctl --root=/mnt enable zfs-import-cache.service zfs-import.target zfs-mount.service zfs.target"
+                    .into(),
+            );
+        }
+
+        // TPM2/FIDO2 tokens and key-file swap are only unlocked by `sd-encrypt`: switch a
+        // udev-based initramfs to the systemd hooks (the encrypt step below adds `sd-encrypt`)
+        // and list token volumes in /etc/crypttab.initramfs with the option selecting their
+        // token (key-file swap is listed by `fstab_check_commands`).
+        if storage_plan.needs_systemd_initramfs() {
+            cmds.push(chroot_cmd(
+                "grep -qP '^HOOKS=.*\\bsystemd\\b' /etc/mkinitcpio.conf || \
+                 sed -i 's/^HOOKS=.*/HOOKS=(base systemd autodetect microcode modconf kms keyboard sd-vconsole block filesystems fsck)/' /etc/mkinitcpio.conf",
+            ));
+            for (device, enc) in storage_plan.luks_volumes() {
+                let Some(options) = enc.enroll.unlock_options() else {
+                    continue;
+                };
+                cmds.push(chroot_cmd(&format!(
+                    "touch /etc/crypttab.initramfs; \
+                     grep -q '^{mapper}[[:space:]]' /etc/crypttab.initramfs || \
+                       printf '%s\\n' \"{mapper} UUID=$(blkid -s UUID -o value {device}) none {options}\" >> /etc/crypttab.initramfs",
+                    mapper = enc.mapper_name,
+                )));
+            }
+        }
+
+        // bcachefs root: the hook (from bcachefs-tools) adds the module and mount helper, and
+        // prompts for the passphrase of a natively encrypted root
+        let bcachefs_root = storage_plan.root_fstype() == Some("bcachefs");
+        if bcachefs_root {
+            cmds.push(chroot_cmd(
+                "grep -qP '^HOOKS=.*\\bbcachefs\\b' /etc/mkinitcpio.conf || \
+                 sed -i '/^HOOKS=/s/\\bblock\\b/block bcachefs/' /etc/mkinitcpio.conf",
+            ));
+        }
+
         // mkinitcpio: for LUKS, ensure the correct encrypt hook is present.
         // Modern Arch (mkinitcpio >=37) defaults to `systemd` hooks → use `sd-encrypt`.
         // Older ISOs still ship `udev` hooks → need `encrypt` instead.  Detect which is
@@ -197,7 +259,46 @@ impl SysConfigService {
                  fi",
             ));
         }
-        if encrypted || uki {
+        // LVM: the `lvm2` hook activates volume groups before `filesystems`, after the LUKS
+        // volume under them is unlocked; with LUKS on the logical volumes it goes before the
+        // encrypt hook instead.
+        let lvm = storage_plan.has_lvm();
+        if lvm {
+            let insert = if storage_plan.has_luks_on_lvm() {
+                "s/\\bblock\\b/block lvm2/"
+            } else {
+                "s/\\bfilesystems\\b/lvm2 filesystems/"
+            };
+            cmds.push(chroot_cmd(&format!(
+                "grep -qP '^HOOKS=.*\\blvm2\\b' /etc/mkinitcpio.conf || \
+                 sed -i '/^HOOKS=/{insert}' /etc/mkinitcpio.conf"
+            )));
+        }
+        // RAID: the arrays go into mdadm.conf (copied into the initramfs) under their
+        // /dev/md names, and `mdadm_udev` assembles them before anything unlocks or
+        // activates what sits on top.
+        let raid = storage_plan.has_raid();
+        if raid {
+            cmds.push(
+                "grep -q '^ARRAY' /mnt/etc/mdadm.conf 2>/dev/null || \
+                 mdadm --detail --scan >> /mnt/etc/mdadm.conf"
+                    .into(),
+            );
+            cmds.push(chroot_cmd(
+                "grep -qP '^HOOKS=.*\\bmdadm_udev\\b' /etc/mkinitcpio.conf || \
+                 sed -i '/^HOOKS=/s/\\bblock\\b/block mdadm_udev/' /etc/mkinitcpio.conf",
+            ));
+        }
+        // Hibernation: the `resume` hook (placed after any unlocking hooks) reads the image named
+        // by `resume=`; a `systemd` initramfs resumes without it.
+        let resume = storage_plan.resume_device().is_some();
+        if resume {
+            cmds.push(chroot_cmd(
+                "grep -qP '^HOOKS=.*\\b(systemd|resume)\\b' /etc/mkinitcpio.conf || \
+                 sed -i '/^HOOKS=/s/\\bfilesystems\\b/resume filesystems/' /etc/mkinitcpio.conf",
+            ));
+        }
+        if encrypted || uki || zfs_root || bcachefs_root || lvm || raid || resume {
             cmds.push(chroot_cmd(MKINITCPIO_P));
         }
 
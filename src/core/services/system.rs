@@ -1,4 +1,10 @@
 use crate::core::state::AppState;
+use crate::core::storage::SwapMode;
+
+/// Third-party repository providing `zfs-dkms` and `zfs-utils`.
+pub const ARCHZFS_SERVER: &str = "https://archzfs.com/$repo/$arch";
+/// archzfs package signing key.
+pub const ARCHZFS_KEY: &str = "DDF7DB817396A49B2A2723F7403BD972F75D9D76";
 
 #[derive(Clone, Debug)]
 pub struct SystemPlan {
@@ -95,6 +101,19 @@ impl SystemService {
             }
         }
 
+        // ZFS root: pacstrap installs with the host pacman.conf and keyring, the target keeps
+        // the repo for updates (its keyring is set up in system configuration).
+        if state.zfs_root_selected() {
+            for conf in ["/etc/pacman.conf", "/mnt/etc/pacman.conf"] {
+                cmds.push(format!(
+                    "grep -q '^\\[archzfs\\]' {conf} || printf '\\n[archzfs]\\nServer = {ARCHZFS_SERVER}\\n' >> {conf}"
+                ));
+            }
+            cmds.push(format!(
+                "pacman-key --recv-keys {ARCHZFS_KEY} && pacman-key --lsign-key {ARCHZFS_KEY}"
+            ));
+        }
+
         // Debug summary
         state.debug_log(&format!(
             "system: pre-install multilib={} testing={} custom_repos={}",
@@ -137,6 +156,63 @@ impl SystemService {
             package_set.insert(k.clone());
         }
 
+        // bcachefs: tools plus the out-of-tree module, built against each kernel's headers
+        if state.bcachefs_selected() {
+            package_set.insert("bcachefs-tools".into());
+            package_set.insert("bcachefs-dkms".into());
+            for k in state.selected_kernels.iter() {
+                package_set.insert(format!("{k}-headers"));
+            }
+        }
+
+        // LVM: the initramfs hook and volume group activation
+        if state.lvm_selected() {
+            package_set.insert("lvm2".into());
+        }
+
+        // GRUB lists the other operating systems it finds next to this install
+        if state.alongside_selected() && state.bootloader_index == 1 {
+            package_set.insert("os-prober".into());
+        }
+
+        // Software RAID: array assembly and the mdadm_udev hook
+        if state.raid_selected() {
+            package_set.insert("mdadm".into());
+        }
+
+        // zram swap is set up at boot by zram-generator
+        if state.swap_mode() == SwapMode::Zram {
+            package_set.insert("zram-generator".into());
+        }
+
+        // Hibernation reads the swap file offset in the chroot: `btrfs inspect-internal` on
+        // btrfs, `filefrag` (e2fsprogs) elsewhere
+        if state.swap_mode() == SwapMode::Swapfile {
+            let tools = if state.btrfs_root_selected() {
+                "btrfs-progs"
+            } else {
+                "e2fsprogs"
+            };
+            package_set.insert(tools.into());
+        }
+
+        // systemd-cryptsetup loads the TPM2 and FIDO2 libraries at unlock time
+        if state.disk_encryption_tpm2 {
+            package_set.insert("tpm2-tss".into());
+        }
+        if state.disk_encryption_fido2 {
+            package_set.insert("libfido2".into());
+        }
+
+        // ZFS root: the DKMS module is built against each kernel's headers
+        if state.zfs_root_selected() {
+            package_set.insert("zfs-dkms".into());
+            package_set.insert("zfs-utils".into());
+            for k in state.selected_kernels.iter() {
+                package_set.insert(format!("{k}-headers"));
+            }
+        }
+
         // Bootloader and EFI tools
         if state.is_uefi() {
             package_set.insert("efibootmgr".into());
@@ -148,11 +224,22 @@ impl SystemService {
             3 => {
                 package_set.insert("limine".into());
             }
+            4 => {
+                package_set.insert("refind".into());
+            }
             _ => {}
         }
         if state.uki_enabled && state.bootloader_index != 1 {
             package_set.insert("systemd-ukify".into());
         }
+        if state.sbctl_signing_selected() {
+            package_set.insert("sbctl".into());
+        }
+        // shim-signed itself comes from the AUR and is built in the bootloader step
+        if state.shim_signing_selected() {
+            package_set.insert("mokutil".into());
+            package_set.insert("sbsigntools".into());
+        }
 
         // Network stack
         if state.network_mode_index == 2 {
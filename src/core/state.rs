@@ -3,10 +3,10 @@ use ratatui::widgets::ListState;
 use std::collections::BTreeSet;
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
 
-use crate::common::InstallLogMsg;
+use crate::common::{CommandFailure, FailureAction, InstallLogMsg};
 use crate::core::types::{
-    AdditionalPackage, CustomRepo, DiskPartitionSpec, Focus, InstallClickTarget, MenuEntry,
-    NetworkInterfaceConfig, PopupKind, Screen, UserAccount,
+    AdditionalPackage, CustomRepo, DiskPartitionSpec, Focus, InstallClickTarget, LogicalVolumeSpec,
+    MenuEntry, NetworkInterfaceConfig, PopupKind, Screen, UserAccount,
 };
 
 pub struct AppState {
@@ -60,7 +60,7 @@ pub struct AppState {
     pub cmdline_buffer: String,
 
     // Disk Partitioning screen state
-    pub disks_focus_index: usize, // 0..=2 items + 3 Continue
+    pub disks_focus_index: usize, // 0..=2 modes, 3 root fs, 4 btrfs preset, 5 LVM, 6 RAID, 7 alongside, 8 Continue
     pub disks_mode_index: usize,  // selected mode index 0..=2
     pub disks_devices: Vec<crate::app::disks::DiskDevice>,
     pub disks_selected_device: Option<String>,
@@ -77,6 +77,26 @@ pub struct AppState {
     pub disks_align: Option<String>,
     pub disks_partitions: Vec<DiskPartitionSpec>,
     pub btrfs_subvolume_preset: usize, // 0=Flat, 1=Standard, 2=Extended
+    pub disks_root_fs_index: usize,    // automatic layout: index into RootFilesystem::NAMES
+    // Guided LVM (sub-screen of Disk Partitioning)
+    pub lvm_open: bool,
+    pub lvm_enabled: bool,
+    pub lvm_vg_name: String,
+    pub lvm_stacking_index: usize, // LvmStacking::NAMES: 0 LVM on LUKS, 1 LUKS on LVM
+    pub lvm_volumes: Vec<LogicalVolumeSpec>,
+    pub lvm_focus_index: usize, // 0: enabled, 1: VG, 2: PV, 3: stacking, then volumes, Add, Back
+    pub lvm_draft: LogicalVolumeSpec, // volume being added or edited by the popups
+    pub lvm_edit_index: Option<usize>, // None while adding
+    // Software RAID sub-screen of Disks (automatic layout)
+    pub raid_open: bool,
+    pub raid_enabled: bool,
+    pub raid_level_index: usize, // RaidLevel::NAMES: 0 raid0, 1 raid1, 2 raid5, 3 raid6, 4 raid10
+    pub raid_devices: Vec<String>, // member disks; the selected disk is always one of them
+    pub raid_focus_index: usize, // 0: enabled, 1: level, 2: member disks, 3: Back
+    // Automatic layout in the free space of the existing partition table (dual boot)
+    pub alongside_enabled: bool,
+    /// When `Some`, the planner uses this table instead of probing the disk with parted (tests).
+    pub alongside_layout_override: Option<crate::core::storage::DiskLayout>,
     /// Cached output of findmnt/swapon for pre-mounted mode (avoid subprocesses every render).
     pub pre_mounted_cache_mount_lines: Vec<String>,
     pub pre_mounted_cache_findmnt_failed: bool,
@@ -123,18 +143,36 @@ pub struct AppState {
     pub disk_encryption_password_confirm: String,
     pub diskenc_reopen_after_info: bool,
     pub disk_encryption_selected_partition: Option<String>,
-
-    // Swap Partition state
-    pub swap_focus_index: usize, // 0: toggle, 1: Continue
-    pub swap_enabled: bool,
+    pub disk_encryption_tpm2: bool,
+    pub disk_encryption_tpm2_pcrs: String, // systemd-cryptenroll --tpm2-pcrs, e.g. "7"
+    pub disk_encryption_fido2: bool,
+    pub disk_encryption_recovery_key: bool,
+    // luksFormat parameters; empty strings / index 0 keep the cryptsetup default
+    pub luks_cipher: String,
+    pub luks_key_size: String, // bits
+    pub luks_hash: String,
+    pub luks_pbkdf_index: usize, // 0: default, else storage::LUKS_PBKDFS[i - 1]
+    pub luks_pbkdf_memory: String, // KiB
+    pub luks_pbkdf_time: String, // ms
+    pub luks_sector_size_index: usize, // 0: default, else storage::LUKS_SECTOR_SIZES[i - 1]
+    pub luks_label: String,
+    pub luks_integrity_index: usize, // 0: none, else storage::LUKS_INTEGRITY[i - 1]
+
+    // Swap state
+    pub swap_focus_index: usize, // 0: mode, 1: swapfile size, 2: zram size, 3: zram compression, 4: encryption, 5: Continue
+    pub swap_mode_index: usize,  // SwapMode::NAMES: 0 partition, 1 swapfile, 2 zram, 3 none
+    pub swapfile_size: String,
+    pub zram_size: String,
+    pub zram_algorithm_index: usize,  // storage::ZRAM_ALGORITHMS
+    pub swap_encryption_index: usize, // SwapEncryption::NAMES: 0 none, 1 random key, 2 key file
 
     // Unified Kernel Images state
     pub uki_focus_index: usize, // 0: toggle, 1: Continue
     pub uki_enabled: bool,
 
     // Bootloader state
-    pub bootloader_focus_index: usize, // 0: selector, 1: Continue
-    pub bootloader_index: usize,       // 0: systemd-boot, 1: grub, 2: efistub, 3: limine
+    pub bootloader_focus_index: usize, // 0-4: bootloaders, 5: signing, 6: Microsoft keys, 7: Continue
+    pub bootloader_index: usize,       // 0: systemd-boot, 1: grub, 2: efistub, 3: limine, 4: refind
     /// When `Some`, install code treats the host as UEFI or BIOS without probing `/sys/firmware/efi` (tests).
     pub firmware_uefi_override: Option<bool>,
     /// Secure Boot status detected at startup (or forced in tests via override).
@@ -145,10 +183,18 @@ pub struct AppState {
     pub secure_boot_setup_mode: bool,
     /// Optional test override for Secure Boot state.
     pub secure_boot_override: Option<bool>,
+    /// Whether the firmware trusts the Microsoft UEFI CA that signs shim (`mokutil --db`).
+    pub secure_boot_third_party_ca: Option<bool>,
+    /// How boot binaries are signed for Secure Boot (`SecureBootSigning` order).
+    pub secure_boot_signing_index: usize,
+    /// Enroll Microsoft's keys next to the sbctl keys (option ROMs, Windows).
+    pub secure_boot_microsoft_keys: bool,
 
     // Kernels state
-    pub kernels_focus_index: usize, // 0: select, 1: Continue
+    pub kernels_focus_index: usize, // 0: select, 1: default, 2: parameters, 3: Continue
     pub selected_kernels: std::collections::BTreeSet<String>,
+    pub default_kernel: Option<String>, // default boot entry; None: first selected kernel
+    pub kernel_parameters: String,      // extra kernel cmdline options, e.g. "quiet splash"
 
     // Audio state
     pub audio_focus_index: usize, // 0..=2 choices + 3 Continue
@@ -238,6 +284,8 @@ pub struct AppState {
     // Additional Packages state
     pub addpkgs_focus_index: usize, // 0: Add package, 1: Select groups, 2: Continue
     pub additional_packages: Vec<AdditionalPackage>,
+    // Config-only hook commands injected into install sections
+    pub install_hooks: Vec<crate::app::install::hooks::InstallHook>,
     pub addpkgs_selected_index: usize, // highlighted row in the package list (↑/↓, j/k)
     pub addpkgs_reopen_after_info: bool,
     // Additional Packages: groups
@@ -266,6 +314,21 @@ pub struct AppState {
     pub install_section_titles: Vec<String>,
     pub install_section_done: Vec<bool>,
     pub install_current_section: Option<usize>,
+    // Resume: section index chosen in the resume popup, and the indices it offered
+    pub install_resume_from: Option<usize>,
+    pub install_resume_choices: Vec<usize>,
+    // Failure recovery: decision channel to the waiting install thread, the pending failure,
+    // skipped-command warnings for the final report, and a pending arch-chroot shell request
+    pub install_decision_tx: Option<Sender<FailureAction>>,
+    pub install_failure: Option<CommandFailure>,
+    /// Set while a real install runs; cancelling stops it and cleans up the target.
+    pub install_cancel: Option<crate::app::install::cancel::CancelToken>,
+    pub install_warnings: Vec<String>,
+    /// LUKS recovery keys copied into the target by the running install.
+    pub install_recovery_keys: Vec<String>,
+    pub install_shell_requested: bool,
+    // JSONL event log for real installs (`--event-log` / ARCHINSTALL_EVENT_LOG)
+    pub install_event_log: Option<std::path::PathBuf>,
 
     // Request to exit TUI and run install in stdout mode
     pub exit_tui_after_install: bool,
@@ -311,7 +374,7 @@ impl AppState {
                 screen: Screen::DiskEncryption,
             },
             MenuEntry {
-                label: "Swap Partition".into(),
+                label: "Swap".into(),
                 content: String::new(),
                 screen: Screen::SwapPartition,
             },
@@ -452,6 +515,22 @@ impl AppState {
             disks_align: Some("1MiB".into()),
             disks_partitions: Vec::new(),
             btrfs_subvolume_preset: 0, // Flat (no subvolumes) by default
+            disks_root_fs_index: 0,    // btrfs
+            lvm_open: false,
+            lvm_enabled: false,
+            lvm_vg_name: "vg0".into(),
+            lvm_stacking_index: 0,
+            lvm_volumes: Vec::new(),
+            lvm_focus_index: 0,
+            lvm_draft: LogicalVolumeSpec::default(),
+            lvm_edit_index: None,
+            raid_open: false,
+            raid_enabled: false,
+            raid_level_index: 1,
+            raid_devices: Vec::new(),
+            raid_focus_index: 0,
+            alongside_enabled: false,
+            alongside_layout_override: None,
             pre_mounted_cache_mount_lines: Vec::new(),
             pre_mounted_cache_findmnt_failed: false,
             pre_mounted_cache_swap_devices: Vec::new(),
@@ -496,9 +575,26 @@ impl AppState {
             disk_encryption_password_confirm: String::new(),
             diskenc_reopen_after_info: false,
             disk_encryption_selected_partition: None,
+            disk_encryption_tpm2: false,
+            disk_encryption_tpm2_pcrs: "7".into(),
+            disk_encryption_fido2: false,
+            disk_encryption_recovery_key: false,
+            luks_cipher: String::new(),
+            luks_key_size: String::new(),
+            luks_hash: String::new(),
+            luks_pbkdf_index: 0,
+            luks_pbkdf_memory: String::new(),
+            luks_pbkdf_time: String::new(),
+            luks_sector_size_index: 0,
+            luks_label: String::new(),
+            luks_integrity_index: 0,
 
             swap_focus_index: 0,
-            swap_enabled: true,
+            swap_mode_index: 0,
+            swapfile_size: "4G".into(),
+            zram_size: "ram / 2".into(),
+            zram_algorithm_index: 0,
+            swap_encryption_index: 0,
 
             uki_focus_index: 0,
             uki_enabled: false,
@@ -510,6 +606,9 @@ impl AppState {
             secure_boot_known: false,
             secure_boot_setup_mode: false,
             secure_boot_override: None,
+            secure_boot_third_party_ca: None,
+            secure_boot_signing_index: 0,
+            secure_boot_microsoft_keys: true,
 
             kernels_focus_index: 0,
             selected_kernels: {
@@ -517,6 +616,8 @@ impl AppState {
                 s.insert("linux".into());
                 s
             },
+            default_kernel: None,
+            kernel_parameters: String::new(),
 
             audio_focus_index: 0,
             audio_index: 1,
@@ -604,6 +705,7 @@ impl AppState {
 
             addpkgs_focus_index: 0,
             additional_packages: Vec::new(),
+            install_hooks: Vec::new(),
             addpkgs_selected_index: 0,
             addpkgs_reopen_after_info: false,
             addpkgs_group_focus: false,
@@ -631,6 +733,15 @@ impl AppState {
             install_section_titles: Vec::new(),
             install_section_done: Vec::new(),
             install_current_section: None,
+            install_resume_from: None,
+            install_resume_choices: Vec::new(),
+            install_decision_tx: None,
+            install_failure: None,
+            install_cancel: None,
+            install_warnings: Vec::new(),
+            install_recovery_keys: Vec::new(),
+            install_shell_requested: false,
+            install_event_log: None,
 
             exit_tui_after_install: false,
             pending_install_sections: None,
@@ -747,6 +858,10 @@ impl AppState {
                     }
                     return;
                 }
+                if let Some(rest) = line.strip_prefix("::warning::") {
+                    self.install_warnings.push(rest.to_string());
+                    return;
+                }
                 if let Some(rest) = line.strip_prefix("::section_done::") {
                     if let Some(idx) = self.install_section_titles.iter().position(|t| t == rest)
                         && idx < self.install_section_done.len()
@@ -763,6 +878,14 @@ impl AppState {
                 // No log line limit: allow install_log to grow as needed
                 self.refresh_install_info_popup_body();
             }
+            InstallLogMsg::CommandFailed(failure) => {
+                self.debug_log(&format!(
+                    "append_install_log_msg: command failed in '{}'",
+                    failure.section
+                ));
+                self.install_failure = Some(failure);
+                self.open_install_failure_popup();
+            }
             InstallLogMsg::ReplaceLastLine(line) => {
                 if line.is_empty() {
                     return;
@@ -819,6 +942,8 @@ impl AppState {
             self.install_running = false;
             self.install_log_rx = None;
             self.install_log_tx = None;
+            self.install_decision_tx = None;
+            self.install_failure = None;
             self.debug_log("drain_install_logs: install_running set to false");
             // Show a message in the TUI log output when channel is disconnected
             self.install_log
@@ -2,9 +2,11 @@ pub mod planner;
 
 use std::fmt;
 
-use crate::common::install_cmd::InstallCmd;
+use serde::Serialize;
 
-#[derive(Clone, Debug)]
+use crate::common::install_cmd::{InstallCmd, shell_quote_arg};
+
+#[derive(Clone, Debug, Serialize)]
 pub struct StoragePlan {
     pub devices: Vec<PlannedDevice>,
     pub mounts: Vec<PlannedMount>,
@@ -12,24 +14,35 @@ pub struct StoragePlan {
     /// Layered device stacks for LVM, RAID, or multi-layer encryption setups.
     /// Empty for simple partition-based layouts. Command generation is stubbed (Phase 6).
     pub stacks: Vec<DeviceStack>,
+    /// ZFS pools created on top of partitions (or stacks); empty unless the root is ZFS.
+    pub pools: Vec<ZfsPoolSpec>,
+    /// bcachefs filesystems, each formatted across its member partitions.
+    pub bcachefs: Vec<BcachefsSpec>,
+    /// Swap file on the root filesystem (swapfile mode only).
+    pub swapfile: Option<SwapfileSpec>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StorageMode {
     Automatic,
     Manual,
     PreMounted,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct PlannedDevice {
     pub path: String,
     pub label: DiskLabel,
     pub wipe: bool,
+    /// Keep the existing partition table and its partitions: `partitions` are only added in
+    /// its free space (installing alongside another OS).
+    pub keep_table: bool,
     pub partitions: Vec<PlannedPartition>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DiskLabel {
     Gpt,
     Msdos,
@@ -44,7 +57,7 @@ impl DiskLabel {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct PlannedPartition {
     pub number: u32,
     pub role: PartitionRole,
@@ -56,7 +69,8 @@ pub struct PlannedPartition {
     pub subvolumes: Vec<SubvolumeSpec>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PartitionRole {
     Esp,
     BiosBoot,
@@ -64,10 +78,28 @@ pub enum PartitionRole {
     Root,
     Home,
     Var,
+    /// LVM physical volume holding the guided volume group.
+    Lvm,
+    /// Member of a guided software RAID array; every member disk gets the same layout.
+    Raid,
     Other(String),
 }
 
 impl PartitionRole {
+    /// Role names understood by [`PartitionRole::from_str_role`] (case-insensitive).
+    pub const KNOWN_NAMES: [&str; 10] = [
+        "BOOT",
+        "EFI",
+        "ESP",
+        "BIOS_BOOT",
+        "SWAP",
+        "ROOT",
+        "HOME",
+        "VAR",
+        "LVM",
+        "OTHER",
+    ];
+
     pub fn from_str_role(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "BOOT" | "EFI" | "ESP" => PartitionRole::Esp,
@@ -76,15 +108,19 @@ impl PartitionRole {
             "ROOT" => PartitionRole::Root,
             "HOME" => PartitionRole::Home,
             "VAR" => PartitionRole::Var,
+            "LVM" => PartitionRole::Lvm,
             other => PartitionRole::Other(other.to_string()),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PartitionFlag {
     Esp,
     BiosGrub,
+    Lvm,
+    Raid,
 }
 
 impl PartitionFlag {
@@ -92,33 +128,240 @@ impl PartitionFlag {
         match self {
             PartitionFlag::Esp => "esp",
             PartitionFlag::BiosGrub => "bios_grub",
+            PartitionFlag::Lvm => "lvm",
+            PartitionFlag::Raid => "raid",
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct FilesystemSpec {
     pub fstype: String,
     pub mkfs_options: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct EncryptionSpec {
     pub method: EncryptionMethod,
     pub mapper_name: String,
     /// Passphrase for LUKS encryption (piped to cryptsetup via stdin at install time).
+    #[serde(skip_serializing)]
     pub passphrase: Option<String>,
+    /// `luksFormat` parameters (cipher, PBKDF, sector size, ...).
+    pub format: LuksFormatOptions,
+    /// Unlock methods added next to the passphrase once the volume is formatted.
+    pub enroll: LuksEnrollment,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EncryptionMethod {
     Luks2,
+    /// LUKS2 keyed only by a random key file installed in the encrypted root
+    /// ([`EncryptionSpec::key_file`]); used for swap that must survive hibernation.
+    Luks2Keyfile,
+    /// Plain dm-crypt with a fresh key from `/dev/urandom` on every boot (`/etc/crypttab`
+    /// `swap` entry); the contents never outlive a power cycle, so no hibernation.
+    PlainRandomKey,
+}
+
+/// Cipher and key size (bits) of random-key swap, at install time and in `/etc/crypttab`.
+pub const PLAIN_SWAP_CIPHER: (&str, u32) = ("aes-xts-plain64", 512);
+
+impl EncryptionSpec {
+    /// Key file of an [`EncryptionMethod::Luks2Keyfile`] volume inside the target root, where
+    /// `systemd-cryptsetup` looks for `<mapper>.key`.
+    pub fn key_file(&self) -> String {
+        format!("/etc/cryptsetup-keys.d/{}.key", self.mapper_name)
+    }
+
+    /// Where the key file is generated on the live system before the root is mounted.
+    pub fn live_key_file(&self) -> String {
+        format!("/tmp/{}.key", self.mapper_name)
+    }
+}
+
+/// PBKDFs accepted by `cryptsetup luksFormat --pbkdf`, in Disk Encryption screen order.
+pub const LUKS_PBKDFS: [&str; 3] = ["argon2id", "argon2i", "pbkdf2"];
+/// LUKS2 encryption sector sizes in bytes; 4096 suits NVMe and Advanced Format drives.
+pub const LUKS_SECTOR_SIZES: [u32; 4] = [512, 1024, 2048, 4096];
+/// dm-integrity algorithms for authenticated encryption (`--integrity`).
+pub const LUKS_INTEGRITY: [&str; 2] = ["hmac-sha256", "hmac-sha512"];
+
+/// `cryptsetup luksFormat` parameters; `None` keeps the cryptsetup default.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LuksFormatOptions {
+    /// e.g. `aes-xts-plain64`
+    pub cipher: Option<String>,
+    /// Key size in bits (512 for AES-256 in XTS mode).
+    pub key_size: Option<u32>,
+    /// Hash for the keyslot digest, e.g. `sha512`.
+    pub hash: Option<String>,
+    pub pbkdf: Option<String>,
+    /// Argon2 memory cost in KiB.
+    pub pbkdf_memory: Option<u32>,
+    /// PBKDF time cost in milliseconds (`--iter-time`).
+    pub pbkdf_time: Option<u32>,
+    pub sector_size: Option<u32>,
+    pub label: Option<String>,
+    pub integrity: Option<String>,
+}
+
+impl LuksFormatOptions {
+    /// Extra `luksFormat` arguments, one per set option.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |flag: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push(format!("--{flag}={value}"));
+            }
+        };
+        push("cipher", self.cipher.clone());
+        push("key-size", self.key_size.map(|v| v.to_string()));
+        push("hash", self.hash.clone());
+        push("pbkdf", self.pbkdf.clone());
+        push("pbkdf-memory", self.pbkdf_memory.map(|v| v.to_string()));
+        push("iter-time", self.pbkdf_time.map(|v| v.to_string()));
+        push("sector-size", self.sector_size.map(|v| v.to_string()));
+        push("label", self.label.clone());
+        push("integrity", self.integrity.clone());
+        args
+    }
+
+    /// Problems with the options as `(config field, message)` pairs.
+    pub fn problems(&self) -> Vec<(&'static str, String)> {
+        let is_name = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        };
+        let mut problems = Vec::new();
+        if let Some(cipher) = self.cipher.as_deref().filter(|c| !is_name(c)) {
+            problems.push((
+                "cipher",
+                format!("invalid cipher '{cipher}' (expected e.g. aes-xts-plain64)"),
+            ));
+        }
+        if let Some(bits) = self.key_size.filter(|b| *b == 0 || b % 8 != 0) {
+            problems.push((
+                "key_size",
+                format!("key size {bits} is not a multiple of 8 bits"),
+            ));
+        }
+        if let Some(hash) = self.hash.as_deref().filter(|h| !is_name(h)) {
+            problems.push((
+                "hash",
+                format!("invalid hash '{hash}' (expected e.g. sha512)"),
+            ));
+        }
+        if let Some(pbkdf) = self.pbkdf.as_deref().filter(|p| !LUKS_PBKDFS.contains(p)) {
+            problems.push((
+                "pbkdf",
+                format!("unknown PBKDF '{pbkdf}' (expected argon2id, argon2i or pbkdf2)"),
+            ));
+        }
+        if let Some(kib) = self.pbkdf_memory {
+            if self.pbkdf.as_deref() == Some("pbkdf2") {
+                problems.push((
+                    "pbkdf_memory",
+                    "PBKDF memory cost needs argon2id or argon2i".into(),
+                ));
+            } else if !(32..=4_194_304).contains(&kib) {
+                problems.push((
+                    "pbkdf_memory",
+                    format!("PBKDF memory cost {kib} KiB is outside 32-4194304"),
+                ));
+            }
+        }
+        if self.pbkdf_time == Some(0) {
+            problems.push(("pbkdf_time", "PBKDF time cost must be at least 1 ms".into()));
+        }
+        if let Some(size) = self.sector_size.filter(|s| !LUKS_SECTOR_SIZES.contains(s)) {
+            problems.push((
+                "sector_size",
+                format!("sector size {size} is not 512, 1024, 2048 or 4096"),
+            ));
+        }
+        if let Some(label) = &self.label
+            && (label.is_empty() || label.len() > 47 || label.chars().any(|c| c.is_control()))
+        {
+            problems.push((
+                "label",
+                format!("label '{label}' must be 1-47 printable bytes"),
+            ));
+        }
+        if let Some(integrity) = self
+            .integrity
+            .as_deref()
+            .filter(|i| !LUKS_INTEGRITY.contains(i))
+        {
+            problems.push((
+                "integrity",
+                format!("unknown integrity '{integrity}' (expected hmac-sha256 or hmac-sha512)"),
+            ));
+        }
+        problems
+    }
+}
+
+/// Keyslots enrolled with `systemd-cryptenroll` after `luksFormat`, unlocked by the passphrase.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LuksEnrollment {
+    /// PCRs the TPM2 key is sealed against (`7`, `0+7`, ...); `None` skips TPM2.
+    pub tpm2_pcrs: Option<String>,
+    pub fido2: bool,
+    /// Generate a recovery key into [`Self::recovery_key_path`] on the live system; mounting
+    /// copies it to [`Self::installed_recovery_key_path`] in the target.
+    pub recovery_key: bool,
+}
+
+impl LuksEnrollment {
+    /// True when a TPM2 or FIDO2 token is enrolled; only the `systemd`/`sd-encrypt`
+    /// initramfs can use them.
+    pub fn has_tokens(&self) -> bool {
+        self.tpm2_pcrs.is_some() || self.fido2
+    }
+
+    /// `crypttab` / `rd.luks.options` options selecting the enrolled token. With both
+    /// tokens no device is named: `systemd-cryptsetup` then tries every LUKS2 token.
+    pub fn unlock_options(&self) -> Option<&'static str> {
+        match (self.tpm2_pcrs.is_some(), self.fido2) {
+            (true, false) => Some("tpm2-device=auto"),
+            (false, true) => Some("fido2-device=auto"),
+            (true, true) => Some("luks"),
+            (false, false) => None,
+        }
+    }
+
+    pub fn recovery_key_path(mapper: &str) -> String {
+        format!("/root/luks-recovery-{mapper}.txt")
+    }
+
+    /// Where the recovery key is kept in the installed system, next to the install record.
+    pub fn installed_recovery_key_path(mapper: &str) -> String {
+        format!("/var/log/archinstall-rs/luks-recovery-{mapper}.txt")
+    }
+}
+
+/// True for a `systemd-cryptenroll --tpm2-pcrs` list: PCR numbers (0-23) or names such as
+/// `secure-boot-policy`, joined by `+`.
+pub fn is_valid_tpm2_pcrs(pcrs: &str) -> bool {
+    !pcrs.is_empty()
+        && pcrs.split('+').all(|pcr| match pcr.parse::<u8>() {
+            Ok(n) => n < 24,
+            Err(_) => {
+                !pcr.is_empty()
+                    && pcr
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            }
+        })
 }
 
 // ── LVM / RAID / Volume layer abstractions (Phase 6) ──
 
 /// LVM logical volume specification.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct LvmSpec {
     pub vg_name: String,
     pub lv_name: String,
@@ -126,8 +369,132 @@ pub struct LvmSpec {
     pub size: String,
 }
 
+/// Where LUKS sits in a guided LVM layout (LVM screen).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LvmStacking {
+    /// One LUKS volume holds the physical volume; a single unlock opens every logical volume.
+    LvmOnLuks,
+    /// Every logical volume is its own LUKS volume on a plain physical volume.
+    LuksOnLvm,
+}
+
+impl LvmStacking {
+    /// Config names, in LVM screen order (`AppState::lvm_stacking_index`).
+    pub const NAMES: [&str; 2] = ["lvm_on_luks", "luks_on_lvm"];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => LvmStacking::LuksOnLvm,
+            _ => LvmStacking::LvmOnLuks,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name.trim()))
+            .map(Self::from_index)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            LvmStacking::LvmOnLuks => "LVM on LUKS (one unlock)",
+            LvmStacking::LuksOnLvm => "LUKS on LVM (per volume)",
+        }
+    }
+}
+
+/// Filesystems offered for guided logical volumes, in LVM screen order.
+pub const LVM_FILESYSTEMS: [&str; 5] = ["ext4", "xfs", "btrfs", "f2fs", "linux-swap"];
+
+/// True for a volume group or logical volume name LVM accepts: letters, digits and `+_.-`,
+/// not starting with `-` and not `.` or `..`.
+pub fn is_valid_lvm_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+_.-".contains(c))
+}
+
+/// True for a logical volume size: an absolute size as for [`is_valid_swapfile_size`]
+/// (`lvcreate -L`), or `N%FREE` / `N%VG` with N from 1 to 100 (`lvcreate -l`).
+pub fn is_valid_lvm_size(size: &str) -> bool {
+    match size.split_once('%') {
+        Some((n, unit)) => {
+            matches!(unit, "FREE" | "VG") && n.parse::<u32>().is_ok_and(|n| (1..=100).contains(&n))
+        }
+        None => is_valid_swapfile_size(size),
+    }
+}
+
+/// Level of the guided software RAID array (RAID screen).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaidLevel {
+    Raid0,
+    Raid1,
+    Raid5,
+    Raid6,
+    Raid10,
+}
+
+impl RaidLevel {
+    /// Config names, in RAID screen order (`AppState::raid_level_index`).
+    pub const NAMES: [&str; 5] = ["raid0", "raid1", "raid5", "raid6", "raid10"];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => RaidLevel::Raid0,
+            2 => RaidLevel::Raid5,
+            3 => RaidLevel::Raid6,
+            4 => RaidLevel::Raid10,
+            _ => RaidLevel::Raid1,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name.trim()))
+            .map(Self::from_index)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            RaidLevel::Raid0 => "RAID0 (striped, no redundancy)",
+            RaidLevel::Raid1 => "RAID1 (mirrored)",
+            RaidLevel::Raid5 => "RAID5 (striped with parity)",
+            RaidLevel::Raid6 => "RAID6 (striped with double parity)",
+            RaidLevel::Raid10 => "RAID10 (striped mirrors)",
+        }
+    }
+
+    /// `mdadm --level` value.
+    pub fn mdadm_level(&self) -> &'static str {
+        &self.name()[4..]
+    }
+
+    pub fn min_devices(&self) -> usize {
+        match self {
+            RaidLevel::Raid0 | RaidLevel::Raid1 => 2,
+            RaidLevel::Raid5 => 3,
+            RaidLevel::Raid6 | RaidLevel::Raid10 => 4,
+        }
+    }
+}
+
 /// Software RAID specification.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct RaidSpec {
     /// RAID level: "0", "1", "5", "6", "10".
     pub level: String,
@@ -138,7 +505,8 @@ pub struct RaidSpec {
 }
 
 /// A single transformation layer in a device stack.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum VolumeLayer {
     Luks(EncryptionSpec),
     Lvm(LvmSpec),
@@ -149,7 +517,7 @@ pub enum VolumeLayer {
 ///
 /// Represents chains like: `/dev/sda3` → LUKS → LVM VG/LV → filesystem → mount.
 /// Each layer transforms the previous device path into a new one.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct DeviceStack {
     /// Base device path (e.g., a partition like "/dev/sda3").
     pub base: String,
@@ -185,16 +553,30 @@ impl DeviceStack {
     /// the commands needed to set up that layer. Finishes with filesystem creation
     /// on the final device when a `FilesystemSpec` is present.
     pub fn setup_commands(&self) -> Vec<InstallCmd> {
+        self.setup_commands_once(&mut Vec::new())
+    }
+
+    /// [`Self::setup_commands`] for stacks sharing their lower layers (logical volumes of one
+    /// group): a LUKS mapper, volume group or array whose device path is in `prepared` is not
+    /// created again, and the ones created here are added to it.
+    pub fn setup_commands_once(&self, prepared: &mut Vec<String>) -> Vec<InstallCmd> {
         let mut cmds = Vec::new();
         let mut current = self.base.clone();
 
         for layer in &self.layers {
             match layer {
                 VolumeLayer::Luks(enc) => {
+                    let mapper = format!("/dev/mapper/{}", enc.mapper_name);
+                    if prepared.contains(&mapper) {
+                        current = mapper;
+                        continue;
+                    }
+                    prepared.push(mapper);
                     cmds.push(InstallCmd::shell(
                         "modprobe -q dm_crypt 2>/dev/null || modprobe -q dm-crypt 2>/dev/null || true",
                     ));
-                    cmds.push(Self::luks_format_cmd(&current, enc.passphrase.as_deref()));
+                    cmds.push(Self::luks_format_cmd(&current, enc));
+                    cmds.extend(Self::luks_enroll_cmds(&current, enc));
                     cmds.push(InstallCmd::shell("udevadm settle"));
                     cmds.push(Self::luks_open_cmd(
                         &current,
@@ -204,11 +586,15 @@ impl DeviceStack {
                     current = format!("/dev/mapper/{}", enc.mapper_name);
                 }
                 VolumeLayer::Lvm(lvm) => {
-                    cmds.push(InstallCmd::shell(format!("pvcreate {current}")));
-                    cmds.push(InstallCmd::shell(format!(
-                        "vgcreate {} {current}",
-                        lvm.vg_name
-                    )));
+                    let group = format!("/dev/{}", lvm.vg_name);
+                    if !prepared.contains(&group) {
+                        prepared.push(group);
+                        cmds.push(InstallCmd::shell(format!("pvcreate {current}")));
+                        cmds.push(InstallCmd::shell(format!(
+                            "vgcreate {} {current}",
+                            lvm.vg_name
+                        )));
+                    }
                     let size_flag = if lvm.size.contains('%') {
                         format!("-l {}", lvm.size)
                     } else {
@@ -221,15 +607,21 @@ impl DeviceStack {
                     current = format!("/dev/{}/{}", lvm.vg_name, lvm.lv_name);
                 }
                 VolumeLayer::Raid(raid) => {
-                    let members_str = raid.members.join(" ");
-                    cmds.push(InstallCmd::shell(format!(
-                        "mdadm --create /dev/md/{} --level={} --raid-devices={} {}",
-                        raid.name,
-                        raid.level,
-                        raid.members.len(),
-                        members_str
-                    )));
-                    current = format!("/dev/md/{}", raid.name);
+                    let array = format!("/dev/md/{}", raid.name);
+                    if !prepared.contains(&array) {
+                        prepared.push(array.clone());
+                        let members_str = raid.members.join(" ");
+                        // `--run`: a mirror with its metadata at the start otherwise stops to ask
+                        // whether it may hold /boot.
+                        cmds.push(InstallCmd::shell(format!(
+                            "mdadm --create /dev/md/{} --run --level={} --raid-devices={} {}",
+                            raid.name,
+                            raid.level,
+                            raid.members.len(),
+                            members_str
+                        )));
+                    }
+                    current = array;
                 }
             }
         }
@@ -241,16 +633,72 @@ impl DeviceStack {
         cmds
     }
 
-    /// LUKS format step: when a passphrase is set, it is passed via stdin by the runner
-    /// (never embedded in a logged shell string).
-    pub(crate) fn luks_format_cmd(device: &str, passphrase: Option<&str>) -> InstallCmd {
-        match passphrase {
+    /// LUKS format step with `enc.format` options: when a passphrase is set, it is passed via
+    /// stdin by the runner (never embedded in a logged shell string). Key-file volumes are
+    /// formatted with [`EncryptionSpec::live_key_file`] only.
+    pub(crate) fn luks_format_cmd(device: &str, enc: &EncryptionSpec) -> InstallCmd {
+        let mut args = enc.format.args();
+        if enc.method == EncryptionMethod::Luks2Keyfile {
+            args.push(format!("--key-file={}", enc.live_key_file()));
+        }
+        match &enc.passphrase {
             Some(pw) => InstallCmd::CryptsetupLuksFormat {
                 device: device.to_string(),
-                passphrase: pw.to_string(),
+                args,
+                passphrase: pw.clone(),
             },
-            None => InstallCmd::shell(format!("cryptsetup luksFormat --type luks2 -q {device}")),
+            None => InstallCmd::shell(format!(
+                "cryptsetup luksFormat --type luks2 -q {}{device}",
+                args.iter()
+                    .map(|a| format!("{} ", shell_quote_arg(a)))
+                    .collect::<String>()
+            )),
+        }
+    }
+
+    /// `systemd-cryptenroll` steps for `enc.enroll`: TPM2, FIDO2 (the key must be touched when
+    /// it blinks), then the recovery key, whose output goes to a file instead of the log.
+    pub(crate) fn luks_enroll_cmds(device: &str, enc: &EncryptionSpec) -> Vec<InstallCmd> {
+        let mut steps: Vec<(Vec<String>, Option<String>)> = Vec::new();
+        if let Some(pcrs) = &enc.enroll.tpm2_pcrs {
+            steps.push((
+                vec!["--tpm2-device=auto".into(), format!("--tpm2-pcrs={pcrs}")],
+                None,
+            ));
+        }
+        if enc.enroll.fido2 {
+            steps.push((
+                vec![
+                    "--fido2-device=auto".into(),
+                    "--fido2-with-client-pin=no".into(),
+                ],
+                None,
+            ));
         }
+        if enc.enroll.recovery_key {
+            steps.push((
+                vec!["--recovery-key".into()],
+                Some(LuksEnrollment::recovery_key_path(&enc.mapper_name)),
+            ));
+        }
+        steps
+            .into_iter()
+            .map(|(args, output)| match &enc.passphrase {
+                Some(pw) => InstallCmd::CryptEnroll {
+                    device: device.to_string(),
+                    args,
+                    passphrase: pw.clone(),
+                    output,
+                },
+                None => {
+                    let cmd = format!("systemd-cryptenroll {} {device}", args.join(" "));
+                    InstallCmd::shell(match output {
+                        Some(path) => format!("(umask 077; {cmd} > {path})"),
+                        None => cmd,
+                    })
+                }
+            })
+            .collect()
     }
 
     /// LUKS open step; passphrase handling matches [`Self::luks_format_cmd`].
@@ -281,12 +729,348 @@ impl DeviceStack {
             "ext2" => format!("mkfs.ext2 -F {device}"),
             "xfs" => format!("mkfs.xfs -f {device}"),
             "f2fs" => format!("mkfs.f2fs -f {device}"),
+            "bcachefs" => format!("bcachefs format -f {device}"),
             _ => format!("mkfs.ext4 -F {device}"),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+// ── Existing partition tables (installing alongside) ──
+
+/// Smallest existing ESP that also holds the kernels and initramfs images at `/boot`.
+pub const ALONGSIDE_MIN_ESP_MIB: u64 = 512;
+/// Smallest free region the automatic layout installs into (root plus swap and a new ESP).
+pub const ALONGSIDE_MIN_FREE_MIB: u64 = 20 * 1024;
+
+/// A partition already on the disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExistingPartition {
+    pub number: u32,
+    pub start_mib: u64,
+    pub end_mib: u64,
+    /// Filesystem as parted reports it (`fat32`, `ntfs`, ...); empty when unknown.
+    pub fstype: String,
+    /// The partition carries the `esp` flag.
+    pub esp: bool,
+}
+
+impl ExistingPartition {
+    pub fn size_mib(&self) -> u64 {
+        self.end_mib.saturating_sub(self.start_mib)
+    }
+}
+
+/// Partition table of a disk before the install, read with `parted -m unit MiB print free`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiskLayout {
+    /// `None` when the disk has no partition table parted understands.
+    pub label: Option<DiskLabel>,
+    pub partitions: Vec<ExistingPartition>,
+    /// Unallocated regions as whole MiB `(start, end)`.
+    pub free: Vec<(u64, u64)>,
+}
+
+impl DiskLayout {
+    /// Read the partition table of `device`; `None` when parted fails.
+    pub fn probe(device: &str) -> Option<Self> {
+        let out = std::process::Command::new("parted")
+            .args(["-m", "-s", device, "unit", "MiB", "print", "free"])
+            .output()
+            .ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        Some(Self::parse_parted(&String::from_utf8_lossy(&out.stdout)))
+    }
+
+    /// Parse `parted -m unit MiB print free` output. Partition boundaries are rounded inwards
+    /// for free regions, so new partitions never overlap an existing one.
+    pub fn parse_parted(output: &str) -> Self {
+        let mib = |field: &str| field.trim_end_matches("MiB").parse::<f64>().ok();
+        let mut layout = DiskLayout::default();
+        for line in output.lines() {
+            let fields: Vec<&str> = line.trim().trim_end_matches(';').split(':').collect();
+            if fields.first().is_some_and(|f| f.starts_with('/')) {
+                layout.label = match fields.get(5) {
+                    Some(&"gpt") => Some(DiskLabel::Gpt),
+                    Some(&"msdos") => Some(DiskLabel::Msdos),
+                    _ => None,
+                };
+                continue;
+            }
+            if fields.len() < 5 {
+                continue;
+            }
+            let (Some(start), Some(end)) = (mib(fields[1]), mib(fields[2])) else {
+                continue;
+            };
+            if fields[4] == "free" {
+                let (start, end) = (start.ceil() as u64, end.floor() as u64);
+                if end > start {
+                    layout.free.push((start, end));
+                }
+            } else if let Ok(number) = fields[0].parse::<u32>() {
+                layout.partitions.push(ExistingPartition {
+                    number,
+                    start_mib: start.floor() as u64,
+                    end_mib: end.ceil() as u64,
+                    fstype: fields[4].to_string(),
+                    esp: fields
+                        .get(6)
+                        .is_some_and(|flags| flags.split(", ").any(|f| f == "esp")),
+                });
+            }
+        }
+        layout
+    }
+
+    /// The largest unallocated region.
+    pub fn largest_free(&self) -> Option<(u64, u64)> {
+        self.free
+            .iter()
+            .copied()
+            .max_by_key(|(start, end)| end - start)
+    }
+
+    /// The existing EFI system partition, if any (the first one when there are several).
+    pub fn esp(&self) -> Option<&ExistingPartition> {
+        self.partitions.iter().find(|p| p.esp)
+    }
+
+    /// Partition numbers not used by an existing partition, in the order parted assigns them.
+    pub fn free_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        (1..).filter(|n| !self.partitions.iter().any(|p| p.number == *n))
+    }
+}
+
+// ── ZFS pools ──
+
+/// Pool name used by the automatic layout.
+pub const ZFS_POOL_NAME: &str = "zroot";
+
+/// A dataset inside a [`ZfsPoolSpec`], named relative to the pool (e.g. `ROOT/default`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ZfsDatasetSpec {
+    pub name: String,
+    /// `zfs create -o` properties, in order.
+    pub properties: Vec<(String, String)>,
+}
+
+impl ZfsDatasetSpec {
+    fn new(name: &str, properties: &[(&str, &str)]) -> Self {
+        Self {
+            name: name.into(),
+            properties: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    /// The `mountpoint` property, unless the dataset is never mounted (`none`,
+    /// `canmount=off`).
+    pub fn mountpoint(&self) -> Option<&str> {
+        let prop = |key: &str| {
+            self.properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        if prop("canmount") == Some("off") {
+            return None;
+        }
+        prop("mountpoint").filter(|m| m.starts_with('/'))
+    }
+}
+
+/// A ZFS pool and the datasets created in it.
+///
+/// Created in the volume stack step: `zpool create` on the member devices, then one
+/// `zfs create` per dataset in order (parents first), then exported so the mounting step
+/// imports it under `/mnt` like any other run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ZfsPoolSpec {
+    pub name: String,
+    /// Member device paths (partitions or mapper devices), one vdev each.
+    pub members: Vec<String>,
+    /// Pool properties (`zpool create -o`).
+    pub pool_properties: Vec<(String, String)>,
+    /// Properties of the pool's root dataset (`zpool create -O`), inherited by all datasets.
+    pub root_properties: Vec<(String, String)>,
+    pub datasets: Vec<ZfsDatasetSpec>,
+    /// Dataset booted as `/`, relative to the pool.
+    pub bootfs: String,
+}
+
+impl ZfsPoolSpec {
+    /// The automatic layout: a boot environment under `ROOT/default` plus separate `home`
+    /// and `var/log` datasets, lz4 compression and POSIX ACLs/xattrs for systemd.
+    pub fn default_layout(name: &str, members: Vec<String>) -> Self {
+        let props = |list: &[(&str, &str)]| {
+            list.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        Self {
+            name: name.into(),
+            members,
+            pool_properties: props(&[("ashift", "12"), ("autotrim", "on")]),
+            root_properties: props(&[
+                ("acltype", "posixacl"),
+                ("xattr", "sa"),
+                ("dnodesize", "auto"),
+                ("normalization", "formD"),
+                ("relatime", "on"),
+                ("compression", "lz4"),
+                ("devices", "off"),
+                ("canmount", "off"),
+                ("mountpoint", "none"),
+            ]),
+            datasets: vec![
+                ZfsDatasetSpec::new("ROOT", &[("mountpoint", "none"), ("canmount", "off")]),
+                ZfsDatasetSpec::new(
+                    "ROOT/default",
+                    &[("mountpoint", "/"), ("canmount", "noauto")],
+                ),
+                ZfsDatasetSpec::new("home", &[("mountpoint", "/home")]),
+                ZfsDatasetSpec::new("var", &[("mountpoint", "/var"), ("canmount", "off")]),
+                ZfsDatasetSpec::new("var/log", &[("mountpoint", "/var/log")]),
+            ],
+            bootfs: "ROOT/default".into(),
+        }
+    }
+
+    /// Full dataset name, e.g. `zroot/ROOT/default`.
+    pub fn dataset_path(&self, dataset: &str) -> String {
+        format!("{}/{dataset}", self.name)
+    }
+
+    /// Mounts for every mountable dataset, `/` first, targets under `/mnt`.
+    pub fn planned_mounts(&self) -> Vec<PlannedMount> {
+        let mut mounts: Vec<PlannedMount> = self
+            .datasets
+            .iter()
+            .filter_map(|ds| {
+                let mountpoint = ds.mountpoint()?;
+                Some(PlannedMount {
+                    source: self.dataset_path(&ds.name),
+                    target: if mountpoint == "/" {
+                        "/mnt".into()
+                    } else {
+                        format!("/mnt{mountpoint}")
+                    },
+                    fstype: "zfs".into(),
+                    options: vec![],
+                    is_swap: false,
+                    subvolume: None,
+                })
+            })
+            .collect();
+        mounts.sort_by_key(|m| (m.target != "/mnt", m.target.clone()));
+        mounts
+    }
+
+    /// Create the pool and its datasets, then export it.
+    pub fn setup_commands(&self) -> Vec<InstallCmd> {
+        let opts = |flag: &str, props: &[(String, String)]| {
+            props
+                .iter()
+                .map(|(k, v)| format!("{flag} {k}={v}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut cmds = vec![
+            InstallCmd::shell(
+                "modprobe zfs || { echo 'ERROR: the zfs kernel module is not available on this live system; boot an ISO with ZFS support.' >&2; exit 1; }",
+            ),
+            InstallCmd::shell("[ -f /etc/hostid ] || zgenhostid"),
+            InstallCmd::shell(format!(
+                "zpool create -f {} {} -R /mnt {} {}",
+                opts("-o", &self.pool_properties),
+                opts("-O", &self.root_properties),
+                self.name,
+                self.members.join(" ")
+            )),
+        ];
+        for ds in &self.datasets {
+            cmds.push(InstallCmd::shell(format!(
+                "zfs create {} {}",
+                opts("-o", &ds.properties),
+                self.dataset_path(&ds.name)
+            )));
+        }
+        cmds.push(InstallCmd::shell(format!(
+            "zpool set bootfs={} {}",
+            self.dataset_path(&self.bootfs),
+            self.name
+        )));
+        cmds.push(InstallCmd::shell(format!("zpool export {}", self.name)));
+        cmds
+    }
+}
+
+// ── bcachefs ──
+
+/// A bcachefs filesystem formatted across one or more member devices.
+///
+/// Formatted in the volume stack step with a single `bcachefs format`. With native
+/// encryption the filesystem is unlocked into the user keyring right away, so the mounting
+/// step mounts it by UUID like an unencrypted one.
+#[derive(Clone, Debug, Serialize)]
+pub struct BcachefsSpec {
+    /// Member device paths; the first one resolves the filesystem UUID.
+    pub members: Vec<String>,
+    /// bcachefs native encryption (instead of LUKS underneath).
+    pub encrypted: bool,
+    /// Passphrase for native encryption (piped to `bcachefs` via stdin at install time).
+    #[serde(skip_serializing)]
+    pub passphrase: Option<String>,
+}
+
+impl BcachefsSpec {
+    /// Multi-device source as the kernel takes it (`/dev/sda2:/dev/sda3`).
+    pub fn device_list(&self) -> String {
+        self.members.join(":")
+    }
+
+    /// Shell expression for the multi-device `UUID=` source of the filesystem whose members
+    /// are `device_list` (as found in [`PlannedMount::source`]).
+    pub fn uuid_source(device_list: &str) -> String {
+        let first = device_list.split(':').next().unwrap_or(device_list);
+        format!("UUID=$(blkid -s UUID -o value {first})")
+    }
+
+    pub fn setup_commands(&self) -> Vec<InstallCmd> {
+        let mut cmds = vec![InstallCmd::shell(
+            "modprobe bcachefs || { echo 'ERROR: the bcachefs kernel module is not available on this live system.' >&2; exit 1; }",
+        )];
+        let members = self.members.join(" ");
+        match (self.encrypted, &self.passphrase) {
+            (true, Some(pw)) => cmds.push(InstallCmd::BcachefsFormat {
+                devices: self.members.clone(),
+                passphrase: pw.clone(),
+            }),
+            (true, None) => cmds.push(InstallCmd::shell(format!(
+                "bcachefs format -f --encrypted {members}"
+            ))),
+            (false, _) => cmds.push(InstallCmd::shell(format!("bcachefs format -f {members}"))),
+        }
+        cmds.push(InstallCmd::shell("udevadm settle"));
+        if self.encrypted {
+            let first = self.members[0].clone();
+            cmds.push(match &self.passphrase {
+                Some(pw) => InstallCmd::BcachefsUnlock {
+                    device: first,
+                    passphrase: pw.clone(),
+                },
+                None => InstallCmd::shell(format!("bcachefs unlock -k user {first}")),
+            });
+        }
+        cmds
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct SubvolumeSpec {
     pub name: String,
     pub mountpoint: String,
@@ -356,7 +1140,221 @@ impl BtrfsSubvolumePreset {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Root filesystem of the automatic layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootFilesystem {
+    Btrfs,
+    Zfs,
+    Bcachefs,
+}
+
+impl RootFilesystem {
+    /// Config names, in Disks screen order (`AppState::disks_root_fs_index`).
+    pub const NAMES: [&str; 3] = ["btrfs", "zfs", "bcachefs"];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => RootFilesystem::Zfs,
+            2 => RootFilesystem::Bcachefs,
+            _ => RootFilesystem::Btrfs,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name.trim()))
+            .map(Self::from_index)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            RootFilesystem::Btrfs => "Btrfs",
+            RootFilesystem::Zfs => "ZFS (pool with ROOT/default, home, var/log)",
+            RootFilesystem::Bcachefs => "bcachefs",
+        }
+    }
+}
+
+/// How the installed system swaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapMode {
+    Partition,
+    Swapfile,
+    Zram,
+    None,
+}
+
+impl SwapMode {
+    /// Config names, in Swap screen order (`AppState::swap_mode_index`).
+    pub const NAMES: [&str; 4] = ["partition", "swapfile", "zram", "none"];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => SwapMode::Partition,
+            1 => SwapMode::Swapfile,
+            2 => SwapMode::Zram,
+            _ => SwapMode::None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name.trim()))
+            .map(Self::from_index)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            SwapMode::Partition => "Swap partition",
+            SwapMode::Swapfile => "Swapfile",
+            SwapMode::Zram => "zram (compressed RAM)",
+            SwapMode::None => "None",
+        }
+    }
+}
+
+/// Encryption of swap partitions, for volumes whose root is encrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapEncryption {
+    None,
+    /// Plain dm-crypt keyed from `/dev/urandom` at every boot; rules out hibernation.
+    RandomKey,
+    /// LUKS2 unlocked in the initramfs by a key file stored in the encrypted root.
+    Keyfile,
+}
+
+impl SwapEncryption {
+    /// Config names, in Swap screen order (`AppState::swap_encryption_index`).
+    pub const NAMES: [&str; 3] = ["none", "random", "keyfile"];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => SwapEncryption::RandomKey,
+            2 => SwapEncryption::Keyfile,
+            _ => SwapEncryption::None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name.trim()))
+            .map(Self::from_index)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            SwapEncryption::None => "None",
+            SwapEncryption::RandomKey => "Random key (no hibernation)",
+            SwapEncryption::Keyfile => "Key file in root",
+        }
+    }
+}
+
+/// Compression algorithms offered for zram, in Swap screen order.
+pub const ZRAM_ALGORITHMS: [&str; 5] = ["zstd", "lz4", "lzo-rle", "lzo", "842"];
+
+/// True for sizes both `mkswap --size` and `btrfs filesystem mkswapfile --size` accept:
+/// a number with an optional `K`/`M`/`G`/`T` suffix, e.g. `4G`.
+pub fn is_valid_swapfile_size(size: &str) -> bool {
+    let digits = size.trim_end_matches(|c: char| "kKmMgGtT".contains(c));
+    size.len() - digits.len() <= 1
+        && !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && digits.parse::<u64>().is_ok_and(|n| n > 0)
+}
+
+/// True for a plausible `zram-size` expression: MiB or a formula over `ram`, such as
+/// `ram / 2` or `min(ram / 2, 8192)`.
+pub fn is_valid_zram_size(size: &str) -> bool {
+    !size.trim().is_empty()
+        && size
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " .,+-*/()".contains(c))
+}
+
+/// A swap file on the root filesystem, created once the target is mounted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SwapfileSpec {
+    /// Path on the installed system, e.g. `/swap/swapfile`.
+    pub path: String,
+    /// Size with an optional `K`/`M`/`G`/`T` suffix, e.g. `4G`.
+    pub size: String,
+    /// On btrfs the file lives in the [`SwapfileSpec::BTRFS_SUBVOLUME`] subvolume and is
+    /// created NOCOW by `btrfs filesystem mkswapfile`.
+    pub btrfs: bool,
+}
+
+impl SwapfileSpec {
+    /// Dedicated subvolume for the swap file, kept out of root snapshots.
+    pub const BTRFS_SUBVOLUME: &str = "@swap";
+
+    pub fn new(size: &str, btrfs: bool) -> Self {
+        Self {
+            path: if btrfs { "/swap/swapfile" } else { "/swapfile" }.into(),
+            size: size.trim().into(),
+            btrfs,
+        }
+    }
+
+    /// The `@swap` subvolume mounted at `/swap`.
+    pub fn btrfs_subvolume() -> SubvolumeSpec {
+        SubvolumeSpec {
+            name: Self::BTRFS_SUBVOLUME.into(),
+            mountpoint: "/swap".into(),
+            mount_options: vec!["noatime".into()],
+        }
+    }
+
+    /// Path of the file under `/mnt` during the install.
+    pub fn target_path(&self) -> String {
+        format!("/mnt{}", self.path)
+    }
+
+    /// Create and activate the file; genfstab then records it like any active swap.
+    pub fn create_commands(&self) -> Vec<String> {
+        let target = self.target_path();
+        let dir = target.rsplit_once('/').map_or("/mnt", |(d, _)| d);
+        let create = if self.btrfs {
+            format!(
+                "btrfs filesystem mkswapfile --size {} --uuid clear {target}",
+                self.size
+            )
+        } else {
+            format!("mkswap -U clear --size {} --file {target}", self.size)
+        };
+        vec![
+            format!("mkdir -p {dir}"),
+            create,
+            format!("swapon {target}"),
+        ]
+    }
+}
+
+/// Where the hibernation image is written and read back from at boot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResumeDevice {
+    /// Swap partition or volume: a plain partition, a LUKS mapper or an LVM LV.
+    Swap(String),
+    /// Swap file; resumed from its filesystem at the file's physical offset.
+    Swapfile(SwapfileSpec),
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct PlannedMount {
     pub source: String,
     pub target: String,
@@ -369,6 +1367,25 @@ pub struct PlannedMount {
 #[derive(Clone, Debug)]
 pub struct ValidationError {
     pub message: String,
+    /// Config field the error is about (`disks.partitions[2].size`), when there is one.
+    pub field: Option<String>,
+}
+
+impl ValidationError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            field: None,
+        }
+    }
+
+    /// Error about the config field `field`.
+    pub fn at(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            field: Some(field.into()),
+        }
+    }
 }
 
 impl fmt::Display for ValidationError {
@@ -380,22 +1397,152 @@ impl fmt::Display for ValidationError {
 impl StoragePlan {
     /// Collect all LUKS mapper names used by this plan (for pre-cleanup).
     pub fn luks_mapper_names(&self) -> Vec<String> {
-        let mut names = Vec::new();
+        self.luks_volumes()
+            .into_iter()
+            .map(|(_, enc)| enc.mapper_name.clone())
+            .collect()
+    }
+
+    /// Mappers whose LUKS volume gets a recovery key.
+    pub fn recovery_key_mappers(&self) -> Vec<String> {
+        self.luks_volumes()
+            .into_iter()
+            .filter(|(_, enc)| enc.enroll.recovery_key)
+            .map(|(_, enc)| enc.mapper_name.clone())
+            .collect()
+    }
+
+    /// Every LUKS volume in the plan with the device it is formatted on, plus plain dm-crypt
+    /// random-key swap (which has no keyslots to enroll). A volume shared by several stacks
+    /// (the LUKS under a volume group) is listed once.
+    pub fn luks_volumes(&self) -> Vec<(String, &EncryptionSpec)> {
+        let mut volumes = Vec::new();
         for device in &self.devices {
             for part in &device.partitions {
                 if let Some(enc) = &part.encryption {
-                    names.push(enc.mapper_name.clone());
+                    volumes.push((Self::partition_path(&device.path, part.number), enc));
                 }
             }
         }
         for stack in &self.stacks {
+            let mut current = stack.base.clone();
             for layer in &stack.layers {
-                if let VolumeLayer::Luks(enc) = layer {
-                    names.push(enc.mapper_name.clone());
+                match layer {
+                    VolumeLayer::Luks(enc) => {
+                        if !volumes.iter().any(|(_, v): &(String, &EncryptionSpec)| {
+                            v.mapper_name == enc.mapper_name
+                        }) {
+                            volumes.push((current, enc));
+                        }
+                        current = format!("/dev/mapper/{}", enc.mapper_name);
+                    }
+                    VolumeLayer::Lvm(lvm) => {
+                        current = format!("/dev/{}/{}", lvm.vg_name, lvm.lv_name)
+                    }
+                    VolumeLayer::Raid(raid) => current = format!("/dev/md/{}", raid.name),
                 }
             }
         }
-        names
+        volumes
+    }
+
+    /// Enrollment of the LUKS volume opened as `/dev/mapper/<mapper>`.
+    pub fn luks_enrollment(&self, mapper: &str) -> Option<&LuksEnrollment> {
+        self.luks_volumes()
+            .into_iter()
+            .find(|(_, enc)| enc.mapper_name == mapper)
+            .map(|(_, enc)| &enc.enroll)
+    }
+
+    /// True when a LUKS volume gets a TPM2 key sealed during the install.
+    pub fn has_tpm2_enrollment(&self) -> bool {
+        self.luks_volumes()
+            .iter()
+            .any(|(_, enc)| enc.enroll.tpm2_pcrs.is_some())
+    }
+
+    /// True when a LUKS volume has a TPM2 or FIDO2 token enrolled.
+    pub fn has_luks_tokens(&self) -> bool {
+        self.luks_volumes()
+            .iter()
+            .any(|(_, enc)| enc.enroll.has_tokens())
+    }
+
+    /// Encrypted swap partitions ([`SwapEncryption`]) with their partition paths.
+    pub fn encrypted_swaps(&self) -> Vec<(String, &EncryptionSpec)> {
+        self.devices
+            .iter()
+            .flat_map(|d| {
+                d.partitions.iter().filter_map(|p| {
+                    let enc = p.encryption.as_ref()?;
+                    (p.role == PartitionRole::Swap && enc.method != EncryptionMethod::Luks2)
+                        .then(|| (Self::partition_path(&d.path, p.number), enc))
+                })
+            })
+            .collect()
+    }
+
+    /// True when the initramfs must use the `systemd`/`sd-encrypt` hooks: for TPM2/FIDO2 tokens,
+    /// or to unlock key-file swap with a key read from the root volume.
+    pub fn needs_systemd_initramfs(&self) -> bool {
+        self.has_luks_tokens()
+            || self
+                .encrypted_swaps()
+                .iter()
+                .any(|(_, enc)| enc.method == EncryptionMethod::Luks2Keyfile)
+    }
+
+    /// Release everything a (partial) run may hold on the target: swap, the `/mnt` tree, this
+    /// plan's stacks (top layer first, so a mapper or volume group goes before what holds it)
+    /// and LUKS mappers. Every step tolerates nothing to undo. Empty for pre-mounted plans,
+    /// whose mounts belong to the user.
+    pub fn teardown_commands(&self) -> Vec<InstallCmd> {
+        if self.mode == StorageMode::PreMounted {
+            return Vec::new();
+        }
+        let mut cmds = vec![
+            InstallCmd::shell("swapoff -a || true"),
+            InstallCmd::shell("umount -R /mnt 2>/dev/null || true"),
+        ];
+        for pool in &self.pools {
+            cmds.push(InstallCmd::shell(format!(
+                "zpool export {} 2>/dev/null || true",
+                pool.name
+            )));
+        }
+        let depth = self
+            .stacks
+            .iter()
+            .map(|s| s.layers.len())
+            .max()
+            .unwrap_or(0);
+        let mut released: Vec<String> = Vec::new();
+        for level in (0..depth).rev() {
+            for layer in self.stacks.iter().filter_map(|s| s.layers.get(level)) {
+                let step = match layer {
+                    VolumeLayer::Luks(enc) => {
+                        format!("cryptsetup close {} 2>/dev/null || true", enc.mapper_name)
+                    }
+                    VolumeLayer::Lvm(lvm) => {
+                        format!("vgchange -an {} 2>/dev/null || true", lvm.vg_name)
+                    }
+                    VolumeLayer::Raid(raid) => {
+                        format!("mdadm --stop /dev/md/{} 2>/dev/null || true", raid.name)
+                    }
+                };
+                if !released.contains(&step) {
+                    released.push(step);
+                }
+            }
+        }
+        for mapper in self.luks_mapper_names() {
+            let step = format!("cryptsetup close {mapper} 2>/dev/null || true");
+            if !released.contains(&step) {
+                released.push(step);
+            }
+        }
+        cmds.extend(released.into_iter().map(InstallCmd::shell));
+        cmds
     }
 
     /// Generate partition path for a device + partition number.
@@ -416,19 +1563,23 @@ impl StoragePlan {
                 cmds.push(InstallCmd::shell(format!("wipefs -a {}", device.path)));
             }
 
-            cmds.push(InstallCmd::shell(format!(
-                "parted -s {} mklabel {}",
-                device.path,
-                device.label.as_parted_str()
-            )));
-            cmds.push(InstallCmd::shell(format!(
-                "partprobe {} || true",
-                device.path
-            )));
-            cmds.push(InstallCmd::shell("udevadm settle"));
+            if !device.keep_table {
+                cmds.push(InstallCmd::shell(format!(
+                    "parted -s {} mklabel {}",
+                    device.path,
+                    device.label.as_parted_str()
+                )));
+                cmds.push(InstallCmd::shell(format!(
+                    "partprobe {} || true",
+                    device.path
+                )));
+                cmds.push(InstallCmd::shell("udevadm settle"));
+            }
 
             for part in &device.partitions {
+                // msdos tables take a partition type here, GPT a partition name
                 let parted_type = match part.role {
+                    _ if device.label == DiskLabel::Msdos => "primary",
                     PartitionRole::Esp => "ESP",
                     PartitionRole::BiosBoot => "biosboot",
                     PartitionRole::Swap => "swap",
@@ -436,8 +1587,15 @@ impl StoragePlan {
                     _ => "primary",
                 };
 
-                // biosboot partitions use only a label, no filesystem hint
-                if part.role == PartitionRole::BiosBoot {
+                // biosboot partitions, ZFS pool members, bcachefs members, LVM physical
+                // volumes and RAID members use only a label, no filesystem hint (parted knows
+                // none of them)
+                if part.role == PartitionRole::BiosBoot
+                    || matches!(
+                        part.filesystem.fstype.as_str(),
+                        "zfs" | "bcachefs" | "lvm" | "raid"
+                    )
+                {
                     cmds.push(InstallCmd::shell(format!(
                         "parted -s {} mkpart {} {} {}",
                         device.path, parted_type, part.start, part.end
@@ -466,15 +1624,13 @@ impl StoragePlan {
                 let part_path = Self::partition_path(&device.path, part.number);
 
                 if let Some(enc) = &part.encryption {
+                    cmds.push(InstallCmd::shell(
+                        "modprobe -q dm_crypt 2>/dev/null || modprobe -q dm-crypt 2>/dev/null || true",
+                    ));
                     match enc.method {
                         EncryptionMethod::Luks2 => {
-                            cmds.push(InstallCmd::shell(
-                                "modprobe -q dm_crypt 2>/dev/null || modprobe -q dm-crypt 2>/dev/null || true",
-                            ));
-                            cmds.push(DeviceStack::luks_format_cmd(
-                                &part_path,
-                                enc.passphrase.as_deref(),
-                            ));
+                            cmds.push(DeviceStack::luks_format_cmd(&part_path, enc));
+                            cmds.extend(DeviceStack::luks_enroll_cmds(&part_path, enc));
                             cmds.push(InstallCmd::shell("udevadm settle"));
                             cmds.push(DeviceStack::luks_open_cmd(
                                 &part_path,
@@ -482,6 +1638,27 @@ impl StoragePlan {
                                 enc.passphrase.as_deref(),
                             ));
                         }
+                        EncryptionMethod::Luks2Keyfile => {
+                            let key = enc.live_key_file();
+                            cmds.push(InstallCmd::shell(format!(
+                                "(umask 077; head -c 4096 /dev/urandom > {key})"
+                            )));
+                            cmds.push(DeviceStack::luks_format_cmd(&part_path, enc));
+                            cmds.push(InstallCmd::shell("udevadm settle"));
+                            cmds.push(InstallCmd::shell(format!(
+                                "cryptsetup open --type luks --key-file={key} {part_path} {}",
+                                enc.mapper_name
+                            )));
+                        }
+                        // Keyed for this session only; `mkswap` below writes the signature
+                        // the install swaps on, and every boot re-keys it from /etc/crypttab.
+                        EncryptionMethod::PlainRandomKey => {
+                            let (cipher, bits) = PLAIN_SWAP_CIPHER;
+                            cmds.push(InstallCmd::shell(format!(
+                                "cryptsetup open --type plain --key-file=/dev/urandom --cipher={cipher} --key-size={bits} {part_path} {}",
+                                enc.mapper_name
+                            )));
+                        }
                     }
                 }
 
@@ -512,6 +1689,12 @@ impl StoragePlan {
                     "ext2" => cmds.push(InstallCmd::shell(format!("mkfs.ext2 -F {format_target}"))),
                     "xfs" => cmds.push(InstallCmd::shell(format!("mkfs.xfs -f {format_target}"))),
                     "f2fs" => cmds.push(InstallCmd::shell(format!("mkfs.f2fs -f {format_target}"))),
+                    // Pool, multi-device and volume group members: set up by
+                    // `stack_setup_commands`.
+                    "zfs" | "bcachefs" | "lvm" => {}
+                    // RAID members too; clearing old signatures keeps `mdadm --create` from
+                    // stopping to ask about a previous array or filesystem.
+                    "raid" => cmds.push(InstallCmd::shell(format!("wipefs -a {format_target}"))),
                     _ => cmds.push(InstallCmd::shell(format!("mkfs.ext4 -F {format_target}"))),
                 }
 
@@ -544,10 +1727,18 @@ impl StoragePlan {
 
         let mut cmds = Vec::new();
         cmds.push("mkdir -p /mnt".into());
+        for pool in &self.pools {
+            cmds.push(format!(
+                "zpool list {0} >/dev/null 2>&1 || zpool import -N -R /mnt {0}",
+                pool.name
+            ));
+        }
 
         for mount in &self.mounts {
             if mount.is_swap {
                 cmds.push(format!("swapon {}", mount.source));
+            } else if mount.fstype == "zfs" {
+                cmds.push(format!("zfs mount {}", mount.source));
             } else {
                 let mut opts = mount.options.clone();
                 if let Some(ref sv) = mount.subvolume {
@@ -607,7 +1798,19 @@ impl StoragePlan {
                     }
                 };
 
-                if mount.fstype == "vfat" {
+                if mount.fstype == "bcachefs" {
+                    // `bcachefs mount` assembles every member from the external UUID.
+                    let opt_flag = if opts.is_empty() {
+                        String::new()
+                    } else {
+                        format!("-o {} ", opts.join(","))
+                    };
+                    cmds.push(format!(
+                        "mkdir -p {target} && bcachefs mount {opt_flag}{} {target}",
+                        BcachefsSpec::uuid_source(&mount.source),
+                        target = mount.target
+                    ));
+                } else if mount.fstype == "vfat" {
                     // FAT support verified above; try vfat first, fall back to fat/msdos
                     // for environments that register the type under a different name.
                     let cmd_vfat = build_mount_cmd(Some("vfat"));
@@ -620,6 +1823,32 @@ impl StoragePlan {
             }
         }
 
+        if let Some(swapfile) = &self.swapfile {
+            cmds.extend(swapfile.create_commands());
+        }
+
+        // The initramfs imports pools from the cache file; the host id must match the one
+        // the pools were created with.
+        for pool in &self.pools {
+            cmds.push(format!(
+                "zpool set cachefile=/etc/zfs/zpool.cache {}",
+                pool.name
+            ));
+        }
+        if !self.pools.is_empty() {
+            cmds.push("install -Dm644 /etc/zfs/zpool.cache /mnt/etc/zfs/zpool.cache".into());
+            cmds.push("install -Dm644 /etc/hostid /mnt/etc/hostid".into());
+        }
+
+        // Recovery keys were generated on the live system, which is gone after the reboot
+        for mapper in self.recovery_key_mappers() {
+            cmds.push(format!(
+                "install -Dm600 {} /mnt{}",
+                LuksEnrollment::recovery_key_path(&mapper),
+                LuksEnrollment::installed_recovery_key_path(&mapper)
+            ));
+        }
+
         cmds
     }
 
@@ -675,7 +1904,16 @@ impl StoragePlan {
             if mount.is_swap {
                 continue;
             }
-            if mount.fstype == "vfat" || mount.fstype == "fat32" {
+            // ZFS datasets are not block devices.
+            if mount.fstype == "vfat" || mount.fstype == "fat32" || mount.fstype == "zfs" {
+                continue;
+            }
+            if mount.fstype == "bcachefs" {
+                for member in mount.source.split(':') {
+                    cmds.push(format!(
+                        "blkid {member} | grep -q 'TYPE=\"bcachefs\"' || echo 'WARN: bcachefs not found on {member}'"
+                    ));
+                }
                 continue;
             }
             cmds.push(format!(
@@ -702,8 +1940,120 @@ impl StoragePlan {
                 );
             }
         }
+        if let Some(swapfile) = &self.swapfile {
+            let target = swapfile.target_path();
+            cmds.push(format!(
+                "swapon --show=NAME --noheadings | grep -qx {target} || echo 'ERROR: swapfile {target} not active'"
+            ));
+        }
 
-        cmds.push("genfstab -U /mnt >> /mnt/etc/fstab".into());
+        // ZFS mounts its own datasets at boot; multi-device bcachefs needs the `UUID=` source,
+        // which genfstab does not produce. Random-key swap gets a new `UUID=` every boot.
+        let encrypted_swaps = self.encrypted_swaps();
+        let random_key = |enc: &&EncryptionSpec| enc.method == EncryptionMethod::PlainRandomKey;
+        let mut skip_types = Vec::new();
+        if encrypted_swaps.iter().any(|(_, enc)| random_key(enc)) {
+            skip_types.push("swap");
+        }
+        if !self.pools.is_empty() {
+            skip_types.push("zfs");
+        }
+        if !self.bcachefs.is_empty() {
+            skip_types.push("bcachefs");
+        }
+        if skip_types.is_empty() {
+            cmds.push("genfstab -U /mnt >> /mnt/etc/fstab".into());
+        } else {
+            let filter = skip_types
+                .iter()
+                .map(|t| format!("$3 != \"{t}\""))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            cmds.push(format!(
+                "genfstab -U /mnt | awk '{filter}' >> /mnt/etc/fstab"
+            ));
+        }
+        for mount in self.mounts.iter().filter(|m| m.fstype == "bcachefs") {
+            let target = mount.target.strip_prefix("/mnt").unwrap_or(&mount.target);
+            let target = if target.is_empty() { "/" } else { target };
+            let options = if mount.options.is_empty() {
+                "defaults".to_string()
+            } else {
+                mount.options.join(",")
+            };
+            cmds.push(format!(
+                "printf '%s\\t%s\\tbcachefs\\t%s\\t0 0\\n' \"{}\" {target} {options} >> /mnt/etc/fstab",
+                BcachefsSpec::uuid_source(&mount.source)
+            ));
+        }
+        for (device, enc) in &encrypted_swaps {
+            let mapper = &enc.mapper_name;
+            if random_key(enc) {
+                let (cipher, bits) = PLAIN_SWAP_CIPHER;
+                cmds.push(format!(
+                    "printf '%s\\n' \"{mapper} PARTUUID=$(blkid -s PARTUUID -o value {device}) /dev/urandom swap,cipher={cipher},size={bits}\" >> /mnt/etc/crypttab"
+                ));
+                cmds.push(format!(
+                    "printf '%s\\tnone\\tswap\\tdefaults\\t0 0\\n' /dev/mapper/{mapper} >> /mnt/etc/fstab"
+                ));
+                continue;
+            }
+            // The initramfs reads the key from the root filesystem (mounted read-only by
+            // systemd-cryptsetup), so the path is relative to its top level.
+            let key = enc.key_file();
+            let Some(root) = self.root_device_path() else {
+                continue;
+            };
+            let key_on_root = match self
+                .mounts
+                .iter()
+                .find(|m| m.target == "/mnt")
+                .and_then(|m| m.subvolume.as_deref())
+            {
+                Some(sv) => format!("/{}{key}", sv.trim_matches('/')),
+                None => key.clone(),
+            };
+            cmds.push(format!(
+                "install -d -m 0700 /mnt/etc/cryptsetup-keys.d && install -m 0400 {live} /mnt{key} && rm -f {live}",
+                live = enc.live_key_file()
+            ));
+            cmds.push(format!(
+                "printf '%s\\n' \"{mapper} UUID=$(blkid -s UUID -o value {device}) {key} luks\" >> /mnt/etc/crypttab"
+            ));
+            cmds.push(format!(
+                "printf '%s\\n' \"{mapper} UUID=$(blkid -s UUID -o value {device}) {key_on_root}:UUID=$(blkid -s UUID -o value {root}) luks\" >> /mnt/etc/crypttab.initramfs"
+            ));
+        }
+        // Other LUKS volumes (a separate /home, LUKS on logical volumes) are opened after the
+        // root by systemd-cryptsetup.
+        let root_mappers = self.root_luks_mappers();
+        for (device, enc) in self.luks_volumes() {
+            if enc.method != EncryptionMethod::Luks2 || root_mappers.contains(&enc.mapper_name) {
+                continue;
+            }
+            cmds.push(format!(
+                "printf '%s\\n' \"{} UUID=$(blkid -s UUID -o value {device}) none {}\" >> /mnt/etc/crypttab",
+                enc.mapper_name,
+                enc.enroll.unlock_options().unwrap_or("luks")
+            ));
+        }
+        if let Some(swapfile) = &self.swapfile {
+            // genfstab records active swap files; add the entry if it did not.
+            cmds.push(format!(
+                "grep -q '^{0}[[:space:]]' /mnt/etc/fstab || printf '%s\\tnone\\tswap\\tdefaults\\t0 0\\n' {0} >> /mnt/etc/fstab",
+                swapfile.path
+            ));
+        }
+        // Mirrored ESPs: a missing member disk must not stop the boot at its /boot mount.
+        let mirrors = self.esp_mirrors();
+        if !mirrors.is_empty() {
+            let primary = self.esp_chroot_mountpoint().to_string();
+            for target in std::iter::once(primary).chain(mirrors) {
+                cmds.push(format!(
+                    "sed -i -E 's#^(\\S+\\s+{target}\\s+vfat\\s+)(\\S+)#\\1\\2,nofail#' /mnt/etc/fstab"
+                ));
+            }
+        }
 
         cmds
     }
@@ -714,9 +2064,9 @@ impl StoragePlan {
         if self.mode == StorageMode::PreMounted {
             let has_root = self.mounts.iter().any(|m| m.target == "/mnt");
             if !has_root {
-                errors.push(ValidationError {
-                    message: "Pre-mounted mode requires a filesystem mounted at /mnt".into(),
-                });
+                errors.push(ValidationError::new(
+                    "Pre-mounted mode requires a filesystem mounted at /mnt",
+                ));
             }
             return errors;
         }
@@ -724,17 +2074,20 @@ impl StoragePlan {
         let has_root = self
             .devices
             .iter()
-            .any(|d| d.partitions.iter().any(|p| p.role == PartitionRole::Root));
+            .any(|d| d.partitions.iter().any(|p| p.role == PartitionRole::Root))
+            || self.root_stack().is_some();
         if !has_root {
-            errors.push(ValidationError {
-                message: "No root partition defined".into(),
-            });
+            errors.push(ValidationError::at(
+                "disks.partitions",
+                "No root partition defined",
+            ));
         }
 
         let has_esp = self
             .devices
             .iter()
-            .any(|d| d.partitions.iter().any(|p| p.role == PartitionRole::Esp));
+            .any(|d| d.partitions.iter().any(|p| p.role == PartitionRole::Esp))
+            || self.reused_esp().is_some();
         let has_biosboot = self.devices.iter().any(|d| {
             d.partitions
                 .iter()
@@ -748,14 +2101,18 @@ impl StoragePlan {
             || has_esp;
 
         if is_uefi && !has_esp {
-            errors.push(ValidationError {
-                message: "UEFI mode requires an ESP partition".into(),
-            });
+            errors.push(ValidationError::at(
+                "disks.partitions",
+                "UEFI mode requires an ESP partition",
+            ));
         }
-        if !is_uefi && !has_biosboot && !has_esp {
-            errors.push(ValidationError {
-                message: "BIOS mode requires a BIOS boot partition or ESP".into(),
-            });
+        // GRUB embeds itself after the MBR of an msdos disk; GPT needs a BIOS boot partition
+        let gpt = self.devices.iter().any(|d| d.label == DiskLabel::Gpt);
+        if !is_uefi && !has_biosboot && !has_esp && gpt {
+            errors.push(ValidationError::at(
+                "disks.partitions",
+                "BIOS mode requires a BIOS boot partition or ESP",
+            ));
         }
 
         let mut mountpoints: Vec<&str> = Vec::new();
@@ -764,9 +2121,10 @@ impl StoragePlan {
                 continue;
             }
             if mountpoints.contains(&mount.target.as_str()) {
-                errors.push(ValidationError {
-                    message: format!("Duplicate mountpoint: {}", mount.target),
-                });
+                errors.push(ValidationError::at(
+                    "disks.partitions",
+                    format!("Duplicate mountpoint: {}", mount.target),
+                ));
             }
             mountpoints.push(&mount.target);
         }
@@ -782,17 +2140,38 @@ impl StoragePlan {
                     ) && a_start < b_end
                         && b_start < a_end
                     {
-                        errors.push(ValidationError {
-                            message: format!(
+                        errors.push(ValidationError::at(
+                            "disks.partitions",
+                            format!(
                                 "Overlapping partitions: #{} and #{} on {}",
                                 a.number, b.number, device.path
                             ),
-                        });
+                        ));
                     }
                 }
             }
         }
 
+        let volumes = self.luks_volumes();
+        for (i, (device, enc)) in volumes.iter().enumerate() {
+            if volumes[..i]
+                .iter()
+                .any(|(d, e)| e.mapper_name == enc.mapper_name && d != device)
+            {
+                errors.push(ValidationError::new(format!(
+                    "Duplicate encrypted volume name: {} (on {device})",
+                    enc.mapper_name
+                )));
+            }
+        }
+
+        if self.has_luks_tokens() && self.zfs_root_pool().is_some() {
+            errors.push(ValidationError::at(
+                "disk_encryption",
+                "TPM2/FIDO2 unlock needs the systemd initramfs, which a ZFS root cannot use",
+            ));
+        }
+
         errors
     }
 
@@ -831,16 +2210,165 @@ impl StoragePlan {
         "/boot"
     }
 
-    /// Collect setup commands for all device stacks (LVM, RAID, multi-layer).
+    /// Collect setup commands for all device stacks (LVM, RAID, multi-layer), ZFS pools and
+    /// bcachefs filesystems.
     /// Returns an empty vec for simple partition-based layouts with no stacks.
     pub fn stack_setup_commands(&self) -> Vec<InstallCmd> {
         let mut cmds = Vec::new();
+        let mut prepared = Vec::new();
         for stack in &self.stacks {
-            cmds.extend(stack.setup_commands());
+            cmds.extend(stack.setup_commands_once(&mut prepared));
+        }
+        for pool in &self.pools {
+            cmds.extend(pool.setup_commands());
+        }
+        for fs in &self.bcachefs {
+            cmds.extend(fs.setup_commands());
         }
         cmds
     }
 
+    /// Mount of an ESP that was already on a kept partition table (installing alongside):
+    /// mounted, never formatted.
+    pub fn reused_esp(&self) -> Option<&PlannedMount> {
+        let device = self.devices.iter().find(|d| d.keep_table)?;
+        self.mounts.iter().find(|m| {
+            m.fstype == "vfat"
+                && m.source.strip_prefix(&device.path).is_some_and(|n| {
+                    n.trim_start_matches('p')
+                        .chars()
+                        .all(|c| c.is_ascii_digit())
+                })
+                && !device
+                    .partitions
+                    .iter()
+                    .any(|p| Self::partition_path(&device.path, p.number) == m.source)
+        })
+    }
+
+    /// True when the plan assembles a software RAID array.
+    pub fn has_raid(&self) -> bool {
+        self.stacks
+            .iter()
+            .any(|s| s.layers.iter().any(|l| matches!(l, VolumeLayer::Raid(_))))
+    }
+
+    /// Disks after the first: RAID members carrying their own copy of the boot partitions.
+    pub fn mirror_disks(&self) -> Vec<&str> {
+        self.devices
+            .iter()
+            .skip(1)
+            .map(|d| d.path.as_str())
+            .collect()
+    }
+
+    /// Chroot mountpoints of the ESPs on [`Self::mirror_disks`] (`/boot2`, ...), kept in sync
+    /// with the primary ESP.
+    pub fn esp_mirrors(&self) -> Vec<String> {
+        self.devices
+            .iter()
+            .skip(1)
+            .flat_map(|d| {
+                d.partitions
+                    .iter()
+                    .filter(|p| p.role == PartitionRole::Esp)
+                    .map(|p| Self::partition_path(&d.path, p.number))
+            })
+            .filter_map(|esp| {
+                let mount = self.mounts.iter().find(|m| m.source == esp)?;
+                mount.target.strip_prefix("/mnt").map(str::to_string)
+            })
+            .collect()
+    }
+
+    /// The stack whose final device is mounted at `/`, e.g. the root logical volume.
+    pub fn root_stack(&self) -> Option<&DeviceStack> {
+        let root = self.root_device_path()?;
+        self.stacks.iter().find(|s| s.final_device_path() == root)
+    }
+
+    /// True when the plan creates a volume group.
+    pub fn has_lvm(&self) -> bool {
+        self.stacks
+            .iter()
+            .any(|s| s.layers.iter().any(|l| matches!(l, VolumeLayer::Lvm(_))))
+    }
+
+    /// True when a LUKS volume sits on a logical volume, so the initramfs activates the
+    /// volume group before unlocking.
+    pub fn has_luks_on_lvm(&self) -> bool {
+        self.stacks.iter().any(|s| {
+            s.layers
+                .iter()
+                .skip_while(|l| !matches!(l, VolumeLayer::Lvm(_)))
+                .any(|l| matches!(l, VolumeLayer::Luks(_)))
+        })
+    }
+
+    /// The LUKS volume under the root's volume group (LVM on LUKS): the initramfs unlocks it
+    /// and the root is found on the logical volume inside.
+    pub fn root_lvm_luks(&self) -> Option<&EncryptionSpec> {
+        let layers = &self.root_stack()?.layers;
+        let lvm = layers
+            .iter()
+            .position(|l| matches!(l, VolumeLayer::Lvm(_)))?;
+        layers[..lvm].iter().find_map(|l| match l {
+            VolumeLayer::Luks(enc) => Some(enc),
+            _ => None,
+        })
+    }
+
+    /// LUKS mappers the initramfs opens to reach `/`: the root volume, the volume under its
+    /// volume group, or the members of a ZFS root pool.
+    fn root_luks_mappers(&self) -> Vec<String> {
+        let mut sources: Vec<String> = self.root_device_path().into_iter().collect();
+        if let Some(pool) = self.zfs_root_pool() {
+            sources.extend(pool.members.iter().cloned());
+        }
+        let mut mappers: Vec<String> = sources
+            .iter()
+            .filter_map(|s| s.strip_prefix("/dev/mapper/"))
+            .map(String::from)
+            .collect();
+        if let Some(enc) = self.root_lvm_luks() {
+            mappers.push(enc.mapper_name.clone());
+        }
+        mappers
+    }
+
+    /// Filesystem type mounted at `/` (`/mnt`), if the plan has one.
+    pub fn root_fstype(&self) -> Option<&str> {
+        self.mounts
+            .iter()
+            .find(|m| m.target == "/mnt" && !m.is_swap)
+            .map(|m| m.fstype.as_str())
+    }
+
+    /// Hibernation target: the first swap device, else the swap file. Pre-mounted swap files
+    /// are not considered (their filesystem is unknown), nor is random-key swap, whose key is
+    /// gone after a reboot.
+    pub fn resume_device(&self) -> Option<ResumeDevice> {
+        let random_key: Vec<String> = self
+            .encrypted_swaps()
+            .iter()
+            .filter(|(_, enc)| enc.method == EncryptionMethod::PlainRandomKey)
+            .map(|(_, enc)| format!("/dev/mapper/{}", enc.mapper_name))
+            .collect();
+        self.mounts
+            .iter()
+            .find(|m| m.is_swap && m.source.starts_with("/dev/") && !random_key.contains(&m.source))
+            .map(|m| ResumeDevice::Swap(m.source.clone()))
+            .or_else(|| self.swapfile.clone().map(ResumeDevice::Swapfile))
+    }
+
+    /// Pool holding the root dataset, when `/` is on ZFS.
+    pub fn zfs_root_pool(&self) -> Option<&ZfsPoolSpec> {
+        let root = self.root_device_path()?;
+        self.pools
+            .iter()
+            .find(|p| root == p.dataset_path(&p.bootfs))
+    }
+
     pub fn has_encryption(&self) -> bool {
         self.devices
             .iter()
@@ -20,12 +20,22 @@ impl StoragePlanner {
     }
 
     fn compile_automatic(state: &AppState) -> Result<StoragePlan, Vec<ValidationError>> {
+        if state.raid_selected() {
+            if state.alongside_selected() {
+                return Err(vec![ValidationError::at(
+                    "disks.install_alongside",
+                    "Install alongside cannot be combined with software RAID",
+                )]);
+            }
+            return Self::compile_automatic_raid(state);
+        }
         let device_path = match &state.disks_selected_device {
             Some(p) => p.clone(),
             None => {
-                return Err(vec![ValidationError {
-                    message: "No target disk selected".into(),
-                }]);
+                return Err(vec![ValidationError::at(
+                    "disks.selected_device",
+                    "No target disk selected",
+                )]);
             }
         };
 
@@ -36,17 +46,75 @@ impl StoragePlanner {
         let align = state.disks_align.clone().unwrap_or_else(|| "1MiB".into());
         let is_uefi = state.is_uefi();
         let luks = state.disk_encryption_type_index == 1;
+        // Logical volumes replace the root filesystem choice
+        let lvm = state.lvm_selected();
+        let root_fs = RootFilesystem::from_index(state.disks_root_fs_index);
+        let root_fstype = if lvm {
+            match state.lvm_root_volume() {
+                Some(lv) => lv.fs.as_str(),
+                None => {
+                    return Err(vec![ValidationError::at(
+                        "disks.lvm.volumes",
+                        "LVM needs a logical volume mounted at /",
+                    )]);
+                }
+            }
+        } else {
+            root_fs.name()
+        };
+        if root_fstype == "zfs" && !is_uefi {
+            // GRUB cannot read a pool with current feature flags; kernels live on the ESP.
+            return Err(vec![ValidationError::new(
+                "ZFS root requires UEFI (kernels are kept on the ESP)",
+            )]);
+        }
+
+        // Alongside another OS: only the largest free region is used, partition numbers the
+        // existing table already has are skipped and its ESP is mounted as it is.
+        let alongside = if state.alongside_selected() {
+            Some(Self::alongside_region(
+                state,
+                &device_path,
+                root_fstype,
+                luks,
+            )?)
+        } else {
+            None
+        };
+        let next_number = |n: u32| {
+            (n..)
+                .find(|k| alongside.as_ref().is_none_or(|r| !r.taken.contains(k)))
+                .unwrap_or(n)
+        };
+        let base = alongside.as_ref().map_or(1, |r| r.start_mib);
+        let root_end = alongside
+            .as_ref()
+            .map_or_else(|| "100%".to_string(), |r| format!("{}MiB", r.end_mib));
 
         let mut partitions = Vec::new();
         let mut mounts = Vec::new();
-        let mut part_num: u32 = 1;
+        let mut part_num: u32 = next_number(1);
+        let mut cursor = base;
 
-        if is_uefi {
+        if let Some((esp_path, target)) = alongside.as_ref().and_then(|r| r.esp.clone()) {
+            mounts.push(PlannedMount {
+                source: esp_path,
+                target: target.into(),
+                fstype: "vfat".into(),
+                options: vec![],
+                is_swap: false,
+                subvolume: None,
+            });
+        } else if is_uefi {
             partitions.push(PlannedPartition {
                 number: part_num,
                 role: PartitionRole::Esp,
-                start: align.clone(),
-                end: "1025MiB".into(),
+                start: if alongside.is_some() {
+                    format!("{base}MiB")
+                } else {
+                    align.clone()
+                },
+                end: format!("{}MiB", base + 1024),
                 filesystem: FilesystemSpec {
                     fstype: "fat32".into(),
                     mkfs_options: vec![],
@@ -64,8 +132,9 @@ impl StoragePlanner {
                 is_swap: false,
                 subvolume: None,
             });
-            part_num += 1;
-        } else {
+            part_num = next_number(part_num + 1);
+            cursor += 1024;
+        } else if alongside.is_none() {
             partitions.push(PlannedPartition {
                 number: part_num,
                 role: PartitionRole::BiosBoot,
@@ -79,27 +148,39 @@ impl StoragePlanner {
                 encryption: None,
                 subvolumes: vec![],
             });
-            part_num += 1;
+            part_num = next_number(part_num + 1);
+            cursor += 1;
         }
 
-        let swap_start = if is_uefi { "1025MiB" } else { "2MiB" };
+        // bcachefs encrypts natively instead of sitting on LUKS
+        let native_encryption = luks && root_fstype == "bcachefs";
+        let root_encrypted = luks;
+        let luks = luks && !native_encryption;
 
-        if state.swap_enabled {
-            let swap_end = if is_uefi { "5121MiB" } else { "4098MiB" };
+        let swap_start = format!("{cursor}MiB");
+        let swap_end = format!("{}MiB", cursor + 4096);
+        let swap_partition = state.swap_mode() == SwapMode::Partition;
+
+        if swap_partition {
+            let encryption =
+                Self::swap_encryption_spec(state, "cryptswap", root_fstype, luks, root_encrypted)?;
             partitions.push(PlannedPartition {
                 number: part_num,
                 role: PartitionRole::Swap,
-                start: swap_start.into(),
-                end: swap_end.into(),
+                start: swap_start.clone(),
+                end: swap_end.clone(),
                 filesystem: FilesystemSpec {
                     fstype: "linux-swap".into(),
                     mkfs_options: vec![],
                 },
                 flags: vec![],
-                encryption: None,
+                encryption: encryption.clone(),
                 subvolumes: vec![],
             });
-            let swap_path = StoragePlan::partition_path(&device_path, part_num);
+            let swap_path = match &encryption {
+                Some(enc) => format!("/dev/mapper/{}", enc.mapper_name),
+                None => StoragePlan::partition_path(&device_path, part_num),
+            };
             mounts.push(PlannedMount {
                 source: swap_path,
                 target: String::new(),
@@ -108,14 +189,46 @@ impl StoragePlanner {
                 is_swap: true,
                 subvolume: None,
             });
-            part_num += 1;
+            part_num = next_number(part_num + 1);
         }
 
-        let root_start = if state.swap_enabled {
-            if is_uefi { "5121MiB" } else { "4098MiB" }
-        } else {
-            swap_start
-        };
+        let root_start = if swap_partition { swap_end } else { swap_start };
+
+        // LVM: the rest of the disk is the physical volume; the logical volumes are mounted
+        // first, like the subvolume layouts.
+        if lvm {
+            let pv = StoragePlan::partition_path(&device_path, part_num);
+            partitions.push(PlannedPartition {
+                number: part_num,
+                role: PartitionRole::Lvm,
+                start: root_start.clone(),
+                end: root_end.clone(),
+                filesystem: FilesystemSpec {
+                    fstype: "lvm".into(),
+                    mkfs_options: vec![],
+                },
+                flags: vec![PartitionFlag::Lvm],
+                encryption: None,
+                subvolumes: vec![],
+            });
+            let (stacks, lvm_mounts) = Self::lvm_layout(state, &pv, luks)?;
+            splice_lvm_mounts(&mut mounts, lvm_mounts);
+            return Ok(StoragePlan {
+                devices: vec![PlannedDevice {
+                    path: device_path,
+                    label: alongside.as_ref().map_or(label, |r| r.label),
+                    wipe: state.disks_wipe && alongside.is_none(),
+                    keep_table: alongside.is_some(),
+                    partitions,
+                }],
+                mounts,
+                mode: StorageMode::Automatic,
+                stacks,
+                pools: vec![],
+                bcachefs: vec![],
+                swapfile: Self::swapfile_spec(state, root_fstype)?,
+            });
+        }
 
         let encryption = if luks {
             let pw = if state.disk_encryption_password.is_empty() {
@@ -127,6 +240,8 @@ impl StoragePlanner {
                 method: EncryptionMethod::Luks2,
                 mapper_name: "cryptroot".into(),
                 passphrase: pw,
+                format: Self::luks_format_options(state)?,
+                enroll: Self::luks_enrollment(state)?,
             })
         } else {
             None
@@ -137,15 +252,23 @@ impl StoragePlanner {
             2 => BtrfsSubvolumePreset::Extended,
             _ => BtrfsSubvolumePreset::Flat,
         };
-        let subvolumes = preset.subvolumes();
+        let mut subvolumes = if root_fs == RootFilesystem::Btrfs {
+            preset.subvolumes()
+        } else {
+            vec![]
+        };
+        let swapfile = Self::swapfile_spec(state, root_fs.name())?;
+        if swapfile.as_ref().is_some_and(|f| f.btrfs) {
+            subvolumes.push(SwapfileSpec::btrfs_subvolume());
+        }
 
         partitions.push(PlannedPartition {
             number: part_num,
             role: PartitionRole::Root,
-            start: root_start.into(),
-            end: "100%".into(),
+            start: root_start,
+            end: root_end,
             filesystem: FilesystemSpec {
-                fstype: "btrfs".into(),
+                fstype: root_fs.name().into(),
                 mkfs_options: vec![],
             },
             flags: vec![],
@@ -159,58 +282,49 @@ impl StoragePlanner {
             StoragePlan::partition_path(&device_path, part_num)
         };
 
-        if subvolumes.is_empty() {
-            // Flat: single root mount, no subvolumes
+        let mut pools = Vec::new();
+        let mut bcachefs = Vec::new();
+        if root_fs == RootFilesystem::Bcachefs {
+            let fs = BcachefsSpec {
+                members: vec![root_source],
+                encrypted: native_encryption,
+                passphrase: Some(state.disk_encryption_password.clone())
+                    .filter(|pw| native_encryption && !pw.is_empty()),
+            };
             mounts.insert(
                 0,
                 PlannedMount {
-                    source: root_source,
+                    source: fs.device_list(),
                     target: "/mnt".into(),
-                    fstype: "btrfs".into(),
+                    fstype: "bcachefs".into(),
                     options: vec![],
                     is_swap: false,
                     subvolume: None,
                 },
             );
+            bcachefs.push(fs);
+        } else if root_fs == RootFilesystem::Zfs {
+            // Dataset mounts first (before ESP/swap mounts), like the subvolume layouts
+            let pool = ZfsPoolSpec::default_layout(ZFS_POOL_NAME, vec![root_source]);
+            for (i, m) in pool.planned_mounts().into_iter().enumerate() {
+                mounts.insert(i, m);
+            }
+            pools.push(pool);
         } else {
-            // Subvolume mounts — root (@) first, then others sorted by mountpoint depth
-            let mut subvol_mounts: Vec<PlannedMount> = subvolumes
-                .iter()
-                .map(|sv| {
-                    let target = if sv.mountpoint == "/" {
-                        "/mnt".into()
-                    } else {
-                        format!("/mnt{}", sv.mountpoint)
-                    };
-                    PlannedMount {
-                        source: root_source.clone(),
-                        target,
-                        fstype: "btrfs".into(),
-                        options: sv.mount_options.clone(),
-                        is_swap: false,
-                        subvolume: Some(sv.name.clone()),
-                    }
-                })
-                .collect();
-            subvol_mounts.sort_by(|a, b| {
-                if a.target == "/mnt" {
-                    return std::cmp::Ordering::Less;
-                }
-                if b.target == "/mnt" {
-                    return std::cmp::Ordering::Greater;
-                }
-                a.target.cmp(&b.target)
-            });
-            // Insert subvolume mounts at the beginning (before ESP/swap mounts)
-            for (i, m) in subvol_mounts.into_iter().enumerate() {
+            // Root and subvolume mounts first (before ESP/swap mounts)
+            for (i, m) in btrfs_mounts(&root_source, &subvolumes, vec![])
+                .into_iter()
+                .enumerate()
+            {
                 mounts.insert(i, m);
             }
         }
 
         let device = PlannedDevice {
             path: device_path,
-            label,
-            wipe: state.disks_wipe,
+            label: alongside.as_ref().map_or(label, |r| r.label),
+            wipe: state.disks_wipe && alongside.is_none(),
+            keep_table: alongside.is_some(),
             partitions,
         };
 
@@ -219,6 +333,325 @@ impl StoragePlanner {
             mounts,
             mode: StorageMode::Automatic,
             stacks: vec![],
+            pools,
+            bcachefs,
+            swapfile,
+        })
+    }
+
+    /// Free region of `device`'s existing partition table for the automatic layout
+    /// ([`AppState::alongside_layout_override`] or `parted`), with the ESP to reuse. An ESP
+    /// too small for the kernels is mounted at `/efi` when GRUB can read them from the root
+    /// (not encrypted, not ZFS or bcachefs).
+    fn alongside_region(
+        state: &AppState,
+        device: &str,
+        root_fstype: &str,
+        luks: bool,
+    ) -> Result<AlongsideRegion, Vec<ValidationError>> {
+        let err = |message: String| {
+            Err(vec![ValidationError::at(
+                "disks.install_alongside",
+                message,
+            )])
+        };
+        let Some(layout) = state
+            .alongside_layout_override
+            .clone()
+            .or_else(|| DiskLayout::probe(device))
+        else {
+            return err(format!(
+                "Install alongside: could not read the partition table of {device}"
+            ));
+        };
+        let Some(label) = layout.label else {
+            return err(format!(
+                "Install alongside: {device} has no partition table to install next to"
+            ));
+        };
+        let is_uefi = state.is_uefi();
+        if !is_uefi && label == DiskLabel::Gpt {
+            return err(
+                "Install alongside on BIOS needs an msdos partition table (GPT boots through an ESP)"
+                    .into(),
+            );
+        }
+        let Some((start_mib, end_mib)) = layout.largest_free() else {
+            return err(format!("Install alongside: {device} has no free space"));
+        };
+        if end_mib - start_mib < ALONGSIDE_MIN_FREE_MIB {
+            return err(format!(
+                "Install alongside: the largest free region on {device} has {} MiB; at least {ALONGSIDE_MIN_FREE_MIB} MiB is needed",
+                end_mib - start_mib
+            ));
+        }
+
+        let esp = match layout.esp() {
+            Some(esp) if is_uefi => {
+                let path = StoragePlan::partition_path(device, esp.number);
+                let target = if esp.size_mib() >= ALONGSIDE_MIN_ESP_MIB {
+                    "/mnt/boot"
+                } else if state.bootloader_index == 1
+                    && !luks
+                    && !matches!(root_fstype, "zfs" | "bcachefs")
+                {
+                    "/mnt/efi"
+                } else {
+                    return err(format!(
+                        "Install alongside: the ESP {path} has {} MiB, less than the {ALONGSIDE_MIN_ESP_MIB} MiB the kernels need; use GRUB without encryption to keep them on the root",
+                        esp.size_mib()
+                    ));
+                };
+                Some((path, target))
+            }
+            _ => None,
+        };
+
+        let needed = 1
+            + usize::from(is_uefi && esp.is_none())
+            + usize::from(state.swap_mode() == SwapMode::Partition);
+        if label == DiskLabel::Msdos
+            && layout.free_numbers().take_while(|n| *n <= 4).count() < needed
+        {
+            return err(format!(
+                "Install alongside: the msdos partition table of {device} has fewer than {needed} free primary partition slots"
+            ));
+        }
+
+        Ok(AlongsideRegion {
+            label,
+            start_mib,
+            end_mib,
+            esp,
+            taken: layout.partitions.iter().map(|p| p.number).collect(),
+        })
+    }
+
+    /// Automatic layout across the RAID member disks ([`AppState::target_disks`]): every disk
+    /// gets the same partitions, the swap and root partitions are assembled into the `swap`
+    /// and `root` arrays, and each disk keeps its own ESP (mounted at `/boot`, `/boot2`, ...)
+    /// or BIOS boot partition so the machine still boots with the first disk gone.
+    fn compile_automatic_raid(state: &AppState) -> Result<StoragePlan, Vec<ValidationError>> {
+        let disks = state.target_disks();
+        if disks.is_empty() {
+            return Err(vec![ValidationError::at(
+                "disks.selected_device",
+                "No target disk selected",
+            )]);
+        }
+        let level = state.raid_level();
+        let is_uefi = state.is_uefi();
+        let lvm = state.lvm_selected();
+        let luks = state.disk_encryption_type_index == 1;
+
+        let mut errors = Vec::new();
+        if disks.len() < level.min_devices() {
+            errors.push(format!(
+                "{} needs at least {} disks ({} selected)",
+                level.name().to_uppercase(),
+                level.min_devices(),
+                disks.len()
+            ));
+        }
+        if is_uefi && state.bootloader_index == 1 {
+            // GRUB keeps its modules and config on the ESP it was installed to
+            errors.push(
+                "RAID on UEFI needs systemd-boot, EFISTUB, Limine or rEFInd, not GRUB".into(),
+            );
+        } else if !is_uefi && state.bootloader_index != 1 {
+            errors.push("RAID on BIOS needs GRUB".into());
+        }
+        if !lvm && RootFilesystem::from_index(state.disks_root_fs_index) != RootFilesystem::Btrfs {
+            errors.push(
+                "RAID needs a btrfs root or LVM (ZFS and bcachefs span disks themselves)".into(),
+            );
+        }
+        if !errors.is_empty() {
+            return Err(errors
+                .into_iter()
+                .map(|message| ValidationError::at("disks.raid", message))
+                .collect());
+        }
+
+        let root_fstype = if lvm {
+            match state.lvm_root_volume() {
+                Some(lv) => lv.fs.clone(),
+                None => {
+                    return Err(vec![ValidationError::at(
+                        "disks.lvm.volumes",
+                        "LVM needs a logical volume mounted at /",
+                    )]);
+                }
+            }
+        } else {
+            RootFilesystem::Btrfs.name().to_string()
+        };
+        let swap_partition = state.swap_mode() == SwapMode::Partition;
+        if swap_partition
+            && Self::swap_encryption_spec(state, "cryptswap", &root_fstype, luks, luks)?.is_some()
+        {
+            return Err(vec![ValidationError::at(
+                "swap.encryption",
+                "RAID swap array cannot be encrypted; use a swap file or zram",
+            )]);
+        }
+
+        let label = match state.disks_label.as_deref().unwrap_or("gpt") {
+            "msdos" => DiskLabel::Msdos,
+            _ => DiskLabel::Gpt,
+        };
+        let align = state.disks_align.clone().unwrap_or_else(|| "1MiB".into());
+        let swap_start = if is_uefi { "1025MiB" } else { "2MiB" };
+        let swap_end = if is_uefi { "5121MiB" } else { "4098MiB" };
+        let root_start = if swap_partition { swap_end } else { swap_start };
+        let member = |number: u32, start: &str, end: &str| PlannedPartition {
+            number,
+            role: PartitionRole::Raid,
+            start: start.into(),
+            end: end.into(),
+            filesystem: FilesystemSpec {
+                fstype: "raid".into(),
+                mkfs_options: vec![],
+            },
+            flags: vec![PartitionFlag::Raid],
+            encryption: None,
+            subvolumes: vec![],
+        };
+
+        let mut devices = Vec::new();
+        let mut esp_mounts = Vec::new();
+        let mut swap_members = Vec::new();
+        let mut root_members = Vec::new();
+        for (i, disk) in disks.iter().enumerate() {
+            let mut partitions = vec![if is_uefi {
+                PlannedPartition {
+                    number: 1,
+                    role: PartitionRole::Esp,
+                    start: align.clone(),
+                    end: "1025MiB".into(),
+                    filesystem: FilesystemSpec {
+                        fstype: "fat32".into(),
+                        mkfs_options: vec![],
+                    },
+                    flags: vec![PartitionFlag::Esp],
+                    encryption: None,
+                    subvolumes: vec![],
+                }
+            } else {
+                PlannedPartition {
+                    number: 1,
+                    role: PartitionRole::BiosBoot,
+                    start: align.clone(),
+                    end: "2MiB".into(),
+                    filesystem: FilesystemSpec {
+                        fstype: "biosboot".into(),
+                        mkfs_options: vec![],
+                    },
+                    flags: vec![PartitionFlag::BiosGrub],
+                    encryption: None,
+                    subvolumes: vec![],
+                }
+            }];
+            if is_uefi {
+                esp_mounts.push(PlannedMount {
+                    source: StoragePlan::partition_path(disk, 1),
+                    target: match i {
+                        0 => "/mnt/boot".into(),
+                        _ => format!("/mnt/boot{}", i + 1),
+                    },
+                    fstype: "vfat".into(),
+                    options: vec![],
+                    is_swap: false,
+                    subvolume: None,
+                });
+            }
+            let mut number = 2;
+            if swap_partition {
+                partitions.push(member(number, swap_start, swap_end));
+                swap_members.push(StoragePlan::partition_path(disk, number));
+                number += 1;
+            }
+            partitions.push(member(number, root_start, "100%"));
+            root_members.push(StoragePlan::partition_path(disk, number));
+            devices.push(PlannedDevice {
+                path: disk.clone(),
+                label,
+                wipe: state.disks_wipe,
+                keep_table: false,
+                partitions,
+            });
+        }
+
+        let array = |name: &str, members: Vec<String>| {
+            VolumeLayer::Raid(RaidSpec {
+                level: level.mdadm_level().into(),
+                members,
+                name: name.into(),
+            })
+        };
+        let mut stacks = Vec::new();
+        let mut mounts = Vec::new();
+        if lvm {
+            let (mut lvm_stacks, lvm_mounts) = Self::lvm_layout(state, "/dev/md/root", luks)?;
+            for stack in &mut lvm_stacks {
+                stack.base = root_members[0].clone();
+                stack.layers.insert(0, array("root", root_members.clone()));
+            }
+            stacks.extend(lvm_stacks);
+            mounts.extend(esp_mounts);
+            splice_lvm_mounts(&mut mounts, lvm_mounts);
+        } else {
+            let mut layers = vec![array("root", root_members.clone())];
+            if luks {
+                layers.push(VolumeLayer::Luks(EncryptionSpec {
+                    method: EncryptionMethod::Luks2,
+                    mapper_name: "cryptroot".into(),
+                    passphrase: Some(state.disk_encryption_password.clone())
+                        .filter(|pw| !pw.is_empty()),
+                    format: Self::luks_format_options(state)?,
+                    enroll: Self::luks_enrollment(state)?,
+                }));
+            }
+            let stack = DeviceStack {
+                base: root_members[0].clone(),
+                layers,
+                filesystem: Some(FilesystemSpec {
+                    fstype: root_fstype.clone(),
+                    mkfs_options: vec![],
+                }),
+            };
+            mounts.extend(btrfs_mounts(&stack.final_device_path(), &[], vec![]));
+            mounts.extend(esp_mounts);
+            stacks.push(stack);
+        }
+        if swap_partition {
+            let stack = DeviceStack {
+                base: swap_members[0].clone(),
+                layers: vec![array("swap", swap_members)],
+                filesystem: Some(FilesystemSpec {
+                    fstype: "linux-swap".into(),
+                    mkfs_options: vec![],
+                }),
+            };
+            mounts.push(PlannedMount {
+                source: stack.final_device_path(),
+                target: String::new(),
+                fstype: "swap".into(),
+                options: vec![],
+                is_swap: true,
+                subvolume: None,
+            });
+            stacks.push(stack);
+        }
+
+        Ok(StoragePlan {
+            devices,
+            mounts,
+            mode: StorageMode::Automatic,
+            stacks,
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: Self::swapfile_spec(state, &root_fstype)?,
         })
     }
 
@@ -226,16 +659,18 @@ impl StoragePlanner {
         let device_path = match &state.disks_selected_device {
             Some(p) => p.clone(),
             None => {
-                return Err(vec![ValidationError {
-                    message: "No target disk selected".into(),
-                }]);
+                return Err(vec![ValidationError::at(
+                    "disks.selected_device",
+                    "No target disk selected",
+                )]);
             }
         };
 
         if state.disks_partitions.is_empty() {
-            return Err(vec![ValidationError {
-                message: "No partitions defined for manual mode".into(),
-            }]);
+            return Err(vec![ValidationError::at(
+                "disks.partitions",
+                "No partitions defined for manual mode",
+            )]);
         }
 
         // Pre-validate each spec before conversion
@@ -248,11 +683,13 @@ impl StoragePlanner {
             }
 
             let label = format!("Partition {}", i + 1);
+            let base = format!("disks.partitions[{i}]");
 
             if spec.role.is_none() || spec.role.as_deref() == Some("") {
-                pre_errors.push(ValidationError {
-                    message: format!("{label}: missing role (BOOT/SWAP/ROOT/OTHER)"),
-                });
+                pre_errors.push(ValidationError::at(
+                    format!("{base}.role"),
+                    format!("{label}: missing role (BOOT/SWAP/ROOT/OTHER)"),
+                ));
             }
 
             let role_str = spec.role.as_deref().unwrap_or("");
@@ -260,22 +697,26 @@ impl StoragePlanner {
 
             if (spec.fs.is_none() || spec.fs.as_deref() == Some(""))
                 && role != PartitionRole::BiosBoot
+                && role != PartitionRole::Lvm
             {
-                pre_errors.push(ValidationError {
-                    message: format!("{label}: missing filesystem type"),
-                });
+                pre_errors.push(ValidationError::at(
+                    format!("{base}.fs"),
+                    format!("{label}: missing filesystem type"),
+                ));
             }
 
             if spec.start.is_none() || spec.start.as_deref() == Some("") {
-                pre_errors.push(ValidationError {
-                    message: format!("{label}: missing start position"),
-                });
+                pre_errors.push(ValidationError::at(
+                    format!("{base}.start"),
+                    format!("{label}: missing start position"),
+                ));
             }
 
             if spec.size.is_none() || spec.size.as_deref() == Some("") {
-                pre_errors.push(ValidationError {
-                    message: format!("{label}: missing size"),
-                });
+                pre_errors.push(ValidationError::at(
+                    format!("{base}.size"),
+                    format!("{label}: missing size"),
+                ));
             }
 
             if let Some(sz) = spec.size.as_deref()
@@ -288,19 +729,31 @@ impl StoragePlanner {
                     .parse::<u64>()
                     .is_err()
             {
-                pre_errors.push(ValidationError {
-                    message: format!(
+                pre_errors.push(ValidationError::at(
+                    format!("{base}.start"),
+                    format!(
                         "{label}: start must be a decimal byte offset when size is a byte length"
                     ),
-                });
+                ));
             }
 
-            if role != PartitionRole::Swap && role != PartitionRole::BiosBoot {
+            if role == PartitionRole::Lvm && !state.lvm_selected() {
+                pre_errors.push(ValidationError::at(
+                    format!("{base}.role"),
+                    format!("{label}: role LVM needs LVM enabled on the LVM screen"),
+                ));
+            }
+
+            if !matches!(
+                role,
+                PartitionRole::Swap | PartitionRole::BiosBoot | PartitionRole::Lvm
+            ) {
                 let mp = spec.mountpoint.as_deref().unwrap_or("");
                 if mp.is_empty() {
-                    pre_errors.push(ValidationError {
-                        message: format!("{label} ({role_str}): missing mountpoint"),
-                    });
+                    pre_errors.push(ValidationError::at(
+                        format!("{base}.mountpoint"),
+                        format!("{label} ({role_str}): missing mountpoint"),
+                    ));
                 }
             }
         }
@@ -314,8 +767,10 @@ impl StoragePlanner {
             _ => DiskLabel::Gpt,
         };
 
-        let mut sorted_specs = state.disks_partitions.clone();
-        sorted_specs.sort_by(|a, b| {
+        // (index in the config, spec), by start offset
+        let mut sorted_specs: Vec<(usize, _)> =
+            state.disks_partitions.iter().cloned().enumerate().collect();
+        sorted_specs.sort_by(|(_, a), (_, b)| {
             let sa = a
                 .start
                 .as_ref()
@@ -338,7 +793,7 @@ impl StoragePlanner {
 
         let mut overlap_errors: Vec<ValidationError> = Vec::new();
         let mut prev = PrevRegion::None;
-        for spec in &sorted_specs {
+        for (_, spec) in &sorted_specs {
             if let Some(spec_device) = &spec.name
                 && spec_device != &device_path
             {
@@ -354,17 +809,13 @@ impl StoragePlanner {
 
             match prev {
                 PrevRegion::ExtendsToDiskEnd => {
-                    overlap_errors.push(ValidationError {
-                        message: "A partition is defined after one that already extends to the end of the disk (size 100%)".into(),
-                    });
+                    overlap_errors.push(ValidationError::at("disks.partitions", "A partition is defined after one that already extends to the end of the disk (size 100%)"));
                     break;
                 }
                 PrevRegion::EndsAtByte(end_prev) if start_b < end_prev => {
-                    overlap_errors.push(ValidationError {
-                        message: format!(
+                    overlap_errors.push(ValidationError::at("disks.partitions", format!(
                             "Partition specs overlap on the target disk (byte {start_b} starts before byte {end_prev})"
-                        ),
-                    });
+                        )));
                     break;
                 }
                 _ => {}
@@ -388,11 +839,39 @@ impl StoragePlanner {
             return Err(overlap_errors);
         }
 
+        let root_fstype = sorted_specs
+            .iter()
+            .map(|(_, spec)| spec)
+            .filter(|spec| spec.name.as_ref().is_none_or(|n| *n == device_path))
+            .find(|spec| {
+                PartitionRole::from_str_role(spec.role.as_deref().unwrap_or(""))
+                    == PartitionRole::Root
+            })
+            .and_then(|spec| spec.fs.as_deref())
+            .or(state.lvm_root_volume().map(|lv| lv.fs.as_str()))
+            .unwrap_or("ext4");
+        let swapfile = Self::swapfile_spec(state, root_fstype)?;
+        let root_encrypted = sorted_specs
+            .iter()
+            .map(|(_, spec)| spec)
+            .filter(|spec| spec.name.as_ref().is_none_or(|n| *n == device_path))
+            .any(|spec| {
+                PartitionRole::from_str_role(spec.role.as_deref().unwrap_or(""))
+                    == PartitionRole::Root
+                    && spec.encrypt.unwrap_or(false)
+            })
+            || (state.lvm_root_volume().is_some() && state.lvm_encrypted());
+        // LVM physical volume: (partition path, encrypted)
+        let mut physical_volume: Option<(String, bool)> = None;
+        let mut swap_count = 0;
+
         let mut partitions = Vec::new();
         let mut mounts = Vec::new();
+        // bcachefs partitions sharing a mountpoint: (target, options, filesystem)
+        let mut bcachefs_groups: Vec<(String, Vec<String>, BcachefsSpec)> = Vec::new();
         let mut part_num: u32 = 1;
 
-        for spec in &sorted_specs {
+        for (index, spec) in &sorted_specs {
             if let Some(spec_device) = &spec.name
                 && spec_device != &device_path
             {
@@ -401,25 +880,52 @@ impl StoragePlanner {
 
             let role_str = spec.role.as_deref().unwrap_or("OTHER");
             let role = PartitionRole::from_str_role(role_str);
-            let fs = spec.fs.as_deref().unwrap_or("ext4");
+            let fs = if role == PartitionRole::Lvm {
+                "lvm"
+            } else {
+                spec.fs.as_deref().unwrap_or("ext4")
+            };
             let start = spec.start.as_deref().unwrap_or("0");
             let size = spec.size.as_deref().unwrap_or("100%");
 
             let start_str = super::bytes_to_parted_unit(start);
             let end_str = super::manual_disk_spec_end_for_parted(start, size).map_err(|msg| {
-                vec![ValidationError {
-                    message: format!("Partition {}: {msg}", part_num),
-                }]
+                vec![ValidationError::at(
+                    format!("disks.partitions[{index}].size"),
+                    format!("Partition {}: {msg}", part_num),
+                )]
             })?;
 
             let mut flags = Vec::new();
             match role {
                 PartitionRole::Esp => flags.push(PartitionFlag::Esp),
                 PartitionRole::BiosBoot => flags.push(PartitionFlag::BiosGrub),
+                PartitionRole::Lvm => flags.push(PartitionFlag::Lvm),
                 _ => {}
             }
 
-            let encryption = if spec.encrypt.unwrap_or(false) {
+            // bcachefs members use native encryption rather than LUKS
+            let native_encryption = fs == "bcachefs" && spec.encrypt.unwrap_or(false);
+            let swap_encryption = if role == PartitionRole::Swap {
+                swap_count += 1;
+                let mapper = match swap_count {
+                    1 => "cryptswap".to_string(),
+                    n => format!("cryptswap{n}"),
+                };
+                Self::swap_encryption_spec(
+                    state,
+                    &mapper,
+                    root_fstype,
+                    root_encrypted && root_fstype != "bcachefs",
+                    root_encrypted,
+                )?
+            } else {
+                None
+            };
+            // The physical volume's encryption is set up with the volume group
+            let encryption = if swap_encryption.is_some() {
+                swap_encryption
+            } else if spec.encrypt.unwrap_or(false) && fs != "bcachefs" && fs != "lvm" {
                 let mapper = match role {
                     PartitionRole::Root => "cryptroot",
                     PartitionRole::Home => "crypthome",
@@ -434,13 +940,15 @@ impl StoragePlanner {
                     method: EncryptionMethod::Luks2,
                     mapper_name: mapper.into(),
                     passphrase: pw,
+                    format: Self::luks_format_options(state)?,
+                    enroll: Self::luks_enrollment(state)?,
                 })
             } else {
                 None
             };
 
             let is_btrfs_root = role == PartitionRole::Root && fs == "btrfs";
-            let subvolumes = if is_btrfs_root {
+            let mut subvolumes = if is_btrfs_root {
                 let preset = match state.btrfs_subvolume_preset {
                     1 => BtrfsSubvolumePreset::Standard,
                     2 => BtrfsSubvolumePreset::Extended,
@@ -450,6 +958,9 @@ impl StoragePlanner {
             } else {
                 vec![]
             };
+            if is_btrfs_root && swapfile.is_some() {
+                subvolumes.push(SwapfileSpec::btrfs_subvolume());
+            }
 
             partitions.push(PlannedPartition {
                 number: part_num,
@@ -484,35 +995,22 @@ impl StoragePlanner {
                     });
                 }
                 PartitionRole::BiosBoot => {}
+                PartitionRole::Lvm => {
+                    if physical_volume.is_some() {
+                        return Err(vec![ValidationError::at(
+                            "disks.partitions",
+                            "LVM uses a single physical volume; only one partition can have role LVM",
+                        )]);
+                    }
+                    physical_volume = Some((source, spec.encrypt.unwrap_or(false)));
+                }
                 _ if !subvolumes.is_empty() => {
-                    let mut subvol_mounts: Vec<PlannedMount> = subvolumes
-                        .iter()
-                        .map(|sv| {
-                            let target = if sv.mountpoint == "/" {
-                                "/mnt".into()
-                            } else {
-                                format!("/mnt{}", sv.mountpoint)
-                            };
-                            PlannedMount {
-                                source: source.clone(),
-                                target,
-                                fstype: "btrfs".into(),
-                                options: sv.mount_options.clone(),
-                                is_swap: false,
-                                subvolume: Some(sv.name.clone()),
-                            }
-                        })
-                        .collect();
-                    subvol_mounts.sort_by(|a, b| {
-                        if a.target == "/mnt" {
-                            return std::cmp::Ordering::Less;
-                        }
-                        if b.target == "/mnt" {
-                            return std::cmp::Ordering::Greater;
-                        }
-                        a.target.cmp(&b.target)
-                    });
-                    mounts.extend(subvol_mounts);
+                    let flat_options = spec
+                        .mount_options
+                        .as_ref()
+                        .map(|o| o.split(',').map(|s| s.trim().to_string()).collect())
+                        .unwrap_or_default();
+                    mounts.extend(btrfs_mounts(&source, &subvolumes, flat_options));
                 }
                 _ => {
                     let mountpoint = spec.mountpoint.as_deref().unwrap_or(match role {
@@ -543,20 +1041,67 @@ impl StoragePlanner {
                         .map(|o| o.split(',').map(|s| s.trim().to_string()).collect())
                         .unwrap_or_default();
 
-                    mounts.push(PlannedMount {
-                        source,
-                        target,
-                        fstype: mount_fstype,
-                        options,
-                        is_swap: false,
-                        subvolume: None,
-                    });
+                    if fs == "bcachefs" {
+                        match bcachefs_groups.iter_mut().find(|(t, _, _)| *t == target) {
+                            Some((_, _, group)) => {
+                                group.members.push(source);
+                                group.encrypted |= native_encryption;
+                            }
+                            None => bcachefs_groups.push((
+                                target,
+                                options,
+                                BcachefsSpec {
+                                    members: vec![source],
+                                    encrypted: native_encryption,
+                                    passphrase: None,
+                                },
+                            )),
+                        }
+                    } else {
+                        mounts.push(PlannedMount {
+                            source,
+                            target,
+                            fstype: mount_fstype,
+                            options,
+                            is_swap: false,
+                            subvolume: None,
+                        });
+                    }
                 }
             }
 
             part_num += 1;
         }
 
+        let mut stacks = Vec::new();
+        if state.lvm_selected() {
+            let Some((pv, encrypted)) = physical_volume else {
+                return Err(vec![ValidationError::at(
+                    "disks.partitions",
+                    "LVM needs a partition with role LVM as its physical volume",
+                )]);
+            };
+            let (lvm_stacks, lvm_mounts) = Self::lvm_layout(state, &pv, encrypted)?;
+            stacks = lvm_stacks;
+            mounts.extend(lvm_mounts);
+        }
+
+        let mut bcachefs = Vec::new();
+        for (target, options, mut fs) in bcachefs_groups {
+            if fs.encrypted && !state.disk_encryption_password.is_empty() {
+                fs.passphrase = Some(state.disk_encryption_password.clone());
+            }
+            mounts.push(PlannedMount {
+                source: fs.device_list(),
+                target,
+                fstype: "bcachefs".into(),
+                options,
+                is_swap: false,
+                subvolume: None,
+            });
+            bcachefs.push(fs);
+        }
+
         // Sort mounts: root first, then by path depth
         mounts.sort_by(|a, b| {
             if a.is_swap != b.is_swap {
@@ -575,6 +1120,7 @@ impl StoragePlanner {
             path: device_path,
             label,
             wipe: state.disks_wipe,
+            keep_table: false,
             partitions,
         };
 
@@ -582,20 +1128,297 @@ impl StoragePlanner {
             devices: vec![device],
             mounts,
             mode: StorageMode::Manual,
-            stacks: vec![],
+            stacks,
+            pools: vec![],
+            bcachefs,
+            swapfile,
         })
     }
 
-    fn compile_pre_mounted(state: &AppState) -> Result<StoragePlan, Vec<ValidationError>> {
-        let mut mounts = Vec::new();
-
-        // Detect mounts under /mnt via findmnt -J -R
-        let findmnt_result = std::process::Command::new("findmnt")
-            .args(["-J", "-R", "--target", "/mnt"])
-            .output();
+    /// `luksFormat` parameters from the Disk Encryption screen, applied to every LUKS volume.
+    pub(crate) fn luks_format_options(
+        state: &AppState,
+    ) -> Result<LuksFormatOptions, Vec<ValidationError>> {
+        let text = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        let number = |field: &str, s: &str| match text(s) {
+            None => Ok(None),
+            Some(v) => v.parse::<u32>().map(Some).map_err(|_| {
+                vec![ValidationError::at(
+                    format!("disk_encryption.{field}"),
+                    format!("LUKS {field}: '{v}' is not a number"),
+                )]
+            }),
+        };
+        let options = LuksFormatOptions {
+            cipher: text(&state.luks_cipher),
+            key_size: number("key_size", &state.luks_key_size)?,
+            hash: text(&state.luks_hash),
+            pbkdf: state
+                .luks_pbkdf_index
+                .checked_sub(1)
+                .and_then(|i| LUKS_PBKDFS.get(i))
+                .map(|p| p.to_string()),
+            pbkdf_memory: number("pbkdf_memory", &state.luks_pbkdf_memory)?,
+            pbkdf_time: number("pbkdf_time", &state.luks_pbkdf_time)?,
+            sector_size: state
+                .luks_sector_size_index
+                .checked_sub(1)
+                .and_then(|i| LUKS_SECTOR_SIZES.get(i))
+                .copied(),
+            label: text(&state.luks_label),
+            integrity: state
+                .luks_integrity_index
+                .checked_sub(1)
+                .and_then(|i| LUKS_INTEGRITY.get(i))
+                .map(|i| i.to_string()),
+        };
+        let problems = options.problems();
+        if problems.is_empty() {
+            Ok(options)
+        } else {
+            Err(problems
+                .into_iter()
+                .map(|(field, message)| {
+                    ValidationError::at(
+                        format!("disk_encryption.{field}"),
+                        format!("LUKS {field}: {message}"),
+                    )
+                })
+                .collect())
+        }
+    }
 
-        match findmnt_result {
-            Ok(out) if out.status.success() => {
+    /// `systemd-cryptenroll` steps chosen on the Disk Encryption screen, applied to every
+    /// LUKS volume of the plan.
+    fn luks_enrollment(state: &AppState) -> Result<LuksEnrollment, Vec<ValidationError>> {
+        let tpm2_pcrs = if state.disk_encryption_tpm2 {
+            let pcrs = state.disk_encryption_tpm2_pcrs.trim();
+            if !is_valid_tpm2_pcrs(pcrs) {
+                return Err(vec![ValidationError::at(
+                    "disk_encryption.tpm2_pcrs",
+                    format!("TPM2 PCRs '{pcrs}' are invalid (expected e.g. 7 or 0+7)"),
+                )]);
+            }
+            Some(pcrs.to_string())
+        } else {
+            None
+        };
+        Ok(LuksEnrollment {
+            tpm2_pcrs,
+            fido2: state.disk_encryption_fido2,
+            recovery_key: state.disk_encryption_recovery_key,
+        })
+    }
+
+    /// Swap partition encryption from the Swap screen. It follows the root: nothing when the
+    /// root is not encrypted, and the key-file mode needs a LUKS root the initramfs can mount.
+    fn swap_encryption_spec(
+        state: &AppState,
+        mapper: &str,
+        root_fstype: &str,
+        root_luks: bool,
+        root_encrypted: bool,
+    ) -> Result<Option<EncryptionSpec>, Vec<ValidationError>> {
+        let method = match state.swap_encryption() {
+            _ if !root_encrypted => return Ok(None),
+            SwapEncryption::None => return Ok(None),
+            SwapEncryption::RandomKey => EncryptionMethod::PlainRandomKey,
+            SwapEncryption::Keyfile if root_fstype == "zfs" => {
+                return Err(vec![ValidationError::at(
+                    "swap.encryption",
+                    "Swap key file unlock needs the systemd initramfs, which a ZFS root cannot use",
+                )]);
+            }
+            SwapEncryption::Keyfile if !root_luks => {
+                return Err(vec![ValidationError::at(
+                    "swap.encryption",
+                    format!(
+                        "Swap key file unlock needs a LUKS root, not an encrypted {root_fstype}; use a random key"
+                    ),
+                )]);
+            }
+            SwapEncryption::Keyfile => EncryptionMethod::Luks2Keyfile,
+        };
+        let format = if method == EncryptionMethod::Luks2Keyfile {
+            Self::luks_format_options(state)?
+        } else {
+            LuksFormatOptions::default()
+        };
+        Ok(Some(EncryptionSpec {
+            method,
+            mapper_name: mapper.into(),
+            passphrase: None,
+            format,
+            enroll: LuksEnrollment::default(),
+        }))
+    }
+
+    /// Guided LVM layout from the LVM screen: one stack per logical volume on the physical
+    /// volume `pv`, with LUKS under the volume group or on every volume when `encrypted`.
+    /// Volumes sized relative to the group (`%FREE`, `%VG`) are created after the fixed ones.
+    fn lvm_layout(
+        state: &AppState,
+        pv: &str,
+        encrypted: bool,
+    ) -> Result<(Vec<DeviceStack>, Vec<PlannedMount>), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let vg = state.lvm_vg_name.trim();
+        if !is_valid_lvm_name(vg) {
+            errors.push(ValidationError::at(
+                "disks.lvm.volume_group",
+                format!("LVM volume group name '{vg}' is invalid"),
+            ));
+        }
+        if state.lvm_volumes.is_empty() {
+            errors.push(ValidationError::at(
+                "disks.lvm.volumes",
+                "LVM needs at least one logical volume",
+            ));
+        }
+        for (i, lv) in state.lvm_volumes.iter().enumerate() {
+            let name = &lv.name;
+            let field = |f: &str| format!("disks.lvm.volumes[{i}].{f}");
+            if !is_valid_lvm_name(name) {
+                errors.push(ValidationError::at(
+                    field("name"),
+                    format!("LVM volume '{name}': invalid name"),
+                ));
+            } else if state.lvm_volumes[..i].iter().any(|o| o.name == *name) {
+                errors.push(ValidationError::at(
+                    field("name"),
+                    format!("LVM volume '{name}': duplicate name"),
+                ));
+            }
+            if !is_valid_lvm_size(&lv.size) {
+                errors.push(ValidationError::at(
+                    field("size"),
+                    format!(
+                        "LVM volume '{name}': size '{}' is invalid (expected e.g. 40G, 50%FREE or 100%FREE)",
+                        lv.size
+                    ),
+                ));
+            }
+            if !LVM_FILESYSTEMS.contains(&lv.fs.as_str()) {
+                errors.push(ValidationError::at(
+                    field("fs"),
+                    format!(
+                        "LVM volume '{name}': filesystem '{}' is not one of {}",
+                        lv.fs,
+                        LVM_FILESYSTEMS.join(", ")
+                    ),
+                ));
+            }
+            if lv.fs != "linux-swap"
+                && !lv.mountpoint.as_deref().is_some_and(|m| m.starts_with('/'))
+            {
+                errors.push(ValidationError::at(
+                    field("mountpoint"),
+                    format!("LVM volume '{name}': missing mountpoint"),
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let luks = |mapper: &str| -> Result<VolumeLayer, Vec<ValidationError>> {
+            Ok(VolumeLayer::Luks(EncryptionSpec {
+                method: EncryptionMethod::Luks2,
+                mapper_name: mapper.into(),
+                passphrase: Some(state.disk_encryption_password.clone())
+                    .filter(|pw| !pw.is_empty()),
+                format: Self::luks_format_options(state)?,
+                enroll: Self::luks_enrollment(state)?,
+            }))
+        };
+        let stacking = state.lvm_stacking();
+        let mut volumes: Vec<_> = state.lvm_volumes.iter().collect();
+        volumes.sort_by_key(|lv| lv.size.contains('%'));
+
+        let mut stacks = Vec::new();
+        let mut mounts = Vec::new();
+        for lv in volumes {
+            let swap = lv.fs == "linux-swap";
+            let mountpoint = lv.mountpoint.as_deref().unwrap_or("");
+            let mut layers = vec![VolumeLayer::Lvm(LvmSpec {
+                vg_name: vg.into(),
+                lv_name: lv.name.clone(),
+                size: lv.size.clone(),
+            })];
+            if encrypted {
+                match stacking {
+                    LvmStacking::LvmOnLuks => layers.insert(0, luks("cryptlvm")?),
+                    LvmStacking::LuksOnLvm if mountpoint == "/" && !swap => {
+                        layers.push(luks("cryptroot")?)
+                    }
+                    LvmStacking::LuksOnLvm => layers.push(luks(&format!("crypt{}", lv.name))?),
+                }
+            }
+            let stack = DeviceStack {
+                base: pv.into(),
+                layers,
+                filesystem: Some(FilesystemSpec {
+                    fstype: lv.fs.clone(),
+                    mkfs_options: vec![],
+                }),
+            };
+            mounts.push(PlannedMount {
+                source: stack.final_device_path(),
+                target: if swap {
+                    String::new()
+                } else if mountpoint == "/" {
+                    "/mnt".into()
+                } else {
+                    format!("/mnt{mountpoint}")
+                },
+                fstype: if swap { "swap".into() } else { lv.fs.clone() },
+                options: vec![],
+                is_swap: swap,
+                subvolume: None,
+            });
+            stacks.push(stack);
+        }
+        Ok((stacks, mounts))
+    }
+
+    /// The swap file for swapfile mode, placed according to the root filesystem.
+    fn swapfile_spec(
+        state: &AppState,
+        root_fstype: &str,
+    ) -> Result<Option<SwapfileSpec>, Vec<ValidationError>> {
+        if state.swap_mode() != SwapMode::Swapfile {
+            return Ok(None);
+        }
+        if !is_valid_swapfile_size(&state.swapfile_size) {
+            return Err(vec![ValidationError::at(
+                "swap.swapfile_size",
+                format!(
+                    "Swapfile size '{}' is invalid (expected e.g. 4G or 512M)",
+                    state.swapfile_size
+                ),
+            )]);
+        }
+        match root_fstype {
+            "zfs" | "bcachefs" => Err(vec![ValidationError::at(
+                "swap.mode",
+                format!(
+                    "Swapfile is not supported on a {root_fstype} root; use a swap partition or zram"
+                ),
+            )]),
+            fs => Ok(Some(SwapfileSpec::new(&state.swapfile_size, fs == "btrfs"))),
+        }
+    }
+
+    fn compile_pre_mounted(state: &AppState) -> Result<StoragePlan, Vec<ValidationError>> {
+        let mut mounts = Vec::new();
+
+        // Detect mounts under /mnt via findmnt -J -R
+        let findmnt_result = std::process::Command::new("findmnt")
+            .args(["-J", "-R", "--target", "/mnt"])
+            .output();
+
+        match findmnt_result {
+            Ok(out) if out.status.success() => {
                 if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&out.stdout)
                     && let Some(filesystems) = json.get("filesystems").and_then(|v| v.as_array())
                 {
@@ -603,11 +1426,10 @@ impl StoragePlanner {
                 }
             }
             _ => {
-                return Err(vec![ValidationError {
-                    message: "Failed to detect mounts under /mnt (findmnt failed). \
-                              Ensure filesystems are mounted at /mnt before selecting pre-mounted mode."
-                        .into(),
-                }]);
+                return Err(vec![ValidationError::new(
+                    "Failed to detect mounts under /mnt (findmnt failed). \
+                              Ensure filesystems are mounted at /mnt before selecting pre-mounted mode.",
+                )]);
             }
         }
 
@@ -637,9 +1459,10 @@ impl StoragePlanner {
 
         let has_root = mounts.iter().any(|m| m.target == "/mnt");
         if !has_root {
-            errors.push(ValidationError {
-                message: "Pre-mounted mode requires a filesystem mounted at /mnt (root)".into(),
-            });
+            errors.push(ValidationError::at(
+                "disks.partitions",
+                "Pre-mounted mode requires a filesystem mounted at /mnt (root)",
+            ));
         }
 
         let is_uefi = state.is_uefi();
@@ -648,17 +1471,19 @@ impl StoragePlanner {
                 .iter()
                 .any(|m| m.target == "/mnt/boot" || m.target == "/mnt/efi");
             if !has_boot {
-                errors.push(ValidationError {
-                    message: "UEFI system detected but no ESP mounted at /mnt/boot or /mnt/efi"
-                        .into(),
-                });
+                errors.push(ValidationError::at(
+                    "disks.partitions",
+                    "UEFI system detected but no ESP mounted at /mnt/boot or /mnt/efi",
+                ));
             }
         }
 
-        if state.swap_enabled && !mounts.iter().any(|m| m.is_swap) {
-            errors.push(ValidationError {
-                message: "Swap is enabled but no active swap device detected".into(),
-            });
+        match state.swap_mode() {
+            SwapMode::Partition if !mounts.iter().any(|m| m.is_swap) => {
+                errors.push(ValidationError::at("swap.mode", "Swap is enabled but no active swap device detected"));
+            }
+            SwapMode::Swapfile => errors.push(ValidationError::at("swap.mode", "Swapfile mode needs an automatic or manual layout; create the swap file on the pre-mounted root yourself or choose zram")),
+            _ => {}
         }
 
         if !errors.is_empty() {
@@ -684,8 +1509,84 @@ impl StoragePlanner {
             mounts,
             mode: StorageMode::PreMounted,
             stacks: vec![],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
+        })
+    }
+}
+
+/// Mounts for a btrfs filesystem: one per subvolume, plus the top level at `/` when no
+/// subvolume is mounted there (flat layout). Root first, then by target.
+/// Where the automatic layout goes when installing alongside another OS.
+struct AlongsideRegion {
+    label: DiskLabel,
+    start_mib: u64,
+    end_mib: u64,
+    /// Existing ESP to mount (partition path, mount target).
+    esp: Option<(String, &'static str)>,
+    /// Partition numbers already in use.
+    taken: Vec<u32>,
+}
+
+/// Logical volume mounts around the partition mounts: root first, then the other volumes by
+/// path, then swap volumes after everything else.
+fn splice_lvm_mounts(mounts: &mut Vec<PlannedMount>, mut lvm_mounts: Vec<PlannedMount>) {
+    lvm_mounts.sort_by(|a, b| {
+        (a.is_swap, a.target != "/mnt", &a.target).cmp(&(b.is_swap, b.target != "/mnt", &b.target))
+    });
+    let swaps_at = lvm_mounts
+        .iter()
+        .position(|m| m.is_swap)
+        .unwrap_or(lvm_mounts.len());
+    let lvm_swaps = lvm_mounts.split_off(swaps_at);
+    mounts.splice(0..0, lvm_mounts);
+    mounts.extend(lvm_swaps);
+}
+
+fn btrfs_mounts(
+    source: &str,
+    subvolumes: &[SubvolumeSpec],
+    flat_options: Vec<String>,
+) -> Vec<PlannedMount> {
+    let mut mounts: Vec<PlannedMount> = subvolumes
+        .iter()
+        .map(|sv| {
+            let target = if sv.mountpoint == "/" {
+                "/mnt".into()
+            } else {
+                format!("/mnt{}", sv.mountpoint)
+            };
+            PlannedMount {
+                source: source.into(),
+                target,
+                fstype: "btrfs".into(),
+                options: sv.mount_options.clone(),
+                is_swap: false,
+                subvolume: Some(sv.name.clone()),
+            }
         })
+        .collect();
+    if !mounts.iter().any(|m| m.target == "/mnt") {
+        mounts.push(PlannedMount {
+            source: source.into(),
+            target: "/mnt".into(),
+            fstype: "btrfs".into(),
+            options: flat_options,
+            is_swap: false,
+            subvolume: None,
+        });
     }
+    mounts.sort_by(|a, b| {
+        if a.target == "/mnt" {
+            return std::cmp::Ordering::Less;
+        }
+        if b.target == "/mnt" {
+            return std::cmp::Ordering::Greater;
+        }
+        a.target.cmp(&b.target)
+    });
+    mounts
 }
 
 /// Recursively collect mount entries from findmnt JSON output into PlannedMount list.
@@ -729,7 +1630,7 @@ fn collect_findmnt_mounts(fs_array: &[serde_json::Value], mounts: &mut Vec<Plann
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     use crate::common::install_cmd::InstallCmd;
@@ -803,6 +1704,8 @@ mod tests {
                 method: EncryptionMethod::Luks2,
                 mapper_name: "cryptroot".into(),
                 passphrase: None,
+                format: LuksFormatOptions::default(),
+                enroll: LuksEnrollment::default(),
             })
         } else {
             None
@@ -844,6 +1747,7 @@ mod tests {
             path: device_path.into(),
             label: DiskLabel::Gpt,
             wipe: true,
+            keep_table: false,
             partitions,
         };
 
@@ -852,6 +1756,9 @@ mod tests {
             mounts,
             mode: StorageMode::Automatic,
             stacks: vec![],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         }
     }
 
@@ -908,6 +1815,8 @@ mod tests {
                 method: EncryptionMethod::Luks2,
                 mapper_name: "cryptroot".into(),
                 passphrase: None,
+                format: LuksFormatOptions::default(),
+                enroll: LuksEnrollment::default(),
             })
         } else {
             None
@@ -949,6 +1858,7 @@ mod tests {
             path: device_path.into(),
             label: DiskLabel::Gpt,
             wipe: true,
+            keep_table: false,
             partitions,
         };
 
@@ -957,6 +1867,9 @@ mod tests {
             mounts,
             mode: StorageMode::Automatic,
             stacks: vec![],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         }
     }
 
@@ -1209,6 +2122,7 @@ mod tests {
                 path: "/dev/sda".into(),
                 label: DiskLabel::Gpt,
                 wipe: true,
+                keep_table: false,
                 partitions: vec![PlannedPartition {
                     number: 1,
                     role: PartitionRole::Esp,
@@ -1233,6 +2147,9 @@ mod tests {
             }],
             mode: StorageMode::Automatic,
             stacks: vec![],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         };
 
         let errors = plan.validate();
@@ -1290,6 +2207,14 @@ mod tests {
         state
     }
 
+    /// Automatic layout on `/dev/sda` with UEFI firmware, the base of the scenario fixtures.
+    pub(crate) fn uefi_auto_state() -> crate::core::state::AppState {
+        let mut state = crate::core::state::AppState::new(true);
+        state.disks_selected_device = Some("/dev/sda".into());
+        state.firmware_uefi_override = Some(true);
+        state
+    }
+
     #[test]
     fn test_manual_esp_root() {
         let mut state = make_manual_state();
@@ -1684,7 +2609,7 @@ mod tests {
         let mut state = crate::core::state::AppState::new(true);
         state.disks_selected_device = Some("/dev/sda".into());
         state.btrfs_subvolume_preset = 0; // Flat
-        state.swap_enabled = true;
+        state.swap_mode_index = SwapMode::Partition as usize;
 
         let plan = StoragePlanner::compile(&state).expect("flat should compile");
         let root_part = plan.devices[0]
@@ -1708,7 +2633,7 @@ mod tests {
         let mut state = crate::core::state::AppState::new(true);
         state.disks_selected_device = Some("/dev/sda".into());
         state.btrfs_subvolume_preset = 1; // Standard
-        state.swap_enabled = true;
+        state.swap_mode_index = SwapMode::Partition as usize;
 
         let plan = StoragePlanner::compile(&state).expect("standard should compile");
         let root_part = plan.devices[0]
@@ -1764,7 +2689,7 @@ mod tests {
         let mut state = crate::core::state::AppState::new(true);
         state.disks_selected_device = Some("/dev/sda".into());
         state.btrfs_subvolume_preset = 2; // Extended
-        state.swap_enabled = false;
+        state.swap_mode_index = SwapMode::None as usize;
 
         let plan = StoragePlanner::compile(&state).expect("extended should compile");
         let root_part = plan.devices[0]
@@ -1791,7 +2716,7 @@ mod tests {
         let mut state = crate::core::state::AppState::new(true);
         state.disks_selected_device = Some("/dev/sda".into());
         state.btrfs_subvolume_preset = 1; // Standard
-        state.swap_enabled = true;
+        state.swap_mode_index = SwapMode::Partition as usize;
         state.disk_encryption_type_index = 1; // LUKS
 
         let plan = StoragePlanner::compile(&state).expect("standard+luks should compile");
@@ -1819,7 +2744,7 @@ mod tests {
         let mut state = crate::core::state::AppState::new(true);
         state.disks_selected_device = Some("/dev/sda".into());
         state.btrfs_subvolume_preset = 1; // Standard
-        state.swap_enabled = false;
+        state.swap_mode_index = SwapMode::None as usize;
 
         let plan = StoragePlanner::compile(&state).expect("should compile");
         let fstab_cmds = plan.fstab_check_commands();
@@ -1876,6 +2801,9 @@ mod tests {
             mounts,
             mode: StorageMode::PreMounted,
             stacks: vec![],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         }
     }
 
@@ -2086,6 +3014,8 @@ mod tests {
                 method: EncryptionMethod::Luks2,
                 mapper_name: "cryptroot".into(),
                 passphrase: None,
+                format: LuksFormatOptions::default(),
+                enroll: LuksEnrollment::default(),
             })],
             filesystem: Some(FilesystemSpec {
                 fstype: "btrfs".into(),
@@ -2144,6 +3074,8 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "cryptroot".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 }),
             ],
             filesystem: Some(FilesystemSpec {
@@ -2164,6 +3096,8 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "cryptlvm".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 }),
                 VolumeLayer::Lvm(LvmSpec {
                     vg_name: "vg_system".into(),
@@ -2193,6 +3127,8 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "cryptlvm".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 }),
                 VolumeLayer::Lvm(LvmSpec {
                     vg_name: "vg0".into(),
@@ -2212,6 +3148,8 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "cryptlvm".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 }),
                 VolumeLayer::Lvm(LvmSpec {
                     vg_name: "vg0".into(),
@@ -2231,6 +3169,8 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "cryptlvm".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 }),
                 VolumeLayer::Lvm(LvmSpec {
                     vg_name: "vg0".into(),
@@ -2254,6 +3194,7 @@ mod tests {
                 path: "/dev/sda".into(),
                 label: DiskLabel::Gpt,
                 wipe: true,
+                keep_table: false,
                 partitions: vec![
                     PlannedPartition {
                         number: 1,
@@ -2282,6 +3223,8 @@ mod tests {
                             method: EncryptionMethod::Luks2,
                             mapper_name: "cryptlvm".into(),
                             passphrase: None,
+                            format: LuksFormatOptions::default(),
+                            enroll: LuksEnrollment::default(),
                         }),
                         subvolumes: vec![],
                     },
@@ -2323,6 +3266,9 @@ mod tests {
             ],
             mode: StorageMode::Manual,
             stacks: vec![root_stack, home_stack, swap_stack],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         };
 
         // The plan should hold together without panics
@@ -2376,6 +3322,7 @@ mod tests {
                     path: "/dev/sda".into(),
                     label: DiskLabel::Gpt,
                     wipe: true,
+                    keep_table: false,
                     partitions: vec![PlannedPartition {
                         number: 1,
                         role: PartitionRole::Root,
@@ -2394,6 +3341,7 @@ mod tests {
                     path: "/dev/sdb".into(),
                     label: DiskLabel::Gpt,
                     wipe: true,
+                    keep_table: false,
                     partitions: vec![PlannedPartition {
                         number: 1,
                         role: PartitionRole::Root,
@@ -2419,6 +3367,9 @@ mod tests {
             }],
             mode: StorageMode::Manual,
             stacks: vec![raid_stack],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         };
 
         assert_eq!(plan.stacks.len(), 1);
@@ -2488,6 +3439,8 @@ mod tests {
                 method: EncryptionMethod::Luks2,
                 mapper_name: "cryptroot".into(),
                 passphrase: None,
+                format: LuksFormatOptions::default(),
+                enroll: LuksEnrollment::default(),
             })],
             filesystem: Some(FilesystemSpec {
                 fstype: "btrfs".into(),
@@ -2570,7 +3523,7 @@ mod tests {
         let cmds = stack.setup_commands();
         assert_eq!(
             cmds[0].for_log(),
-            "mdadm --create /dev/md/md0 --level=1 --raid-devices=2 /dev/sda1 /dev/sdb1"
+            "mdadm --create /dev/md/md0 --run --level=1 --raid-devices=2 /dev/sda1 /dev/sdb1"
         );
         assert_eq!(cmds[1].for_log(), "mkfs.ext4 -F /dev/md/md0");
         assert_eq!(cmds.len(), 2);
@@ -2585,6 +3538,8 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "cryptlvm".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 }),
                 VolumeLayer::Lvm(LvmSpec {
                     vg_name: "vg_system".into(),
@@ -2626,6 +3581,8 @@ mod tests {
                 method: EncryptionMethod::Luks2,
                 mapper_name: "crypt".into(),
                 passphrase: None,
+                format: LuksFormatOptions::default(),
+                enroll: LuksEnrollment::default(),
             })],
             filesystem: None,
         };
@@ -2669,6 +3626,8 @@ mod tests {
                         method: EncryptionMethod::Luks2,
                         mapper_name: "crypt".into(),
                         passphrase: None,
+                        format: LuksFormatOptions::default(),
+                        enroll: LuksEnrollment::default(),
                     })],
                     filesystem: None,
                 },
@@ -2685,6 +3644,9 @@ mod tests {
                     }),
                 },
             ],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         };
         let cmds = plan.stack_setup_commands();
         assert!(
@@ -2715,12 +3677,17 @@ mod tests {
                     method: EncryptionMethod::Luks2,
                     mapper_name: "crypt".into(),
                     passphrase: None,
+                    format: LuksFormatOptions::default(),
+                    enroll: LuksEnrollment::default(),
                 })],
                 filesystem: Some(FilesystemSpec {
                     fstype: "ext4".into(),
                     mkfs_options: vec![],
                 }),
             }],
+            pools: vec![],
+            bcachefs: vec![],
+            swapfile: None,
         };
         assert!(plan.has_encryption());
     }
@@ -2742,4 +3709,1087 @@ mod tests {
         let cmds = stack.setup_commands();
         assert!(cmds.last().unwrap().for_log().contains("mkswap"));
     }
+
+    // ── ZFS root tests ──
+
+    fn compile_auto_zfs(luks: bool) -> StoragePlan {
+        let mut state = uefi_auto_state();
+        state.swap_mode_index = SwapMode::None as usize;
+        state.disks_root_fs_index = 1; // ZFS
+        state.btrfs_subvolume_preset = 1; // ignored for ZFS
+        if luks {
+            state.disk_encryption_type_index = 1;
+        }
+        StoragePlanner::compile(&state).expect("zfs plan should compile")
+    }
+
+    #[test]
+    fn test_zfs_root_creates_pool_and_datasets() {
+        let plan = compile_auto_zfs(false);
+        let root_part = plan.devices[0]
+            .partitions
+            .iter()
+            .find(|p| p.role == PartitionRole::Root)
+            .unwrap();
+        assert_eq!(root_part.filesystem.fstype, "zfs");
+        assert!(root_part.subvolumes.is_empty());
+
+        let parts = partition_cmds_joined(&plan.partition_commands());
+        assert!(parts.contains("mkpart root 1025MiB 100%"), "{parts}");
+        assert!(!parts.contains("mkfs.ext4"), "{parts}");
+        assert!(!parts.contains("mkfs.btrfs"), "{parts}");
+
+        let setup = partition_cmds_joined(&plan.stack_setup_commands());
+        assert!(setup.contains("modprobe zfs"), "{setup}");
+        assert!(
+            setup.contains("-o ashift=12") && setup.contains("-O acltype=posixacl"),
+            "{setup}"
+        );
+        assert!(setup.contains("-R /mnt zroot /dev/sda2"), "{setup}");
+        for ds in [
+            "zroot/ROOT",
+            "zroot/ROOT/default",
+            "zroot/home",
+            "zroot/var/log",
+        ] {
+            assert!(setup.contains(&format!(" {ds}\n")), "{ds}: {setup}");
+        }
+        assert!(
+            setup.contains("zpool set bootfs=zroot/ROOT/default zroot"),
+            "{setup}"
+        );
+        assert!(setup.ends_with("zpool export zroot"), "{setup}");
+
+        let mounts = plan.mount_commands();
+        assert!(
+            mounts[1].contains("zpool import -N -R /mnt zroot"),
+            "{mounts:?}"
+        );
+        assert_eq!(mounts[2], "zfs mount zroot/ROOT/default");
+        assert!(mounts.contains(&"zfs mount zroot/home".to_string()));
+        assert!(mounts.contains(&"zfs mount zroot/var/log".to_string()));
+        assert!(!mounts.iter().any(|m| m.contains("zroot/var ")));
+        assert!(
+            mounts
+                .iter()
+                .any(|m| m.contains("/mnt/etc/zfs/zpool.cache"))
+        );
+
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(!fstab.contains("blkid zroot"), "{fstab}");
+        assert!(fstab.contains("awk '$3 != \"zfs\"'"), "{fstab}");
+        assert!(fstab.contains("mountpoint -q /mnt/home"), "{fstab}");
+
+        assert_eq!(
+            plan.zfs_root_pool().map(|p| p.name.as_str()),
+            Some(ZFS_POOL_NAME)
+        );
+        assert!(
+            plan.teardown_commands()
+                .iter()
+                .any(|c| c.for_log() == "zpool export zroot 2>/dev/null || true")
+        );
+    }
+
+    #[test]
+    fn test_zfs_root_on_luks_uses_mapper_vdev() {
+        let plan = compile_auto_zfs(true);
+        let parts = partition_cmds_joined(&plan.partition_commands());
+        assert!(parts.contains("cryptsetup open"), "{parts}");
+        let setup = partition_cmds_joined(&plan.stack_setup_commands());
+        assert!(setup.contains("zroot /dev/mapper/cryptroot"), "{setup}");
+        assert!(plan.has_encryption());
+    }
+
+    #[test]
+    fn test_zfs_root_requires_uefi() {
+        let mut state = crate::core::state::AppState::new(true);
+        state.disks_selected_device = Some("/dev/sda".into());
+        state.firmware_uefi_override = Some(false);
+        state.disks_root_fs_index = 1;
+        let errors = StoragePlanner::compile(&state).unwrap_err();
+        assert!(errors[0].message.contains("UEFI"), "{}", errors[0].message);
+    }
+
+    // ── bcachefs tests ──
+
+    fn bcachefs_part(role: &str, start: &str, size: &str) -> crate::core::types::DiskPartitionSpec {
+        crate::core::types::DiskPartitionSpec {
+            name: Some("/dev/sda".into()),
+            role: Some(role.into()),
+            fs: Some("bcachefs".into()),
+            start: Some(start.into()),
+            size: Some(size.into()),
+            mountpoint: Some("/".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bcachefs_auto_root_formats_and_mounts_by_uuid() {
+        let mut state = uefi_auto_state();
+        state.swap_mode_index = SwapMode::None as usize;
+        state.disks_root_fs_index = 2; // bcachefs
+        let plan = StoragePlanner::compile(&state).expect("bcachefs plan should compile");
+
+        let parts = partition_cmds_joined(&plan.partition_commands());
+        assert!(parts.contains("mkpart root 1025MiB 100%"), "{parts}");
+        assert!(!parts.contains("mkfs.ext4"), "{parts}");
+
+        let setup = partition_cmds_joined(&plan.stack_setup_commands());
+        assert!(setup.contains("bcachefs format -f /dev/sda2"), "{setup}");
+        assert!(!setup.contains("unlock"), "{setup}");
+
+        let mounts = plan.mount_commands();
+        assert!(
+            mounts.contains(
+                &"mkdir -p /mnt && bcachefs mount UUID=$(blkid -s UUID -o value /dev/sda2) /mnt"
+                    .to_string()
+            ),
+            "{mounts:?}"
+        );
+
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(fstab.contains("awk '$3 != \"bcachefs\"'"), "{fstab}");
+        assert!(
+            fstab.contains("\"UUID=$(blkid -s UUID -o value /dev/sda2)\" / defaults"),
+            "{fstab}"
+        );
+        assert_eq!(plan.root_fstype(), Some("bcachefs"));
+    }
+
+    #[test]
+    fn test_bcachefs_auto_encryption_is_native() {
+        let mut state = uefi_auto_state();
+        state.disks_root_fs_index = 2;
+        state.disk_encryption_type_index = 1;
+        state.disk_encryption_password = "hunter2".into();
+        let plan = StoragePlanner::compile(&state).expect("bcachefs plan should compile");
+
+        assert!(!plan.has_encryption(), "no LUKS under bcachefs");
+        let parts = partition_cmds_joined(&plan.partition_commands());
+        assert!(!parts.contains("cryptsetup"), "{parts}");
+
+        let setup = plan.stack_setup_commands();
+        let kinds: Vec<&str> = setup.iter().map(|c| c.kind()).collect();
+        assert!(kinds.contains(&"bcachefs_format"), "{kinds:?}");
+        assert!(kinds.contains(&"bcachefs_unlock"), "{kinds:?}");
+        let joined = partition_cmds_joined(&setup);
+        assert!(!joined.contains("hunter2"), "{joined}");
+        assert!(plan.mounts[0].source.ends_with("3"), "{:?}", plan.mounts[0]);
+    }
+
+    #[test]
+    fn test_bcachefs_manual_members_share_one_filesystem() {
+        let mut state = make_manual_state();
+        state
+            .disks_partitions
+            .push(crate::core::types::DiskPartitionSpec {
+                name: Some("/dev/sda".into()),
+                role: Some("BOOT".into()),
+                fs: Some("fat32".into()),
+                start: Some("1048576".into()),
+                size: Some("1073741824".into()),
+                mountpoint: Some("/boot".into()),
+                ..Default::default()
+            });
+        state
+            .disks_partitions
+            .push(bcachefs_part("ROOT", "1074790400", "10737418240"));
+        state
+            .disks_partitions
+            .push(bcachefs_part("OTHER", "11812208640", "100%"));
+
+        let plan = StoragePlanner::compile(&state).expect("multi-device should compile");
+        assert_eq!(plan.bcachefs.len(), 1);
+        assert_eq!(plan.bcachefs[0].members, vec!["/dev/sda2", "/dev/sda3"]);
+        let root = plan.mounts.iter().find(|m| m.target == "/mnt").unwrap();
+        assert_eq!(root.source, "/dev/sda2:/dev/sda3");
+        assert_eq!(plan.mounts.iter().filter(|m| m.target == "/mnt").count(), 1);
+
+        let setup = partition_cmds_joined(&plan.stack_setup_commands());
+        assert!(
+            setup.contains("bcachefs format -f /dev/sda2 /dev/sda3"),
+            "{setup}"
+        );
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(
+            fstab.contains("blkid /dev/sda3 | grep -q 'TYPE=\"bcachefs\"'"),
+            "{fstab}"
+        );
+    }
+
+    // ── Swap mode tests ──
+
+    fn swapfile_state(preset: usize) -> crate::core::state::AppState {
+        let mut state = uefi_auto_state();
+        state.btrfs_subvolume_preset = preset;
+        state.swap_mode_index = SwapMode::Swapfile as usize;
+        state.swapfile_size = "8G".into();
+        state
+    }
+
+    #[test]
+    fn test_swapfile_btrfs_uses_swap_subvolume() {
+        let plan = StoragePlanner::compile(&swapfile_state(1)).expect("swapfile should compile");
+        let parts = &plan.devices[0].partitions;
+        assert!(parts.iter().all(|p| p.role != PartitionRole::Swap));
+        let root = parts
+            .iter()
+            .find(|p| p.role == PartitionRole::Root)
+            .unwrap();
+        assert_eq!(root.start, "1025MiB");
+        assert!(root.subvolumes.iter().any(|sv| sv.name == "@swap"));
+
+        let swap_mount = plan
+            .mounts
+            .iter()
+            .find(|m| m.target == "/mnt/swap")
+            .expect("@swap mounted at /mnt/swap");
+        assert_eq!(swap_mount.subvolume.as_deref(), Some("@swap"));
+        assert!(!plan.mounts.iter().any(|m| m.is_swap));
+
+        let mount = plan.mount_commands().join("\n");
+        assert!(
+            mount.contains("btrfs filesystem mkswapfile --size 8G --uuid clear /mnt/swap/swapfile"),
+            "{mount}"
+        );
+        assert!(mount.contains("swapon /mnt/swap/swapfile"), "{mount}");
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(fstab.contains("grep -qx /mnt/swap/swapfile"), "{fstab}");
+        assert!(
+            fstab.contains("grep -q '^/swap/swapfile[[:space:]]' /mnt/etc/fstab"),
+            "{fstab}"
+        );
+    }
+
+    #[test]
+    fn test_swapfile_flat_btrfs_keeps_top_level_root() {
+        let plan = StoragePlanner::compile(&swapfile_state(0)).expect("swapfile should compile");
+        let root = plan.mounts.iter().find(|m| m.target == "/mnt").unwrap();
+        assert!(root.subvolume.is_none());
+        assert_eq!(plan.mounts[0].target, "/mnt");
+        assert!(
+            plan.mounts
+                .iter()
+                .any(|m| m.target == "/mnt/swap" && m.subvolume.as_deref() == Some("@swap"))
+        );
+        let part = partition_cmds_joined(&plan.partition_commands());
+        assert!(part.contains("btrfs subvolume create /mnt/@swap"), "{part}");
+    }
+
+    #[test]
+    fn test_swapfile_manual_ext4_root() {
+        let mut state = make_manual_state();
+        state.swap_mode_index = SwapMode::Swapfile as usize;
+        state
+            .disks_partitions
+            .push(crate::core::types::DiskPartitionSpec {
+                name: Some("/dev/sda".into()),
+                role: Some("BOOT".into()),
+                fs: Some("fat32".into()),
+                start: Some("1048576".into()),
+                size: Some("1073741824".into()),
+                mountpoint: Some("/boot".into()),
+                ..Default::default()
+            });
+        state
+            .disks_partitions
+            .push(crate::core::types::DiskPartitionSpec {
+                name: Some("/dev/sda".into()),
+                role: Some("ROOT".into()),
+                fs: Some("ext4".into()),
+                start: Some("1074790400".into()),
+                size: Some("100%".into()),
+                mountpoint: Some("/".into()),
+                ..Default::default()
+            });
+
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        let swapfile = plan.swapfile.as_ref().expect("swapfile planned");
+        assert_eq!(swapfile.path, "/swapfile");
+        assert!(!swapfile.btrfs);
+        let mount = plan.mount_commands().join("\n");
+        assert!(
+            mount.contains("mkswap -U clear --size 4G --file /mnt/swapfile"),
+            "{mount}"
+        );
+    }
+
+    #[test]
+    fn test_swapfile_rejected_on_zfs_and_bcachefs_roots() {
+        for fs in [RootFilesystem::Zfs, RootFilesystem::Bcachefs] {
+            let mut state = swapfile_state(0);
+            state.disks_root_fs_index = fs as usize;
+            let errors = StoragePlanner::compile(&state).expect_err("swapfile unsupported");
+            assert!(
+                errors[0].message.starts_with("Swapfile is not supported"),
+                "{}",
+                errors[0].message
+            );
+        }
+    }
+
+    #[test]
+    fn test_zram_mode_plans_no_disk_swap() {
+        let mut state = swapfile_state(1);
+        state.swap_mode_index = SwapMode::Zram as usize;
+        let plan = StoragePlanner::compile(&state).expect("zram should compile");
+        assert!(plan.swapfile.is_none());
+        assert!(!plan.mounts.iter().any(|m| m.is_swap));
+        let root = plan.devices[0]
+            .partitions
+            .iter()
+            .find(|p| p.role == PartitionRole::Root)
+            .unwrap();
+        assert!(root.subvolumes.iter().all(|sv| sv.name != "@swap"));
+        assert!(!plan.mount_commands().join("\n").contains("swapon"));
+    }
+
+    // ── LUKS token enrollment tests ──
+
+    fn enrolled_luks_state() -> crate::core::state::AppState {
+        let mut state = uefi_auto_state();
+        state.disk_encryption_type_index = 1;
+        state.disk_encryption_password = "secret".into();
+        state.disk_encryption_password_confirm = "secret".into();
+        state.disk_encryption_tpm2 = true;
+        state.disk_encryption_tpm2_pcrs = "0+7".into();
+        state.disk_encryption_recovery_key = true;
+        state
+    }
+
+    #[test]
+    fn test_luks_enrollment_follows_luks_format() {
+        let plan = StoragePlanner::compile(&enrolled_luks_state()).expect("should compile");
+        assert!(plan.has_luks_tokens());
+        let cmds = plan.partition_commands();
+        let kinds: Vec<&str> = cmds.iter().map(|c| c.kind()).collect();
+        let format = kinds
+            .iter()
+            .position(|k| *k == "cryptsetup_luks_format")
+            .unwrap();
+        assert_eq!(
+            kinds[format + 1..format + 3],
+            ["cryptenroll", "cryptenroll"]
+        );
+        assert_eq!(
+            cmds[format + 1].for_log(),
+            "systemd-cryptenroll --tpm2-device=auto --tpm2-pcrs=0+7 '/dev/sda3'"
+        );
+        assert_eq!(
+            cmds[format + 2].for_log(),
+            "systemd-cryptenroll --recovery-key '/dev/sda3' > '/root/luks-recovery-cryptroot.txt'"
+        );
+        let joined = partition_cmds_joined(&cmds);
+        assert!(!joined.contains("secret"), "{joined}");
+
+        // The live copy is lost at reboot; mounting keeps one in the target
+        let mounts = plan.mount_commands();
+        let root = mounts.iter().position(|c| c.ends_with(" /mnt")).unwrap();
+        let copy = mounts
+            .iter()
+            .position(|c| {
+                c == "install -Dm600 /root/luks-recovery-cryptroot.txt \
+                      /mnt/var/log/archinstall-rs/luks-recovery-cryptroot.txt"
+            })
+            .expect("recovery key copied into the target");
+        assert!(root < copy, "{mounts:?}");
+    }
+
+    #[test]
+    fn test_luks_enrollment_rejects_bad_pcrs_and_zfs_root() {
+        let mut state = enrolled_luks_state();
+        state.disk_encryption_tpm2_pcrs = "7+99".into();
+        let errors = StoragePlanner::compile(&state).expect_err("invalid PCRs");
+        assert!(errors[0].message.starts_with("TPM2 PCRs"), "{errors:?}");
+        assert!(is_valid_tpm2_pcrs("7"));
+        assert!(is_valid_tpm2_pcrs("secure-boot-policy+14"));
+        assert!(!is_valid_tpm2_pcrs("0+"));
+
+        let mut state = enrolled_luks_state();
+        state.disks_root_fs_index = RootFilesystem::Zfs as usize;
+        let errors = StoragePlanner::compile(&state).expect_err("zfs needs udev hooks");
+        assert!(errors[0].message.starts_with("TPM2/FIDO2"), "{errors:?}");
+
+        // a recovery key alone works with any initramfs
+        state.disk_encryption_tpm2 = false;
+        let plan = StoragePlanner::compile(&state).expect("recovery key only");
+        assert!(!plan.has_luks_tokens());
+    }
+
+    #[test]
+    fn test_luks_format_options_reach_luks_format() {
+        let mut state = enrolled_luks_state();
+        state.disk_encryption_tpm2 = false;
+        state.disk_encryption_recovery_key = false;
+        state.luks_pbkdf_index = 1; // argon2id
+        state.luks_pbkdf_memory = "262144".into();
+        state.luks_sector_size_index = 4; // 4096
+        state.luks_label = "Arch Root".into();
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        let format = plan
+            .partition_commands()
+            .into_iter()
+            .find(|c| c.kind() == "cryptsetup_luks_format")
+            .unwrap();
+        assert_eq!(
+            format.for_log(),
+            "cryptsetup luksFormat --type luks2 -q --pbkdf=argon2id --pbkdf-memory=262144 \
+             --sector-size=4096 '--label=Arch Root' --key-file=- '/dev/sda3'"
+        );
+
+        state.disk_encryption_password.clear();
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        let joined = partition_cmds_joined(&plan.partition_commands());
+        assert!(
+            joined.contains("--sector-size=4096 '--label=Arch Root' /dev/sda3"),
+            "{joined}"
+        );
+    }
+
+    #[test]
+    fn test_luks_format_options_are_validated() {
+        let mut state = enrolled_luks_state();
+        state.luks_pbkdf_index = 3; // pbkdf2
+        state.luks_pbkdf_memory = "65536".into();
+        state.luks_key_size = "500".into();
+        let errors = StoragePlanner::compile(&state).expect_err("invalid LUKS options");
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert!(messages[0].starts_with("LUKS key_size:"), "{messages:?}");
+        assert!(
+            messages[1].starts_with("LUKS pbkdf_memory:"),
+            "{messages:?}"
+        );
+
+        state.luks_key_size = "lots".into();
+        let errors = StoragePlanner::compile(&state).expect_err("not a number");
+        assert_eq!(errors[0].message, "LUKS key_size: 'lots' is not a number");
+    }
+
+    // ── Swap encryption tests ──
+
+    fn encrypted_swap_state(mode: SwapEncryption) -> crate::core::state::AppState {
+        let mut state = enrolled_luks_state();
+        state.disk_encryption_tpm2 = false;
+        state.disk_encryption_recovery_key = false;
+        state.btrfs_subvolume_preset = 1; // Standard: root on @
+        state.swap_encryption_index = mode as usize;
+        state
+    }
+
+    #[test]
+    fn test_random_key_swap_is_rekeyed_every_boot() {
+        let plan = StoragePlanner::compile(&encrypted_swap_state(SwapEncryption::RandomKey))
+            .expect("should compile");
+        let joined = partition_cmds_joined(&plan.partition_commands());
+        assert!(
+            joined.contains(
+                "cryptsetup open --type plain --key-file=/dev/urandom --cipher=aes-xts-plain64 \
+                 --key-size=512 /dev/sda2 cryptswap\n"
+            ),
+            "{joined}"
+        );
+        assert!(joined.contains("mkswap /dev/mapper/cryptswap"), "{joined}");
+        assert!(
+            plan.mount_commands()
+                .contains(&"swapon /dev/mapper/cryptswap".to_string())
+        );
+        assert!(plan.resume_device().is_none(), "random key cannot resume");
+        assert!(!plan.needs_systemd_initramfs());
+
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(
+            fstab.contains("genfstab -U /mnt | awk '$3 != \"swap\"' >> /mnt/etc/fstab"),
+            "{fstab}"
+        );
+        assert!(
+            fstab.contains(
+                "\"cryptswap PARTUUID=$(blkid -s PARTUUID -o value /dev/sda2) /dev/urandom \
+                 swap,cipher=aes-xts-plain64,size=512\" >> /mnt/etc/crypttab"
+            ),
+            "{fstab}"
+        );
+        assert!(
+            fstab.contains("' /dev/mapper/cryptswap >> /mnt/etc/fstab"),
+            "{fstab}"
+        );
+
+        // Without disk encryption the swap stays plain
+        let mut state = encrypted_swap_state(SwapEncryption::RandomKey);
+        state.disk_encryption_type_index = 0;
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert!(!plan.has_encryption());
+        assert!(plan.encrypted_swaps().is_empty());
+    }
+
+    #[test]
+    fn test_keyfile_swap_unlocks_from_the_root() {
+        let plan = StoragePlanner::compile(&encrypted_swap_state(SwapEncryption::Keyfile))
+            .expect("should compile");
+        assert!(plan.needs_systemd_initramfs());
+        assert!(matches!(
+            plan.resume_device(),
+            Some(ResumeDevice::Swap(ref s)) if s == "/dev/mapper/cryptswap"
+        ));
+
+        let cmds = plan.partition_commands();
+        let joined = partition_cmds_joined(&cmds);
+        assert!(
+            joined.contains("(umask 077; head -c 4096 /dev/urandom > /tmp/cryptswap.key)"),
+            "{joined}"
+        );
+        assert!(
+            joined.contains(
+                "cryptsetup luksFormat --type luks2 -q --key-file=/tmp/cryptswap.key /dev/sda2"
+            ),
+            "{joined}"
+        );
+        assert!(
+            joined.contains(
+                "cryptsetup open --type luks --key-file=/tmp/cryptswap.key /dev/sda2 cryptswap"
+            ),
+            "{joined}"
+        );
+        // only the root is formatted with the passphrase
+        assert_eq!(
+            cmds.iter()
+                .filter(|c| c.kind() == "cryptsetup_luks_format")
+                .count(),
+            1
+        );
+
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(
+            fstab.contains(
+                "install -m 0400 /tmp/cryptswap.key /mnt/etc/cryptsetup-keys.d/cryptswap.key"
+            ),
+            "{fstab}"
+        );
+        assert!(
+            fstab.contains("/etc/cryptsetup-keys.d/cryptswap.key luks\" >> /mnt/etc/crypttab\n"),
+            "{fstab}"
+        );
+        assert!(
+            fstab.contains(
+                "/@/etc/cryptsetup-keys.d/cryptswap.key:UUID=$(blkid -s UUID -o value \
+                 /dev/mapper/cryptroot) luks\" >> /mnt/etc/crypttab.initramfs"
+            ),
+            "{fstab}"
+        );
+        assert!(
+            fstab.contains("genfstab -U /mnt >> /mnt/etc/fstab"),
+            "{fstab}"
+        );
+    }
+
+    #[test]
+    fn test_keyfile_swap_needs_a_luks_root() {
+        let mut state = encrypted_swap_state(SwapEncryption::Keyfile);
+        state.disks_root_fs_index = RootFilesystem::Zfs as usize;
+        let errors = StoragePlanner::compile(&state).expect_err("zfs needs udev hooks");
+        assert!(errors[0].message.starts_with("Swap key file"), "{errors:?}");
+
+        state.disks_root_fs_index = RootFilesystem::Bcachefs as usize;
+        let errors = StoragePlanner::compile(&state).expect_err("no LUKS under bcachefs");
+        assert!(
+            errors[0].message.contains("encrypted bcachefs"),
+            "{errors:?}"
+        );
+
+        state.swap_encryption_index = SwapEncryption::RandomKey as usize;
+        let plan = StoragePlanner::compile(&state).expect("random key works anywhere");
+        assert_eq!(plan.encrypted_swaps().len(), 1);
+    }
+
+    fn lvm_state(encrypted: bool) -> crate::core::state::AppState {
+        let mut state = uefi_auto_state();
+        state.toggle_lvm();
+        state
+            .lvm_volumes
+            .push(crate::core::types::LogicalVolumeSpec {
+                name: "swap".into(),
+                size: "4G".into(),
+                fs: "linux-swap".into(),
+                mountpoint: None,
+            });
+        if encrypted {
+            state.disk_encryption_type_index = 1;
+            state.disk_encryption_password = "secret".into();
+            state.disk_encryption_password_confirm = "secret".into();
+        }
+        state
+    }
+
+    #[test]
+    fn test_lvm_on_luks_opens_the_volume_group_once() {
+        let plan = StoragePlanner::compile(&lvm_state(true)).expect("should compile");
+        let pv = plan.devices[0]
+            .partitions
+            .iter()
+            .find(|p| p.role == PartitionRole::Lvm)
+            .unwrap();
+        assert_eq!(pv.filesystem.fstype, "lvm");
+        assert!(pv.encryption.is_none());
+        assert!(plan.has_lvm());
+        assert!(!plan.has_luks_on_lvm());
+        assert_eq!(plan.root_lvm_luks().unwrap().mapper_name, "cryptlvm");
+
+        let cmds: Vec<String> = plan
+            .stack_setup_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        let joined = cmds.join("\n");
+        assert_eq!(joined.matches("luksFormat").count(), 1, "{joined}");
+        assert_eq!(joined.matches("vgcreate vg0").count(), 1, "{joined}");
+        assert!(joined.contains("pvcreate /dev/mapper/cryptlvm"), "{joined}");
+        // fixed sizes first so 100%FREE takes what is left
+        let swap = joined.find("-n swap").unwrap();
+        let home = joined.find("-n home").unwrap();
+        assert!(swap < home, "{joined}");
+        assert!(joined.contains("mkfs.ext4 -F /dev/vg0/root"), "{joined}");
+
+        assert_eq!(plan.mounts[0].source, "/dev/vg0/root");
+        assert_eq!(plan.mounts[0].target, "/mnt");
+        assert!(
+            plan.mounts
+                .iter()
+                .any(|m| m.source == "/dev/vg0/swap" && m.is_swap)
+        );
+        assert_eq!(plan.luks_volumes().len(), 1);
+
+        let teardown: Vec<String> = plan
+            .teardown_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        let vg = teardown.iter().position(|c| c.contains("vgchange -an vg0"));
+        let close = teardown
+            .iter()
+            .position(|c| c.contains("cryptsetup close cryptlvm"));
+        assert!(vg.unwrap() < close.unwrap(), "{teardown:?}");
+    }
+
+    #[test]
+    fn test_luks_on_lvm_encrypts_each_volume() {
+        let mut state = lvm_state(true);
+        state.lvm_stacking_index = LvmStacking::LuksOnLvm as usize;
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert!(plan.has_luks_on_lvm());
+        assert!(plan.root_lvm_luks().is_none());
+        let mappers: Vec<&str> = plan
+            .luks_volumes()
+            .iter()
+            .map(|(_, enc)| enc.mapper_name.as_str())
+            .collect();
+        assert!(mappers.contains(&"cryptroot"), "{mappers:?}");
+        assert!(mappers.contains(&"crypthome"), "{mappers:?}");
+        assert_eq!(plan.mounts[0].source, "/dev/mapper/cryptroot");
+
+        let checks = plan.fstab_check_commands().join("\n");
+        assert!(checks.contains("crypthome UUID=$(blkid"), "{checks}");
+        assert!(!checks.contains("cryptroot UUID"), "{checks}");
+    }
+
+    #[test]
+    fn test_manual_lvm_physical_volume() {
+        let mut state = make_manual_state();
+        state.firmware_uefi_override = Some(true);
+        state.toggle_lvm();
+        state
+            .disks_partitions
+            .push(crate::core::types::DiskPartitionSpec {
+                name: Some("/dev/sda".into()),
+                role: Some("BOOT".into()),
+                fs: Some("fat32".into()),
+                start: Some("1048576".into()),
+                size: Some("1073741824".into()),
+                mountpoint: Some("/boot".into()),
+                ..Default::default()
+            });
+        state
+            .disks_partitions
+            .push(crate::core::types::DiskPartitionSpec {
+                name: Some("/dev/sda".into()),
+                role: Some("OTHER".into()),
+                fs: Some("ext4".into()),
+                start: Some("1074790400".into()),
+                size: Some("100%".into()),
+                mountpoint: Some("/data".into()),
+                ..Default::default()
+            });
+        let errors = StoragePlanner::compile(&state).expect_err("no physical volume");
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.message.starts_with("LVM needs a partition")),
+            "{errors:?}"
+        );
+
+        state.cycle_lvm_physical_volume(true);
+        assert_eq!(state.lvm_physical_volume(), Some(1));
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert!(plan.stacks.iter().all(|s| s.base == "/dev/sda2"));
+        assert!(plan.mounts.iter().any(|m| m.target == "/mnt/home"));
+
+        state.lvm_enabled = false;
+        let errors = StoragePlanner::compile(&state).expect_err("LVM role without LVM");
+        assert!(errors[0].message.contains("role LVM"), "{errors:?}");
+    }
+
+    #[test]
+    fn test_lvm_validation() {
+        let mut state = lvm_state(false);
+        state.lvm_vg_name = "bad name".into();
+        state.lvm_volumes[1].size = "lots".into();
+        state.lvm_volumes[2].name = "home".into();
+        let errors = StoragePlanner::compile(&state).expect_err("invalid LVM");
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert!(
+            messages[0].starts_with("LVM volume group name"),
+            "{messages:?}"
+        );
+        assert!(messages.iter().any(|m| m.contains("duplicate name")));
+        assert!(messages.iter().any(|m| m.contains("size 'lots'")));
+        // Errors carry the config field they are about
+        let fields: Vec<Option<&str>> = errors.iter().map(|e| e.field.as_deref()).collect();
+        assert_eq!(
+            fields,
+            [
+                Some("disks.lvm.volume_group"),
+                Some("disks.lvm.volumes[1].size"),
+                Some("disks.lvm.volumes[2].name"),
+            ],
+            "{errors:?}"
+        );
+
+        let mut state = lvm_state(false);
+        state.lvm_volumes.remove(0);
+        let errors = StoragePlanner::compile(&state).expect_err("no root volume");
+        assert!(errors[0].message.contains("mounted at /"), "{errors:?}");
+
+        assert!(is_valid_lvm_size("100%FREE"));
+        assert!(is_valid_lvm_size("20%VG"));
+        assert!(!is_valid_lvm_size("0%FREE"));
+        assert!(!is_valid_lvm_size("101%FREE"));
+        assert!(is_valid_lvm_name("lv_root-1"));
+        assert!(!is_valid_lvm_name("-root"));
+    }
+
+    fn raid_state(disks: &[&str]) -> crate::core::state::AppState {
+        let mut state = uefi_auto_state();
+        state.disks_selected_device = Some(disks[0].into());
+        state.toggle_raid();
+        state.raid_devices = disks.iter().map(|d| d.to_string()).collect();
+        state
+    }
+
+    #[test]
+    fn test_raid1_partitions_every_member_alike() {
+        let plan = StoragePlanner::compile(&raid_state(&["/dev/sda", "/dev/sdb"]))
+            .expect("should compile");
+        assert_eq!(plan.devices.len(), 2);
+        for device in &plan.devices {
+            let layout: Vec<_> = device
+                .partitions
+                .iter()
+                .map(|p| (p.number, p.role.clone(), p.start.as_str(), p.end.as_str()))
+                .collect();
+            assert_eq!(
+                layout,
+                vec![
+                    (1, PartitionRole::Esp, "1MiB", "1025MiB"),
+                    (2, PartitionRole::Raid, "1025MiB", "5121MiB"),
+                    (3, PartitionRole::Raid, "5121MiB", "100%"),
+                ]
+            );
+        }
+        assert!(plan.has_raid());
+        assert_eq!(plan.mirror_disks(), vec!["/dev/sdb"]);
+        assert_eq!(plan.esp_mirrors(), vec!["/boot2".to_string()]);
+        assert_eq!(plan.root_device_path().as_deref(), Some("/dev/md/root"));
+
+        let part = plan
+            .partition_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(part.contains("wipefs -a /dev/sdb3"), "{part}");
+        assert!(part.contains("mkfs.fat -F 32 /dev/sdb1"), "{part}");
+        assert!(!part.contains("mkfs.ext4"), "{part}");
+
+        let cmds: Vec<String> = plan
+            .stack_setup_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        assert_eq!(
+            cmds,
+            vec![
+                "mdadm --create /dev/md/root --run --level=1 --raid-devices=2 /dev/sda3 /dev/sdb3",
+                "mkfs.btrfs -f /dev/md/root",
+                "mdadm --create /dev/md/swap --run --level=1 --raid-devices=2 /dev/sda2 /dev/sdb2",
+                "mkswap /dev/md/swap",
+            ]
+        );
+        let mounts = plan.mount_commands().join("\n");
+        assert!(
+            mounts.contains("mount -t vfat --mkdir /dev/sdb1 /mnt/boot2"),
+            "{mounts}"
+        );
+        assert!(mounts.contains("swapon /dev/md/swap"), "{mounts}");
+        let fstab = plan.fstab_check_commands().join("\n");
+        assert!(fstab.contains("\\s+/boot2\\s+vfat"), "{fstab}");
+        let teardown: Vec<String> = plan
+            .teardown_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        assert!(teardown.contains(&"mdadm --stop /dev/md/root 2>/dev/null || true".to_string()));
+    }
+
+    #[test]
+    fn test_raid_with_luks_and_lvm() {
+        let mut state = raid_state(&["/dev/sda", "/dev/sdb"]);
+        state.swap_mode_index = 2;
+        state.disk_encryption_type_index = 1;
+        state.disk_encryption_password = "secret".into();
+        state.disk_encryption_password_confirm = "secret".into();
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert_eq!(plan.devices[1].partitions.len(), 2);
+        assert_eq!(
+            plan.root_device_path().as_deref(),
+            Some("/dev/mapper/cryptroot")
+        );
+        let cmds: Vec<String> = plan
+            .stack_setup_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        assert!(cmds[0].starts_with("mdadm --create /dev/md/root"));
+        assert!(
+            cmds.iter()
+                .any(|c| c.contains("'/dev/md/root' 'cryptroot'")),
+            "{cmds:?}"
+        );
+
+        state.toggle_lvm();
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert!(plan.has_lvm());
+        assert_eq!(plan.root_lvm_luks().unwrap().mapper_name, "cryptlvm");
+        let cmds: Vec<String> = plan
+            .stack_setup_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        assert_eq!(
+            cmds.iter()
+                .filter(|c| c.starts_with("mdadm --create"))
+                .count(),
+            1,
+            "{cmds:?}"
+        );
+        assert!(cmds.contains(&"pvcreate /dev/mapper/cryptlvm".to_string()));
+    }
+
+    #[test]
+    fn test_raid_bios_uses_bios_boot_on_every_disk() {
+        let mut state = raid_state(&["/dev/sda", "/dev/sdb", "/dev/sdc"]);
+        state.firmware_uefi_override = Some(false);
+        state.bootloader_index = 1;
+        state.raid_level_index = RaidLevel::Raid5 as usize;
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert!(plan.devices.iter().all(|d| {
+            d.partitions[0].role == PartitionRole::BiosBoot && d.partitions.len() == 3
+        }));
+        assert_eq!(plan.mirror_disks(), vec!["/dev/sdb", "/dev/sdc"]);
+        assert!(plan.esp_mirrors().is_empty());
+        let cmds: Vec<String> = plan
+            .stack_setup_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect();
+        assert!(cmds[0].contains("--level=5 --raid-devices=3"), "{cmds:?}");
+    }
+
+    #[test]
+    fn test_raid_validation() {
+        let state = raid_state(&["/dev/sda"]);
+        let errors = StoragePlanner::compile(&state).expect_err("one disk");
+        assert_eq!(
+            errors[0].message,
+            "RAID1 needs at least 2 disks (1 selected)"
+        );
+
+        let mut state = raid_state(&["/dev/sda", "/dev/sdb"]);
+        state.raid_level_index = RaidLevel::Raid6 as usize;
+        state.bootloader_index = 1;
+        state.disks_root_fs_index = RootFilesystem::Zfs as usize;
+        let messages: Vec<String> = StoragePlanner::compile(&state)
+            .expect_err("invalid RAID")
+            .into_iter()
+            .map(|e| e.message)
+            .collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].starts_with("RAID6 needs at least 4 disks"));
+        assert!(messages[1].contains("not GRUB"));
+        assert!(messages[2].contains("btrfs root or LVM"));
+
+        let mut state = raid_state(&["/dev/sda", "/dev/sdb"]);
+        state.disk_encryption_type_index = 1;
+        state.disk_encryption_password = "secret".into();
+        state.swap_encryption_index = 1;
+        let errors = StoragePlanner::compile(&state).expect_err("encrypted swap array");
+        assert!(
+            errors[0].message.starts_with("RAID swap array"),
+            "{errors:?}"
+        );
+    }
+
+    /// `parted -m unit MiB print free` of a disk with Windows on it and 40 GiB left free.
+    fn windows_disk(esp_mib: u64) -> String {
+        let esp_end = 1 + esp_mib;
+        format!(
+            "BYT;\n\
+             /dev/sda:102400MiB:scsi:512:512:gpt:ATA VBOX HARDDISK:;\n\
+             1:0.02MiB:1.00MiB:0.98MiB:free;\n\
+             1:1.00MiB:{esp_end}MiB:{esp_mib}MiB:fat32:EFI system partition:boot, esp;\n\
+             2:{esp_end}MiB:{msr_end}MiB:16.0MiB::Microsoft reserved partition:msftres;\n\
+             3:{msr_end}MiB:61000MiB:60000MiB:ntfs:Basic data partition:msftdata;\n\
+             4:61000MiB:61600MiB:600MiB:ntfs::hidden, diag;\n\
+             1:61600MiB:102399.98MiB:40799.98MiB:free;\n",
+            msr_end = esp_end + 16
+        )
+    }
+
+    fn alongside_state(esp_mib: u64) -> crate::core::state::AppState {
+        let mut state = uefi_auto_state();
+        state.alongside_enabled = true;
+        state.alongside_layout_override = Some(DiskLayout::parse_parted(&windows_disk(esp_mib)));
+        state
+    }
+
+    #[test]
+    fn test_parse_parted_free_space() {
+        let layout = DiskLayout::parse_parted(&windows_disk(100));
+        assert_eq!(layout.label, Some(DiskLabel::Gpt));
+        assert_eq!(layout.partitions.len(), 4);
+        assert_eq!(layout.partitions[1].fstype, "");
+        let esp = layout.esp().unwrap();
+        assert_eq!((esp.number, esp.size_mib()), (1, 100));
+        assert_eq!(layout.free, vec![(61600, 102399)]);
+        assert_eq!(layout.largest_free(), Some((61600, 102399)));
+        assert_eq!(
+            layout.free_numbers().take(2).collect::<Vec<_>>(),
+            vec![5, 6]
+        );
+
+        let blank =
+            DiskLayout::parse_parted("BYT;\n/dev/sdb:8192MiB:scsi:512:512:unknown:Disk:;\n");
+        assert_eq!(blank.label, None);
+    }
+
+    #[test]
+    fn test_alongside_installs_into_free_space_and_reuses_esp() {
+        let plan = StoragePlanner::compile(&alongside_state(600)).expect("should compile");
+        let device = &plan.devices[0];
+        assert!(device.keep_table);
+        assert!(!device.wipe);
+        let layout: Vec<_> = device
+            .partitions
+            .iter()
+            .map(|p| (p.number, p.role.clone(), p.start.as_str(), p.end.as_str()))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                (5, PartitionRole::Swap, "61600MiB", "65696MiB"),
+                (6, PartitionRole::Root, "65696MiB", "102399MiB"),
+            ]
+        );
+        assert_eq!(plan.reused_esp().unwrap().source, "/dev/sda1");
+        assert_eq!(plan.esp_chroot_mountpoint(), "/boot");
+
+        let cmds = plan
+            .partition_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(!cmds.contains("mklabel"), "{cmds}");
+        assert!(!cmds.contains("wipefs"), "{cmds}");
+        assert!(!cmds.contains("/dev/sda1"), "{cmds}");
+        assert!(
+            cmds.contains("parted -s /dev/sda mkpart root btrfs 65696MiB 102399MiB"),
+            "{cmds}"
+        );
+        assert!(cmds.contains("mkfs.btrfs -f /dev/sda6"), "{cmds}");
+        let mounts = plan.mount_commands().join("\n");
+        assert!(mounts.contains("--mkdir /dev/sda1 /mnt/boot"), "{mounts}");
+    }
+
+    #[test]
+    fn test_alongside_small_esp() {
+        let errors = StoragePlanner::compile(&alongside_state(100)).expect_err("ESP too small");
+        assert!(
+            errors[0].message.contains("/dev/sda1 has 100 MiB"),
+            "{errors:?}"
+        );
+
+        // GRUB keeps the kernels on the root and only puts its loader on the ESP
+        let mut state = alongside_state(100);
+        state.bootloader_index = 1;
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert_eq!(plan.esp_chroot_mountpoint(), "/efi");
+        assert!(plan.validate().is_empty());
+
+        state.swap_mode_index = 3;
+        state.firmware_uefi_override = Some(false);
+        let errors = StoragePlanner::compile(&state).expect_err("GPT on BIOS");
+        assert!(errors[0].message.contains("msdos"), "{errors:?}");
+    }
+
+    #[test]
+    fn test_alongside_needs_free_space() {
+        let mut state = alongside_state(600);
+        state.alongside_layout_override = Some(DiskLayout::parse_parted(
+            "BYT;\n/dev/sda:102400MiB:scsi:512:512:msdos:Disk:;\n\
+             1:1.00MiB:100000MiB:99999MiB:ntfs::boot;\n\
+             1:100000MiB:102400MiB:2400MiB:free;\n",
+        ));
+        let errors = StoragePlanner::compile(&state).expect_err("too little free space");
+        assert_eq!(
+            errors[0].message,
+            "Install alongside: the largest free region on /dev/sda has 2400 MiB; at least 20480 MiB is needed"
+        );
+
+        // BIOS with msdos: primary partitions after the existing one, no BIOS boot partition
+        state.firmware_uefi_override = Some(false);
+        state.bootloader_index = 1;
+        state.alongside_layout_override = Some(DiskLayout::parse_parted(
+            "BYT;\n/dev/sda:102400MiB:scsi:512:512:msdos:Disk:;\n\
+             1:1.00MiB:50000MiB:49999MiB:ntfs::boot;\n\
+             1:50000MiB:102400MiB:52400MiB:free;\n",
+        ));
+        let plan = StoragePlanner::compile(&state).expect("should compile");
+        assert_eq!(plan.devices[0].label, DiskLabel::Msdos);
+        let numbers: Vec<u32> = plan.devices[0]
+            .partitions
+            .iter()
+            .map(|p| p.number)
+            .collect();
+        assert_eq!(numbers, vec![2, 3]);
+        let cmds = plan
+            .partition_commands()
+            .iter()
+            .map(|c| c.for_log())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(
+            cmds.contains("parted -s /dev/sda mkpart primary linux-swap 50000MiB 54096MiB"),
+            "{cmds}"
+        );
+    }
 }
@@ -93,7 +93,30 @@ pub enum PopupKind {
     // New: AUR helper selection
     AurHelperSelect,
     BtrfsSubvolumePreset,
+    RootFilesystemSelect,
+    SwapModeSelect,
+    // Guided LVM: volume group name and the add/edit logical volume flow
+    LvmVolumeGroupName,
+    LvmVolumeName,
+    LvmVolumeSize,
+    LvmVolumeFilesystem,
+    LvmVolumeMountpoint,
+    // Software RAID member disks (space toggles)
+    RaidMemberDisks,
+    SwapfileSizeInput,
+    ZramSizeInput,
+    DiskEncryptionTpm2Pcrs,
+    DiskEncryptionLuksParam,
+    KernelParametersInput,
     ConfigLoadSelect,
+    // Resume a previously failed install from its checkpoint
+    ResumeInstall,
+    ResumeSectionSelect,
+    // A command failed; the install thread waits for retry/edit/skip/shell/abort
+    InstallFailureRecovery,
+    InstallFailureEdit,
+    // Confirm stopping a running install and releasing the target
+    InstallCancelConfirm,
 }
 
 #[derive(Clone)]
@@ -168,6 +191,15 @@ pub struct DiskPartitionSpec {
     pub encrypt: Option<bool>,
 }
 
+/// A logical volume of the guided LVM layout (LVM screen).
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct LogicalVolumeSpec {
+    pub name: String,
+    pub size: String, // lvcreate size: "40G", "50%FREE", "100%FREE", ...
+    pub fs: String,   // one of storage::LVM_FILESYSTEMS
+    pub mountpoint: Option<String>, // None for linux-swap
+}
+
 #[derive(Clone)]
 pub struct ManualPartitionRowMeta {
     pub kind: String,              // "existing" | "created" | "free"
@@ -0,0 +1,458 @@
+//! Headless CLI entry points: `--config <path> --unattended`, `plan <path>` and `validate <path>`.
+//!
+//! Unattended mode and `plan` load a saved TOML config and validate it the same way the TUI does
+//! before installing. Unattended mode then runs the install sections without ratatui and streams
+//! the live log to stdout; `plan` prints the sections and storage plan as JSON without executing
+//! anything. `validate` reports per-field diagnostics for CI.
+
+use std::path::Path;
+use std::sync::mpsc;
+
+use sha2::{Digest, Sha256};
+
+use crate::app::AppState;
+use crate::app::config::validate::Severity;
+use crate::app::install::InstallRunOptions;
+use crate::app::install::artifacts::InstallArtifacts;
+use crate::app::install::checkpoint;
+use crate::app::install::events::EventLog;
+use crate::app::install::executor::SystemExecutor;
+use crate::common::InstallLogMsg;
+use crate::core::storage::StoragePlan;
+use crate::core::storage::planner::StoragePlanner;
+
+/// Plaintext root password (configs only store a SHA-256 hash).
+pub const ENV_ROOT_PASSWORD: &str = "ARCHINSTALL_ROOT_PASSWORD";
+/// LUKS passphrase, required when the config enables disk encryption.
+pub const ENV_ENCRYPTION_PASSWORD: &str = "ARCHINSTALL_ENCRYPTION_PASSWORD";
+/// Per-user password prefix, e.g. `ARCHINSTALL_USER_PASSWORD_ALICE` for user `alice`.
+pub const ENV_USER_PASSWORD_PREFIX: &str = "ARCHINSTALL_USER_PASSWORD_";
+
+/// Exit code for config, secret or validation problems (nothing was executed).
+pub const EXIT_INVALID_CONFIG: i32 = 2;
+/// Exit code when a command of the install plan failed.
+pub const EXIT_INSTALL_FAILED: i32 = 1;
+
+fn sha256_hex(value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(value.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Environment variable holding the password of `username`.
+pub fn user_password_env_var(username: &str) -> String {
+    let suffix: String = username
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{ENV_USER_PASSWORD_PREFIX}{suffix}")
+}
+
+/// Fill the plaintext secrets that `load_config_*` never restores.
+///
+/// `lookup` resolves variable names (normally `std::env::var`). When the config carries a
+/// password hash, the provided plaintext must match it.
+pub fn apply_unattended_secrets<F>(app: &mut AppState, lookup: F) -> Result<(), String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut issues: Vec<String> = Vec::new();
+
+    match lookup(ENV_ROOT_PASSWORD).filter(|p| !p.is_empty()) {
+        Some(pw) => {
+            if let Some(hash) = app.root_password_hash.as_deref()
+                && !hash.is_empty()
+                && sha256_hex(&pw) != hash
+            {
+                issues.push(format!(
+                    "{ENV_ROOT_PASSWORD} does not match system.root_password_hash"
+                ));
+            }
+            app.root_password = pw.clone();
+            app.root_password_confirm = pw;
+        }
+        None => issues.push(format!("{ENV_ROOT_PASSWORD} is not set")),
+    }
+
+    if app.disk_encryption_type_index == 1 {
+        match lookup(ENV_ENCRYPTION_PASSWORD).filter(|p| !p.is_empty()) {
+            Some(pw) => {
+                app.disk_encryption_password = pw.clone();
+                app.disk_encryption_password_confirm = pw;
+            }
+            None => issues.push(format!(
+                "{ENV_ENCRYPTION_PASSWORD} is not set (disk encryption is LUKS)"
+            )),
+        }
+    }
+
+    for user in app.users.iter_mut() {
+        let var = user_password_env_var(&user.username);
+        match lookup(&var).filter(|p| !p.is_empty()) {
+            Some(pw) => {
+                if let Some(hash) = user.password_hash.as_deref()
+                    && sha256_hex(&pw) != hash
+                {
+                    issues.push(format!(
+                        "{var} does not match the password_hash of user '{}'",
+                        user.username
+                    ));
+                }
+                user.password = pw;
+            }
+            None => issues.push(format!("{var} is not set (user '{}')", user.username)),
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues.join("\n- "))
+    }
+}
+
+fn print_log_msg(msg: InstallLogMsg) {
+    match msg {
+        // Markers drive the TUI progress list and report; a readable line always accompanies them
+        InstallLogMsg::Line(line)
+            if line.starts_with("::section_start::")
+                || line.starts_with("::section_done::")
+                || line.starts_with("::warning::") => {}
+        InstallLogMsg::Line(line) | InstallLogMsg::ReplaceLastLine(line) => println!("{line}"),
+        // Only sent when a decision channel is attached, which unattended runs never do
+        InstallLogMsg::CommandFailed(_) => {}
+    }
+}
+
+/// Create an `AppState` for CLI use and load `config_path` with its disk layout kept.
+fn load_cli_state(config_path: &Path, dry_run: bool, debug_enabled: bool) -> Result<AppState, i32> {
+    let mut app = AppState::new(dry_run);
+    app.debug_enabled = debug_enabled;
+    app.detect_secure_boot_state();
+    app.debug_log(&format!(
+        "cli: config={} dry_run={dry_run}",
+        config_path.display()
+    ));
+    if let Err(e) = app.load_config_unattended(config_path) {
+        eprintln!("error: {}: {e}", config_path.display());
+        return Err(EXIT_INVALID_CONFIG);
+    }
+    app.apply_secure_boot_uki_policy();
+    Ok(app)
+}
+
+/// Install-screen validation plus storage compilation; problems are printed to stderr.
+fn validated_storage_plan(app: &AppState) -> Result<StoragePlan, i32> {
+    if let Some(msg) = app.validate_install_requirements() {
+        eprintln!("error: {msg}");
+        return Err(EXIT_INVALID_CONFIG);
+    }
+    StoragePlanner::compile(app).map_err(|errors| {
+        for e in &errors {
+            eprintln!("error: storage: {}", e.message);
+        }
+        EXIT_INVALID_CONFIG
+    })
+}
+
+/// Where an unattended run starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResumeMode {
+    /// Run every section (the default).
+    Fresh,
+    /// `--resume`: continue after the last section recorded in the host checkpoint.
+    Checkpoint,
+    /// `--resume-from <section>`: start at the named section.
+    Section(String),
+}
+
+/// Run a full install from `config_path` without the TUI and return the process exit code.
+pub fn run_unattended(
+    config_path: &Path,
+    dry_run: bool,
+    debug_enabled: bool,
+    resume: &ResumeMode,
+    event_log: Option<&Path>,
+) -> i32 {
+    let mut app = match load_cli_state(config_path, dry_run, debug_enabled) {
+        Ok(app) => app,
+        Err(code) => return code,
+    };
+    println!("Loaded configuration from {}", config_path.display());
+
+    if let Err(msg) = apply_unattended_secrets(&mut app, |k| std::env::var(k).ok()) {
+        eprintln!("error: missing or mismatched secrets:\n- {msg}");
+        return EXIT_INVALID_CONFIG;
+    }
+
+    let storage_plan = match validated_storage_plan(&app) {
+        Ok(plan) => plan,
+        Err(code) => return code,
+    };
+    println!(
+        "Storage plan: {} device(s), {} mount(s), {} stack(s)",
+        storage_plan.devices.len(),
+        storage_plan.mounts.len(),
+        storage_plan.stacks.len()
+    );
+
+    // Same prechecks as the TUI, but refuse instead of prompting
+    let target = app.install_target().unwrap_or_default();
+    // A resumed run already passed these checks and may have left the target mounted
+    if !target.is_empty() && !dry_run && *resume == ResumeMode::Fresh {
+        for disk in app.target_disks() {
+            if !app.disks_devices.iter().any(|d| d.path == disk) {
+                let key = if disk == target {
+                    "disks.selected_device"
+                } else {
+                    "disks.raid.devices"
+                };
+                eprintln!("error: {key} {disk} is not a disk on this system");
+                return EXIT_INVALID_CONFIG;
+            }
+            if app.disk_has_mounted_partitions(&disk) {
+                eprintln!(
+                    "error: device {disk} has mounted partitions. Unmount before proceeding."
+                );
+                return EXIT_INVALID_CONFIG;
+            }
+            if !app.disks_wipe && !app.alongside_selected() && app.disk_freespace_low(&disk) {
+                eprintln!(
+                    "error: device {disk} has little free space; set disks.wipe = true to wipe it"
+                );
+                return EXIT_INVALID_CONFIG;
+            }
+        }
+    }
+
+    let sections = app.build_install_sections(&target);
+    app.debug_log(&format!(
+        "cli: unattended target='{target}' sections={}",
+        sections.len()
+    ));
+
+    let checkpoint_path = checkpoint::host_checkpoint_path();
+    let previous =
+        checkpoint::InstallCheckpoint::load(&checkpoint_path).filter(|cp| cp.target == target);
+    let start = match resume {
+        ResumeMode::Fresh => 0,
+        ResumeMode::Checkpoint | ResumeMode::Section(_) => {
+            let requested = match resume {
+                ResumeMode::Section(name) => Some(name.as_str()),
+                _ => None,
+            };
+            match checkpoint::plan_resume(previous.as_ref(), &sections, requested) {
+                Ok(start) => start,
+                Err(e) => {
+                    eprintln!("error: resume: {e} ({})", checkpoint_path.display());
+                    return EXIT_INVALID_CONFIG;
+                }
+            }
+        }
+    };
+    if start > 0 {
+        if !dry_run
+            && checkpoint::start_is_past_storage(&sections, start)
+            && !checkpoint::is_mountpoint(Path::new("/mnt"))
+        {
+            eprintln!(
+                "error: resume: the target is no longer mounted at /mnt; resume from an earlier section to set up storage again"
+            );
+            return EXIT_INVALID_CONFIG;
+        }
+        println!(
+            "Resuming from '{}' ({start} section(s) already completed)",
+            sections[start].0
+        );
+        if let Some(cp) = &previous {
+            for title in cp.changed_sections(&sections[..start]) {
+                println!("WARN: '{title}' changed since it ran and will not be re-run");
+            }
+        }
+    }
+
+    if dry_run {
+        println!("Starting dry-run (no commands will be executed)...");
+        for (title, cmds) in sections.into_iter().skip(start) {
+            println!("=== {title} ===");
+            for cmd in cmds {
+                println!("[Dry-Run] $ {}", cmd.for_log());
+            }
+            println!();
+        }
+        println!("Dry-run completed.");
+        return 0;
+    }
+
+    let mut recorder = checkpoint::CheckpointRecorder::for_run(
+        previous.unwrap_or_else(|| checkpoint::InstallCheckpoint::new(&target)),
+        &sections,
+        start,
+    );
+    let mut events = match event_log {
+        Some(path) => match EventLog::create(path) {
+            Ok(log) => {
+                println!("Writing install events to {}", path.display());
+                log.with_machine(&app.hostname_value, &target)
+            }
+            Err(e) => {
+                eprintln!("error: could not open event log {}: {e}", path.display());
+                return EXIT_INVALID_CONFIG;
+            }
+        },
+        None => EventLog::disabled(),
+    };
+    let artifacts = match InstallArtifacts::collect(&app) {
+        Ok(artifacts) => Some(artifacts),
+        Err(e) => {
+            println!("WARN: the install record will not be saved: {e}");
+            None
+        }
+    };
+    let recovery_keys = crate::app::install::recovery_key_report_paths(&storage_plan);
+    let sections: Vec<_> = sections.into_iter().skip(start).collect();
+    let (tx, rx) = mpsc::channel::<InstallLogMsg>();
+    let worker = std::thread::spawn(move || {
+        crate::app::install::run_install_sections(
+            sections,
+            &tx,
+            debug_enabled,
+            &mut SystemExecutor {
+                debug_enabled,
+                cancel: None,
+            },
+            InstallRunOptions {
+                checkpoint: Some(&mut recorder),
+                events: Some(&mut events),
+                artifacts: artifacts.as_ref(),
+                recovery_keys,
+                ..InstallRunOptions::default()
+            },
+        )
+    });
+    for msg in rx {
+        print_log_msg(msg);
+    }
+    match worker.join() {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => {
+            eprintln!("error: installation failed: {e}");
+            eprintln!(
+                "Progress was saved to {}; fix the cause and rerun with --resume.",
+                checkpoint_path.display()
+            );
+            EXIT_INSTALL_FAILED
+        }
+        Err(_) => {
+            eprintln!("error: installation thread panicked");
+            EXIT_INSTALL_FAILED
+        }
+    }
+}
+
+/// Placeholder for secrets in `plan` output; commands carrying them are redacted anyway.
+const PLAN_SECRET_PLACEHOLDER: &str = "<plan-placeholder>";
+
+/// Give every secret a placeholder so the plan contains the password/LUKS steps.
+fn fill_plan_secrets(app: &mut AppState) {
+    if app.root_password.is_empty() {
+        app.root_password = PLAN_SECRET_PLACEHOLDER.into();
+        app.root_password_confirm = PLAN_SECRET_PLACEHOLDER.into();
+    }
+    if app.disk_encryption_type_index == 1 && app.disk_encryption_password.is_empty() {
+        app.disk_encryption_password = PLAN_SECRET_PLACEHOLDER.into();
+        app.disk_encryption_password_confirm = PLAN_SECRET_PLACEHOLDER.into();
+    }
+    for user in app.users.iter_mut() {
+        if user.password.is_empty() {
+            user.password = PLAN_SECRET_PLACEHOLDER.into();
+        }
+    }
+}
+
+/// Machine-readable install plan: ordered sections with redacted commands plus the
+/// already compiled `storage_plan`.
+pub fn plan_json(app: &AppState, target: &str, storage_plan: &StoragePlan) -> serde_json::Value {
+    let sections: Vec<serde_json::Value> = app
+        .build_install_sections(target)
+        .iter()
+        .map(|(title, cmds)| {
+            let commands: Vec<serde_json::Value> = cmds
+                .iter()
+                .map(|cmd| serde_json::json!({ "kind": cmd.kind(), "command": cmd.for_log() }))
+                .collect();
+            serde_json::json!({ "title": title, "commands": commands })
+        })
+        .collect();
+    serde_json::json!({
+        "target": target,
+        "uefi": app.is_uefi(),
+        "sections": sections,
+        "storage": storage_plan,
+    })
+}
+
+/// `archinstall-rs plan <config>`: print the install plan as JSON without executing anything.
+///
+/// `firmware_uefi` overrides host detection so plans for other machines can be reviewed.
+pub fn run_plan(config_path: &Path, firmware_uefi: Option<bool>, debug_enabled: bool) -> i32 {
+    let mut app = match load_cli_state(config_path, true, debug_enabled) {
+        Ok(app) => app,
+        Err(code) => return code,
+    };
+    if firmware_uefi.is_some() {
+        app.firmware_uefi_override = firmware_uefi;
+    }
+    fill_plan_secrets(&mut app);
+    let storage_plan = match validated_storage_plan(&app) {
+        Ok(plan) => plan,
+        Err(code) => return code,
+    };
+    let target = app.install_target().unwrap_or_default();
+    match serde_json::to_string_pretty(&plan_json(&app, &target, &storage_plan)) {
+        Ok(text) => {
+            println!("{text}");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: failed to serialize plan: {e}");
+            EXIT_INVALID_CONFIG
+        }
+    }
+}
+
+/// `archinstall-rs validate <config>`: print per-field diagnostics; non-zero exit on any error.
+pub fn run_validate(config_path: &Path, firmware_uefi: Option<bool>, debug_enabled: bool) -> i32 {
+    let text = match std::fs::read_to_string(config_path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("error: {}: {e}", config_path.display());
+            return EXIT_INVALID_CONFIG;
+        }
+    };
+    let mut app = AppState::new(true);
+    app.debug_enabled = debug_enabled;
+    if firmware_uefi.is_some() {
+        app.firmware_uefi_override = firmware_uefi;
+    }
+    let diagnostics = app.validate_config_text(&text);
+    for d in &diagnostics {
+        println!("{}:{d}", config_path.display());
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!(
+        "{}: {errors} error(s), {warnings} warning(s)",
+        config_path.display()
+    );
+    if errors == 0 { 0 } else { EXIT_INVALID_CONFIG }
+}
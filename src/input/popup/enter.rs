@@ -367,6 +367,7 @@ pub(crate) fn handle_enter(app: &mut AppState) -> bool {
                 app.close_popup();
             }
         }
+        Some(PopupKind::RaidMemberDisks) => app.finalize_raid_members(),
         Some(PopupKind::KernelSelect) => {
             if app.selected_kernels.is_empty() {
                 app.selected_kernels.insert("linux".into());
@@ -800,6 +801,13 @@ pub(crate) fn handle_enter(app: &mut AppState) -> bool {
             app.close_popup();
             return true;
         }
+        Some(PopupKind::InstallCancelConfirm) => {
+            if app.popup_visible_indices.get(app.popup_selected_visible) == Some(&0) {
+                app.cancel_install();
+            } else {
+                app.close_popup();
+            }
+        }
         Some(PopupKind::MinimalClearConfirm) => {
             if let Some(&global_idx) = app.popup_visible_indices.get(app.popup_selected_visible) {
                 if global_idx == 0 {
@@ -835,12 +843,180 @@ pub(crate) fn handle_enter(app: &mut AppState) -> bool {
         | Some(PopupKind::XorgTypeSelect) => {
             app.close_popup();
         }
+        Some(PopupKind::RootFilesystemSelect) => {
+            if let Some(&gi) = app.popup_visible_indices.get(app.popup_selected_visible) {
+                app.disks_root_fs_index = gi;
+            }
+            app.close_popup();
+        }
+        Some(PopupKind::SwapModeSelect) => {
+            if let Some(&gi) = app.popup_visible_indices.get(app.popup_selected_visible) {
+                app.swap_mode_index = gi;
+            }
+            app.close_popup();
+        }
+        Some(PopupKind::SwapfileSizeInput) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            if crate::core::storage::is_valid_swapfile_size(&value) {
+                app.swapfile_size = value;
+                app.custom_input_buffer.clear();
+                app.close_popup();
+            } else {
+                app.open_info_popup(
+                    "Swapfile size must be a number with K, M, G or T, e.g. 4G".into(),
+                );
+            }
+        }
+        Some(PopupKind::ZramSizeInput) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            if crate::core::storage::is_valid_zram_size(&value) {
+                app.zram_size = value;
+                app.custom_input_buffer.clear();
+                app.close_popup();
+            } else {
+                app.open_info_popup("zram size must be MiB or a formula such as ram / 2".into());
+            }
+        }
+        Some(PopupKind::LvmVolumeGroupName) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            if crate::core::storage::is_valid_lvm_name(&value) {
+                app.lvm_vg_name = value;
+                app.custom_input_buffer.clear();
+                app.close_popup();
+            } else {
+                app.open_info_popup(
+                    "Volume group names use letters, digits and + _ . - (not starting with -)"
+                        .into(),
+                );
+            }
+        }
+        Some(PopupKind::LvmVolumeName) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            let taken = app
+                .lvm_volumes
+                .iter()
+                .enumerate()
+                .any(|(i, lv)| lv.name == value && Some(i) != app.lvm_edit_index);
+            if !crate::core::storage::is_valid_lvm_name(&value) {
+                app.open_info_popup(
+                    "Logical volume names use letters, digits and + _ . - (not starting with -)"
+                        .into(),
+                );
+            } else if taken {
+                app.open_info_popup(format!("A logical volume named {value} already exists"));
+            } else {
+                app.lvm_draft.name = value;
+                app.open_lvm_volume_size();
+            }
+        }
+        Some(PopupKind::LvmVolumeSize) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            if crate::core::storage::is_valid_lvm_size(&value) {
+                app.lvm_draft.size = value;
+                app.open_lvm_volume_filesystem();
+            } else {
+                app.open_info_popup("Size must be e.g. 40G, 512M, 50%FREE or 100%FREE".into());
+            }
+        }
+        Some(PopupKind::LvmVolumeFilesystem) => {
+            if let Some(&gi) = app.popup_visible_indices.get(app.popup_selected_visible)
+                && let Some(fs) = crate::core::storage::LVM_FILESYSTEMS.get(gi)
+            {
+                app.lvm_draft.fs = fs.to_string();
+                if *fs == "linux-swap" {
+                    app.finalize_lvm_volume();
+                } else {
+                    app.open_lvm_volume_mountpoint();
+                }
+            }
+        }
+        Some(PopupKind::LvmVolumeMountpoint) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            if value.starts_with('/') {
+                app.lvm_draft.mountpoint = Some(value);
+                app.finalize_lvm_volume();
+            } else {
+                app.open_info_popup("Mountpoint must be an absolute path, e.g. /home".into());
+            }
+        }
+        Some(PopupKind::DiskEncryptionTpm2Pcrs) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            if crate::core::storage::is_valid_tpm2_pcrs(&value) {
+                app.disk_encryption_tpm2_pcrs = value;
+                app.custom_input_buffer.clear();
+                app.close_popup();
+            } else {
+                app.open_info_popup("TPM2 PCRs must be numbers 0-23 joined by +, e.g. 0+7".into());
+            }
+        }
+        Some(PopupKind::DiskEncryptionLuksParam) => {
+            let row = app.diskenc_focus_index;
+            let value = app.custom_input_buffer.trim().to_string();
+            let Some(field) = app.luks_param_text_mut(row) else {
+                app.close_popup();
+                return false;
+            };
+            let previous = std::mem::replace(field, value);
+            match crate::core::storage::planner::StoragePlanner::luks_format_options(app) {
+                Ok(_) => {
+                    app.custom_input_buffer.clear();
+                    app.close_popup();
+                }
+                Err(errors) => {
+                    if let Some(field) = app.luks_param_text_mut(row) {
+                        *field = previous;
+                    }
+                    app.open_info_popup(errors[0].message.clone());
+                }
+            }
+        }
+        Some(PopupKind::KernelParametersInput) => {
+            let value = app.custom_input_buffer.trim().to_string();
+            match crate::app::kernels::kernel_parameters_error(&value) {
+                None => {
+                    app.kernel_parameters = value;
+                    app.custom_input_buffer.clear();
+                    app.close_popup();
+                }
+                Some(reason) => app.open_info_popup(format!("Kernel parameters: {reason}")),
+            }
+        }
         Some(PopupKind::BtrfsSubvolumePreset) => {
             if let Some(&gi) = app.popup_visible_indices.get(app.popup_selected_visible) {
                 app.btrfs_subvolume_preset = gi;
             }
             app.close_popup();
         }
+        Some(PopupKind::InstallFailureRecovery) => {
+            if let Some(&global_idx) = app.popup_visible_indices.get(app.popup_selected_visible) {
+                app.handle_install_failure_choice(global_idx);
+            }
+        }
+        Some(PopupKind::InstallFailureEdit) => {
+            let edited = app.custom_input_buffer.trim().to_string();
+            if !edited.is_empty() {
+                app.resolve_install_failure(crate::common::FailureAction::RetryWith(edited));
+            }
+        }
+        Some(PopupKind::ResumeInstall) => {
+            if let Some(&global_idx) = app.popup_visible_indices.get(app.popup_selected_visible) {
+                match global_idx {
+                    0 => {
+                        let start = app.install_resume_choices.last().copied();
+                        app.choose_install_resume(start.or(Some(0)));
+                    }
+                    1 => app.open_resume_section_popup(),
+                    _ => app.choose_install_resume(None),
+                }
+            }
+        }
+        Some(PopupKind::ResumeSectionSelect) => {
+            if let Some(&gi) = app.popup_visible_indices.get(app.popup_selected_visible)
+                && let Some(&start) = app.install_resume_choices.get(gi)
+            {
+                app.choose_install_resume(Some(start));
+            }
+        }
         Some(PopupKind::ConfigLoadSelect) => {
             if let Some(&gi) = app.popup_visible_indices.get(app.popup_selected_visible)
                 && let Some(row) = app.config_popup_rows.get(gi)
@@ -38,6 +38,10 @@ pub(crate) fn handle_popup_keys(app: &mut AppState, code: KeyCode) -> bool {
                     | Some(crate::app::PopupKind::ManualPartitionFilesystem)
                     | Some(crate::app::PopupKind::ManualPartitionMountpoint)
                     | Some(crate::app::PopupKind::ManualPartitionEdit)
+                    | Some(crate::app::PopupKind::InstallFailureEdit)
+                    | Some(crate::app::PopupKind::ZramSizeInput)
+                    | Some(crate::app::PopupKind::LvmVolumeMountpoint)
+                    | Some(crate::app::PopupKind::KernelParametersInput)
             ) {
                 if text::handle_text_char(app, '/') {
                     return false;
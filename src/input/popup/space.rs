@@ -2,6 +2,13 @@ use crate::app::{AppState, PopupKind};
 
 pub(crate) fn handle_space(app: &mut AppState) -> bool {
     match app.popup_kind {
+        // Shell commands, zram size formulas, LUKS labels and kernel parameters need spaces
+        Some(PopupKind::InstallFailureEdit)
+        | Some(PopupKind::ZramSizeInput)
+        | Some(PopupKind::DiskEncryptionLuksParam)
+        | Some(PopupKind::KernelParametersInput) => {
+            app.custom_input_buffer.push(' ');
+        }
         Some(PopupKind::MirrorsRegions) => {
             if let Some(&global_idx) = app.popup_visible_indices.get(app.popup_selected_visible) {
                 if app.mirrors_regions_selected.contains(&global_idx) {
@@ -51,6 +58,15 @@ pub(crate) fn handle_space(app: &mut AppState) -> bool {
                 app.aur_helper_index = Some(global_idx);
             }
         }
+        Some(PopupKind::RaidMemberDisks) => {
+            if let Some(&global_idx) = app.popup_visible_indices.get(app.popup_selected_visible)
+                && let Some(row) = app.popup_items.get(global_idx)
+            {
+                let path = AppState::raid_member_row_path(row).to_string();
+                app.toggle_raid_member(&path);
+            }
+            return false;
+        }
         Some(PopupKind::KernelSelect) => {
             if let Some(&global_idx) = app.popup_visible_indices.get(app.popup_selected_visible)
                 && let Some(name) = app.popup_items.get(global_idx)
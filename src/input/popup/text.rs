@@ -26,6 +26,16 @@ pub(crate) fn handle_text_backspace(app: &mut AppState) -> bool {
             | Some(PopupKind::UserAddPasswordConfirm)
             | Some(PopupKind::ManualPartitionCreate)
             | Some(PopupKind::ManualPartitionMountpoint)
+            | Some(PopupKind::InstallFailureEdit)
+            | Some(PopupKind::SwapfileSizeInput)
+            | Some(PopupKind::ZramSizeInput)
+            | Some(PopupKind::LvmVolumeGroupName)
+            | Some(PopupKind::LvmVolumeName)
+            | Some(PopupKind::LvmVolumeSize)
+            | Some(PopupKind::LvmVolumeMountpoint)
+            | Some(PopupKind::DiskEncryptionTpm2Pcrs)
+            | Some(PopupKind::DiskEncryptionLuksParam)
+            | Some(PopupKind::KernelParametersInput)
     ) {
         // In ManualPartitionCreate, only handle backspace when size field is focused
         if matches!(app.popup_kind, Some(PopupKind::ManualPartitionCreate))
@@ -64,6 +74,16 @@ pub(crate) fn handle_text_char(app: &mut AppState, c: char) -> bool {
             | Some(PopupKind::UserAddPasswordConfirm)
             | Some(PopupKind::ManualPartitionCreate)
             | Some(PopupKind::ManualPartitionMountpoint)
+            | Some(PopupKind::InstallFailureEdit)
+            | Some(PopupKind::SwapfileSizeInput)
+            | Some(PopupKind::ZramSizeInput)
+            | Some(PopupKind::LvmVolumeGroupName)
+            | Some(PopupKind::LvmVolumeName)
+            | Some(PopupKind::LvmVolumeSize)
+            | Some(PopupKind::LvmVolumeMountpoint)
+            | Some(PopupKind::DiskEncryptionTpm2Pcrs)
+            | Some(PopupKind::DiskEncryptionLuksParam)
+            | Some(PopupKind::KernelParametersInput)
     ) {
         if matches!(app.popup_kind, Some(PopupKind::ManualPartitionCreate)) {
             // When units focused, don't consume j/k so nav can handle
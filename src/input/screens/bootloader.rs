@@ -5,7 +5,7 @@ pub(crate) fn move_bootloader_up(app: &mut AppState) {
         return;
     }
     if app.bootloader_focus_index == 0 {
-        app.bootloader_focus_index = 4;
+        app.bootloader_focus_index = 7;
     } else {
         app.bootloader_focus_index -= 1;
     }
@@ -14,24 +14,32 @@ pub(crate) fn move_bootloader_down(app: &mut AppState) {
     if app.current_screen() != Screen::Bootloader || app.focus != Focus::Content {
         return;
     }
-    app.bootloader_focus_index = (app.bootloader_focus_index + 1) % 5;
+    app.bootloader_focus_index = (app.bootloader_focus_index + 1) % 8;
 }
-pub(crate) fn change_bootloader_value(app: &mut AppState, _next: bool) {
+pub(crate) fn change_bootloader_value(app: &mut AppState, next: bool) {
     if app.current_screen() != Screen::Bootloader || app.focus != Focus::Content {
         return;
     }
-    if app.bootloader_focus_index < 4 {
-        app.bootloader_index = app.bootloader_focus_index;
-        app.apply_secure_boot_uki_policy();
+    match app.bootloader_focus_index {
+        0..=4 => {
+            app.bootloader_index = app.bootloader_focus_index;
+            app.apply_secure_boot_uki_policy();
+        }
+        5 => app.cycle_secure_boot_signing(next),
+        6 => app.secure_boot_microsoft_keys = !app.secure_boot_microsoft_keys,
+        _ => {}
     }
 }
 
 pub(crate) fn handle_enter_bootloader(app: &mut AppState) {
-    if app.bootloader_focus_index < 4 {
-        app.bootloader_index = app.bootloader_focus_index;
-        app.apply_secure_boot_uki_policy();
-        app.update_unified_kernel_images_visibility();
-    } else {
-        super::common::advance(app);
+    match app.bootloader_focus_index {
+        0..=4 => {
+            app.bootloader_index = app.bootloader_focus_index;
+            app.apply_secure_boot_uki_policy();
+            app.update_unified_kernel_images_visibility();
+        }
+        5 => app.cycle_secure_boot_signing(true),
+        6 => app.secure_boot_microsoft_keys = !app.secure_boot_microsoft_keys,
+        _ => super::common::advance(app),
     }
 }
@@ -8,11 +8,7 @@ pub(crate) fn move_diskenc_up(app: &mut AppState) {
         app.diskenc_focus_index = 0;
         return;
     }
-    let max = if app.disk_encryption_type_index == 1 {
-        5
-    } else {
-        2
-    };
+    let max = app.diskenc_continue_index() + 1;
     if app.diskenc_focus_index == 0 {
         app.diskenc_focus_index = max - 1;
     } else {
@@ -27,11 +23,7 @@ pub(crate) fn move_diskenc_down(app: &mut AppState) {
         app.diskenc_focus_index = 0;
         return;
     }
-    let max = if app.disk_encryption_type_index == 1 {
-        5
-    } else {
-        2
-    };
+    let max = app.diskenc_continue_index() + 1;
     app.diskenc_focus_index = (app.diskenc_focus_index + 1) % max;
 }
 pub(crate) fn change_diskenc_value(app: &mut AppState, next: bool) {
@@ -41,6 +33,14 @@ pub(crate) fn change_diskenc_value(app: &mut AppState, next: bool) {
     if !app.disk_encryption_available() {
         return;
     }
+    if app.disk_encryption_type_index == 1 {
+        match app.diskenc_focus_index {
+            4 => app.disk_encryption_tpm2 = !app.disk_encryption_tpm2,
+            6 => app.disk_encryption_fido2 = !app.disk_encryption_fido2,
+            7 => app.disk_encryption_recovery_key = !app.disk_encryption_recovery_key,
+            row => app.cycle_luks_param(row, next),
+        }
+    }
     if app.diskenc_focus_index == 0 {
         let mut idx = app.disk_encryption_type_index;
         if next {
@@ -62,11 +62,7 @@ pub(crate) fn handle_enter_diskenc(app: &mut AppState) {
         super::common::advance(app);
         return;
     }
-    let continue_index = if app.disk_encryption_type_index == 1 {
-        4
-    } else {
-        1
-    };
+    let continue_index = app.diskenc_continue_index();
     if app.diskenc_focus_index == 0 {
         app.open_disk_encryption_type_popup();
     } else if app.disk_encryption_type_index == 1 {
@@ -82,6 +78,12 @@ pub(crate) fn handle_enter_diskenc(app: &mut AppState) {
                     app.open_disk_encryption_partition_list();
                 }
             }
+            4 => app.disk_encryption_tpm2 = !app.disk_encryption_tpm2,
+            5 if app.disk_encryption_tpm2 => app.open_disk_encryption_tpm2_pcrs_input(),
+            6 => app.disk_encryption_fido2 = !app.disk_encryption_fido2,
+            7 => app.disk_encryption_recovery_key = !app.disk_encryption_recovery_key,
+            11 | 14 | 16 => app.cycle_luks_param(app.diskenc_focus_index, true),
+            8..=16 => app.open_luks_param_input(),
             idx if idx == continue_index => super::common::advance(app),
             _ => {}
         }
@@ -1,10 +1,15 @@
 use crate::app::{AppState, Focus, Screen};
+use crate::core::storage::RootFilesystem;
 use crate::core::storage::planner::StoragePlanner;
 
 pub(crate) fn move_disks_up(app: &mut AppState) {
     if app.current_screen() == Screen::Disks && app.focus == Focus::Content {
-        if app.disks_focus_index == 0 {
-            app.disks_focus_index = 4;
+        if app.lvm_open {
+            super::lvm::move_lvm_up(app);
+        } else if app.raid_open {
+            super::raid::move_raid_up(app);
+        } else if app.disks_focus_index == 0 {
+            app.disks_focus_index = 8;
         } else {
             app.disks_focus_index -= 1;
         }
@@ -12,18 +17,33 @@ pub(crate) fn move_disks_up(app: &mut AppState) {
 }
 pub(crate) fn move_disks_down(app: &mut AppState) {
     if app.current_screen() == Screen::Disks && app.focus == Focus::Content {
-        app.disks_focus_index = (app.disks_focus_index + 1) % 5;
+        if app.lvm_open {
+            super::lvm::move_lvm_down(app);
+        } else if app.raid_open {
+            super::raid::move_raid_down(app);
+        } else {
+            app.disks_focus_index = (app.disks_focus_index + 1) % 9;
+        }
     }
 }
-pub(crate) fn change_disks_value(app: &mut AppState, _next: bool) {
+pub(crate) fn change_disks_value(app: &mut AppState, next: bool) {
+    // Selecting a partitioning mode should happen explicitly on Enter.
+    // Left/Right on this screen only change values on the LVM and RAID sub-screens.
     if app.current_screen() == Screen::Disks && app.focus == Focus::Content {
-        // Selecting a partitioning mode should happen explicitly on Enter.
-        // Left/Right on this screen do not implicitly change the selected mode.
+        if app.lvm_open {
+            super::lvm::change_lvm_value(app, next);
+        } else if app.raid_open {
+            super::raid::change_raid_value(app, next);
+        }
     }
 }
 
 pub(crate) fn handle_enter_disks(app: &mut AppState) {
-    if app.disks_focus_index <= 2 {
+    if app.lvm_open {
+        super::lvm::handle_enter_lvm(app);
+    } else if app.raid_open {
+        super::raid::handle_enter_raid(app);
+    } else if app.disks_focus_index <= 2 {
         let prev_mode = app.disks_mode_index;
         app.disks_mode_index = app.disks_focus_index;
         if app.disks_mode_index == 2 {
@@ -69,6 +89,11 @@ pub(crate) fn handle_enter_disks(app: &mut AppState) {
             }
         }
     } else if app.disks_focus_index == 3 {
+        // Match `root_fs_available` in `draw_disks` (src/app/disks.rs).
+        if app.disks_mode_index == 0 && !app.lvm_selected() {
+            app.open_root_filesystem_popup();
+        }
+    } else if app.disks_focus_index == 4 {
         let has_btrfs_root = app.disks_partitions.iter().any(|p| {
             p.role
                 .as_deref()
@@ -77,12 +102,38 @@ pub(crate) fn handle_enter_disks(app: &mut AppState) {
                 && p.fs.as_deref() == Some("btrfs")
         });
         // Match `preset_available` in `draw_disks` (src/app/disks.rs).
-        if app.disks_mode_index == 0 || (app.disks_mode_index == 1 && has_btrfs_root) {
+        if ((app.disks_mode_index == 0 && app.root_filesystem() == RootFilesystem::Btrfs)
+            || (app.disks_mode_index == 1 && has_btrfs_root))
+            && !app.lvm_selected()
+            && !app.raid_selected()
+        {
             app.open_btrfs_subvolume_preset_popup();
         }
-    } else if app.disks_focus_index == 4 {
+    } else if app.disks_focus_index == 5 {
+        // Match `lvm_available` in `draw_disks` (src/app/disks.rs).
+        if app.disks_mode_index < 2 {
+            app.lvm_open = true;
+            app.lvm_focus_index = 0;
+        }
+    } else if app.disks_focus_index == 6 {
+        // Match `raid_available` in `draw_disks` (src/app/disks.rs).
+        if app.disks_mode_index == 0 {
+            app.raid_open = true;
+            app.raid_focus_index = 0;
+        }
+    } else if app.disks_focus_index == 7 {
+        // Match `alongside_available` in `draw_disks` (src/app/disks.rs).
+        if app.disks_mode_index == 0 {
+            app.alongside_enabled = !app.alongside_enabled;
+        }
+    } else if app.disks_focus_index == 8 {
         // Validate storage plan before advancing from the Disks screen
-        if (app.disks_mode_index == 1 || app.disks_mode_index == 2)
+        if (app.disks_mode_index == 1
+            || app.disks_mode_index == 2
+            || app.zfs_root_selected()
+            || app.lvm_selected()
+            || app.raid_selected()
+            || app.alongside_selected())
             && let Err(errors) = StoragePlanner::compile(app)
         {
             let msg = errors
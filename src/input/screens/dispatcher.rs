@@ -273,7 +273,7 @@ pub(crate) fn move_disks_up(app: &mut AppState) {
         return;
     }
     if app.disks_focus_index == 0 {
-        app.disks_focus_index = 3;
+        app.disks_focus_index = 8;
     } else {
         app.disks_focus_index -= 1;
     }
@@ -282,7 +282,7 @@ pub(crate) fn move_disks_down(app: &mut AppState) {
     if app.current_screen() != Screen::Disks || app.focus != Focus::Content {
         return;
     }
-    app.disks_focus_index = (app.disks_focus_index + 1) % 4;
+    app.disks_focus_index = (app.disks_focus_index + 1) % 9;
 }
 pub(crate) fn change_disks_value(app: &mut AppState, next: bool) {
     if app.current_screen() != Screen::Disks || app.focus != Focus::Content {
@@ -302,68 +302,22 @@ pub(crate) fn change_disks_value(app: &mut AppState, next: bool) {
     }
 }
 pub(crate) fn move_diskenc_up(app: &mut AppState) {
-    if app.current_screen() != Screen::DiskEncryption || app.focus != Focus::Content {
-        return;
-    }
-    let max = if app.disk_encryption_type_index == 1 {
-        5
-    } else {
-        2
-    };
-    if app.diskenc_focus_index == 0 {
-        app.diskenc_focus_index = max - 1;
-    } else {
-        app.diskenc_focus_index -= 1;
-    }
+    de::move_diskenc_up(app);
 }
 pub(crate) fn move_diskenc_down(app: &mut AppState) {
-    if app.current_screen() != Screen::DiskEncryption || app.focus != Focus::Content {
-        return;
-    }
-    let max = if app.disk_encryption_type_index == 1 {
-        5
-    } else {
-        2
-    };
-    app.diskenc_focus_index = (app.diskenc_focus_index + 1) % max;
+    de::move_diskenc_down(app);
 }
 pub(crate) fn change_diskenc_value(app: &mut AppState, next: bool) {
-    if app.current_screen() != Screen::DiskEncryption || app.focus != Focus::Content {
-        return;
-    }
-    if app.diskenc_focus_index == 0 {
-        let mut idx = app.disk_encryption_type_index;
-        if next {
-            idx = (idx + 1) % 2;
-        } else {
-            idx = (idx + 2 - 1) % 2;
-        }
-        app.disk_encryption_type_index = idx;
-    }
+    de::change_diskenc_value(app, next);
 }
 pub(crate) fn move_swap_up(app: &mut AppState) {
-    if app.current_screen() != Screen::SwapPartition || app.focus != Focus::Content {
-        return;
-    }
-    if app.swap_focus_index == 0 {
-        app.swap_focus_index = 1;
-    } else {
-        app.swap_focus_index -= 1;
-    }
+    swap::move_swap_up(app);
 }
 pub(crate) fn move_swap_down(app: &mut AppState) {
-    if app.current_screen() != Screen::SwapPartition || app.focus != Focus::Content {
-        return;
-    }
-    app.swap_focus_index = (app.swap_focus_index + 1) % 2;
+    swap::move_swap_down(app);
 }
-pub(crate) fn change_swap_value(app: &mut AppState, _next: bool) {
-    if app.current_screen() != Screen::SwapPartition || app.focus != Focus::Content {
-        return;
-    }
-    if app.swap_focus_index == 0 {
-        app.swap_enabled = !app.swap_enabled;
-    }
+pub(crate) fn change_swap_value(app: &mut AppState, next: bool) {
+    swap::change_swap_value(app, next);
 }
 pub(crate) fn move_uki_up(app: &mut AppState) {
     if app.current_screen() != Screen::UnifiedKernelImages || app.focus != Focus::Content {
@@ -447,7 +401,7 @@ pub(crate) fn move_bootloader_up(app: &mut AppState) {
         return;
     }
     if app.bootloader_focus_index == 0 {
-        app.bootloader_focus_index = 4;
+        app.bootloader_focus_index = 7;
     } else {
         app.bootloader_focus_index -= 1;
     }
@@ -456,14 +410,17 @@ pub(crate) fn move_bootloader_down(app: &mut AppState) {
     if app.current_screen() != Screen::Bootloader || app.focus != Focus::Content {
         return;
     }
-    app.bootloader_focus_index = (app.bootloader_focus_index + 1) % 5;
+    app.bootloader_focus_index = (app.bootloader_focus_index + 1) % 8;
 }
-pub(crate) fn change_bootloader_value(app: &mut AppState, _next: bool) {
+pub(crate) fn change_bootloader_value(app: &mut AppState, next: bool) {
     if app.current_screen() != Screen::Bootloader || app.focus != Focus::Content {
         return;
     }
-    if app.bootloader_focus_index < 4 {
-        app.bootloader_index = app.bootloader_focus_index;
+    match app.bootloader_focus_index {
+        0..=4 => app.bootloader_index = app.bootloader_focus_index,
+        5 => app.cycle_secure_boot_signing(next),
+        6 => app.secure_boot_microsoft_keys = !app.secure_boot_microsoft_keys,
+        _ => {}
     }
 }
 
@@ -472,7 +429,7 @@ pub(crate) fn move_kernels_up(app: &mut AppState) {
         return;
     }
     if app.kernels_focus_index == 0 {
-        app.kernels_focus_index = 1;
+        app.kernels_focus_index = 3;
     } else {
         app.kernels_focus_index -= 1;
     }
@@ -482,10 +439,17 @@ pub(crate) fn move_kernels_down(app: &mut AppState) {
     if app.current_screen() != Screen::Kernels || app.focus != Focus::Content {
         return;
     }
-    app.kernels_focus_index = (app.kernels_focus_index + 1) % 2;
+    app.kernels_focus_index = (app.kernels_focus_index + 1) % 4;
 }
 
-pub(crate) fn change_kernels_value(_app: &mut AppState, _next: bool) {}
+pub(crate) fn change_kernels_value(app: &mut AppState, next: bool) {
+    if app.current_screen() != Screen::Kernels || app.focus != Focus::Content {
+        return;
+    }
+    if app.kernels_focus_index == 1 {
+        app.cycle_default_kernel(next);
+    }
+}
 
 pub(crate) fn move_audio_up(app: &mut AppState) {
     if app.current_screen() != Screen::Audio || app.focus != Focus::Content {
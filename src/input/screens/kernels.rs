@@ -7,7 +7,7 @@ pub(crate) fn move_kernels_up(app: &mut AppState) {
         return;
     }
     if app.kernels_focus_index == 0 {
-        app.kernels_focus_index = 1;
+        app.kernels_focus_index = 3;
     } else {
         app.kernels_focus_index -= 1;
     }
@@ -19,15 +19,26 @@ pub(crate) fn move_kernels_down(app: &mut AppState) {
     {
         return;
     }
-    app.kernels_focus_index = (app.kernels_focus_index + 1) % 2;
+    app.kernels_focus_index = (app.kernels_focus_index + 1) % 4;
 }
 
-pub(crate) fn change_kernels_value(_app: &mut AppState, _next: bool) {}
+pub(crate) fn change_kernels_value(app: &mut AppState, next: bool) {
+    if app.current_screen() != crate::app::Screen::Kernels
+        || app.focus != crate::app::Focus::Content
+    {
+        return;
+    }
+    if app.kernels_focus_index == 1 {
+        app.cycle_default_kernel(next);
+    }
+}
 
 pub(crate) fn handle_enter_kernels(app: &mut AppState) {
     match app.kernels_focus_index {
         0 => app.open_kernels_popup(),
-        1 => super::common::advance(app),
+        1 => app.cycle_default_kernel(true),
+        2 => app.open_kernel_parameters_input(),
+        3 => super::common::advance(app),
         _ => {}
     }
 }
@@ -0,0 +1,44 @@
+use crate::app::AppState;
+
+// The LVM sub-screen of Disk Partitioning; the Disks handlers delegate here while it is open.
+
+pub(crate) fn move_lvm_up(app: &mut AppState) {
+    if app.lvm_focus_index == 0 {
+        app.lvm_focus_index = app.lvm_add_row() + 1;
+    } else {
+        app.lvm_focus_index -= 1;
+    }
+}
+pub(crate) fn move_lvm_down(app: &mut AppState) {
+    app.lvm_focus_index = (app.lvm_focus_index + 1) % (app.lvm_add_row() + 2);
+}
+pub(crate) fn change_lvm_value(app: &mut AppState, next: bool) {
+    match app.lvm_focus_index {
+        0 => app.toggle_lvm(),
+        2 if app.lvm_enabled && app.disks_mode_index == 1 => app.cycle_lvm_physical_volume(next),
+        3 if app.lvm_enabled && app.lvm_encrypted() => app.cycle_lvm_stacking(),
+        _ => {}
+    }
+}
+
+pub(crate) fn handle_enter_lvm(app: &mut AppState) {
+    let add_row = app.lvm_add_row();
+    match app.lvm_focus_index {
+        0 => app.toggle_lvm(),
+        i if i == add_row + 1 => app.lvm_open = false,
+        _ if !app.lvm_enabled => {}
+        1 => app.open_lvm_vg_name_input(),
+        2 if app.disks_mode_index == 1 => app.cycle_lvm_physical_volume(true),
+        3 if app.lvm_encrypted() => app.cycle_lvm_stacking(),
+        i if i == add_row => app.start_lvm_volume(None),
+        i if i >= 4 => app.start_lvm_volume(Some(i - 4)),
+        _ => {}
+    }
+}
+
+/// Delete/Backspace on a volume row removes that volume.
+pub(crate) fn delete_lvm_volume(app: &mut AppState) {
+    if app.lvm_enabled && app.lvm_focus_index >= 4 && app.lvm_focus_index < app.lvm_add_row() {
+        app.remove_lvm_volume(app.lvm_focus_index - 4);
+    }
+}
@@ -12,9 +12,11 @@ pub mod experience;
 pub mod hostname;
 pub mod kernels;
 pub mod locales;
+pub mod lvm;
 pub mod mirrors;
 pub mod network;
 pub mod popups;
+pub mod raid;
 pub mod rootpass;
 pub mod save_config;
 pub mod swap;
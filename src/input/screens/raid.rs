@@ -0,0 +1,35 @@
+use crate::app::AppState;
+
+// The software RAID sub-screen of Disk Partitioning; the Disks handlers delegate here while it
+// is open.
+
+const RAID_BACK_ROW: usize = 3;
+
+pub(crate) fn move_raid_up(app: &mut AppState) {
+    if app.raid_focus_index == 0 {
+        app.raid_focus_index = RAID_BACK_ROW;
+    } else {
+        app.raid_focus_index -= 1;
+    }
+}
+pub(crate) fn move_raid_down(app: &mut AppState) {
+    app.raid_focus_index = (app.raid_focus_index + 1) % (RAID_BACK_ROW + 1);
+}
+pub(crate) fn change_raid_value(app: &mut AppState, next: bool) {
+    match app.raid_focus_index {
+        0 => app.toggle_raid(),
+        1 if app.raid_enabled => app.cycle_raid_level(next),
+        _ => {}
+    }
+}
+
+pub(crate) fn handle_enter_raid(app: &mut AppState) {
+    match app.raid_focus_index {
+        0 => app.toggle_raid(),
+        RAID_BACK_ROW => app.raid_open = false,
+        _ if !app.raid_enabled => {}
+        1 => app.cycle_raid_level(true),
+        2 => app.open_raid_member_disks(),
+        _ => {}
+    }
+}
@@ -1,11 +1,12 @@
-use crate::app::{AppState, Focus, Screen};
+use crate::app::{AppState, Focus, PopupKind, Screen};
+use crate::core::storage::SwapMode;
 
 pub(crate) fn move_swap_up(app: &mut AppState) {
     if app.current_screen() != Screen::SwapPartition || app.focus != Focus::Content {
         return;
     }
     if app.swap_focus_index == 0 {
-        app.swap_focus_index = 1;
+        app.swap_focus_index = 5;
     } else {
         app.swap_focus_index -= 1;
     }
@@ -14,21 +15,36 @@ pub(crate) fn move_swap_down(app: &mut AppState) {
     if app.current_screen() != Screen::SwapPartition || app.focus != Focus::Content {
         return;
     }
-    app.swap_focus_index = (app.swap_focus_index + 1) % 2;
+    app.swap_focus_index = (app.swap_focus_index + 1) % 6;
 }
-pub(crate) fn change_swap_value(app: &mut AppState, _next: bool) {
+pub(crate) fn change_swap_value(app: &mut AppState, next: bool) {
     if app.current_screen() != Screen::SwapPartition || app.focus != Focus::Content {
         return;
     }
-    if app.swap_focus_index == 0 {
-        app.swap_enabled = !app.swap_enabled;
+    match app.swap_focus_index {
+        0 => {
+            let len = SwapMode::NAMES.len();
+            app.swap_mode_index = if next {
+                (app.swap_mode_index + 1) % len
+            } else {
+                (app.swap_mode_index + len - 1) % len
+            };
+        }
+        3 if app.swap_mode() == SwapMode::Zram => app.cycle_zram_algorithm(next),
+        4 if app.swap_encryption_available() => app.cycle_swap_encryption(next),
+        _ => {}
     }
 }
 
 pub(crate) fn handle_enter_swap(app: &mut AppState) {
-    if app.swap_focus_index == 0 {
-        app.swap_enabled = !app.swap_enabled;
-    } else if app.swap_focus_index == 1 {
-        super::common::advance(app);
+    let mode = app.swap_mode();
+    match app.swap_focus_index {
+        0 => app.open_swap_mode_popup(),
+        1 if mode == SwapMode::Swapfile => app.open_swap_size_input(PopupKind::SwapfileSizeInput),
+        2 if mode == SwapMode::Zram => app.open_swap_size_input(PopupKind::ZramSizeInput),
+        3 if mode == SwapMode::Zram => app.cycle_zram_algorithm(true),
+        4 if app.swap_encryption_available() => app.cycle_swap_encryption(true),
+        5 => super::common::advance(app),
+        _ => {}
     }
 }
@@ -6,18 +6,65 @@ use super::screens::{
     change_value, move_menu_down, move_menu_up, move_screen_down, move_screen_up,
 };
 use super::{cmdline::handle_cmdline_keys, popup::handle_popup_keys};
-use crate::app::{AppState, Focus, Screen};
+use crate::app::{AppState, Focus, PopupKind, Screen};
 
 // Returns true if the app should quit
 pub fn handle_event(app: &mut AppState, ev: Event) -> bool {
     match ev {
         Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+            // A running install is cancelled (with cleanup) instead of abandoned
+            KeyCode::Char('c')
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && app.install_running
+                    && app.install_cancel.is_some() =>
+            {
+                // Popups (the recovery popup in particular) must be answered first
+                if !app.popup_open {
+                    app.open_install_cancel_popup();
+                }
+                return false;
+            }
             // Quit only with Ctrl-C; ESC never quits
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            // The install thread waits on the recovery popup: answer it, never dismiss it
+            KeyCode::Esc | KeyCode::Char('q')
+                if app.popup_open
+                    && matches!(
+                        app.popup_kind,
+                        Some(PopupKind::InstallFailureRecovery | PopupKind::InstallFailureEdit)
+                    ) =>
+            {
+                if app.popup_kind == Some(PopupKind::InstallFailureEdit) {
+                    if key.code == KeyCode::Char('q') {
+                        app.custom_input_buffer.push('q');
+                        return false;
+                    }
+                    app.open_install_failure_popup();
+                }
+                return false;
+            }
+            KeyCode::Esc | KeyCode::Char('q')
+                if !app.popup_open && app.install_running && app.install_cancel.is_some() =>
+            {
+                app.open_install_cancel_popup();
+                return false;
+            }
             KeyCode::Esc | KeyCode::Char('q') => {
                 if app.popup_open {
                     app.debug_log("handle_event: ESC/q in popup -> close_popup");
                     app.close_popup();
+                } else if app.focus == Focus::Content
+                    && app.current_screen() == Screen::Disks
+                    && app.lvm_open
+                {
+                    app.debug_log("handle_event: ESC/q -> close LVM screen");
+                    app.lvm_open = false;
+                } else if app.focus == Focus::Content
+                    && app.current_screen() == Screen::Disks
+                    && app.raid_open
+                {
+                    app.debug_log("handle_event: ESC/q -> close RAID screen");
+                    app.raid_open = false;
                 } else if app.focus == Focus::Content {
                     if app.current_screen() == Screen::Locales {
                         app.discard_locales_edit();
@@ -108,26 +155,29 @@ pub fn handle_event(app: &mut AppState, ev: Event) -> bool {
             // Vim motions inside decision menu
             KeyCode::Left | KeyCode::Char('h') => change_value(app, false),
             KeyCode::Right | KeyCode::Char('l') => change_value(app, true),
-            KeyCode::Backspace | KeyCode::Delete => {
+            KeyCode::Backspace | KeyCode::Delete
                 if app.focus == Focus::Content
                     && app.current_screen() == Screen::AdditionalPackages
                     && !app.additional_packages.is_empty()
-                    && app.addpkgs_selected_index < app.additional_packages.len()
-                {
-                    app.additional_packages.remove(app.addpkgs_selected_index);
-                    if app.addpkgs_selected_index >= app.additional_packages.len() {
-                        app.addpkgs_selected_index =
-                            app.additional_packages.len().saturating_sub(1);
-                    }
+                    && app.addpkgs_selected_index < app.additional_packages.len() =>
+            {
+                app.additional_packages.remove(app.addpkgs_selected_index);
+                if app.addpkgs_selected_index >= app.additional_packages.len() {
+                    app.addpkgs_selected_index = app.additional_packages.len().saturating_sub(1);
                 }
             }
+            KeyCode::Backspace | KeyCode::Delete
+                if app.focus == Focus::Content
+                    && app.current_screen() == Screen::Disks
+                    && app.lvm_open =>
+            {
+                super::screens::lvm::delete_lvm_volume(app);
+            }
             // Open command line (Locales)
-            KeyCode::Char(':') => {
-                if app.focus == Focus::Content {
-                    app.debug_log("handle_event: entering cmdline");
-                    app.cmdline_open = true;
-                    app.cmdline_buffer.clear();
-                }
+            KeyCode::Char(':') if app.focus == Focus::Content => {
+                app.debug_log("handle_event: entering cmdline");
+                app.cmdline_open = true;
+                app.cmdline_buffer.clear();
             }
             _ => {}
         },
@@ -136,42 +186,36 @@ pub fn handle_event(app: &mut AppState, ev: Event) -> bool {
             column,
             row,
             ..
-        }) => {
+        }) if app.current_screen() == Screen::Install && !app.popup_open => {
             // Only handle clicks in Install screen decision menu
-            if app.current_screen() == Screen::Install && !app.popup_open {
-                let x = column;
-                let y = row;
-                app.debug_log(&format!("handle_event: mouse click at ({x}, {y})"));
-                // Check click against computed targets
-                for (i, (rect, target)) in app.install_click_targets.clone().into_iter().enumerate()
+            let x = column;
+            let y = row;
+            app.debug_log(&format!("handle_event: mouse click at ({x}, {y})"));
+            // Check click against computed targets
+            for (i, (rect, target)) in app.install_click_targets.clone().into_iter().enumerate() {
+                if x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
                 {
-                    if x >= rect.x
-                        && x < rect.x + rect.width
-                        && y >= rect.y
-                        && y < rect.y + rect.height
-                    {
-                        app.install_focus_index = i;
-                        app.debug_log(&format!("handle_event: install_focus_index -> {i}"));
-                        match target {
-                            crate::core::types::InstallClickTarget::Section(screen) => {
-                                app.debug_log(&format!(
-                                    "handle_event: click matched Section({screen:?})"
-                                ));
-                                if let Some(idx) =
-                                    app.menu_entries.iter().position(|m| m.screen == screen)
-                                {
-                                    app.selected_index = idx;
-                                    app.list_state.select(Some(idx));
-                                    app.focus = Focus::Content;
-                                }
-                            }
-                            crate::core::types::InstallClickTarget::InstallButton => {
-                                app.debug_log("handle_event: click matched InstallButton");
-                                super::screens::dispatcher::handle_enter(app);
+                    app.install_focus_index = i;
+                    app.debug_log(&format!("handle_event: install_focus_index -> {i}"));
+                    match target {
+                        crate::core::types::InstallClickTarget::Section(screen) => {
+                            app.debug_log(&format!(
+                                "handle_event: click matched Section({screen:?})"
+                            ));
+                            if let Some(idx) =
+                                app.menu_entries.iter().position(|m| m.screen == screen)
+                            {
+                                app.selected_index = idx;
+                                app.list_state.select(Some(idx));
+                                app.focus = Focus::Content;
                             }
                         }
-                        break;
+                        crate::core::types::InstallClickTarget::InstallButton => {
+                            app.debug_log("handle_event: click matched InstallButton");
+                            super::screens::dispatcher::handle_enter(app);
+                        }
                     }
+                    break;
                 }
             }
         }
@@ -1,6 +1,7 @@
 pub mod app;
 pub mod common;
 pub mod core;
+pub mod headless;
 pub mod input;
 pub mod render;
 pub mod runner;
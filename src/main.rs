@@ -1,6 +1,7 @@
 pub mod app;
 pub mod common;
 pub mod core;
+pub mod headless;
 pub mod input;
 pub mod render;
 pub mod runner;
@@ -29,12 +30,37 @@ fn print_info(debug_enabled: bool, msg: &str) {
 fn main() -> std::io::Result<()> {
     // Detect flags first
     let args: Vec<String> = std::env::args().collect();
+    if let Some(sub @ ("plan" | "validate")) = args.get(1).map(String::as_str) {
+        std::process::exit(run_config_subcommand(sub, &args));
+    }
     let dry_run = args.iter().any(|arg| arg == "--dry-run" || arg == "--dry");
     let debug_enabled = args.iter().any(|arg| arg == "--debug");
+    let unattended = args.iter().any(|arg| arg == "--unattended");
+    let config_path = parse_flag_value(&args, "--config").map(std::path::PathBuf::from);
+    let event_log = app::install::events::event_log_path(
+        parse_flag_value(&args, "--event-log").map(std::path::PathBuf::from),
+    );
+    let resume = match parse_flag_value(&args, "--resume-from") {
+        Some(section) => headless::ResumeMode::Section(section),
+        None if args.iter().any(|arg| arg == "--resume") => headless::ResumeMode::Checkpoint,
+        None => headless::ResumeMode::Fresh,
+    };
     debug_log(
         debug_enabled,
-        &format!("main: parsed flags dry_run={dry_run} debug_enabled={debug_enabled}"),
+        &format!(
+            "main: parsed flags dry_run={dry_run} debug_enabled={debug_enabled} unattended={unattended} config={config_path:?} resume={resume:?} event_log={event_log:?}"
+        ),
     );
+    if unattended && config_path.is_none() {
+        eprintln!("error: --unattended requires --config <path>");
+        std::process::exit(headless::EXIT_INVALID_CONFIG);
+    }
+    if !unattended && resume != headless::ResumeMode::Fresh {
+        eprintln!(
+            "error: --resume/--resume-from require --unattended (the TUI offers to resume on Install)"
+        );
+        std::process::exit(headless::EXIT_INVALID_CONFIG);
+    }
 
     debug_log(debug_enabled, "preflight: start");
     let had_warnings = run_preflight_checks(dry_run, debug_enabled);
@@ -53,8 +79,79 @@ fn main() -> std::io::Result<()> {
             "preflight: warnings detected; continuing without prompt",
         );
     }
-    // TODO(v0.5.0): Parse config path and unattended flags to run non-interactively.
-    runner::run_with_debug(dry_run, debug_enabled)
+    if unattended && let Some(path) = config_path.as_deref() {
+        let code =
+            headless::run_unattended(path, dry_run, debug_enabled, &resume, event_log.as_deref());
+        debug_log(debug_enabled, &format!("unattended: exit code {code}"));
+        std::process::exit(code);
+    }
+    runner::run_with_config(
+        dry_run,
+        debug_enabled,
+        config_path.as_deref(),
+        event_log.as_deref(),
+    )
+}
+
+/// `archinstall-rs plan|validate <config.toml> [--firmware uefi|bios] [--debug]`
+///
+/// Runs before preflight checks so stdout carries only the command's output.
+fn run_config_subcommand(sub: &str, args: &[String]) -> i32 {
+    let debug_enabled = args.iter().any(|arg| arg == "--debug");
+    let mut config_path: Option<std::path::PathBuf> = None;
+    let mut firmware_uefi: Option<bool> = None;
+    let mut iter = args.iter().skip(2);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--debug" => {}
+            "--firmware" => match iter.next().map(String::as_str) {
+                Some("uefi") => firmware_uefi = Some(true),
+                Some("bios") => firmware_uefi = Some(false),
+                other => {
+                    eprintln!("error: --firmware expects 'uefi' or 'bios', got {other:?}");
+                    return headless::EXIT_INVALID_CONFIG;
+                }
+            },
+            other if config_path.is_none() && !other.starts_with("--") => {
+                config_path = Some(other.into());
+            }
+            other => {
+                eprintln!("error: unexpected argument '{other}'");
+                return headless::EXIT_INVALID_CONFIG;
+            }
+        }
+    }
+    let Some(path) = config_path else {
+        eprintln!("usage: archinstall-rs {sub} <config.toml> [--firmware uefi|bios]");
+        return headless::EXIT_INVALID_CONFIG;
+    };
+    debug_log(
+        debug_enabled,
+        &format!(
+            "main: {sub} config={} firmware_uefi={firmware_uefi:?}",
+            path.display()
+        ),
+    );
+    if sub == "validate" {
+        headless::run_validate(&path, firmware_uefi, debug_enabled)
+    } else {
+        headless::run_plan(&path, firmware_uefi, debug_enabled)
+    }
+}
+
+/// Value of `<flag> <value>` or `<flag>=<value>`, if given.
+fn parse_flag_value(args: &[String], flag: &str) -> Option<String> {
+    let prefix = format!("{flag}=");
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+        if arg == flag {
+            return iter.next().cloned();
+        }
+    }
+    None
 }
 
 fn run_preflight_checks(dry_run: bool, debug_enabled: bool) -> bool {
@@ -370,6 +370,7 @@ fn build_items(app: &mut AppState) -> Vec<ListItem<'static>> {
             | Some(PopupKind::XorgTypeSelect)
             | Some(PopupKind::KernelSelect)
             | Some(PopupKind::AdditionalPackageGroupPackages)
+            | Some(PopupKind::RaidMemberDisks)
     );
 
     app.popup_visible_indices
@@ -393,6 +394,10 @@ fn build_items(app: &mut AppState) -> Vec<ListItem<'static>> {
                             false
                         }
                     }
+                    Some(PopupKind::RaidMemberDisks) => app.popup_items.get(i).is_some_and(|row| {
+                        let path = AppState::raid_member_row_path(row);
+                        app.raid_devices.iter().any(|d| d == path)
+                    }),
                     _ => false,
                 };
                 let marker = if checked { "[x]" } else { "[ ]" };
@@ -37,6 +37,7 @@ pub fn draw_popup(frame: &mut Frame, app: &mut AppState) {
     } else if matches!(
         app.popup_kind,
         Some(PopupKind::AbortConfirm)
+            | Some(PopupKind::InstallCancelConfirm)
             | Some(PopupKind::HostnameInput)
             | Some(PopupKind::RootPassword)
             | Some(PopupKind::RootPasswordConfirm)
@@ -54,6 +55,18 @@ pub fn draw_popup(frame: &mut Frame, app: &mut AppState) {
             | Some(PopupKind::NetworkDNS)
             | Some(PopupKind::AurHelperSelect)
             | Some(PopupKind::BtrfsSubvolumePreset)
+            | Some(PopupKind::RootFilesystemSelect)
+            | Some(PopupKind::SwapModeSelect)
+            | Some(PopupKind::SwapfileSizeInput)
+            | Some(PopupKind::ZramSizeInput)
+            | Some(PopupKind::LvmVolumeGroupName)
+            | Some(PopupKind::LvmVolumeName)
+            | Some(PopupKind::LvmVolumeSize)
+            | Some(PopupKind::LvmVolumeFilesystem)
+            | Some(PopupKind::LvmVolumeMountpoint)
+            | Some(PopupKind::DiskEncryptionTpm2Pcrs)
+            | Some(PopupKind::DiskEncryptionLuksParam)
+            | Some(PopupKind::KernelParametersInput)
     ) {
         let w = area.width.clamp(28, 56);
         let h = area.height.clamp(9, 12);
@@ -141,7 +154,25 @@ pub fn draw_popup(frame: &mut Frame, app: &mut AppState) {
         Some(PopupKind::TimezoneSelect) => " Select Timezone ",
         Some(PopupKind::AurHelperSelect) => " Choose AUR helper ",
         Some(PopupKind::BtrfsSubvolumePreset) => " Btrfs Subvolume Layout ",
+        Some(PopupKind::RootFilesystemSelect) => " Root Filesystem ",
+        Some(PopupKind::SwapModeSelect) => " Swap ",
+        Some(PopupKind::SwapfileSizeInput) => " Swapfile Size ",
+        Some(PopupKind::ZramSizeInput) => " zram Size ",
+        Some(PopupKind::LvmVolumeGroupName) => " Volume Group ",
+        Some(PopupKind::LvmVolumeName) => " Logical Volume Name ",
+        Some(PopupKind::LvmVolumeSize) => " Logical Volume Size ",
+        Some(PopupKind::LvmVolumeFilesystem) => " Logical Volume Filesystem ",
+        Some(PopupKind::RaidMemberDisks) => " RAID Member Disks (space to toggle) ",
+        Some(PopupKind::LvmVolumeMountpoint) => " Logical Volume Mountpoint ",
+        Some(PopupKind::DiskEncryptionTpm2Pcrs) => " TPM2 PCRs ",
+        Some(PopupKind::DiskEncryptionLuksParam) => " LUKS Parameter ",
+        Some(PopupKind::KernelParametersInput) => " Kernel Parameters ",
         Some(PopupKind::ConfigLoadSelect) => " Load Configuration (Enter to select) ",
+        Some(PopupKind::ResumeInstall) => " Resume previous installation? ",
+        Some(PopupKind::InstallFailureRecovery) => " Command failed: how to continue? ",
+        Some(PopupKind::InstallCancelConfirm) => " Cancel the installation? ",
+        Some(PopupKind::InstallFailureEdit) => " Edit failed command ",
+        Some(PopupKind::ResumeSectionSelect) => " Resume from section ",
         None => " Select ",
     };
 
@@ -167,6 +198,16 @@ pub fn draw_popup(frame: &mut Frame, app: &mut AppState) {
                 | PopupKind::NetworkIP
                 | PopupKind::NetworkGateway
                 | PopupKind::NetworkDNS
+                | PopupKind::InstallFailureEdit
+                | PopupKind::SwapfileSizeInput
+                | PopupKind::ZramSizeInput
+                | PopupKind::LvmVolumeGroupName
+                | PopupKind::LvmVolumeName
+                | PopupKind::LvmVolumeSize
+                | PopupKind::LvmVolumeMountpoint
+                | PopupKind::DiskEncryptionTpm2Pcrs
+                | PopupKind::DiskEncryptionLuksParam
+                | PopupKind::KernelParametersInput
         )
     ) {
         text_input::draw(frame, app, popup_rect, title_text);
@@ -207,6 +248,9 @@ pub fn draw_popup(frame: &mut Frame, app: &mut AppState) {
             | Some(PopupKind::ManualPartitionEdit)
             | Some(PopupKind::AurHelperSelect)
             | Some(PopupKind::BtrfsSubvolumePreset)
+            | Some(PopupKind::RootFilesystemSelect)
+            | Some(PopupKind::SwapModeSelect)
+            | Some(PopupKind::LvmVolumeFilesystem)
     );
     if !hide_search {
         let search_label = if app.popup_in_search { "/" } else { "" };
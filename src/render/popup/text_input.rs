@@ -41,6 +41,23 @@ pub fn draw(frame: &mut Frame, app: &mut AppState, popup_rect: Rect, title_text:
         Some(PopupKind::NetworkIP) => "Enter IPv4 optional), e.g., 192.168.1.1 or 192.168.1.1/24:",
         Some(PopupKind::NetworkGateway) => "Enter gateway (optional):",
         Some(PopupKind::NetworkDNS) => "Enter DNS (optional):",
+        Some(PopupKind::InstallFailureEdit) => "Edit the command, Enter to run it:",
+        Some(PopupKind::SwapfileSizeInput) => "Swapfile size, e.g. 4G or 512M:",
+        Some(PopupKind::ZramSizeInput) => "MiB or formula, e.g. ram / 2:",
+        Some(PopupKind::LvmVolumeGroupName) => "Volume group name, e.g. vg0:",
+        Some(PopupKind::LvmVolumeName) => "Logical volume name, e.g. root or home:",
+        Some(PopupKind::LvmVolumeSize) => "Size, e.g. 40G, 50%FREE or 100%FREE:",
+        Some(PopupKind::LvmVolumeMountpoint) => "Mountpoint, e.g. / or /home:",
+        Some(PopupKind::DiskEncryptionTpm2Pcrs) => "PCRs joined by +, e.g. 7 or 0+7:",
+        Some(PopupKind::DiskEncryptionLuksParam) => match app.diskenc_focus_index {
+            8 => "Cipher, e.g. aes-xts-plain64 (empty: default):",
+            9 => "Key size in bits, e.g. 512 (empty: default):",
+            10 => "Hash, e.g. sha512 (empty: default):",
+            12 => "Argon2 memory in KiB, e.g. 262144 (empty: default):",
+            13 => "Unlock time in ms, e.g. 2000 (empty: default):",
+            _ => "LUKS label (empty: none):",
+        },
+        Some(PopupKind::KernelParametersInput) => "Extra parameters, e.g. quiet splash:",
         _ => "Enter value:",
     };
     let prompt = Paragraph::new(Line::from(prompt_text))
@@ -12,7 +12,8 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
         0 => "Systemd-boot",
         1 => "Grub",
         2 => EFISTUB_MENU_LABEL,
-        _ => "Limine",
+        3 => "Limine",
+        _ => "rEFInd",
     };
     let info_lines = vec![
         Line::from(Span::styled(
@@ -23,6 +24,7 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
         )),
         Line::from(format!("Bootloader: {bl}")),
         Line::from(format!("Secure Boot: {}", app.secure_boot_status_text())),
+        Line::from(format!("Signing: {}", app.secure_boot_signing().label())),
     ];
 
     let mut desc_lines = vec![Line::from(Span::styled(
@@ -35,6 +37,21 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
     desc_lines.push(Line::from(
         "Efistub (experimental, UEFI only) registers the kernel or a UKI directly with the firmware. Some firmware ignores cmdline options for direct kernel boot; use Unified Kernel Images in that case.",
     ));
+    desc_lines.push(Line::from(
+        "rEFInd (UEFI only) is a graphical boot manager that finds kernels, Unified Kernel Images and other operating systems on its own. The kernel options go to refind_linux.conf next to the kernels.",
+    ));
+    desc_lines.push(Line::from(
+        "Secure Boot signing with sbctl creates your own keys, enrolls them when the firmware is in Setup Mode (optionally next to Microsoft's keys, which option ROMs and Windows need) and signs the boot loader, UKIs and kernels. sbctl's pacman hook keeps them signed after updates.",
+    ));
+    desc_lines.push(Line::from(
+        "Secure Boot signing with shim + MOK keeps the firmware keys as they are: Microsoft's signed shim starts GRUB or systemd-boot signed with your own Machine Owner Key. On the first boot MokManager asks to enroll the key; confirm it with the root password.",
+    ));
+    if let Some(warning) = app.secure_boot_signing_warning() {
+        desc_lines.push(Line::from(Span::styled(
+            warning,
+            Style::default().fg(Color::Yellow),
+        )));
+    }
     if app.bootloader_index == 2 && !app.uki_enabled {
         desc_lines.push(Line::from(Span::styled(
             "Note: Without UKI, Efistub has no UEFI standard fallback path (EFI/BOOT/BOOTX64.EFI). Consider enabling Unified Kernel Images for maximum firmware compatibility.",
@@ -56,6 +56,16 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
                 .clone()
                 .unwrap_or_else(|| "(none)".into());
             info_lines.push(Line::from(format!("Partition: {part}")));
+            if let Some(unlock) = app.disk_encryption_unlock_summary() {
+                info_lines.push(Line::from(format!("Also unlocks with: {unlock}")));
+            }
+            info_lines.push(Line::from(format!("Format: {}", app.luks_format_summary())));
+            if app.disk_encryption_recovery_key {
+                info_lines.push(Line::from(format!(
+                    "Recovery key: {} in the installed system",
+                    crate::core::storage::LuksEnrollment::installed_recovery_key_path("cryptroot")
+                )));
+            }
         }
     }
 
@@ -66,6 +76,10 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             .add_modifier(Modifier::BOLD),
     ))];
     desc_lines.push(Line::from("Disk encryption protects data by converting the contents of a drive into unreadable code, accessible only with a key or password. This ensures sensitive information remains secure even if the device is lost or stolen, safeguarding system and user data against unauthorized access."));
+    desc_lines.push(Line::from(""));
+    desc_lines.push(Line::from("TPM2 and FIDO2 unlock are enrolled with systemd-cryptenroll after formatting and use the systemd (sd-encrypt) initramfs. The TPM2 key is sealed against the listed PCRs (7 = Secure Boot state), so changing them requires re-enrolling; the passphrase always keeps working."));
+    desc_lines.push(Line::from(""));
+    desc_lines.push(Line::from("Empty LUKS parameters keep the cryptsetup defaults (aes-xts-plain64, argon2id). Lower the Argon2 memory on low-RAM machines, and use 4096-byte sectors on NVMe and Advanced Format drives."));
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -5,6 +5,7 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use crate::app::AppState;
+use crate::core::storage::{RootFilesystem, SwapMode, SwapfileSpec};
 
 pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
     let mode = match app.disks_mode_index {
@@ -20,7 +21,13 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             .fg(Color::Cyan)
             .add_modifier(Modifier::BOLD),
     ))];
-    desc_lines.push(Line::from("Disk partitioning divides a storage device into independent sections for system management. The root partition (/) holds essential OS files, home (/home) stores user data, boot (/boot) contains files needed to start the system, and swap ([SWAP]) provides virtual memory to supplement RAM, aiding stability and hibernation."));
+    if app.lvm_open {
+        desc_lines.push(Line::from("LVM pools a physical volume into a volume group and carves it into logical volumes that can be resized later. With encryption, LVM on LUKS unlocks the whole group with one passphrase; LUKS on LVM encrypts each logical volume separately. Sizes accept 40G, 512M, 50%FREE or 100%FREE; percentage volumes are created last."));
+    } else if app.raid_open {
+        desc_lines.push(Line::from("Software RAID (mdadm) gives every member disk the same partitions and assembles the swap and root partitions into arrays. RAID1 mirrors, RAID0 stripes without redundancy, RAID5/6 survive one/two failed disks and RAID10 stripes mirrors. Each disk keeps its own ESP (kept in sync after every package transaction) or GRUB boot code, so the system still boots when a disk fails."));
+    } else {
+        desc_lines.push(Line::from("Disk partitioning divides a storage device into independent sections for system management. The root partition (/) holds essential OS files, home (/home) stores user data, boot (/boot) contains files needed to start the system, and swap ([SWAP]) provides virtual memory to supplement RAM, aiding stability and hibernation."));
+    }
 
     if app.disks_mode_index == 1 {
         // Manual Partitioning: vertical split then 50/50 columns
@@ -77,6 +84,7 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
                 "Btrfs subvolumes: {btrfs_preset_label}"
             )));
         }
+        left_info.extend(lvm_lines(app));
         let left_block = Paragraph::new(left_info)
             .block(Block::default().borders(Borders::ALL).title(" Info "))
             .wrap(Wrap { trim: true });
@@ -204,6 +212,7 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             1 => "GRUB",
             2 => crate::app::bootloader::EFISTUB_SLUG_LABEL,
             3 => "Limine",
+            4 => "rEFInd",
             _ => "other",
         };
         info_lines.push(Line::from(format!("Bootloader: {bl}")));
@@ -212,29 +221,60 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
         if let Some(dev) = &app.disks_selected_device {
             info_lines.push(Line::from(format!("Selected drive: {dev}")));
         }
+        info_lines.extend(raid_lines(app));
+        if app.alongside_selected() {
+            info_lines.push(Line::from(
+                "Install alongside: existing partitions are kept; the layout below goes into the largest free region",
+            ));
+            if app.is_uefi() {
+                info_lines.push(Line::from(
+                    "An existing ESP is reused without formatting (the new one below is only created when there is none)",
+                ));
+            }
+        }
         info_lines.push(Line::from("Planned layout:"));
+        let root_fs = app.root_filesystem().name();
         if app.is_uefi() {
             info_lines.push(Line::from("- gpt: 1024MiB EFI (FAT, ESP) -> /boot"));
-            if app.swap_enabled {
+            if app.swap_mode() == SwapMode::Partition {
                 info_lines.push(Line::from("- swap: 4GiB"));
             }
-            let enc = if app.disk_encryption_type_index == 1 {
-                " (LUKS)"
+            if app.lvm_selected() {
+                info_lines.extend(lvm_lines(app));
             } else {
-                ""
-            };
-            info_lines.push(Line::from(format!("- root: btrfs{enc} (rest)")));
+                let enc = match app.disk_encryption_type_index {
+                    1 if root_fs == "bcachefs" => " (native encryption)",
+                    1 => " (LUKS)",
+                    _ => "",
+                };
+                info_lines.push(Line::from(format!("- root: {root_fs}{enc} (rest)")));
+            }
+            if app.swap_mode() == SwapMode::Swapfile {
+                info_lines.push(Line::from(swapfile_line(app)));
+            }
         } else {
             info_lines.push(Line::from("- gpt: 1MiB bios_boot [bios_grub]"));
-            if app.swap_enabled {
+            if app.swap_mode() == SwapMode::Partition {
                 info_lines.push(Line::from("- swap: 4GiB"));
             }
-            let enc = if app.disk_encryption_type_index == 1 {
-                " (LUKS)"
+            if app.lvm_selected() {
+                info_lines.extend(lvm_lines(app));
             } else {
-                ""
-            };
-            info_lines.push(Line::from(format!("- root: btrfs{enc} (rest)")));
+                let enc = match app.disk_encryption_type_index {
+                    1 if root_fs == "bcachefs" => " (native encryption)",
+                    1 => " (LUKS)",
+                    _ => "",
+                };
+                info_lines.push(Line::from(format!("- root: {root_fs}{enc} (rest)")));
+            }
+            if app.swap_mode() == SwapMode::Swapfile {
+                info_lines.push(Line::from(swapfile_line(app)));
+            }
+            if app.zfs_root_selected() {
+                info_lines.push(Line::from(
+                    "Warning: ZFS root requires UEFI (kernels are kept on the ESP).",
+                ));
+            }
             if bl != "GRUB" && bl != "Limine" {
                 info_lines.push(Line::from(
                     "Warning: Selected bootloader requires UEFI; choose GRUB or Limine for BIOS.",
@@ -242,14 +282,24 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             }
         }
 
-        let btrfs_preset_label = match app.btrfs_subvolume_preset {
-            1 => "Standard (@, @home, @snapshots)",
-            2 => "Extended (@, @home, @var_log, @snapshots)",
-            _ => "Flat (no subvolumes)",
-        };
-        info_lines.push(Line::from(format!(
-            "Btrfs subvolumes: {btrfs_preset_label}"
-        )));
+        if app.zfs_root_selected() {
+            info_lines.push(Line::from(format!(
+                "ZFS pool {}: ROOT/default -> /, home, var/log",
+                crate::core::storage::ZFS_POOL_NAME
+            )));
+            info_lines.push(Line::from(
+                "Requires a live ISO with the zfs module; zfs-dkms comes from archzfs.",
+            ));
+        } else if root_fs == "btrfs" && !app.lvm_selected() {
+            let btrfs_preset_label = match app.btrfs_subvolume_preset {
+                1 => "Standard (@, @home, @snapshots)",
+                2 => "Extended (@, @home, @var_log, @snapshots)",
+                _ => "Flat (no subvolumes)",
+            };
+            info_lines.push(Line::from(format!(
+                "Btrfs subvolumes: {btrfs_preset_label}"
+            )));
+        }
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -332,3 +382,56 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
         frame.render_widget(info, chunks[1]);
     }
 }
+
+fn swapfile_line(app: &AppState) -> String {
+    match app.root_filesystem() {
+        RootFilesystem::Btrfs => format!(
+            "- swapfile: {} in {} -> /swap/swapfile",
+            app.swapfile_size,
+            SwapfileSpec::BTRFS_SUBVOLUME
+        ),
+        fs => format!(
+            "- swapfile: not supported on {}; use a partition or zram",
+            fs.name()
+        ),
+    }
+}
+
+/// Array summary for the planned layout; empty when RAID is off.
+fn raid_lines(app: &AppState) -> Vec<Line<'static>> {
+    if !app.raid_selected() {
+        return Vec::new();
+    }
+    vec![
+        Line::from(format!("RAID: {}", app.raid_summary())),
+        Line::from(format!("Member disks: {}", app.target_disks().join(", "))),
+        Line::from("Each member gets the layout below; root (and swap) are md arrays"),
+    ]
+}
+
+/// Volume group summary for the planned layout; empty when LVM is off.
+fn lvm_lines(app: &AppState) -> Vec<Line<'static>> {
+    if !app.lvm_selected() {
+        return Vec::new();
+    }
+    let enc = if app.lvm_encrypted() {
+        format!(", {}", app.lvm_stacking().label())
+    } else {
+        String::new()
+    };
+    let mut lines = vec![Line::from(format!(
+        "- lvm: {} on {}{enc}",
+        app.lvm_vg_name,
+        app.lvm_physical_volume_label()
+    ))];
+    for lv in &app.lvm_volumes {
+        lines.push(Line::from(format!(
+            "  - {} {} {} -> {}",
+            lv.name,
+            lv.size,
+            lv.fs,
+            lv.mountpoint.as_deref().unwrap_or("[SWAP]")
+        )));
+    }
+    lines
+}
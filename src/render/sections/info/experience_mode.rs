@@ -36,11 +36,7 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
                     let available_width = area.width.saturating_sub(4) as usize;
                     let max_name_len = pkgs.iter().map(|s| s.len()).max().unwrap_or(0).min(32);
                     let col_width = (max_name_len + 2).max(6);
-                    let mut num_cols = if col_width == 0 {
-                        1
-                    } else {
-                        available_width / col_width
-                    };
+                    let mut num_cols = available_width.checked_div(col_width).unwrap_or(1);
                     if num_cols == 0 {
                         num_cols = 1;
                     }
@@ -88,11 +84,7 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
                     let available_width = area.width.saturating_sub(4) as usize;
                     let max_name_len = pkgs.iter().map(|s| s.len()).max().unwrap_or(0).min(32);
                     let col_width = (max_name_len + 2).max(6);
-                    let mut num_cols = if col_width == 0 {
-                        1
-                    } else {
-                        available_width / col_width
-                    };
+                    let mut num_cols = available_width.checked_div(col_width).unwrap_or(1);
                     if num_cols == 0 {
                         num_cols = 1;
                     }
@@ -275,11 +267,7 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             let available_width = area.width.saturating_sub(4) as usize;
             let max_name_len = pkgs.iter().map(|s| s.len()).max().unwrap_or(0).min(32);
             let col_width = (max_name_len + 2).max(6);
-            let mut num_cols = if col_width == 0 {
-                1
-            } else {
-                available_width / col_width
-            };
+            let mut num_cols = available_width.checked_div(col_width).unwrap_or(1);
             if num_cols == 0 {
                 num_cols = 1;
             }
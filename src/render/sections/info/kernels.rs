@@ -20,6 +20,13 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
         let mut v: Vec<&str> = app.selected_kernels.iter().map(|s| s.as_str()).collect();
         v.sort_unstable();
         info_lines.push(Line::from(format!("Kernels: {}", v.join(", "))));
+        info_lines.push(Line::from(format!(
+            "Default boot entry: {}",
+            app.default_kernel()
+        )));
+    }
+    if let Some(params) = app.extra_kernel_parameters() {
+        info_lines.push(Line::from(format!("Extra parameters: {params}")));
     }
 
     let mut desc_lines = vec![Line::from(Span::styled(
@@ -29,6 +36,8 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             .add_modifier(Modifier::BOLD),
     ))];
     desc_lines.push(Line::from("Kernels are the core component of Arch Linux, responsible for managing hardware, system resources, and communication between software and hardware. Arch Linux provides several kernel options, including the latest stable, LTS (Long Term Support), and specialized kernels like zen or hardened, each offering different features and performance characteristics. Users can easily install, switch, or maintain multiple kernels via the package manager. Recommended are at least two Kernels to install."));
+    desc_lines.push(Line::from("Every selected kernel gets its own boot entry and a fallback entry; the default kernel is the one the boot menu starts."));
+    desc_lines.push(Line::from("Extra parameters such as quiet splash, nvidia-drm.modeset=1 or mitigations=off are added to every boot entry after the root and encryption options the installer sets."));
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -5,6 +5,7 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use crate::app::AppState;
+use crate::core::storage::{SwapEncryption, SwapMode};
 
 pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
     let mut info_lines = vec![Line::from(Span::styled(
@@ -14,21 +15,57 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             .add_modifier(Modifier::BOLD),
     ))];
 
-    let swap = if app.swap_enabled {
-        "Enabled"
-    } else {
-        "Disabled"
-    };
-    info_lines.push(Line::from(format!("Swapon: {swap}")));
-    info_lines.push(Line::from(
-        "Swapon can be used to activate the swap partition.",
-    ));
-    info_lines.push(Line::from(
-        "If disabled here, the system will be configured with swapoff.",
-    ));
-    info_lines.push(Line::from(
-        "You can always run 'swapon' later to activate the swap partition.",
-    ));
+    info_lines.push(Line::from(format!("Swap: {}", app.swap_summary())));
+    match app.swap_mode() {
+        SwapMode::Partition => {
+            info_lines.push(Line::from(
+                "Automatic layouts get a 4GiB swap partition; manual layouts use SWAP partitions.",
+            ));
+            info_lines.push(Line::from(
+                "It is activated during the install and recorded in /etc/fstab.",
+            ));
+            match app.swap_encryption() {
+                _ if !app.swap_encryption_available() => info_lines.push(Line::from(
+                    "Encryption applies when the root is encrypted (Disk Encryption: LUKS).",
+                )),
+                SwapEncryption::None => info_lines.push(Line::from(
+                    "Encryption: none — swapped-out memory is written in plaintext.",
+                )),
+                SwapEncryption::RandomKey => info_lines.push(Line::from(
+                    "Encryption: plain dm-crypt from /etc/crypttab with a new random key every boot. Hibernation is not possible.",
+                )),
+                SwapEncryption::Keyfile => info_lines.push(Line::from(
+                    "Encryption: LUKS2 unlocked in the initramfs by a key file in /etc/cryptsetup-keys.d on the encrypted root (systemd initramfs). Hibernation works.",
+                )),
+            }
+        }
+        SwapMode::Swapfile => {
+            info_lines.push(Line::from(
+                "On btrfs the file is /swap/swapfile in its own @swap subvolume, created NOCOW with 'btrfs filesystem mkswapfile' and kept out of root snapshots.",
+            ));
+            info_lines.push(Line::from(
+                "Other filesystems get /swapfile. ZFS and bcachefs roots cannot hold a swap file.",
+            ));
+            info_lines.push(Line::from("The file is recorded in /etc/fstab."));
+        }
+        SwapMode::Zram => {
+            info_lines.push(Line::from(
+                "zram-generator creates a compressed swap device in RAM at boot from /etc/systemd/zram-generator.conf.",
+            ));
+            info_lines.push(Line::from(
+                "Size is in MiB or a formula over 'ram', e.g. 'ram / 2' or 'min(ram, 8192)'.",
+            ));
+            info_lines.push(Line::from(
+                "No disk space is used and nothing goes in /etc/fstab.",
+            ));
+        }
+        SwapMode::None => {
+            info_lines.push(Line::from("The system is installed without swap."));
+            info_lines.push(Line::from(
+                "You can add a swap file or zram-generator later.",
+            ));
+        }
+    }
 
     let mut desc_lines = vec![Line::from(Span::styled(
         "Description",
@@ -36,7 +73,8 @@ pub(super) fn render(frame: &mut Frame, app: &mut AppState, area: Rect) {
             .fg(Color::Cyan)
             .add_modifier(Modifier::BOLD),
     ))];
-    desc_lines.push(Line::from("A swap partition in Linux acts as an extension of physical memory (RAM), using disk space to store inactive memory pages when RAM is full. It helps prevent system crashes under heavy load, enables hibernation by saving the RAM state to disk, and allows the operating system to run more applications than would otherwise fit in physical memory."));
+    desc_lines.push(Line::from("Swap in Linux acts as an extension of physical memory (RAM), using disk space to store inactive memory pages when RAM is full. It helps prevent system crashes under heavy load, enables hibernation by saving the RAM state to disk, and allows the operating system to run more applications than would otherwise fit in physical memory."));
+    desc_lines.push(Line::from("zram keeps swapped pages compressed in RAM instead: faster than disk swap, but it cannot hold a hibernation image."));
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -42,13 +42,15 @@ fn draw_install_split(
             Span::styled(title.clone(), style),
         ]));
     }
-    let left_block = Block::default()
-        .borders(Borders::ALL)
-        .title(if app.install_running {
+    let left_block = Block::default().borders(Borders::ALL).title(
+        if app.install_running && app.install_cancel.is_some() {
+            " Installation in progress (Esc: cancel) "
+        } else if app.install_running {
             " Installation in progress "
         } else {
             " Installation status "
-        });
+        },
+    );
     let left_par = Paragraph::new(left_lines)
         .block(left_block)
         .wrap(Wrap { trim: true });
@@ -178,20 +180,36 @@ pub fn draw_sections_with_theme(frame: &mut Frame, app: &mut AppState, theme: Th
             x: size.width / 4,
             y: size.height / 3,
             width: size.width / 2,
-            height: 7,
+            height: 7
+                + u16::from(!app.install_warnings.is_empty())
+                + app.install_recovery_keys.len() as u16,
         };
         // Clear popup area to avoid background artifacts
         frame.render_widget(ratatui::widgets::Clear, area);
         let block = Block::default()
             .borders(Borders::ALL)
             .title(" Reboot now? ");
-        let text = vec![
-            Line::from(Span::styled(
-                "Installation completed.",
-                Style::default()
-                    .fg(theme.success)
-                    .add_modifier(Modifier::BOLD),
-            )),
+        let mut text = vec![Line::from(Span::styled(
+            "Installation completed.",
+            Style::default()
+                .fg(theme.success)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        if !app.install_warnings.is_empty() {
+            text.push(Line::from(Span::styled(
+                format!(
+                    "{} command(s) were skipped; see the log for details.",
+                    app.install_warnings.len()
+                ),
+                Style::default().fg(theme.warning),
+            )));
+        }
+        for path in &app.install_recovery_keys {
+            text.push(Line::from(format!(
+                "LUKS recovery key saved to {path}; copy it somewhere safe."
+            )));
+        }
+        text.extend([
             Line::from(""),
             Line::from("Do you want to reboot now? [Y/n]"),
             Line::from(""),
@@ -199,7 +217,7 @@ pub fn draw_sections_with_theme(frame: &mut Frame, app: &mut AppState, theme: Th
                 "Press Y/Enter to reboot, N/Esc to cancel.",
                 Style::default().fg(theme.highlight),
             )),
-        ];
+        ]);
         let par = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
         frame.render_widget(par, area);
     }
@@ -71,6 +71,16 @@ fn format_runlog_line(raw: &str) -> Option<String> {
 }
 
 pub fn run_with_debug(dry_run: bool, debug_enabled: bool) -> io::Result<()> {
+    run_with_config(dry_run, debug_enabled, None, None)
+}
+
+/// Run the TUI, optionally preloading the config at `config_path` (`--config` without `--unattended`).
+pub fn run_with_config(
+    dry_run: bool,
+    debug_enabled: bool,
+    config_path: Option<&std::path::Path>,
+    event_log: Option<&std::path::Path>,
+) -> io::Result<()> {
     debug_log(debug_enabled, "TUI init: enable_raw_mode");
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -89,7 +99,13 @@ pub fn run_with_debug(dry_run: bool, debug_enabled: bool) -> io::Result<()> {
     debug_log(debug_enabled, "TUI init: terminal initialized and cleared");
 
     debug_log(debug_enabled, "run loop: start run_loop_with_debug");
-    let res = run_loop_with_debug(&mut terminal, dry_run, debug_enabled);
+    let res = run_loop_with_debug(
+        &mut terminal,
+        dry_run,
+        debug_enabled,
+        config_path,
+        event_log,
+    );
     debug_log(
         debug_enabled,
         &format!(
@@ -122,6 +138,8 @@ fn run_loop_with_debug(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     dry_run: bool,
     debug_enabled: bool,
+    config_path: Option<&std::path::Path>,
+    event_log: Option<&std::path::Path>,
 ) -> io::Result<()> {
     debug_log(
         debug_enabled,
@@ -131,8 +149,13 @@ fn run_loop_with_debug(
     );
     let mut app = AppState::new(dry_run);
     app.debug_enabled = debug_enabled;
+    app.install_event_log = event_log.map(std::path::Path::to_path_buf);
     app.detect_secure_boot_state();
     app.apply_secure_boot_uki_policy();
+    if let Some(path) = config_path {
+        let msg = app.load_config_and_message(path);
+        app.open_info_popup(msg);
+    }
     debug_log(
         debug_enabled,
         "run_loop_with_debug: entering run_loop_inner",
@@ -140,6 +163,46 @@ fn run_loop_with_debug(
     run_loop_inner(terminal, &mut app)
 }
 
+/// Leave the TUI, run an interactive shell for fixing a failed command, then restore the TUI.
+fn run_recovery_shell(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &AppState,
+) -> io::Result<()> {
+    debug_log(app.debug_enabled, "recovery shell: suspending TUI");
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        terminal::LeaveAlternateScreen
+    )?;
+    terminal.show_cursor()?;
+    if let Some(failure) = &app.install_failure {
+        println!("Failed in '{}': {}", failure.section, failure.command);
+        println!("{}", failure.message);
+    }
+    println!("Type 'exit' to return to the installer.\n");
+    let mut cmd = crate::app::install::recovery_shell_command();
+    match cmd.status() {
+        Ok(st) => debug_log(
+            app.debug_enabled,
+            &format!("recovery shell: exited with {:?}", st.code()),
+        ),
+        Err(e) => {
+            debug_log(app.debug_enabled, &format!("recovery shell: error {e}"));
+            eprintln!("Could not start a shell: {e}");
+        }
+    }
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        terminal::EnterAlternateScreen,
+        EnableMouseCapture
+    )?;
+    terminal.clear()?;
+    debug_log(app.debug_enabled, "recovery shell: TUI restored");
+    Ok(())
+}
+
 fn run_loop_inner(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut AppState,
@@ -191,6 +254,15 @@ fn run_loop_inner(
             app.install_completed = true;
             debug_log(app.debug_enabled, "state: install_completed=true");
         }
+        // An install that ended before its last section failed; progress is checkpointed
+        if !app.dry_run
+            && install_was_running
+            && !app.install_running
+            && !app.install_completed
+            && !app.install_section_titles.is_empty()
+        {
+            app.handle_install_failed();
+        }
         install_was_running = app.install_running;
 
         // If install just finished (not dry-run), trigger reboot prompt
@@ -284,6 +356,13 @@ fn run_loop_inner(
             }
         }
 
+        // Recovery shell requested from the failure popup: suspend the TUI while it runs
+        if app.install_shell_requested {
+            app.install_shell_requested = false;
+            run_recovery_shell(terminal, app)?;
+            app.open_install_failure_popup();
+        }
+
         // Handle reboot decision without tearing down the TUI
         if let Some(true) = app.reboot_confirmed {
             debug_log(
@@ -394,7 +473,7 @@ fn run_loop_inner(
                         }
                     };
                     if let Err(e) = cmd.write_passphrase_to_stdin(&mut child) {
-                        let msg = format!("Failed to pass LUKS passphrase: {red} ({e})");
+                        let msg = format!("Failed to pass passphrase: {red} ({e})");
                         any_error = Some(msg.clone());
                         eprintln!("{}", any_error.as_ref().unwrap());
                         debug_log(app.debug_enabled, &format!("stdout-mode: stdin error: {e}"));
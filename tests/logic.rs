@@ -4,6 +4,14 @@ fn make_state() -> ai::app::AppState {
     ai::app::AppState::new(true)
 }
 
+/// Automatic layout on `/dev/sda` with UEFI firmware, the base of the scenario fixtures.
+fn uefi_auto_state() -> ai::app::AppState {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.firmware_uefi_override = Some(true);
+    state
+}
+
 #[test]
 fn utils_redact_chpasswd_and_cryptsetup() {
     // chpasswd preserves username, redacts password
@@ -298,9 +306,51 @@ fn bootloader_grub_luks_injects_cmdline() {
     assert!(joined.contains("grub-mkconfig"), "{joined}");
 }
 
+#[test]
+fn bootloader_grub_writes_config_to_boot_when_esp_is_at_efi() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_mode_index = 1; // manual
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.bootloader_index = 1; // GRUB
+    for (role, fs, start, size, mountpoint) in [
+        ("BOOT", "fat32", "1048576", "536870912", "/efi"),
+        ("ROOT", "ext4", "537919488", "100%", "/"),
+    ] {
+        state
+            .disks_partitions
+            .push(ai::core::types::DiskPartitionSpec {
+                name: Some("/dev/sda".into()),
+                role: Some(role.into()),
+                fs: Some(fs.into()),
+                start: Some(start.into()),
+                size: Some(size.into()),
+                mountpoint: Some(mountpoint.into()),
+                ..Default::default()
+            });
+    }
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("manual plan should compile");
+    assert_eq!(storage_plan.esp_chroot_mountpoint(), "/efi");
+    let joined = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    )
+    .commands
+    .join("\n");
+    assert!(joined.contains("--efi-directory=/efi"), "{joined}");
+    assert!(
+        joined.contains("grub-mkconfig -o /boot/grub/grub.cfg"),
+        "{joined}"
+    );
+    assert!(!joined.contains("/efi/grub/grub.cfg"), "{joined}");
+}
+
 #[test]
 fn bootloader_efistub_creates_efibootmgr_entry() {
     let mut state = make_state(); // UEFI
+    state.firmware_uefi_override = Some(true);
     state.disks_selected_device = Some("/dev/sda".into());
     state.bootloader_index = 2; // EFISTUB
     let device = "/dev/sda";
@@ -350,6 +400,7 @@ fn bootloader_efistub_creates_efibootmgr_entry() {
 #[test]
 fn bootloader_efistub_luks_uses_shared_cmdline() {
     let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
     state.disks_selected_device = Some("/dev/sda".into());
     state.disk_encryption_type_index = 1; // LUKS
     state.bootloader_index = 2; // EFISTUB
@@ -513,272 +564,769 @@ fn bootloader_limine_luks_cmdline() {
 }
 
 #[test]
-fn uki_pacstrap_includes_systemd_ukify_when_enabled() {
-    let mut state = make_state();
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.uki_enabled = true;
-    state.bootloader_index = 0; // systemd-boot
-    let plan = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
-    let joined = plan.commands.join("\n");
-    assert!(
-        joined.contains("systemd-ukify"),
-        "UKI requires systemd-ukify in pacstrap: {joined}"
-    );
-}
-
-#[test]
-fn uki_pacstrap_omits_systemd_ukify_for_grub() {
-    let mut state = make_state();
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.uki_enabled = true;
-    state.bootloader_index = 1; // GRUB — UKI not integrated
-    let plan = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
-    let joined = plan.commands.join("\n");
-    assert!(
-        !joined.contains("systemd-ukify"),
-        "GRUB path should not pull in systemd-ukify: {joined}"
-    );
-}
-
-#[test]
-fn uki_sysconfig_writes_cmdline_and_preset() {
-    let mut state = make_state();
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.uki_enabled = true;
-    state.bootloader_index = 0;
+fn bootloader_refind_uefi_writes_refind_linux_conf_and_hook() {
+    let mut state = uefi_auto_state();
+    state.bootloader_index = 4; // rEFInd
+    state.uki_enabled = false;
+    let device = "/dev/sda";
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
-    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        device,
+        &storage_plan,
+    );
     let joined = plan.commands.join("\n");
     assert!(
-        joined.contains("/etc/kernel/cmdline"),
-        "UKI needs /etc/kernel/cmdline before mkinitcpio: {joined}"
+        joined.contains("/usr/share/refind/refind_x64.efi /boot/EFI/refind/refind_x64.efi"),
+        "{joined}"
     );
+    assert!(joined.contains("/boot/EFI/refind/refind.conf"), "{joined}");
     assert!(
-        joined.contains("mkinitcpio.d/linux.preset"),
-        "UKI should patch linux.preset: {joined}"
+        joined.contains("extra_kernel_version_strings linux"),
+        "{joined}"
     );
+    assert!(joined.contains("/boot/refind_linux.conf"), "{joined}");
     assert!(
-        joined.contains("default_uki=") && joined.contains("/boot/EFI/Linux/arch-linux.efi"),
+        joined.contains("\"Boot with standard options\"  \"$OPTS"),
         "{joined}"
     );
+    assert!(joined.contains("initramfs-%v-fallback.img"), "{joined}");
+    assert!(joined.contains("99-refind.hook"), "{joined}");
+    assert!(joined.contains("Target = refind"), "{joined}");
     assert!(
-        joined.contains("out=$(mkinitcpio -P 2>&1); rc=$?;"),
+        joined.contains("--label '\\''rEFInd Boot Manager'\\''"),
         "{joined}"
     );
+    assert!(!joined.contains("scan_all_linux_kernels"), "{joined}");
 }
 
 #[test]
-fn uki_sysconfig_runs_mkinitcpio_without_encryption() {
-    let mut state = make_state();
-    state.disks_selected_device = Some("/dev/sda".into());
+fn bootloader_refind_uki_scans_efi_linux_only() {
+    let mut state = uefi_auto_state();
+    state.bootloader_index = 4;
     state.uki_enabled = true;
-    state.bootloader_index = 2; // EFISTUB
-    state.disk_encryption_type_index = 0; // none
+    state.selected_kernels = ["linux".to_string(), "linux-lts".to_string()].into();
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
-    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
     let joined = plan.commands.join("\n");
     assert!(
-        joined.contains("out=$(mkinitcpio -P 2>&1); rc=$?;"),
-        "UKI must run mkinitcpio even without LUKS: {joined}"
+        joined.contains("also_scan_dirs +,EFI/Linux\nscan_all_linux_kernels false"),
+        "{joined}"
     );
     assert!(
-        !joined.contains("block sd-encrypt") && !joined.contains("block encrypt"),
-        "non-encrypted UKI must not inject encrypt hooks: {joined}"
+        joined.contains("extra_kernel_version_strings linux-lts,linux\n"),
+        "longer kernel names must come first: {joined}"
     );
+    assert!(joined.contains("/boot/EFI/Linux/*.efi"), "{joined}");
+    assert!(!joined.contains("refind_linux.conf"), "{joined}");
 }
 
 #[test]
-fn uki_systemd_boot_uses_efi_path() {
+fn bootloader_refind_is_uefi_only() {
     let mut state = make_state();
-    state.firmware_uefi_override = Some(true);
+    state.firmware_uefi_override = Some(false);
     state.disks_selected_device = Some("/dev/sda".into());
-    state.uki_enabled = true;
-    state.bootloader_index = 0;
-    let device = "/dev/sda";
+    state.bootloader_index = 4;
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
     let plan = ai::core::services::bootloader::BootloaderService::build_plan(
         &state,
-        device,
+        "/dev/sda",
         &storage_plan,
     );
-    let joined = plan.commands.join("\n");
-    assert!(
-        joined.contains("efi     /EFI/Linux/arch-linux.efi"),
-        "systemd-boot UKI entries use efi path: {joined}"
-    );
     assert!(
-        !joined.contains("linux   /vmlinuz-linux"),
-        "UKI entries should not use a separate linux line: {joined}"
+        plan.commands.iter().all(|c| !c.contains("refind")),
+        "{:?}",
+        plan.commands
     );
 }
 
 #[test]
-fn uki_efistub_points_to_uki_loader() {
-    let mut state = make_state();
-    state.firmware_uefi_override = Some(true);
-    state.disks_selected_device = Some("/dev/sda".into());
+fn sbctl_signing_enrolls_keys_and_signs_the_boot_chain() {
+    let mut state = uefi_auto_state();
     state.uki_enabled = true;
-    state.bootloader_index = 2;
-    let device = "/dev/sda";
+    state.secure_boot_signing_index = 1; // sbctl
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
-    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
-        &state,
-        device,
-        &storage_plan,
-    );
-    let joined = plan.commands.join("\n");
-    assert!(
-        joined.contains("arch-linux.efi") && joined.contains("EFI"),
-        "EFISTUB UKI should register the combined UKI path: {joined}"
-    );
+    let build = |state: &ai::app::AppState| {
+        ai::core::services::bootloader::BootloaderService::build_plan(
+            state,
+            "/dev/sda",
+            &storage_plan,
+        )
+        .commands
+        .join("\n")
+    };
+
+    let joined = build(&state);
+    let loader = joined.find("bootctl --no-pager install").expect(&joined);
+    let keys = joined.find("sbctl create-keys").expect(&joined);
+    assert!(loader < keys, "signing runs after the loader is in place");
+    assert!(joined.contains("sbctl enroll-keys --microsoft"), "{joined}");
     assert!(
-        !joined.contains("vmlinuz-linux"),
-        "EFISTUB UKI should not register raw vmlinuz: {joined}"
+        joined.contains("-o /usr/lib/systemd/boot/efi/systemd-bootx64.efi.signed"),
+        "{joined}"
     );
     assert!(
-        joined.contains("EFI/BOOT/BOOTX64.EFI"),
-        "EFISTUB UKI should install UEFI fallback copy: {joined}"
+        joined.contains("find /boot/EFI -path /boot/EFI/Microsoft -prune"),
+        "{joined}"
     );
+    assert!(joined.contains("sbctl sign -s \"$f\""), "{joined}");
+    assert!(!joined.contains("zz-sbctl-bootloader.hook"), "{joined}");
+    let pacstrap = ai::core::services::system::SystemService::build_pacstrap_plan(&state)
+        .commands
+        .join("\n");
+    assert!(pacstrap.contains("sbctl"), "{pacstrap}");
+
+    state.bootloader_index = 4; // rEFInd copies its binary from /usr/share
+    state.secure_boot_microsoft_keys = false;
+    let joined = build(&state);
     assert!(
-        joined.contains("99-efistub-uki-fallback.hook"),
-        "EFISTUB UKI should install pacman hook to refresh fallback: {joined}"
+        joined.contains("sbctl enroll-keys --yes-this-might-brick-my-machine"),
+        "{joined}"
     );
+    assert!(joined.contains("zz-sbctl-bootloader.hook"), "{joined}");
+    assert!(joined.contains("Target = refind"), "{joined}");
+    assert!(!joined.contains("systemd-bootx64.efi.signed"), "{joined}");
+
+    state.secure_boot_signing_index = 0;
+    assert!(!build(&state).contains("sbctl"));
 }
 
 #[test]
-fn uki_limine_uses_efi_protocol() {
+fn secure_boot_signing_round_trips_and_warns_when_missing() {
     let mut state = make_state();
-    state.firmware_uefi_override = Some(true);
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.uki_enabled = true;
-    state.bootloader_index = 3;
-    let device = "/dev/sda";
-    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
-        .expect("auto plan should compile");
-    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
-        &state,
-        device,
-        &storage_plan,
-    );
-    let joined = plan.commands.join("\n");
-    assert!(joined.contains("protocol: efi"), "{joined}");
-    assert!(joined.contains("/EFI/Linux/arch-linux.efi"), "{joined}");
+    state.secure_boot_override = Some(true);
+    assert!(state.secure_boot_signing_warning().is_some());
+    state.cycle_secure_boot_signing(true);
+    assert!(state.secure_boot_signing_warning().is_none());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sbctl.toml");
+    std::fs::write(
+        &path,
+        "[bootloader]\nkind = \"limine\"\nsigning = \"sbctl\"\nmicrosoft_keys = false\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert_eq!(state.secure_boot_signing_index, 1);
+    assert!(!state.secure_boot_microsoft_keys);
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("signing = \"sbctl\""), "{saved}");
+    assert!(saved.contains("microsoft_keys = false"), "{saved}");
+
+    let diags =
+        make_state().validate_config_text("[bootloader]\nkind = \"grub\"\nsigning = \"sbctl\"\n");
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"bootloader.signing"), "{diags:?}");
+    let diags =
+        make_state().validate_config_text("[bootloader]\nkind = \"limine\"\nsigning = \"mok\"\n");
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"bootloader.signing"), "{diags:?}");
 }
 
-/// Pre-mounted install flow passes an empty partition target; EFISTUB still resolves ESP via `findmnt /boot`.
 #[test]
-fn pre_mounted_efistub_empty_target_still_generates_efibootmgr() {
-    let mut state = make_state();
-    state.firmware_uefi_override = Some(true);
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.bootloader_index = 2;
+fn shim_signing_chainloads_a_mok_signed_loader() {
+    let mut state = uefi_auto_state();
+    state.bootloader_index = 1; // GRUB
+    state.secure_boot_signing_index = 2; // shim
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
-    let plan =
-        ai::core::services::bootloader::BootloaderService::build_plan(&state, "", &storage_plan);
-    let joined = plan.commands.join("\n");
+    let build = |state: &ai::app::AppState| {
+        ai::core::services::bootloader::BootloaderService::build_plan(
+            state,
+            "/dev/sda",
+            &storage_plan,
+        )
+        .commands
+        .join("\n")
+    };
+
+    let joined = build(&state);
     assert!(
-        joined.contains("efibootmgr --create"),
-        "EFISTUB must not depend on a whole-disk target string: {joined}"
+        joined.contains("--sbat /usr/share/grub/sbat.csv"),
+        "{joined}"
     );
-    assert!(joined.contains("vmlinuz-linux"), "{joined}");
+    assert!(joined.contains("--modules=\"all_video"), "{joined}");
     assert!(
-        joined.contains("startup.nsh"),
-        "pre-mounted non-UKI EFISTUB should still write startup.nsh: {joined}"
+        joined.contains("aur.archlinux.org/shim-signed.git"),
+        "{joined}"
+    );
+    // The reviewed AUR commit is built and its binaries are checked before reaching the ESP
+    use ai::core::services::bootloader::{MMX64_SHA256, SHIM_SIGNED_AUR_COMMIT, SHIMX64_SHA256};
+    assert!(
+        joined.contains(&format!(
+            "git checkout --detach {SHIM_SIGNED_AUR_COMMIT} && makepkg"
+        )),
+        "{joined}"
+    );
+    let verify = joined
+        .find("| sha256sum -c -")
+        .expect("pinned sha256 check");
+    assert!(
+        joined[..verify].contains(&format!("{SHIMX64_SHA256} /usr/share/shim-signed/shimx64.efi {MMX64_SHA256} /usr/share/shim-signed/mmx64.efi")),
+        "{joined}"
+    );
+    assert!(
+        verify
+            < joined
+                .find("cp /usr/share/shim-signed/shimx64.efi")
+                .unwrap(),
+        "{joined}"
+    );
+    assert!(
+        joined.contains("--output /boot/EFI/GRUB/grubx64.efi /boot/EFI/GRUB/grubx64.efi"),
+        "{joined}"
+    );
+    assert!(
+        joined.contains("shimx64.efi /boot/EFI/BOOT/BOOTX64.EFI"),
+        "{joined}"
+    );
+    assert!(joined.contains("zz-mok-sign.hook"), "{joined}");
+    let enroll = ai::core::services::bootloader::BootloaderService::mok_enroll_cmd(&state)
+        .expect("MOK enrollment");
+    assert_eq!(
+        enroll.for_log(),
+        "arch-chroot /mnt mokutil --import '/etc/secureboot/mok/MOK.cer'"
+    );
+    assert!(joined.contains("EFI\\GRUB\\shimx64.efi"), "{joined}");
+    assert!(!joined.contains("sbctl"), "{joined}");
+    let pacstrap = ai::core::services::system::SystemService::build_pacstrap_plan(&state)
+        .commands
+        .join("\n");
+    assert!(pacstrap.contains("mokutil"), "{pacstrap}");
+    assert!(pacstrap.contains("sbsigntools"), "{pacstrap}");
+
+    state.bootloader_index = 0; // systemd-boot goes in as grubx64.efi
+    let joined = build(&state);
+    assert!(
+        joined.contains(
+            "--output /boot/EFI/systemd/grubx64.efi /usr/lib/systemd/boot/efi/systemd-bootx64.efi"
+        ),
+        "{joined}"
+    );
+    assert!(
+        joined.contains("Target = usr/lib/systemd/boot/efi/systemd-bootx64.efi"),
+        "{joined}"
     );
+    assert!(!joined.contains("--sbat"), "{joined}");
+
+    state.bootloader_index = 3; // Limine is not supported behind shim
+    assert!(!build(&state).contains("shimx64.efi"));
+    assert!(ai::core::services::bootloader::BootloaderService::mok_enroll_cmd(&state).is_none());
 }
 
-/// Same as pre-mounted: Limine UEFI uses `findmnt` + `efibootmgr`, not the partition-target disk string.
 #[test]
-fn pre_mounted_limine_uefi_empty_target_still_installs() {
-    let mut state = make_state();
-    state.firmware_uefi_override = Some(true);
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.bootloader_index = 3;
-    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
-        .expect("auto plan should compile");
-    let plan =
-        ai::core::services::bootloader::BootloaderService::build_plan(&state, "", &storage_plan);
-    let joined = plan.commands.join("\n");
-    assert!(joined.contains("/boot/limine.conf"), "{joined}");
-    assert!(joined.contains("efibootmgr --create"), "{joined}");
+fn shim_mok_password_is_redacted_and_config_validated() {
+    use ai::common::install_cmd::InstallCmd;
+    let mut state = uefi_auto_state();
+    state.bootloader_index = 1; // GRUB
+    state.secure_boot_signing_index = 2; // shim
+    let password = "pa$HOME`id`\\\"x";
+    state.root_password = password.into();
+    let sections = state.build_install_sections("/dev/sda");
+    let (_, boot) = sections
+        .iter()
+        .find(|(title, _)| title == "Bootloader setup")
+        .unwrap();
+    // The password only travels inside the stdin-fed variant, never in shell text
+    assert!(matches!(
+        boot.last(),
+        Some(InstallCmd::MokImport { password: pw, .. }) if pw == password
+    ));
+    for cmd in sections.iter().flat_map(|(_, cmds)| cmds) {
+        let text = match cmd {
+            InstallCmd::Shell(text) => text.clone(),
+            other => other.for_log(),
+        };
+        assert!(!text.contains("pa$HOME"), "{text}");
+        assert!(!text.contains("--generate-hash"), "{text}");
+    }
+
+    let diags =
+        make_state().validate_config_text("[bootloader]\nkind = \"limine\"\nsigning = \"shim\"\n");
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"bootloader.signing"), "{diags:?}");
+    let diags =
+        make_state().validate_config_text("[bootloader]\nkind = \"grub\"\nsigning = \"shim\"\n");
     assert!(
-        joined.contains("EFI/BOOT/BOOTX64.EFI"),
-        "should install to UEFI fallback path: {joined}"
+        diags.iter().all(|d| d.path != "bootloader.signing"),
+        "{diags:?}"
     );
 }
 
-// ── Multi-kernel tests ──
+fn zfs_state() -> ai::app::AppState {
+    let mut state = uefi_auto_state();
+    state.disks_root_fs_index = 1; // ZFS
+    state
+}
 
 #[test]
-fn multi_kernel_efistub_creates_entries_for_each_kernel() {
-    let mut state = make_state();
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.bootloader_index = 2; // EFISTUB
-    state.selected_kernels.insert("linux-lts".into());
-    let device = "/dev/sda";
+fn zfs_root_sysconfig_uses_udev_zfs_hooks_and_services() {
+    let state = zfs_state();
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
-        .expect("auto plan should compile");
-    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
-        &state,
-        device,
-        &storage_plan,
-    );
+        .expect("zfs plan should compile");
+    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
     let joined = plan.commands.join("\n");
     assert!(
-        joined.contains("vmlinuz-linux-lts"),
-        "EFISTUB should create entry for linux-lts: {joined}"
-    );
-    assert!(
-        joined.contains("vmlinuz-linux'") || joined.contains("vmlinuz-linux\\"),
-        "EFISTUB should also have entry for linux: {joined}"
+        joined.contains("HOOKS=(base udev autodetect microcode modconf kms keyboard keymap consolefont block zfs filesystems)"),
+        "{joined}"
     );
     assert!(
-        joined.contains("initramfs-linux-lts.img"),
-        "EFISTUB should reference linux-lts initramfs: {joined}"
+        joined.contains("enable zfs-import-cache.service"),
+        "{joined}"
     );
+    assert!(joined.contains("mkinitcpio -P"), "{joined}");
 }
 
 #[test]
-fn multi_kernel_systemd_boot_creates_entries_for_each_kernel() {
-    let mut state = make_state();
-    state.disks_selected_device = Some("/dev/sda".into());
-    state.bootloader_index = 0; // systemd-boot
-    state.selected_kernels.insert("linux-lts".into());
-    let device = "/dev/sda";
+fn zfs_root_bootloaders_use_zfs_cmdline() {
+    let mut state = zfs_state();
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
-        .expect("auto plan should compile");
+        .expect("zfs plan should compile");
     let plan = ai::core::services::bootloader::BootloaderService::build_plan(
         &state,
-        device,
+        "/dev/sda",
         &storage_plan,
     );
     let joined = plan.commands.join("\n");
     assert!(
-        joined.contains("arch-linux-lts.conf"),
-        "systemd-boot should create conf for linux-lts: {joined}"
+        joined.contains("root=ZFS=zroot/ROOT/default rw"),
+        "{joined}"
     );
-    assert!(
-        joined.contains("arch.conf") || joined.contains("arch-fallback.conf"),
-        "systemd-boot should still have linux entries: {joined}"
+    assert!(!joined.contains("rootdev=$(findmnt"), "{joined}");
+
+    state.bootloader_index = 1; // GRUB
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
     );
+    let joined = plan.commands.join("\n");
+    assert!(joined.contains("GRUB_CMDLINE_LINUX"), "{joined}");
     assert!(
-        joined.contains("vmlinuz-linux-lts"),
-        "systemd-boot should reference linux-lts kernel: {joined}"
+        joined.contains("ZPOOL_VDEV_NAME_PATH=1 grub-mkconfig"),
+        "{joined}"
     );
 }
 
 #[test]
-fn multi_kernel_limine_creates_entries_for_each_kernel() {
+fn zfs_root_pulls_archzfs_packages_and_headers() {
+    let state = zfs_state();
+    let pre = ai::core::services::system::SystemService::build_pre_install_plan(&state);
+    let pre = pre.commands.join("\n");
+    assert!(
+        pre.contains("[archzfs]") && pre.contains("/etc/pacman.conf"),
+        "{pre}"
+    );
+    let plan = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    let joined = plan.commands.join("\n");
+    for pkg in ["zfs-dkms", "zfs-utils", "linux-headers"] {
+        assert!(joined.contains(pkg), "{pkg}: {joined}");
+    }
+}
+
+#[test]
+fn bcachefs_root_boots_by_filesystem_uuid() {
+    let mut state = uefi_auto_state();
+    state.disks_root_fs_index = 2; // bcachefs
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("bcachefs plan should compile");
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let joined = plan.commands.join("\n");
+    assert!(joined.contains("rootfstype=bcachefs"), "{joined}");
+    assert!(joined.contains("${rootdev%%:*}"), "{joined}");
+
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    assert!(sys.contains("block bcachefs"), "{sys}");
+    assert!(sys.contains("mkinitcpio -P"), "{sys}");
+
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    let pkgs = pkgs.commands.join("\n");
+    assert!(pkgs.contains("bcachefs-tools"), "{pkgs}");
+}
+
+#[test]
+fn zram_swap_writes_generator_config_and_installs_package() {
+    let mut state = uefi_auto_state();
+    state.swap_mode_index = ai::core::storage::SwapMode::Zram as usize;
+    state.zram_size = "min(ram / 2, 8192)".into();
+    state.zram_algorithm_index = 1; // lz4
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("zram plan should compile");
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    for line in [
+        "[zram0]",
+        "zram-size = min(ram / 2, 8192)",
+        "compression-algorithm = lz4",
+        "> /etc/systemd/zram-generator.conf",
+    ] {
+        assert!(sys.contains(line), "{line}: {sys}");
+    }
+    assert!(sys.contains("vm.swappiness = 180"), "{sys}");
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    assert!(pkgs.commands.join("\n").contains("zram-generator"));
+
+    state.swap_mode_index = ai::core::storage::SwapMode::Partition as usize;
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    assert!(!sys.commands.join("\n").contains("zram-generator"));
+}
+
+#[test]
+fn hibernation_resume_reaches_boot_entries_and_initramfs() {
+    let mut state = uefi_auto_state();
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("swap partition plan should compile");
+    let boot = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let boot = boot.commands.join("\n");
+    assert!(boot.contains("resume=UUID=$swapuuid"), "{boot}");
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    assert!(sys.contains("resume filesystems"), "{sys}");
+    assert!(sys.contains("mkinitcpio -P"), "{sys}");
+
+    state.bootloader_index = 1; // GRUB, unencrypted
+    let grub = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let grub = grub.commands.join("\n");
+    assert!(grub.contains("GRUB_CMDLINE_LINUX"), "{grub}");
+    assert!(grub.contains("resume=UUID="), "{grub}");
+
+    state.swap_mode_index = ai::core::storage::SwapMode::Swapfile as usize;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("swapfile plan should compile");
+    let grub = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let grub = grub.commands.join("\n");
+    assert!(grub.contains("map-swapfile -r /swap/swapfile"), "{grub}");
+    assert!(grub.contains("resume_offset=$offset"), "{grub}");
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    assert!(pkgs.commands.join("\n").contains("btrfs-progs"));
+
+    state.swap_mode_index = ai::core::storage::SwapMode::Zram as usize;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("zram plan should compile");
+    assert!(storage_plan.resume_device().is_none());
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    assert!(!sys.commands.join("\n").contains("resume filesystems"));
+}
+
+#[test]
+fn swap_config_loads_legacy_switch_and_modes() {
+    use ai::core::storage::SwapMode;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("swap.toml");
+    let load = |text: &str| {
+        std::fs::write(&path, text).unwrap();
+        let mut state = make_state();
+        state.load_config_from_path(&path).unwrap();
+        state
+    };
+    assert_eq!(
+        load("[swap]\nenabled = true\n").swap_mode(),
+        SwapMode::Partition
+    );
+    assert_eq!(
+        load("[swap]\nenabled = false\n").swap_mode(),
+        SwapMode::None
+    );
+    let state = load("[swap]\nmode = \"zram\"\nzram_size = \"ram\"\nzram_algorithm = \"lz4\"\n");
+    assert_eq!(state.swap_mode(), SwapMode::Zram);
+    assert_eq!(state.zram_size, "ram");
+    assert_eq!(state.zram_algorithm(), "lz4");
+    let state = load("[swap]\nmode = \"swapfile\"\nswapfile_size = \"16G\"\n");
+    assert_eq!(state.swapfile_size, "16G");
+    assert!(
+        state
+            .redacted_config_toml()
+            .unwrap()
+            .contains("mode = \"swapfile\"")
+    );
+
+    let mut state = make_state();
+    let diags = state.validate_config_text("[swap]\nmode = \"file\"\nswapfile_size = \"lots\"\n");
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"swap.mode"), "{diags:?}");
+    assert!(paths.contains(&"swap.swapfile_size"), "{diags:?}");
+}
+
+#[test]
+fn tpm2_unlock_is_rejected_with_secure_boot_signing() {
+    for (kind, signing) in [("systemd-boot", "sbctl"), ("grub", "shim")] {
+        let diags = make_state().validate_config_text(&format!(
+            "[disk_encryption]\nencryption_type = \"LUKS\"\ntpm2 = true\n\n\
+             [bootloader]\nkind = \"{kind}\"\nsigning = \"{signing}\"\n"
+        ));
+        let conflict = diags
+            .iter()
+            .find(|d| d.path == "disk_encryption.tpm2")
+            .unwrap_or_else(|| panic!("no TPM2 conflict for {signing}: {diags:?}"));
+        assert!(conflict.message.contains(signing), "{conflict:?}");
+        assert!(conflict.message.contains("PCR 7"), "{conflict:?}");
+    }
+    let diags = make_state().validate_config_text(
+        "[disk_encryption]\nencryption_type = \"LUKS\"\ntpm2 = true\n\n\
+         [bootloader]\nkind = \"systemd-boot\"\nsigning = \"none\"\n",
+    );
+    assert!(
+        diags.iter().all(|d| d.path != "disk_encryption.tpm2"),
+        "{diags:?}"
+    );
+}
+
+#[test]
+fn tpm2_enrollment_uses_sd_encrypt_and_crypttab_initramfs() {
+    let mut state = uefi_auto_state();
+    state.disk_encryption_type_index = 1;
+    state.disk_encryption_password = "pw".into();
+    state.disk_encryption_password_confirm = "pw".into();
+    state.disk_encryption_tpm2 = true;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("enrolled LUKS plan should compile");
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    for needle in [
+        "HOOKS=(base systemd autodetect microcode modconf kms keyboard sd-vconsole block filesystems fsck)",
+        "block sd-encrypt",
+        "cryptroot UUID=$(blkid -s UUID -o value /dev/sda3) none tpm2-device=auto",
+        ">> /etc/crypttab.initramfs",
+        "mkinitcpio -P",
+    ] {
+        assert!(sys.contains(needle), "{needle}: {sys}");
+    }
+    let hooks = sys.find("sd-vconsole block filesystems").unwrap();
+    assert!(hooks < sys.find("block sd-encrypt").unwrap());
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    assert!(pkgs.commands.join("\n").contains("tpm2-tss"));
+
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("tpm2 = true"), "{saved}");
+    assert!(saved.contains("tpm2_pcrs = \"7\""), "{saved}");
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tpm2.toml");
+    std::fs::write(&path, &saved).unwrap();
+    let mut loaded = make_state();
+    loaded.load_config_from_path(&path).unwrap();
+    assert!(loaded.disk_encryption_tpm2);
+    assert!(!loaded.disk_encryption_fido2);
+
+    let diags = make_state().validate_config_text(
+        "[disk_encryption]\nencryption_type = \"LUKS\"\ntpm2 = true\ntpm2_pcrs = \"7+boot\"\n",
+    );
+    assert!(
+        diags.iter().all(|d| d.path != "disk_encryption.tpm2_pcrs"),
+        "{diags:?}"
+    );
+    let diags = make_state().validate_config_text(
+        "[disk_encryption]\nencryption_type = \"LUKS\"\ntpm2_pcrs = \"99\"\n",
+    );
+    assert!(
+        diags.iter().any(|d| d.path == "disk_encryption.tpm2_pcrs"),
+        "{diags:?}"
+    );
+}
+
+#[test]
+fn luks_format_parameters_round_trip_through_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("luks.toml");
+    std::fs::write(
+        &path,
+        "[disk_encryption]\nencryption_type = \"LUKS\"\ncipher = \"aes-xts-plain64\"\nkey_size = 512\n\
+         pbkdf = \"argon2id\"\npbkdf_memory = 131072\npbkdf_time = 2000\nsector_size = 4096\n\
+         label = \"cryptroot\"\nintegrity = \"hmac-sha256\"\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert_eq!(state.luks_key_size, "512");
+    assert_eq!(state.luks_sector_size_index, 4);
+    assert_eq!(
+        state.luks_format_summary(),
+        "--cipher=aes-xts-plain64 --key-size=512 --pbkdf=argon2id --pbkdf-memory=131072 \
+         --iter-time=2000 --sector-size=4096 --label=cryptroot --integrity=hmac-sha256"
+    );
+    let saved = state.redacted_config_toml().unwrap();
+    for line in [
+        "pbkdf_memory = 131072",
+        "sector_size = 4096",
+        "integrity = \"hmac-sha256\"",
+    ] {
+        assert!(saved.contains(line), "{line}: {saved}");
+    }
+
+    let diags = make_state().validate_config_text(
+        "[disk_encryption]\nencryption_type = \"LUKS\"\npbkdf = \"scrypt\"\nsector_size = 520\n",
+    );
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"disk_encryption.pbkdf"), "{diags:?}");
+    assert!(paths.contains(&"disk_encryption.sector_size"), "{diags:?}");
+}
+
+#[test]
+fn keyfile_swap_switches_to_systemd_initramfs_and_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("swap.toml");
+    std::fs::write(
+        &path,
+        "[disk_encryption]\nencryption_type = \"LUKS\"\n\n[swap]\nmode = \"partition\"\nencryption = \"keyfile\"\n",
+    )
+    .unwrap();
     let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert_eq!(
+        state.swap_encryption(),
+        ai::core::storage::SwapEncryption::Keyfile
+    );
+    assert!(
+        state
+            .redacted_config_toml()
+            .unwrap()
+            .contains("encryption = \"keyfile\"")
+    );
+
+    state.disks_mode_index = 0;
+    state.disks_selected_device = Some("/dev/sda".into());
     state.firmware_uefi_override = Some(true);
+    state.disk_encryption_password = "pw".into();
+    state.disk_encryption_password_confirm = "pw".into();
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("key-file swap plan should compile");
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    assert!(sys.contains("HOOKS=(base systemd autodetect"), "{sys}");
+    assert!(sys.contains("block sd-encrypt"), "{sys}");
+    let boot = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let boot = boot.commands.join("\n");
+    assert!(
+        boot.contains("rd.luks.name=$swapluks=cryptswap resume=/dev/mapper/cryptswap"),
+        "{boot}"
+    );
+
+    let diags =
+        make_state().validate_config_text("[swap]\nmode = \"zram\"\nencryption = \"random\"\n");
+    assert!(
+        diags.iter().any(|d| d.path == "swap.encryption"),
+        "{diags:?}"
+    );
+    let diags = make_state().validate_config_text("[swap]\nencryption = \"sometimes\"\n");
+    assert!(
+        diags.iter().any(|d| d.path == "swap.encryption"),
+        "{diags:?}"
+    );
+}
+
+#[test]
+fn uki_pacstrap_includes_systemd_ukify_when_enabled() {
+    let mut state = make_state();
     state.disks_selected_device = Some("/dev/sda".into());
-    state.bootloader_index = 3; // Limine
-    state.selected_kernels.insert("linux-lts".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 0; // systemd-boot
+    let plan = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("systemd-ukify"),
+        "UKI requires systemd-ukify in pacstrap: {joined}"
+    );
+}
+
+#[test]
+fn uki_pacstrap_omits_systemd_ukify_for_grub() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 1; // GRUB — UKI not integrated
+    let plan = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    let joined = plan.commands.join("\n");
+    assert!(
+        !joined.contains("systemd-ukify"),
+        "GRUB path should not pull in systemd-ukify: {joined}"
+    );
+}
+
+#[test]
+fn uki_sysconfig_writes_cmdline_and_preset() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 0;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("/etc/kernel/cmdline"),
+        "UKI needs /etc/kernel/cmdline before mkinitcpio: {joined}"
+    );
+    assert!(
+        joined.contains("mkinitcpio.d/linux.preset"),
+        "UKI should patch linux.preset: {joined}"
+    );
+    assert!(
+        joined.contains("default_uki=") && joined.contains("/boot/EFI/Linux/arch-linux.efi"),
+        "{joined}"
+    );
+    assert!(
+        joined.contains("out=$(mkinitcpio -P 2>&1); rc=$?;"),
+        "{joined}"
+    );
+}
+
+#[test]
+fn uki_sysconfig_runs_mkinitcpio_without_encryption() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 2; // EFISTUB
+    state.disk_encryption_type_index = 0; // none
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("out=$(mkinitcpio -P 2>&1); rc=$?;"),
+        "UKI must run mkinitcpio even without LUKS: {joined}"
+    );
+    assert!(
+        !joined.contains("block sd-encrypt") && !joined.contains("block encrypt"),
+        "non-encrypted UKI must not inject encrypt hooks: {joined}"
+    );
+}
+
+#[test]
+fn uki_systemd_boot_uses_efi_path() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 0;
     let device = "/dev/sda";
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
@@ -789,53 +1337,1647 @@ fn multi_kernel_limine_creates_entries_for_each_kernel() {
     );
     let joined = plan.commands.join("\n");
     assert!(
-        joined.contains("vmlinuz-linux-lts"),
-        "Limine should have entry for linux-lts: {joined}"
+        joined.contains("efi     /EFI/Linux/arch-linux.efi"),
+        "systemd-boot UKI entries use efi path: {joined}"
     );
     assert!(
-        joined.contains("vmlinuz-linux"),
-        "Limine should also have entry for linux: {joined}"
+        !joined.contains("linux   /vmlinuz-linux"),
+        "UKI entries should not use a separate linux line: {joined}"
     );
 }
 
 #[test]
-fn multi_kernel_sysconfig_uki_patches_all_presets() {
+fn uki_efistub_points_to_uki_loader() {
     let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
     state.disks_selected_device = Some("/dev/sda".into());
     state.uki_enabled = true;
-    state.bootloader_index = 0;
-    state.selected_kernels.insert("linux-lts".into());
+    state.bootloader_index = 2;
+    let device = "/dev/sda";
     let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
         .expect("auto plan should compile");
-    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        device,
+        &storage_plan,
+    );
     let joined = plan.commands.join("\n");
     assert!(
-        joined.contains("linux.preset"),
-        "UKI should patch linux.preset: {joined}"
+        joined.contains("arch-linux.efi") && joined.contains("EFI"),
+        "EFISTUB UKI should register the combined UKI path: {joined}"
     );
     assert!(
-        joined.contains("linux-lts.preset"),
-        "UKI should patch linux-lts.preset: {joined}"
+        !joined.contains("vmlinuz-linux"),
+        "EFISTUB UKI should not register raw vmlinuz: {joined}"
     );
     assert!(
-        joined.contains("arch-linux-lts.efi"),
-        "UKI should reference linux-lts UKI filename: {joined}"
+        joined.contains("EFI/BOOT/BOOTX64.EFI"),
+        "EFISTUB UKI should install UEFI fallback copy: {joined}"
+    );
+    assert!(
+        joined.contains("99-efistub-uki-fallback.hook"),
+        "EFISTUB UKI should install pacman hook to refresh fallback: {joined}"
     );
 }
 
 #[test]
-fn kernel_artifacts_returns_correct_names() {
-    let ka = ai::core::services::bootloader::kernel_artifacts("linux");
-    assert_eq!(ka.vmlinuz, "vmlinuz-linux");
-    assert_eq!(ka.initramfs, "initramfs-linux.img");
-    assert_eq!(ka.initramfs_fallback, "initramfs-linux-fallback.img");
-    assert_eq!(ka.uki_default, "arch-linux.efi");
-    assert_eq!(ka.uki_fallback, "arch-linux-fallback.efi");
-    assert_eq!(ka.preset, "linux.preset");
+fn uki_limine_uses_efi_protocol() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 3;
+    let device = "/dev/sda";
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        device,
+        &storage_plan,
+    );
+    let joined = plan.commands.join("\n");
+    assert!(joined.contains("protocol: efi"), "{joined}");
+    assert!(joined.contains("/EFI/Linux/arch-linux.efi"), "{joined}");
+}
 
-    let ka_lts = ai::core::services::bootloader::kernel_artifacts("linux-lts");
-    assert_eq!(ka_lts.vmlinuz, "vmlinuz-linux-lts");
-    assert_eq!(ka_lts.initramfs, "initramfs-linux-lts.img");
-    assert_eq!(ka_lts.uki_default, "arch-linux-lts.efi");
-    assert_eq!(ka_lts.preset, "linux-lts.preset");
+/// Pre-mounted install flow passes an empty partition target; EFISTUB still resolves ESP via `findmnt /boot`.
+#[test]
+fn pre_mounted_efistub_empty_target_still_generates_efibootmgr() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.bootloader_index = 2;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan =
+        ai::core::services::bootloader::BootloaderService::build_plan(&state, "", &storage_plan);
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("efibootmgr --create"),
+        "EFISTUB must not depend on a whole-disk target string: {joined}"
+    );
+    assert!(joined.contains("vmlinuz-linux"), "{joined}");
+    assert!(
+        joined.contains("startup.nsh"),
+        "pre-mounted non-UKI EFISTUB should still write startup.nsh: {joined}"
+    );
+}
+
+/// Same as pre-mounted: Limine UEFI uses `findmnt` + `efibootmgr`, not the partition-target disk string.
+#[test]
+fn pre_mounted_limine_uefi_empty_target_still_installs() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.bootloader_index = 3;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan =
+        ai::core::services::bootloader::BootloaderService::build_plan(&state, "", &storage_plan);
+    let joined = plan.commands.join("\n");
+    assert!(joined.contains("/boot/limine.conf"), "{joined}");
+    assert!(joined.contains("efibootmgr --create"), "{joined}");
+    assert!(
+        joined.contains("EFI/BOOT/BOOTX64.EFI"),
+        "should install to UEFI fallback path: {joined}"
+    );
+}
+
+// ── Multi-kernel tests ──
+
+#[test]
+fn multi_kernel_efistub_creates_entries_for_each_kernel() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.bootloader_index = 2; // EFISTUB
+    state.selected_kernels.insert("linux-lts".into());
+    let device = "/dev/sda";
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        device,
+        &storage_plan,
+    );
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("vmlinuz-linux-lts"),
+        "EFISTUB should create entry for linux-lts: {joined}"
+    );
+    assert!(
+        joined.contains("vmlinuz-linux'") || joined.contains("vmlinuz-linux\\"),
+        "EFISTUB should also have entry for linux: {joined}"
+    );
+    assert!(
+        joined.contains("initramfs-linux-lts.img"),
+        "EFISTUB should reference linux-lts initramfs: {joined}"
+    );
+}
+
+#[test]
+fn multi_kernel_systemd_boot_creates_entries_for_each_kernel() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.bootloader_index = 0; // systemd-boot
+    state.selected_kernels.insert("linux-lts".into());
+    let device = "/dev/sda";
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        device,
+        &storage_plan,
+    );
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("arch-linux-lts.conf"),
+        "systemd-boot should create conf for linux-lts: {joined}"
+    );
+    assert!(
+        joined.contains("arch.conf") || joined.contains("arch-fallback.conf"),
+        "systemd-boot should still have linux entries: {joined}"
+    );
+    assert!(
+        joined.contains("vmlinuz-linux-lts"),
+        "systemd-boot should reference linux-lts kernel: {joined}"
+    );
+}
+
+#[test]
+fn multi_kernel_limine_creates_entries_for_each_kernel() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.bootloader_index = 3; // Limine
+    state.selected_kernels.insert("linux-lts".into());
+    let device = "/dev/sda";
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        device,
+        &storage_plan,
+    );
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("vmlinuz-linux-lts"),
+        "Limine should have entry for linux-lts: {joined}"
+    );
+    assert!(
+        joined.contains("vmlinuz-linux"),
+        "Limine should also have entry for linux: {joined}"
+    );
+}
+
+#[test]
+fn multi_kernel_sysconfig_uki_patches_all_presets() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.uki_enabled = true;
+    state.bootloader_index = 0;
+    state.selected_kernels.insert("linux-lts".into());
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let plan = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let joined = plan.commands.join("\n");
+    assert!(
+        joined.contains("linux.preset"),
+        "UKI should patch linux.preset: {joined}"
+    );
+    assert!(
+        joined.contains("linux-lts.preset"),
+        "UKI should patch linux-lts.preset: {joined}"
+    );
+    assert!(
+        joined.contains("arch-linux-lts.efi"),
+        "UKI should reference linux-lts UKI filename: {joined}"
+    );
+}
+
+#[test]
+fn default_kernel_leads_every_boot_menu() {
+    let mut state = uefi_auto_state();
+    state.selected_kernels.insert("linux-lts".into());
+    state.default_kernel = Some("linux-lts".into());
+    assert_eq!(state.boot_kernels(), vec!["linux-lts", "linux"]);
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let build = |state: &ai::app::AppState| {
+        ai::core::services::bootloader::BootloaderService::build_plan(
+            state,
+            "/dev/sda",
+            &storage_plan,
+        )
+        .commands
+        .join("\n")
+    };
+
+    state.bootloader_index = 0;
+    let joined = build(&state);
+    assert!(joined.contains("default  arch-linux-lts.conf"), "{joined}");
+    assert!(joined.contains("arch-linux-lts-fallback.conf"), "{joined}");
+    assert!(joined.contains("arch-fallback.conf"), "{joined}");
+
+    state.bootloader_index = 1;
+    let joined = build(&state);
+    assert!(
+        joined.contains("GRUB_TOP_LEVEL=\"/boot/vmlinuz-linux-lts\""),
+        "{joined}"
+    );
+
+    state.bootloader_index = 3;
+    let joined = build(&state);
+    let lts = joined.find("/Arch Linux (linux-lts)\n").expect(&joined);
+    let linux = joined.find("/Arch Linux\n").expect(&joined);
+    assert!(lts < linux, "Limine boots its first entry: {joined}");
+
+    state.bootloader_index = 4;
+    let joined = build(&state);
+    assert!(
+        joined.contains("fold_linux_kernels false\ndefault_selection \"vmlinuz-linux-lts\""),
+        "{joined}"
+    );
+
+    state.bootloader_index = 2;
+    state.uki_enabled = true;
+    let joined = build(&state);
+    assert!(joined.contains("(fallback UKI)"), "{joined}");
+    assert!(
+        joined.contains("EFI/Linux/arch-linux-lts.efi /boot/EFI/BOOT/BOOTX64.EFI"),
+        "{joined}"
+    );
+    assert!(
+        joined.contains("-v want='\\''Arch Linux (linux-lts)'\\''"),
+        "{joined}"
+    );
+}
+
+#[test]
+fn default_kernel_falls_back_and_round_trips() {
+    let mut state = make_state();
+    state.selected_kernels.insert("linux-zen".into());
+    assert_eq!(state.default_kernel(), "linux");
+    state.cycle_default_kernel(true);
+    assert_eq!(state.default_kernel(), "linux-zen");
+    state.cycle_default_kernel(true);
+    assert_eq!(state.default_kernel(), "linux");
+    state.cycle_default_kernel(false);
+    assert_eq!(state.default_kernel(), "linux-zen");
+    // Deselecting the default kernel hands the default back to the first selected one
+    state.selected_kernels.remove("linux-zen");
+    assert_eq!(state.default_kernel(), "linux");
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("kernels.toml");
+    std::fs::write(
+        &path,
+        "[kernels]\nselected = [\"linux\", \"linux-lts\"]\ndefault = \"linux-lts\"\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert_eq!(state.default_kernel(), "linux-lts");
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("default = \"linux-lts\""), "{saved}");
+    assert!(
+        !make_state()
+            .redacted_config_toml()
+            .unwrap()
+            .contains("default = \"linux\""),
+        "no default is saved until one is picked"
+    );
+
+    let diags = make_state()
+        .validate_config_text("[kernels]\nselected = [\"linux\"]\ndefault = \"linux-zen\"\n");
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"kernels.default"), "{diags:?}");
+}
+
+#[test]
+fn extra_kernel_parameters_reach_every_boot_path() {
+    let mut state = uefi_auto_state();
+    state.kernel_parameters = " quiet  splash nvidia-drm.modeset=1 ".into();
+    let params = "quiet splash nvidia-drm.modeset=1";
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("auto plan should compile");
+    let build = |state: &ai::app::AppState| {
+        ai::core::services::bootloader::BootloaderService::build_plan(
+            state,
+            "/dev/sda",
+            &storage_plan,
+        )
+        .commands
+        .join("\n")
+    };
+
+    for index in [0, 2, 3, 4] {
+        state.bootloader_index = index;
+        let joined = build(&state);
+        assert!(joined.contains(&format!(") {params}")), "{index}: {joined}");
+    }
+    state.bootloader_index = 1;
+    let joined = build(&state);
+    let grub = joined
+        .lines()
+        .find(|l| l.contains("GRUB_CMDLINE_LINUX="))
+        .expect(&joined);
+    assert!(grub.contains(params), "{grub}");
+
+    state.bootloader_index = 0;
+    state.uki_enabled = true;
+    let sysconfig =
+        ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan)
+            .commands
+            .join("\n");
+    let cmdline = sysconfig
+        .lines()
+        .find(|l| l.contains("/etc/kernel/cmdline"))
+        .expect(&sysconfig);
+    assert!(cmdline.contains(params), "{cmdline}");
+
+    state.kernel_parameters.clear();
+    assert!(!build(&state).contains("quiet"));
+}
+
+#[test]
+fn kernel_parameters_are_validated_and_round_trip() {
+    use ai::app::kernels::kernel_parameters_error;
+    assert!(kernel_parameters_error("").is_none());
+    assert!(kernel_parameters_error("quiet splash mitigations=off").is_none());
+    assert!(kernel_parameters_error("nvidia-drm.modeset=1 console=ttyS0,115200n8").is_none());
+    assert!(kernel_parameters_error("quiet; reboot").is_some());
+    assert!(kernel_parameters_error("dyndbg=\"file foo.c +p\"").is_some());
+    assert!(kernel_parameters_error("$(id)").is_some());
+    assert!(kernel_parameters_error("=1").is_some());
+    assert!(kernel_parameters_error("root=/dev/sda2").is_some());
+    assert!(kernel_parameters_error("resume=UUID=x").is_some());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("params.toml");
+    std::fs::write(
+        &path,
+        "[kernels]\nselected = [\"linux\"]\nparameters = \"quiet splash\"\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert_eq!(
+        state.extra_kernel_parameters().as_deref(),
+        Some("quiet splash")
+    );
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("parameters = \"quiet splash\""), "{saved}");
+    assert!(
+        !make_state()
+            .redacted_config_toml()
+            .unwrap()
+            .contains("parameters ="),
+        "no parameters are saved when none are set"
+    );
+
+    let diags = make_state()
+        .validate_config_text("[kernels]\nselected = [\"linux\"]\nparameters = \"rw `id`\"\n");
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"kernels.parameters"), "{diags:?}");
+}
+
+#[test]
+fn kernel_artifacts_returns_correct_names() {
+    let ka = ai::core::services::bootloader::kernel_artifacts("linux");
+    assert_eq!(ka.vmlinuz, "vmlinuz-linux");
+    assert_eq!(ka.initramfs, "initramfs-linux.img");
+    assert_eq!(ka.initramfs_fallback, "initramfs-linux-fallback.img");
+    assert_eq!(ka.uki_default, "arch-linux.efi");
+    assert_eq!(ka.uki_fallback, "arch-linux-fallback.efi");
+    assert_eq!(ka.preset, "linux.preset");
+
+    let ka_lts = ai::core::services::bootloader::kernel_artifacts("linux-lts");
+    assert_eq!(ka_lts.vmlinuz, "vmlinuz-linux-lts");
+    assert_eq!(ka_lts.initramfs, "initramfs-linux-lts.img");
+    assert_eq!(ka_lts.uki_default, "arch-linux-lts.efi");
+    assert_eq!(ka_lts.preset, "linux-lts.preset");
+}
+
+const UNATTENDED_CONFIG: &str = r#"
+[disks]
+mode = "Manual Partitioning"
+selected_device = "/dev/vda"
+
+[[disks.partitions]]
+role = "efi"
+fs = "fat32"
+start = "1MiB"
+size = "512MiB"
+mountpoint = "/boot"
+
+[[disks.partitions]]
+role = "root"
+fs = "ext4"
+start = "513MiB"
+size = "100%"
+mountpoint = "/"
+
+[system]
+hostname = "box"
+root_password_hash = "4813494d137e1631bba301d5acab6e7bb7aa74ce1185d456565ef51d737677b2"
+
+[[users]]
+username = "alice"
+password_hash = ""
+is_sudo = true
+"#;
+
+#[test]
+fn load_config_unattended_keeps_disk_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("unattended.toml");
+    std::fs::write(&path, UNATTENDED_CONFIG).unwrap();
+
+    let mut interactive = make_state();
+    interactive.load_config_from_path(&path).unwrap();
+    assert!(interactive.disks_selected_device.is_none());
+    assert!(interactive.disks_partitions.is_empty());
+
+    let mut state = make_state();
+    state.load_config_unattended(&path).unwrap();
+    assert_eq!(state.disks_mode_index, 1);
+    assert_eq!(state.disks_selected_device.as_deref(), Some("/dev/vda"));
+    assert_eq!(state.disks_partitions.len(), 2);
+    assert_eq!(state.disks_partitions[1].role.as_deref(), Some("root"));
+}
+
+#[test]
+fn unattended_secrets_fill_passwords_and_check_hashes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("unattended.toml");
+    std::fs::write(&path, UNATTENDED_CONFIG).unwrap();
+    let mut state = make_state();
+    state.load_config_unattended(&path).unwrap();
+
+    // sha256("root") matches system.root_password_hash above
+    let env = |k: &str| match k {
+        "ARCHINSTALL_ROOT_PASSWORD" => Some("root".to_string()),
+        "ARCHINSTALL_USER_PASSWORD_ALICE" => Some("wonderland".to_string()),
+        _ => None,
+    };
+    ai::headless::apply_unattended_secrets(&mut state, env).expect("secrets should apply");
+    assert_eq!(state.root_password, "root");
+    assert_eq!(state.root_password_confirm, "root");
+    assert_eq!(state.users[0].password, "wonderland");
+
+    let wrong = |k: &str| match k {
+        "ARCHINSTALL_ROOT_PASSWORD" => Some("not-root".to_string()),
+        _ => None,
+    };
+    let err = ai::headless::apply_unattended_secrets(&mut state, wrong).unwrap_err();
+    assert!(err.contains("root_password_hash"), "{err}");
+    assert!(err.contains("ARCHINSTALL_USER_PASSWORD_ALICE"), "{err}");
+}
+
+#[test]
+fn plan_json_lists_sections_and_storage_without_secrets() {
+    let mut state = uefi_auto_state();
+    state.disk_encryption_type_index = 1;
+    state.disk_encryption_password = "hunter2".into();
+    state.disk_encryption_password_confirm = "hunter2".into();
+
+    let storage_plan =
+        ai::core::storage::planner::StoragePlanner::compile(&state).expect("plan should compile");
+    let json = ai::headless::plan_json(&state, "/dev/sda", &storage_plan);
+    let sections = json["sections"].as_array().expect("sections array");
+    let titles: Vec<&str> = sections
+        .iter()
+        .filter_map(|s| s["title"].as_str())
+        .collect();
+    assert!(titles.contains(&"Partitioning"), "{titles:?}");
+    assert!(titles.contains(&"Bootloader setup"), "{titles:?}");
+
+    let kinds: Vec<&str> = sections
+        .iter()
+        .flat_map(|s| s["commands"].as_array().unwrap().iter())
+        .filter_map(|c| c["kind"].as_str())
+        .collect();
+    assert!(kinds.contains(&"cryptsetup_luks_format"), "{kinds:?}");
+    assert!(kinds.contains(&"cryptsetup_open"), "{kinds:?}");
+
+    assert_eq!(json["storage"]["mode"], "automatic");
+    assert_eq!(json["storage"]["devices"][0]["path"], "/dev/sda");
+    assert!(json["storage"]["mounts"].as_array().unwrap().len() >= 2);
+    let text = json.to_string();
+    assert!(
+        !text.contains("hunter2"),
+        "plan must not leak the passphrase"
+    );
+}
+
+#[test]
+fn install_target_is_empty_in_pre_mounted_mode() {
+    let mut state = make_state();
+    assert_eq!(state.install_target(), None);
+
+    state.disks_selected_device = Some("/dev/sda".into());
+    assert_eq!(state.install_target().as_deref(), Some("/dev/sda"));
+
+    // A leftover selection from another mode must not leak into the pre-mounted plan
+    state.disks_mode_index = 2;
+    assert_eq!(state.install_target().as_deref(), Some(""));
+}
+
+#[test]
+fn validate_config_reports_field_paths_and_positions() {
+    let text = r#"[disks]
+mode = "Manual Partitioning"
+selected_device = "/dev/vda"
+
+[[disks.partitions]]
+role = "efi"
+fs = "fat32"
+size = "512MiB"
+
+[[disks.partitions]]
+role = "scratch"
+fs = "ext4"
+size = "lots"
+
+[bootloader]
+kind = "lilo"
+"#;
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    let diags = state.validate_config_text(text);
+    let find = |path: &str| {
+        diags
+            .iter()
+            .find(|d| d.path == path)
+            .unwrap_or_else(|| panic!("no diagnostic for {path}: {diags:?}"))
+    };
+
+    let size = find("disks.partitions[1].size");
+    assert_eq!((size.line, size.column), (13, 8));
+    assert_eq!(size.severity, ai::app::config::validate::Severity::Error);
+    let role = find("disks.partitions[1].role");
+    assert_eq!(role.line, 11);
+    let kind = find("bootloader.kind");
+    assert!(kind.message.contains("lilo"), "{}", kind.message);
+}
+
+#[test]
+fn validate_config_reports_type_errors_and_accepts_clean_configs() {
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    let diags = state.validate_config_text("[disks]\nmode = 3\n");
+    assert_eq!(diags.len(), 1, "{diags:?}");
+    assert_eq!(diags[0].path, "disks.mode");
+    assert_eq!((diags[0].line, diags[0].column), (2, 8));
+
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    let diags = state.validate_config_text(UNATTENDED_CONFIG);
+    assert!(
+        !diags
+            .iter()
+            .any(|d| d.severity == ai::app::config::validate::Severity::Error),
+        "{diags:?}"
+    );
+}
+
+#[test]
+fn validate_config_ignores_dry_run_placeholder_lists() {
+    use ai::app::config::validate::Severity;
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    // The dry-run loaders fall back to placeholder lists without localectl/reflector
+    let _ = state.load_locales_options();
+    let _ = state.load_mirrors_options();
+    let diags = state.validate_config_text(
+        "[locales]\nkeyboard_layout = \"de\"\nlocale_language = \"de_DE.UTF-8\"\n\
+         [mirrors]\nregions = [\"Germany                DE   999\"]\n",
+    );
+    let probed: Vec<_> = diags
+        .iter()
+        .filter(|d| d.path.starts_with("locales.") || d.path.starts_with("mirrors.regions"))
+        .collect();
+    assert!(
+        probed.iter().all(|d| d.severity == Severity::Warning),
+        "{probed:?}"
+    );
+}
+
+#[test]
+fn mirror_regions_match_by_country_not_mirror_count() {
+    use ai::app::mirrors::mirror_region_matches;
+    let line = "United Kingdom         GB    75";
+    assert!(mirror_region_matches(
+        line,
+        "United Kingdom         GB    33"
+    ));
+    assert!(mirror_region_matches(line, "United Kingdom GB"));
+    assert!(mirror_region_matches(line, "united kingdom"));
+    assert!(mirror_region_matches(line, "GB"));
+    assert!(!mirror_region_matches(
+        line,
+        "Germany                DE   120"
+    ));
+    assert!(!mirror_region_matches(line, "United States"));
+    assert!(!mirror_region_matches(
+        line,
+        "Ukraine                UA    33"
+    ));
+
+    // Loading a config picks the host's line for the same country
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mirrors.toml");
+    std::fs::write(
+        &path,
+        "[mirrors]\nregions = [\"United Kingdom         GB    33\"]\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.mirrors_regions_options = vec!["Germany                DE   120".into(), line.into()];
+    state.mirrors_loaded = true;
+    state.load_config_from_path(&path).unwrap();
+    assert!(state.mirrors_regions_selected.contains(&1));
+}
+
+fn checkpoint_sections() -> Vec<(String, Vec<ai::common::install_cmd::InstallCmd>)> {
+    use ai::common::install_cmd::InstallCmd;
+    [
+        "Locales",
+        "Pre-cleanup",
+        "Partitioning",
+        "Mounting",
+        "System package installations",
+        "System configuration",
+        "Bootloader setup",
+    ]
+    .iter()
+    .map(|t| (t.to_string(), vec![InstallCmd::shell(format!("echo {t}"))]))
+    .collect()
+}
+
+#[test]
+fn checkpoint_recorder_mirrors_into_target_after_mounting() {
+    use ai::app::install::checkpoint::{CheckpointRecorder, InstallCheckpoint};
+    let dir = tempfile::tempdir().unwrap();
+    let host = dir.path().join("host/checkpoint.json");
+    let target_root = dir.path().join("mnt");
+    let sections = checkpoint_sections();
+    let mut rec = CheckpointRecorder::new(
+        InstallCheckpoint::new("/dev/vda"),
+        host.clone(),
+        target_root.clone(),
+        false,
+    );
+
+    rec.section_done(&sections[2].0, &sections[2].1).unwrap();
+    assert!(host.exists());
+    assert!(!rec.target_path().exists(), "not mirrored before Mounting");
+    rec.section_done(&sections[3].0, &sections[3].1).unwrap();
+    assert!(rec.target_path().starts_with(&target_root));
+    assert!(rec.target_path().exists());
+
+    rec.section_failed("System package installations").unwrap();
+    let saved = InstallCheckpoint::load(&host).unwrap();
+    assert_eq!(
+        saved.failed.as_deref(),
+        Some("System package installations")
+    );
+    assert_eq!(saved.completed.len(), 2);
+    assert_eq!(InstallCheckpoint::load(&rec.target_path()).unwrap(), saved);
+
+    rec.finish().unwrap();
+    assert!(!host.exists(), "host checkpoint is dropped after success");
+    assert!(rec.target_path().exists());
+}
+
+#[test]
+fn checkpoint_resume_skips_completed_sections_and_restarts_storage() {
+    use ai::app::install::checkpoint::{InstallCheckpoint, plan_resume, resume_choices};
+    let mut sections = checkpoint_sections();
+    let mut cp = InstallCheckpoint::new("/dev/vda");
+    cp.restart_at(&sections, 5);
+    cp.failed = Some("System configuration".into());
+
+    assert_eq!(plan_resume(Some(&cp), &sections, None), Ok(5));
+    // Storage sections are resumed as a unit from the first of them
+    assert_eq!(plan_resume(Some(&cp), &sections, Some("mounting")), Ok(1));
+    assert_eq!(
+        plan_resume(Some(&cp), &sections, Some("System package installations")),
+        Ok(4)
+    );
+    assert!(plan_resume(Some(&cp), &sections, Some("Bootloader setup")).is_err());
+    assert!(plan_resume(Some(&cp), &sections, Some("nope")).is_err());
+    assert!(plan_resume(None, &sections, None).is_err());
+    assert_eq!(resume_choices(&sections, 5), vec![0, 1, 4, 5]);
+
+    // A changed non-storage section is reported but not re-run
+    sections[4].1 = vec![ai::common::install_cmd::InstallCmd::shell(
+        "pacstrap /mnt vim",
+    )];
+    assert_eq!(
+        cp.changed_sections(&sections),
+        vec!["System package installations"]
+    );
+    assert_eq!(cp.resume_index(&sections), 5);
+    // A changed disk layout restarts storage setup
+    sections[2].1 = vec![ai::common::install_cmd::InstallCmd::shell(
+        "parted /dev/vdb",
+    )];
+    assert_eq!(cp.resume_index(&sections), 1);
+}
+
+#[test]
+fn failed_command_opens_recovery_popup_and_forwards_the_choice() {
+    use ai::common::{CommandFailure, FailureAction, InstallLogMsg};
+    let mut state = make_state();
+    let (tx, rx) = std::sync::mpsc::channel::<FailureAction>();
+    state.install_decision_tx = Some(tx);
+    let failure = CommandFailure {
+        section: "System package installations".into(),
+        command: "pacstrap /mnt base".into(),
+        editable: Some("pacstrap /mnt base".into()),
+        message: "Command failed (exit 1): pacstrap /mnt base".into(),
+    };
+
+    state.append_install_log_msg(InstallLogMsg::CommandFailed(failure.clone()));
+    assert!(state.popup_open);
+    assert_eq!(
+        state.popup_kind,
+        Some(ai::app::PopupKind::InstallFailureRecovery)
+    );
+    state.handle_install_failure_choice(0);
+    assert_eq!(rx.try_recv(), Ok(FailureAction::Retry));
+    assert!(!state.popup_open);
+
+    // Edit prefills the raw command; Enter sends it back as RetryWith
+    state.append_install_log_msg(InstallLogMsg::CommandFailed(failure.clone()));
+    state.handle_install_failure_choice(1);
+    assert_eq!(
+        state.popup_kind,
+        Some(ai::app::PopupKind::InstallFailureEdit)
+    );
+    assert_eq!(state.custom_input_buffer, "pacstrap /mnt base");
+    state.custom_input_buffer.push_str(" vim");
+    state.resolve_install_failure(FailureAction::RetryWith(state.custom_input_buffer.clone()));
+    assert_eq!(
+        rx.try_recv(),
+        Ok(FailureAction::RetryWith("pacstrap /mnt base vim".into()))
+    );
+
+    // Commands carrying secrets cannot be edited; the popup stays open
+    state.append_install_log_msg(InstallLogMsg::CommandFailed(CommandFailure {
+        editable: None,
+        ..failure
+    }));
+    state.handle_install_failure_choice(1);
+    assert_eq!(
+        state.popup_kind,
+        Some(ai::app::PopupKind::InstallFailureRecovery)
+    );
+    state.handle_install_failure_choice(2);
+    assert_eq!(rx.try_recv(), Ok(FailureAction::Skip));
+
+    state.append_install_log_msg(InstallLogMsg::Line(
+        "::warning::skipped in 'Locales': loadkeys us".into(),
+    ));
+    assert_eq!(
+        state.install_warnings,
+        vec!["skipped in 'Locales': loadkeys us"]
+    );
+}
+
+fn drain_log(rx: &std::sync::mpsc::Receiver<ai::common::InstallLogMsg>) -> Vec<String> {
+    rx.try_iter()
+        .filter_map(|m| match m {
+            ai::common::InstallLogMsg::Line(s) | ai::common::InstallLogMsg::ReplaceLastLine(s) => {
+                Some(s)
+            }
+            ai::common::InstallLogMsg::CommandFailed(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn fake_executor_runs_full_plan_and_streams_output() {
+    use ai::app::install::executor::FakeExecutor;
+    let state = uefi_auto_state();
+    let sections = state.build_install_sections("/dev/sda");
+    let titles: Vec<String> = sections.iter().map(|(t, _)| t.clone()).collect();
+    let total: usize = sections.iter().map(|(_, c)| c.len()).sum();
+    assert!(total > 0);
+
+    let mut exec = FakeExecutor::new();
+    exec.respond(
+        "pacman -Syy",
+        0,
+        &[":: Synchronizing package databases...\x07", ""],
+    );
+    let (tx, rx) = std::sync::mpsc::channel();
+    let result =
+        ai::app::install::run_install_sections(sections, &tx, false, &mut exec, Default::default());
+    assert_eq!(result, Ok(()));
+    assert_eq!(exec.calls.len(), total);
+
+    let log = drain_log(&rx);
+    let starts: Vec<&str> = log
+        .iter()
+        .filter_map(|l| l.strip_prefix("::section_start::"))
+        .collect();
+    assert_eq!(starts, titles);
+    assert!(starts.contains(&"Bootloader setup"), "{starts:?}");
+    assert!(
+        log.iter()
+            .any(|l| l == ":: Synchronizing package databases..."),
+        "output should be sanitized and forwarded: {log:?}"
+    );
+    assert_eq!(
+        log.last().map(String::as_str),
+        Some("Installation completed.")
+    );
+}
+
+#[test]
+fn fake_executor_failure_is_retried_after_recovery_choice() {
+    use ai::app::install::executor::FakeExecutor;
+    use ai::common::FailureAction;
+    let mut exec = FakeExecutor::new();
+    exec.fail("echo Partitioning", 1);
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (decide, decisions) = std::sync::mpsc::channel();
+    decide.send(FailureAction::Retry).unwrap();
+
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut exec,
+        ai::app::install::InstallRunOptions {
+            decisions: Some(&decisions),
+            ..Default::default()
+        },
+    );
+    assert_eq!(result, Ok(()));
+    assert_eq!(exec.calls_matching("echo Partitioning"), 2);
+    assert_eq!(exec.calls.len(), checkpoint_sections().len() + 1);
+    let log = drain_log(&rx);
+    let failed = log
+        .iter()
+        .position(|l| l == "Command failed (exit 1): echo Partitioning")
+        .expect("failure is logged");
+    assert_eq!(log[failed + 1], "Retrying...");
+    assert_eq!(log[failed + 2], "$ echo Partitioning");
+}
+
+#[test]
+fn fake_executor_abort_stops_run_and_records_failed_section() {
+    use ai::app::install::checkpoint::{CheckpointRecorder, InstallCheckpoint};
+    use ai::app::install::executor::FakeExecutor;
+    use ai::common::{FailureAction, InstallLogMsg};
+    let dir = tempfile::tempdir().unwrap();
+    let mut rec = CheckpointRecorder::new(
+        InstallCheckpoint::new("/dev/vda"),
+        dir.path().join("checkpoint.json"),
+        dir.path().join("mnt"),
+        false,
+    );
+    let mut exec = FakeExecutor::new();
+    exec.fail("echo Mounting", 32);
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (decide, decisions) = std::sync::mpsc::channel();
+    decide.send(FailureAction::Abort).unwrap();
+
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut exec,
+        ai::app::install::InstallRunOptions {
+            checkpoint: Some(&mut rec),
+            decisions: Some(&decisions),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        result,
+        Err("Command failed (exit 32): echo Mounting".to_string())
+    );
+    assert_eq!(exec.calls_matching("echo System"), 0);
+    assert_eq!(rec.checkpoint().failed.as_deref(), Some("Mounting"));
+    let completed: Vec<&str> = rec
+        .checkpoint()
+        .completed
+        .iter()
+        .map(|c| c.title.as_str())
+        .collect();
+    assert_eq!(completed, ["Locales", "Pre-cleanup", "Partitioning"]);
+    let failure = rx.try_iter().find_map(|m| match m {
+        InstallLogMsg::CommandFailed(f) => Some(f),
+        _ => None,
+    });
+    assert_eq!(failure.map(|f| f.section), Some("Mounting".to_string()));
+
+    // Without a decision channel (headless) the first failure ends the run
+    let mut exec = FakeExecutor::new();
+    exec.fail("echo Locales", 1);
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut exec,
+        Default::default(),
+    );
+    assert!(result.is_err());
+    assert_eq!(exec.calls, ["echo Locales"]);
+}
+
+/// Cancels the run while the command matching `pattern` is running.
+struct CancelDuring<'a> {
+    inner: ai::app::install::executor::FakeExecutor,
+    pattern: &'a str,
+    cancel: &'a ai::app::install::cancel::CancelToken,
+}
+
+impl ai::app::install::executor::CommandExecutor for CancelDuring<'_> {
+    fn run(
+        &mut self,
+        cmd: &ai::common::install_cmd::InstallCmd,
+        output: &mut dyn FnMut(ai::app::install::executor::CommandOutput<'_>),
+    ) -> Result<i32, ai::app::install::executor::ExecError> {
+        if cmd.for_log().contains(self.pattern) {
+            self.cancel.cancel();
+            self.inner.fail(self.pattern, 143);
+        }
+        self.inner.run(cmd, output)
+    }
+}
+
+#[test]
+fn cancelled_install_stops_and_releases_the_target() {
+    use ai::app::install::cancel::CancelToken;
+    use ai::app::install::events::{EventLog, EventStatus, InstallEvent, parse_event_log};
+    use ai::app::install::executor::FakeExecutor;
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.disk_encryption_type_index = 1; // LUKS
+    let plan = ai::core::storage::planner::StoragePlanner::compile(&state).unwrap();
+    let teardown = plan.teardown_commands();
+    let teardown_text: Vec<String> = teardown.iter().map(|c| c.for_log()).collect();
+    assert_eq!(
+        teardown_text[..2],
+        ["swapoff -a || true", "umount -R /mnt 2>/dev/null || true"]
+    );
+    assert!(
+        teardown_text[2..]
+            .iter()
+            .all(|c| c.starts_with("cryptsetup close "))
+    );
+    assert_eq!(teardown_text.len(), 2 + plan.luks_mapper_names().len());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("events.jsonl");
+    let mut events = EventLog::create(&path).unwrap();
+    let cancel = CancelToken::new();
+    let mut exec = CancelDuring {
+        inner: FakeExecutor::new(),
+        pattern: "echo Partitioning",
+        cancel: &cancel,
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (_decide, decisions) = std::sync::mpsc::channel();
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut exec,
+        ai::app::install::InstallRunOptions {
+            decisions: Some(&decisions),
+            events: Some(&mut events),
+            cancel: Some(&cancel),
+            teardown,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        result,
+        Err("Installation cancelled by the user".to_string())
+    );
+    // No recovery prompt, nothing after the cancelled command, then the teardown
+    assert!(
+        !rx.try_iter()
+            .any(|m| matches!(m, ai::common::InstallLogMsg::CommandFailed(_)))
+    );
+    let mut expected = vec!["echo Locales", "echo Pre-cleanup", "echo Partitioning"];
+    expected.extend(teardown_text.iter().map(String::as_str));
+    assert_eq!(exec.inner.calls, expected);
+
+    let records = parse_event_log(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(records.iter().any(|r| matches!(
+        &r.event,
+        InstallEvent::SectionEnd { section, status: EventStatus::Cancelled, .. } if section == "Partitioning"
+    )));
+    assert!(matches!(
+        records.last().map(|r| &r.event),
+        Some(InstallEvent::InstallEnd {
+            status: EventStatus::Cancelled,
+            ..
+        })
+    ));
+}
+
+/// Cancels during Partitioning and returns the teardown summary line that was streamed.
+fn cancel_summary(
+    teardown: Vec<ai::common::install_cmd::InstallCmd>,
+    failing: Option<&str>,
+) -> String {
+    use ai::app::install::cancel::CancelToken;
+    use ai::app::install::executor::FakeExecutor;
+    let cancel = CancelToken::new();
+    let mut inner = FakeExecutor::new();
+    if let Some(pattern) = failing {
+        inner.fail(pattern, 32);
+    }
+    let mut exec = CancelDuring {
+        inner,
+        pattern: "echo Partitioning",
+        cancel: &cancel,
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut exec,
+        ai::app::install::InstallRunOptions {
+            cancel: Some(&cancel),
+            teardown,
+            ..Default::default()
+        },
+    );
+    assert!(result.is_err());
+    rx.try_iter()
+        .find_map(|m| match m {
+            ai::common::InstallLogMsg::Line(l) if l.starts_with("Cleanup: ") => Some(l),
+            _ => None,
+        })
+        .expect("no cleanup summary")
+}
+
+#[test]
+fn cancel_message_reports_what_the_teardown_did() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.disk_encryption_type_index = 1; // LUKS
+    let plan = ai::core::storage::planner::StoragePlanner::compile(&state).unwrap();
+    let mapper = plan.luks_mapper_names()[0].clone();
+
+    let released = cancel_summary(plan.teardown_commands(), None);
+    assert!(released.contains("swap turned off"), "{released}");
+    assert!(released.contains("/mnt unmounted"), "{released}");
+    assert!(
+        released.contains(&format!("LUKS mapping {mapper} closed")),
+        "{released}"
+    );
+    assert!(!released.contains("Failed"), "{released}");
+
+    let partial = cancel_summary(plan.teardown_commands(), Some("umount -R /mnt"));
+    assert!(!partial.contains("/mnt unmounted"), "{partial}");
+    assert!(partial.contains("Failed: umount -R /mnt"), "{partial}");
+
+    // Pre-mounted installs have nothing to release
+    let as_is = cancel_summary(Vec::new(), None);
+    assert!(as_is.contains("left as-is"), "{as_is}");
+    assert!(!as_is.contains("unmounted"), "{as_is}");
+
+    // The popup shows the streamed summary instead of a fixed text
+    let cancel = ai::app::install::cancel::CancelToken::new();
+    cancel.cancel();
+    state.install_cancel = Some(cancel);
+    state.install_section_titles = vec!["Partitioning".into()];
+    state.install_current_section = Some(0);
+    state.install_log = vec![partial.clone(), "Installation cancelled.".into()];
+    state.handle_install_failed();
+    let popup = &state.popup_items[0];
+    assert!(popup.contains("cancelled during 'Partitioning'"), "{popup}");
+    assert!(popup.contains(&partial), "{popup}");
+    assert!(!popup.contains("LUKS mappings closed"), "{popup}");
+}
+
+#[test]
+fn completion_report_lists_the_recovery_key_in_the_target() {
+    let mut state = make_state();
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.disk_encryption_type_index = 1; // LUKS
+    state.disk_encryption_recovery_key = true;
+    let plan = ai::core::storage::planner::StoragePlanner::compile(&state).unwrap();
+    let keys = ai::app::install::recovery_key_report_paths(&plan);
+    assert_eq!(
+        keys,
+        ["/var/log/archinstall-rs/luks-recovery-cryptroot.txt"]
+    );
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut ai::app::install::executor::FakeExecutor::new(),
+        ai::app::install::InstallRunOptions {
+            recovery_keys: keys,
+            ..Default::default()
+        },
+    );
+    assert!(result.is_ok());
+    let lines: Vec<String> = rx
+        .try_iter()
+        .filter_map(|m| match m {
+            ai::common::InstallLogMsg::Line(l) => Some(l),
+            _ => None,
+        })
+        .collect();
+    let done = lines
+        .iter()
+        .position(|l| l == "Installation completed.")
+        .expect("completion report");
+    assert!(
+        lines[done..].iter().any(|l| l.contains(
+            "LUKS recovery key saved to /var/log/archinstall-rs/luks-recovery-cryptroot.txt"
+        )),
+        "{lines:?}"
+    );
+}
+
+#[test]
+fn event_log_records_sections_commands_output_and_timings() {
+    use ai::app::install::events::{EventLog, EventStatus, InstallEvent, parse_event_log};
+    use ai::app::install::executor::FakeExecutor;
+    use ai::common::FailureAction;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("logs/events.jsonl");
+    let mut events = EventLog::create(&path)
+        .unwrap()
+        .with_machine("node-7", "/dev/vda");
+    let mut exec = FakeExecutor::new();
+    exec.respond("echo Locales", 0, &["WARN: keymap not found", "loaded us"])
+        .fail("echo Mounting", 32);
+    let (tx, _rx) = std::sync::mpsc::channel();
+    let (decide, decisions) = std::sync::mpsc::channel();
+    decide.send(FailureAction::Skip).unwrap();
+
+    let result = ai::app::install::run_install_sections(
+        checkpoint_sections(),
+        &tx,
+        false,
+        &mut exec,
+        ai::app::install::InstallRunOptions {
+            decisions: Some(&decisions),
+            events: Some(&mut events),
+            ..Default::default()
+        },
+    );
+    assert_eq!(result, Ok(()));
+    let records = parse_event_log(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let events: Vec<&InstallEvent> = records.iter().map(|r| &r.event).collect();
+    assert!(records.iter().all(|r| !r.ts.is_empty()));
+
+    match events[0] {
+        InstallEvent::InstallStart {
+            hostname,
+            target,
+            sections,
+        } => {
+            assert_eq!((hostname.as_str(), target.as_str()), ("node-7", "/dev/vda"));
+            assert_eq!(sections.len(), checkpoint_sections().len());
+        }
+        other => panic!("expected install_start, got {other:?}"),
+    }
+    assert_eq!(
+        events[1..6],
+        [
+            &InstallEvent::SectionStart {
+                section: "Locales".into()
+            },
+            &InstallEvent::CommandStart {
+                section: "Locales".into(),
+                command: "echo Locales".into()
+            },
+            &InstallEvent::Warning {
+                section: Some("Locales".into()),
+                message: "keymap not found".into()
+            },
+            &InstallEvent::Output {
+                section: "Locales".into(),
+                line: "WARN: keymap not found".into()
+            },
+            &InstallEvent::Output {
+                section: "Locales".into(),
+                line: "loaded us".into()
+            },
+        ]
+    );
+    assert!(events.iter().any(|e| matches!(
+        e,
+        InstallEvent::CommandEnd { section, exit_code: Some(32), error: Some(_), .. }
+            if section == "Mounting"
+    )));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        InstallEvent::Warning { message, .. } if message == "skipped in 'Mounting': echo Mounting"
+    )));
+    let section_ends = events
+        .iter()
+        .filter(|e| {
+            matches!(
+                e,
+                InstallEvent::SectionEnd {
+                    status: EventStatus::Completed,
+                    ..
+                }
+            )
+        })
+        .count();
+    assert_eq!(section_ends, checkpoint_sections().len());
+    assert!(matches!(
+        events.last(),
+        Some(InstallEvent::InstallEnd {
+            status: EventStatus::Completed,
+            warnings: 1,
+            error: None,
+            ..
+        })
+    ));
+
+    // Raw JSON uses snake_case event tags a dashboard can filter on
+    let first = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        first
+            .lines()
+            .next()
+            .unwrap()
+            .contains("\"event\":\"install_start\"")
+    );
+}
+
+#[test]
+fn install_record_is_written_into_target_without_secrets() {
+    use ai::app::install::artifacts::{InstallArtifacts, TARGET_LOG_DIR};
+    use ai::app::install::executor::FakeExecutor;
+    let mut state = uefi_auto_state();
+    state.root_password = "hunter2".into();
+    state.root_password_confirm = "hunter2".into();
+    state.disk_encryption_type_index = 1;
+    state.disk_encryption_password = "luks-secret".into();
+    state.disk_encryption_password_confirm = "luks-secret".into();
+    let dir = tempfile::tempdir().unwrap();
+    let mut artifacts = InstallArtifacts::collect(&state).expect("record compiles");
+    artifacts.target_root = dir.path().to_path_buf();
+
+    let sections = state.build_install_sections("/dev/sda");
+    let mut exec = FakeExecutor::new();
+    exec.respond("pacman -Syy", 0, &["synced"]);
+    let (tx, rx) = std::sync::mpsc::channel();
+    let result = ai::app::install::run_install_sections(
+        sections,
+        &tx,
+        false,
+        &mut exec,
+        ai::app::install::InstallRunOptions {
+            artifacts: Some(&artifacts),
+            ..Default::default()
+        },
+    );
+    assert_eq!(result, Ok(()));
+
+    let record = dir.path().join(TARGET_LOG_DIR);
+    let log = std::fs::read_to_string(record.join("install.log")).unwrap();
+    assert!(log.contains("=== Partitioning ==="), "{log}");
+    assert!(log.contains("synced"), "{log}");
+    assert!(!log.contains("::section_start::"), "{log}");
+    assert!(log.trim_end().ends_with("Installation completed."), "{log}");
+    let config = std::fs::read_to_string(record.join("config.toml")).unwrap();
+    assert!(config.contains("[disks]"), "{config}");
+    assert!(!config.contains("password_hash = \""), "{config}");
+    let plan: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(record.join("storage-plan.json")).unwrap())
+            .unwrap();
+    assert_eq!(plan["devices"][0]["path"], "/dev/sda");
+    for text in [&log, &config] {
+        assert!(!text.contains("hunter2") && !text.contains("luks-secret"));
+    }
+    let saved = drain_log(&rx);
+    assert!(
+        saved.iter().any(|l| l.starts_with("Saved the install log")),
+        "{saved:?}"
+    );
+}
+
+#[test]
+fn config_hooks_are_injected_into_their_sections() {
+    let text = format!(
+        r#"{UNATTENDED_CONFIG}
+[hooks]
+"after Mounting" = [{{ run = "cp /root/site-ca.pem /mnt/etc/ca-certificates/trust-source/anchors/", target = "host" }}]
+"before system configuration" = [{{ run = "echo 'enrolling'" }}]
+"#
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hooks.toml");
+    std::fs::write(&path, &text).unwrap();
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    state.load_config_unattended(&path).unwrap();
+    assert_eq!(state.install_hooks.len(), 2);
+
+    let sections = state.build_install_sections("/dev/vda");
+    let cmds = |title: &str| -> Vec<String> {
+        sections
+            .iter()
+            .find(|(t, _)| t == title)
+            .map(|(_, c)| c.iter().map(|c| c.for_log()).collect())
+            .unwrap_or_default()
+    };
+    let mounting = cmds("Mounting");
+    assert_eq!(
+        mounting.last().map(String::as_str),
+        Some("cp /root/site-ca.pem /mnt/etc/ca-certificates/trust-source/anchors/")
+    );
+    let sysconfig = cmds("System configuration");
+    assert_eq!(
+        sysconfig[0],
+        "arch-chroot /mnt bash -lc 'echo '\\''enrolling'\\'''"
+    );
+
+    // Saved configs keep the hooks under their canonical keys
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("\"after Mounting\""), "{saved}");
+    assert!(saved.contains("\"before System configuration\""), "{saved}");
+}
+
+#[test]
+fn validate_config_reports_bad_hooks() {
+    let text = format!(
+        r#"{UNATTENDED_CONFIG}
+[hooks]
+"during Mounting" = [{{ run = "true" }}]
+"after Mounting" = [{{ run = "true", target = "container" }}, {{ run = " " }}]
+"after Volume stack setup (LVM/RAID)" = [{{ run = "true", target = "host" }}]
+"before Locales" = [{{ run = "true", target = "host" }}]
+"#
+    );
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    let diags = state.validate_config_text(&text);
+    let find = |path: &str| {
+        diags
+            .iter()
+            .find(|d| d.path == path)
+            .unwrap_or_else(|| panic!("no diagnostic for {path}: {diags:?}"))
+    };
+    assert!(
+        find("hooks.during Mounting")
+            .message
+            .contains("'before' or 'after'")
+    );
+    assert!(
+        find("hooks.after Mounting[0].target")
+            .message
+            .contains("container")
+    );
+    assert_eq!(find("hooks.after Mounting[1].run").line, 31);
+    // Locales are applied in System configuration; there is no standalone section to hook
+    let locales = find("hooks.before Locales");
+    assert!(locales.message.contains("unknown section"), "{locales:?}");
+    assert_eq!(locales.severity, ai::app::config::validate::Severity::Error);
+    let absent = find("hooks.after Volume stack setup (LVM/RAID)");
+    assert_eq!(
+        absent.severity,
+        ai::app::config::validate::Severity::Warning
+    );
+}
+
+#[test]
+fn chroot_hooks_before_pacstrap_are_rejected_and_skipped() {
+    let text = format!(
+        r#"{UNATTENDED_CONFIG}
+[hooks]
+"after Mounting" = [{{ run = "update-ca-trust" }}, {{ run = "true", target = "host" }}]
+"before System package installations" = [{{ run = "true", target = "chroot" }}]
+"after System package installations" = [{{ run = "update-ca-trust" }}]
+"#
+    );
+    let mut state = make_state();
+    state.firmware_uefi_override = Some(true);
+    let diags = state.validate_config_text(&text);
+    let errors: Vec<&str> = diags
+        .iter()
+        .filter(|d| d.severity == ai::app::config::validate::Severity::Error)
+        .map(|d| d.path.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "hooks.after Mounting[0].target",
+            "hooks.before System package installations[0].target",
+        ],
+        "{diags:?}"
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hooks.toml");
+    std::fs::write(&path, &text).unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    let kept: Vec<String> = state.install_hooks.iter().map(|h| h.key()).collect();
+    assert_eq!(
+        kept,
+        ["after Mounting", "after System package installations"],
+        "{:?}",
+        state.install_hooks
+    );
+    assert_eq!(
+        state.install_hooks[0].target,
+        ai::app::install::hooks::HookTarget::Host
+    );
+}
+
+#[test]
+fn lvm_on_luks_adds_lvm2_hook_and_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lvm.toml");
+    std::fs::write(
+        &path,
+        "[disk_encryption]\nencryption_type = \"LUKS\"\n\n[disks]\nmode = \"Best-effort partition layout\"\n\n\
+         [disks.lvm]\nvolume_group = \"arch\"\nstacking = \"lvm_on_luks\"\n\n\
+         [[disks.lvm.volumes]]\nname = \"root\"\nsize = \"30G\"\nfs = \"xfs\"\nmountpoint = \"/\"\n\n\
+         [[disks.lvm.volumes]]\nname = \"home\"\nsize = \"100%FREE\"\nfs = \"ext4\"\nmountpoint = \"/home\"\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert!(state.lvm_selected());
+    assert_eq!(state.lvm_vg_name, "arch");
+    assert_eq!(state.lvm_volumes.len(), 2);
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("[disks.lvm]"), "{saved}");
+    assert!(saved.contains("stacking = \"lvm_on_luks\""), "{saved}");
+
+    state.disks_selected_device = Some("/dev/sda".into());
+    state.firmware_uefi_override = Some(true);
+    state.disk_encryption_password = "pw".into();
+    state.disk_encryption_password_confirm = "pw".into();
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("LVM on LUKS plan should compile");
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    assert!(sys.contains("lvm2 filesystems"), "{sys}");
+    assert!(sys.contains("mkinitcpio -P"), "{sys}");
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    assert!(pkgs.commands.join("\n").contains("lvm2"));
+    let boot = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let boot = boot.commands.join("\n");
+    assert!(
+        boot.contains("cryptdevice=UUID=$luksuuid:cryptlvm root=/dev/arch/root rw"),
+        "{boot}"
+    );
+
+    let diags = make_state().validate_config_text(
+        "[disks]\nmode = \"Best-effort partition layout\"\n\n[disks.lvm]\nstacking = \"lvm_in_luks\"\n\n\
+         [[disks.lvm.volumes]]\nname = \"root\"\nsize = \"30G\"\nfs = \"zfs\"\nmountpoint = \"/\"\n",
+    );
+    let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+    assert!(paths.contains(&"disks.lvm.stacking"), "{diags:?}");
+    assert!(paths.contains(&"disks.lvm.volumes[0].fs"), "{diags:?}");
+}
+
+#[test]
+fn raid1_assembles_arrays_and_boots_from_every_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("raid.toml");
+    std::fs::write(
+        &path,
+        "[disks]\nmode = \"Best-effort partition layout\"\nselected_device = \"/dev/sda\"\n\n\
+         [disks.raid]\nlevel = \"raid1\"\ndevices = [\"/dev/sda\", \"/dev/sdb\"]\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_from_path(&path).unwrap();
+    assert!(state.raid_selected());
+    // Interactive loads ask for the disks again
+    assert!(state.raid_devices.is_empty());
+    state.load_config_unattended(&path).unwrap();
+    assert_eq!(state.raid_devices, vec!["/dev/sda", "/dev/sdb"]);
+    assert_eq!(state.target_disks(), vec!["/dev/sda", "/dev/sdb"]);
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("[disks.raid]"), "{saved}");
+    assert!(saved.contains("level = \"raid1\""), "{saved}");
+
+    state.firmware_uefi_override = Some(true);
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("RAID1 plan should compile");
+    let sys = ai::core::services::sysconfig::SysConfigService::build_plan(&state, &storage_plan);
+    let sys = sys.commands.join("\n");
+    assert!(
+        sys.contains("mdadm --detail --scan >> /mnt/etc/mdadm.conf"),
+        "{sys}"
+    );
+    assert!(sys.contains("s/\\bblock\\b/block mdadm_udev/"), "{sys}");
+    assert!(sys.contains("mkinitcpio -P"), "{sys}");
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    assert!(pkgs.commands.join("\n").contains("mdadm"));
+    let boot = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let boot = boot.commands.join("\n");
+    assert!(boot.contains("for esp in /boot2; do"), "{boot}");
+    assert!(boot.contains("99-esp-mirror.hook"), "{boot}");
+
+    state.firmware_uefi_override = Some(false);
+    state.bootloader_index = 1;
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("BIOS RAID1 plan should compile");
+    let boot = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let boot = boot.commands.join("\n");
+    assert!(
+        boot.contains("grub-install --target=i386-pc /dev/sda"),
+        "{boot}"
+    );
+    assert!(
+        boot.contains("grub-install --target=i386-pc /dev/sdb"),
+        "{boot}"
+    );
+
+    let diags = make_state().validate_config_text(
+        "[disks]\nmode = \"Best-effort partition layout\"\n\n[disks.raid]\nlevel = \"raid3\"\n",
+    );
+    assert!(
+        diags.iter().any(|d| d.path == "disks.raid.level"),
+        "{diags:?}"
+    );
+}
+
+#[test]
+fn install_alongside_keeps_other_boot_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("alongside.toml");
+    std::fs::write(
+        &path,
+        "[disks]\nmode = \"Best-effort partition layout\"\nselected_device = \"/dev/sda\"\ninstall_alongside = true\n",
+    )
+    .unwrap();
+    let mut state = make_state();
+    state.load_config_unattended(&path).unwrap();
+    assert!(state.alongside_selected());
+    let saved = state.redacted_config_toml().unwrap();
+    assert!(saved.contains("install_alongside = true"), "{saved}");
+
+    state.firmware_uefi_override = Some(true);
+    state.bootloader_index = 1;
+    state.alongside_layout_override = Some(ai::core::storage::DiskLayout::parse_parted(
+        "BYT;\n/dev/sda:102400MiB:scsi:512:512:gpt:Disk:;\n\
+         1:1.00MiB:101MiB:100MiB:fat32:EFI system partition:boot, esp;\n\
+         2:101MiB:60000MiB:59899MiB:ntfs:Basic data partition:msftdata;\n\
+         1:60000MiB:102399MiB:42399MiB:free;\n",
+    ));
+    let storage_plan = ai::core::storage::planner::StoragePlanner::compile(&state)
+        .expect("alongside plan should compile");
+    let boot = ai::core::services::bootloader::BootloaderService::build_plan(
+        &state,
+        "/dev/sda",
+        &storage_plan,
+    );
+    let boot = boot.commands.join("\n");
+    assert!(boot.contains("--efi-directory=/efi"), "{boot}");
+    assert!(boot.contains("GRUB_DISABLE_OS_PROBER=false"), "{boot}");
+    assert!(
+        boot.contains("grub-mkconfig -o /boot/grub/grub.cfg"),
+        "{boot}"
+    );
+    let pkgs = ai::core::services::system::SystemService::build_pacstrap_plan(&state);
+    assert!(pkgs.commands.join("\n").contains("os-prober"));
+
+    let sections = state.build_install_sections("/dev/sda");
+    let all: Vec<String> = sections
+        .iter()
+        .flat_map(|(_, cmds)| cmds.iter().map(|c| c.for_log()))
+        .collect();
+    assert!(
+        !all.iter()
+            .any(|c| c.contains("mklabel") || c.starts_with("wipefs"))
+    );
+    assert!(!all.iter().any(|c| c.contains("efibootmgr -b")));
+
+    let diags = make_state().validate_config_text(
+        "[disks]\nmode = \"Best-effort partition layout\"\ninstall_alongside = \"yes\"\n",
+    );
+    assert!(!diags.is_empty());
 }